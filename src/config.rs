@@ -1,14 +1,309 @@
+pub mod daily;
+pub mod footer;
+pub mod schema;
+
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::fs;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fs};
+
+/// Overrides `file_path`, below `--file` but above the config file
+pub const FILE_ENV: &str = "TODO_FILE";
+/// Where to read the config from instead of the default location, unless
+/// `--config` is given
+pub const CONFIG_ENV: &str = "TODO_CONFIG";
+
+/// The table of named profiles, each overriding some of the other keys
+pub const PROFILES_KEY: &str = "profiles";
+
+// Set from `--config`
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
+// Set from `--profile`
+static PROFILE: OnceLock<String> = OnceLock::new();
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
+    /// The TODO file; date fields (`~/notes/%Y-%m-%d.md`) make it a file per day
     pub file_path: String,
+    /// Open the nearest `TODO.md` or `.todo.md` in the current directory or
+    /// above it instead of `file_path`, so each project can have its own list
+    #[serde(default)]
+    pub discover_local_file: bool,
+    /// Keep a `.bak` copy of the previous file contents on every save
+    #[serde(default)]
+    pub backup: bool,
+    /// Start the TUI with completed todos hidden
+    #[serde(default)]
+    pub hide_completed: bool,
+    /// Start the TUI with todos blocked by open ones (`⛔ id`) hidden
+    #[serde(default)]
+    pub hide_blocked: bool,
+    /// Ask before deleting items in the TUI
+    #[serde(default = "default_confirm_delete")]
+    pub confirm_delete: bool,
+    /// Complete a parent todo when its last open child is checked, and reopen
+    /// it when a child is unchecked
+    #[serde(default)]
+    pub auto_complete_parents: bool,
+    /// After confirming a newly added todo or note, start another one below
+    /// it; confirming an empty one or pressing Esc stops
+    #[serde(default)]
+    pub continue_lists: bool,
+    /// Stamp todos and notes added in the TUI with the date they were created
+    /// (`➕ 2024-05-01`)
+    #[serde(default)]
+    pub stamp_created_date: bool,
+    /// Stamp todos with the date they're checked off in the TUI (`✅ 2024-05-01`)
+    #[serde(default)]
+    pub stamp_done_date: bool,
+    /// Append what's added, checked off, deleted and moved in the TUI to a log
+    /// next to the file (`TODO.md.log`), shown by `todo log`
+    #[serde(default)]
+    pub activity_log: bool,
+    /// On startup, archive todos stamped as done more than this many days ago
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_archive_after_days: Option<u32>,
+    /// Keep every file encrypted with age, not just those ending in `.age`
+    #[serde(default)]
+    pub encrypt: bool,
+    /// age identity file (from `age-keygen`) for encrypted files; without one,
+    /// a passphrase is asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_identity_file: Option<String>,
+    /// While the TUI is open, send desktop notifications for todos that are
+    /// due today or overdue
+    #[serde(default)]
+    pub notifications: bool,
+    /// Length of a pomodoro started with `P` in the TUI, in minutes
+    #[serde(default = "default_pomodoro_minutes")]
+    pub pomodoro_minutes: u32,
+    /// API token used by `todo sync todoist`, from Todoist's integration settings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub todoist_api_token: Option<String>,
+    /// Rhai scripts loaded by the TUI, which can add commands and keys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<String>,
+    /// Keys rebound in the TUI, as key = action name (e.g. `"ctrl+n" =
+    /// "move_down"`, or `"none"` to unbind one)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+    /// How long the TUI waits after the last change before writing the file;
+    /// 0 writes on every change
+    #[serde(default = "default_autosave_interval_ms")]
+    pub autosave_interval_ms: u64,
+    /// How long the TUI waits for the next key of a multi-key command such as
+    /// `g g` before dropping the keys typed so far; 0 waits indefinitely
+    #[serde(default = "default_chord_timeout_ms")]
+    pub chord_timeout_ms: u64,
+    /// How many changes the TUI can undo
+    #[serde(default = "default_undo_levels")]
+    pub undo_levels: usize,
+    /// Keep the undo history next to the file (`TODO.md.undo.json`), so it
+    /// survives restarting the TUI
+    #[serde(default)]
+    pub persistent_undo: bool,
+    /// Numbers beside the rows of the TUI's list: off, absolute, or relative
+    /// to the cursor
+    #[serde(default)]
+    pub line_numbers: LineNumbers,
+    /// Layout of the summary in the TUI's footer, with placeholders `{total}`,
+    /// `{done}`, `{pending}`, `{file}`, `{section}` and `{selection}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer_format: Option<String>,
+    /// Colors used by the TUI
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Colors for items with a tag, by tag name (without the `#`)
+    #[serde(default)]
+    pub tag_colors: BTreeMap<String, String>,
+    /// Commands run when the list is saved or a todo is added or checked off
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Text that new todos, sections and daily files start with
+    #[serde(default)]
+    pub templates: TemplatesConfig,
+    /// Additional named files that can be switched to from the TUI
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            file_path: String::new(),
+            discover_local_file: false,
+            backup: false,
+            hide_completed: false,
+            hide_blocked: false,
+            confirm_delete: default_confirm_delete(),
+            auto_complete_parents: false,
+            continue_lists: false,
+            stamp_created_date: false,
+            stamp_done_date: false,
+            activity_log: false,
+            auto_archive_after_days: None,
+            encrypt: false,
+            age_identity_file: None,
+            notifications: false,
+            pomodoro_minutes: default_pomodoro_minutes(),
+            todoist_api_token: None,
+            plugins: Vec::new(),
+            keys: BTreeMap::new(),
+            autosave_interval_ms: default_autosave_interval_ms(),
+            chord_timeout_ms: default_chord_timeout_ms(),
+            undo_levels: default_undo_levels(),
+            persistent_undo: false,
+            line_numbers: LineNumbers::default(),
+            footer_format: None,
+            theme: ThemeConfig::default(),
+            tag_colors: BTreeMap::new(),
+            hooks: HooksConfig::default(),
+            templates: TemplatesConfig::default(),
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_autosave_interval_ms() -> u64 {
+    500
+}
+
+fn default_chord_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_undo_levels() -> usize {
+    20
+}
+
+fn default_pomodoro_minutes() -> u32 {
+    25
+}
+
+fn default_confirm_delete() -> bool {
+    true
+}
+
+/// Numbers shown in a gutter beside the TUI's list, counting shown rows
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineNumbers {
+    #[default]
+    Off,
+    Absolute,
+    /// The distance from the cursor, which shows its own row's number
+    Relative,
+}
+
+/// The `[hooks]` table: shell commands run by `crate::hooks`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct HooksConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_save: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_complete: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_add: Option<String>,
+}
+
+/// The `[templates]` table, filled in by `crate::templates`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct TemplatesConfig {
+    /// What a new todo starts as; `{cursor}` is where typing starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<String>,
+    /// Markdown added under a new heading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// A new daily file, instead of a heading with the date
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+/// The `[theme]` table: a preset plus optional per-color overrides
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub preset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_progress: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Colors for heading levels 1, 2, ...; deeper levels reuse the last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headings: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection_bg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_bg: Option<String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            preset: "dark".to_string(),
+            header: None,
+            text: None,
+            note: None,
+            completed: None,
+            completed_strikethrough: None,
+            in_progress: None,
+            code: None,
+            headings: None,
+            selection_fg: None,
+            selection_bg: None,
+            edit_fg: None,
+            edit_bg: None,
+        }
+    }
 }
 
 impl Config {
     pub fn load() -> Result<Self, ConfigError> {
+        let table = Self::load_table()?;
+        Self::from_table(table)
+    }
+
+    /// The config in `table` with the active profile applied, its paths
+    /// expanded and the environment's overrides applied
+    pub fn from_table(table: toml::Table) -> Result<Self, ConfigError> {
+        let mut config: Config = toml::Value::Table(apply_profile(table, active_profile())?)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::ParseError(e.to_string()))?;
+        config.file_path = expand_path(&config.file_path);
+        config.age_identity_file = config.age_identity_file.as_deref().map(expand_path);
+        for path in config.plugins.iter_mut().chain(config.files.values_mut()) {
+            *path = expand_path(path);
+        }
+        Ok(config.with_environment())
+    }
+
+    pub fn with_environment(mut self) -> Self {
+        if let Some(path) = file_from_environment() {
+            self.file_path = path;
+        }
+        self
+    }
+
+    /// The config file as written, without defaults filled in
+    pub fn load_table() -> Result<toml::Table, ConfigError> {
         let config_path = get_config_file_path()?;
         
         if !config_path.exists() {
@@ -18,13 +313,13 @@ impl Config {
         let content = fs::read_to_string(&config_path)
             .map_err(|e| ConfigError::ReadError(e.to_string()))?;
         
-        let config: Config = toml::from_str(&content)
-            .map_err(|e| ConfigError::ParseError(e.to_string()))?;
-        
-        Ok(config)
+        toml::from_str(&content)
+            .map_err(|e| ConfigError::ParseError(e.to_string()))
     }
 
-    pub fn save(&self) -> Result<(), ConfigError> {
+    /// Writes the config file, keeping only the keys that were set, so the
+    /// rest keep following their defaults
+    pub fn save_table(table: &toml::Table) -> Result<(), ConfigError> {
         let config_path = get_config_file_path()?;
         
         if let Some(parent) = config_path.parent() {
@@ -32,7 +327,7 @@ impl Config {
                 .map_err(|e| ConfigError::WriteError(e.to_string()))?;
         }
 
-        let content = toml::to_string(self)
+        let content = toml::to_string(table)
             .map_err(|e| ConfigError::SerializeError(e.to_string()))?;
         
         fs::write(&config_path, content)
@@ -40,13 +335,104 @@ impl Config {
         
         Ok(())
     }
+}
 
-    pub fn set_file_path(&mut self, path: String) {
-        self.file_path = path;
+/// The TODO file named by `TODO_FILE`, if it's set
+pub fn file_from_environment() -> Option<String> {
+    env::var(FILE_ENV).ok().filter(|path| !path.is_empty()).map(|path| expand_path(&path))
+}
+
+/// File names `discover_local_file` looks for, in order of preference
+pub const LOCAL_FILE_NAMES: &[&str] = &["TODO.md", ".todo.md"];
+
+/// The nearest file named in `LOCAL_FILE_NAMES` in `start` or one of its
+/// parent directories
+pub fn find_local_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .flat_map(|dir| LOCAL_FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// Expands a leading `~` to the home directory and `$VAR` or `${VAR}` to the
+/// variable's value. Unset variables are left as they are.
+pub fn expand_path(path: &str) -> String {
+    let (mut expanded, rest) = match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => (home.to_string_lossy().to_string(), rest),
+        _ => (String::new(), path),
+    };
+    let mut chars = rest.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+        let braced = chars.next_if(|&(_, c)| c == '{').is_some();
+        let mut name = String::new();
+        while let Some((_, c)) = chars.next_if(|&(_, c)| c.is_ascii_alphanumeric() || c == '_') {
+            name.push(c);
+        }
+        let closed = !braced || chars.next_if(|&(_, c)| c == '}').is_some();
+        let end = chars.peek().map_or(rest.len(), |&(index, _)| index);
+        match env::var(&name) {
+            Ok(value) if !name.is_empty() && closed => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[start..end]),
+        }
+    }
+    expanded
+}
+
+/// Uses the `[profiles.<name>]` table from now on, for `--profile`
+pub fn use_profile(name: String) {
+    // Only called once, at startup
+    PROFILE.set(name).ok();
+}
+
+pub fn active_profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// `table` without its profiles, with the keys of `profile` laid over it.
+/// Nested tables like `[theme]` are merged key by key.
+pub fn apply_profile(mut table: toml::Table, profile: Option<&str>) -> Result<toml::Table, ConfigError> {
+    let profiles = table.remove(PROFILES_KEY);
+    let Some(name) = profile else {
+        return Ok(table);
+    };
+    match profiles.as_ref().and_then(|profiles| profiles.get(name)) {
+        Some(toml::Value::Table(overrides)) => {
+            merge_tables(&mut table, overrides.clone());
+            Ok(table)
+        }
+        Some(_) => Err(ConfigError::ParseError(format!("profile '{}' isn't a table", name))),
+        None => Err(ConfigError::UnknownProfile(name.to_string())),
     }
 }
 
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => merge_tables(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Reads and writes the config at `path` from now on, for `--config`
+pub fn use_config_file(path: PathBuf) {
+    // Only called once, at startup
+    CONFIG_FILE.set(path).ok();
+}
+
 fn get_config_file_path() -> Result<PathBuf, ConfigError> {
+    if let Some(path) = CONFIG_FILE.get() {
+        return Ok(path.clone());
+    }
+    if let Some(path) = env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
     let config_dir = dirs::config_dir()
         .ok_or(ConfigError::ConfigDirNotFound)?;
     
@@ -61,13 +447,16 @@ pub enum ConfigError {
     WriteError(String),
     ParseError(String),
     SerializeError(String),
+    UnknownKey { key: String, suggestion: Option<String> },
+    InvalidValue { key: String, message: String },
+    UnknownProfile(String),
 }
 
 impl std::fmt::Display for ConfigError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ConfigError::ConfigNotFound => {
-                write!(f, "Configuration not found. Run 'todo config set file_path <path>' to configure your TODO file location.")
+                write!(f, "Configuration not found. Run 'todo init', or 'todo config set file_path <path>', to configure your TODO file location.")
             }
             ConfigError::ConfigDirNotFound => {
                 write!(f, "Could not find config directory")
@@ -84,8 +473,80 @@ impl std::fmt::Display for ConfigError {
             ConfigError::SerializeError(msg) => {
                 write!(f, "Failed to serialize config: {}", msg)
             }
+            ConfigError::UnknownKey { key, suggestion } => {
+                write!(f, "Unknown configuration key '{}'", key)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ". Did you mean '{}'?", suggestion),
+                    None => write!(f, ". Run 'todo config list' to see every key."),
+                }
+            }
+            ConfigError::InvalidValue { key, message } => {
+                write!(f, "Invalid value for '{}': {}", key, message)
+            }
+            ConfigError::UnknownProfile(name) => {
+                write!(f, "No profile named '{}'. Create it with 'todo --profile {} config set file_path <path>'.", name, name)
+            }
         }
     }
 }
 
-impl std::error::Error for ConfigError {}
\ No newline at end of file
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+        assert_eq!(expand_path("~/notes/TODO.md"), format!("{}/notes/TODO.md", home));
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/$TODO_SURELY_UNSET_VARIABLE"), format!("{}/$TODO_SURELY_UNSET_VARIABLE", home));
+        assert_eq!(expand_path("~other/TODO.md"), "~other/TODO.md");
+        assert_eq!(expand_path("/notes/TODO.md"), "/notes/TODO.md");
+
+        // PATH is set wherever the tests run; the other name almost certainly isn't
+        let path = env::var("PATH").unwrap();
+        assert_eq!(expand_path("$PATH/x"), format!("{}/x", path));
+        assert_eq!(expand_path("${PATH}x"), format!("{}x", path));
+        assert_eq!(expand_path("/a/$TODO_SURELY_UNSET_VARIABLE/b"), "/a/$TODO_SURELY_UNSET_VARIABLE/b");
+        assert_eq!(expand_path("/a/${PATH/b"), "/a/${PATH/b");
+        assert_eq!(expand_path("/a/$/b"), "/a/$/b");
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let table: toml::Table = toml::from_str(
+            "file_path = \"/home.md\"\nbackup = true\n[theme]\npreset = \"light\"\nheader = \"red\"\n\
+             [profiles.work]\nfile_path = \"/work.md\"\n[profiles.work.theme]\nheader = \"blue\"\n",
+        )
+        .unwrap();
+        let work = apply_profile(table.clone(), Some("work")).unwrap();
+        assert_eq!(work["file_path"].as_str(), Some("/work.md"));
+        assert_eq!(work["backup"].as_bool(), Some(true));
+        assert_eq!(work["theme"]["preset"].as_str(), Some("light"));
+        assert_eq!(work["theme"]["header"].as_str(), Some("blue"));
+        assert!(!work.contains_key(PROFILES_KEY));
+
+        let home = apply_profile(table.clone(), None).unwrap();
+        assert_eq!(home["file_path"].as_str(), Some("/home.md"));
+        assert!(!home.contains_key(PROFILES_KEY));
+        assert!(matches!(apply_profile(table, Some("play")), Err(ConfigError::UnknownProfile(_))));
+    }
+
+    #[test]
+    fn test_find_local_file() {
+        let root = env::temp_dir().join(format!("todo_local_{}", std::process::id()));
+        let nested = root.join("project/src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(".todo.md"), "").unwrap();
+        assert_eq!(find_local_file(&nested), Some(root.join(".todo.md")));
+
+        // The nearest file wins, and TODO.md over .todo.md
+        fs::write(root.join("project/.todo.md"), "").unwrap();
+        fs::write(root.join("project/TODO.md"), "").unwrap();
+        assert_eq!(find_local_file(&nested), Some(root.join("project/TODO.md")));
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+//! Daily files: a `file_path` with date fields, like `~/notes/%Y-%m-%d.md`,
+//! names a file per day. Today's is opened (and created if it's missing), and
+//! the TUI can step to the days before and after.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Days, NaiveDate};
+use std::path::Path;
+
+// How many days away the next existing file is looked for
+const SEARCH_DAYS: u64 = 366;
+
+pub fn is_dated(path: &str) -> bool {
+    path.contains('%')
+}
+
+/// Checks that the date fields in `path` are ones chrono knows
+pub fn check(path: &str) -> Result<(), String> {
+    if StrftimeItems::new(path).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("unknown date field in {} (use fields like %Y, %m and %d)", path));
+    }
+    Ok(())
+}
+
+/// The file for `date`; a path without date fields is the same every day
+pub fn path_for(path: &str, date: NaiveDate) -> String {
+    if !is_dated(path) || check(path).is_err() {
+        return path.to_string();
+    }
+    date.format(path).to_string()
+}
+
+/// The day `file` is for, if it's one of the files `path` names
+pub fn date_of(path: &str, file: &str) -> Option<NaiveDate> {
+    if !is_dated(path) {
+        return None;
+    }
+    NaiveDate::parse_from_str(file, path).ok().filter(|date| path_for(path, *date) == file)
+}
+
+/// The nearest day before (or after) `date` that has a file, and the file
+pub fn neighbour(path: &str, date: NaiveDate, forward: bool) -> Option<(NaiveDate, String)> {
+    (1..=SEARCH_DAYS)
+        .filter_map(|days| if forward { date.checked_add_days(Days::new(days)) } else { date.checked_sub_days(Days::new(days)) })
+        .map(|date| (date, path_for(path, date)))
+        .find(|(_, file)| Path::new(file).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn test_path_for_and_date_of() {
+        assert_eq!(path_for("/notes/%Y-%m-%d.md", date(1)), "/notes/2024-05-01.md");
+        assert_eq!(path_for("/notes/TODO.md", date(1)), "/notes/TODO.md");
+        assert_eq!(date_of("/notes/%Y-%m-%d.md", "/notes/2024-05-01.md"), Some(date(1)));
+        assert_eq!(date_of("/notes/%Y-%m-%d.md", "/other/2024-05-01.md"), None);
+        assert_eq!(date_of("/notes/TODO.md", "/notes/TODO.md"), None);
+        assert!(check("/notes/%Y/%m/%d.md").is_ok());
+        assert!(check("/notes/%Q.md").is_err());
+    }
+
+    #[test]
+    fn test_neighbour() {
+        let dir = std::env::temp_dir().join(format!("todo_daily_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = format!("{}/%Y-%m-%d.md", dir.display());
+        for day in [1, 4] {
+            fs::write(path_for(&path, date(day)), "").unwrap();
+        }
+
+        assert_eq!(neighbour(&path, date(4), false), Some((date(1), path_for(&path, date(1)))));
+        assert_eq!(neighbour(&path, date(1), true).map(|(date, _)| date), Some(date(4)));
+        assert_eq!(neighbour(&path, date(4), true), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+//! The `footer_format` setting: the layout of the summary in the TUI's
+//! footer, e.g. `"{done}/{total} done | {section}"`.
+
+pub const PLACEHOLDERS: &[&str] = &["total", "done", "pending", "file", "section", "selection"];
+
+pub enum Part<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// The text and placeholders of `format`; `{{` and `}}` stand for literal braces
+pub fn parse(format: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut rest = format;
+    while let Some(start) = rest.find(['{', '}']) {
+        parts.push(Part::Text(&rest[..start]));
+        let brace = &rest[start..start + 1];
+        if rest[start + 1..].starts_with(brace) {
+            parts.push(Part::Text(brace));
+            rest = &rest[start + 2..];
+        } else if brace == "}" {
+            return Err(format!("unmatched '}}' in footer_format: {}", format));
+        } else {
+            let end = rest[start..].find('}').ok_or_else(|| format!("unclosed '{{' in footer_format: {}", format))?;
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!("unknown footer_format placeholder {{{}}}; use {}", name, PLACEHOLDERS.join(", ")));
+            }
+            parts.push(Part::Placeholder(name));
+            rest = &rest[start + end + 1..];
+        }
+    }
+    parts.push(Part::Text(rest));
+    Ok(parts)
+}
+
+/// Checks that `format` only uses known placeholders
+pub fn check_format(format: &str) -> Result<(), String> {
+    parse(format).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_format() {
+        assert!(check_format("{done}/{total}").is_ok());
+        assert!(check_format("{dne}").unwrap_err().contains("{dne}"));
+        assert!(check_format("{done").unwrap_err().contains("unclosed"));
+        assert!(check_format("done}").unwrap_err().contains("unmatched"));
+    }
+}
@@ -0,0 +1,449 @@
+//! The keys `todo config set/get/unset/list` know about, with the type of
+//! each. Nested tables are reached with dotted keys (`theme.preset`,
+//! `hooks.on_save`, `files.work`, `tag_colors.urgent`, `keys.ctrl+n`). With a profile, keys are set in its
+//! `[profiles.<name>]` table instead.
+
+use super::{apply_profile, daily, expand_path, footer, file_from_environment, Config, ConfigError, FILE_ENV, PROFILES_KEY};
+use crate::templates;
+use crate::tui::keymap::Keymap;
+use crate::tui::theme::Theme;
+use std::path::Path;
+use toml::{Table, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    Integer,
+    Text,
+    /// Given as `a,b,c` or a TOML array
+    List,
+    /// A file; a relative path is taken relative to where `config set` runs
+    Path,
+    /// Files, given like a `List`
+    PathList,
+}
+
+pub struct KeySpec {
+    pub key: &'static str,
+    pub kind: ValueKind,
+}
+
+const fn key(key: &'static str, kind: ValueKind) -> KeySpec {
+    KeySpec { key, kind }
+}
+
+/// Every key in the order `config list` prints them. `files.<name>`,
+/// `tag_colors.<tag>` and `keys.<key>` keys are added for each named file,
+/// colored tag and rebound key.
+pub const KEYS: &[KeySpec] = &[
+    key("file_path", ValueKind::Path),
+    key("discover_local_file", ValueKind::Bool),
+    key("backup", ValueKind::Bool),
+    key("hide_completed", ValueKind::Bool),
+    key("hide_blocked", ValueKind::Bool),
+    key("confirm_delete", ValueKind::Bool),
+    key("auto_complete_parents", ValueKind::Bool),
+    key("continue_lists", ValueKind::Bool),
+    key("stamp_created_date", ValueKind::Bool),
+    key("stamp_done_date", ValueKind::Bool),
+    key("activity_log", ValueKind::Bool),
+    key("auto_archive_after_days", ValueKind::Integer),
+    key("encrypt", ValueKind::Bool),
+    key("age_identity_file", ValueKind::Path),
+    key("notifications", ValueKind::Bool),
+    key("pomodoro_minutes", ValueKind::Integer),
+    key("todoist_api_token", ValueKind::Text),
+    key("plugins", ValueKind::PathList),
+    key("autosave_interval_ms", ValueKind::Integer),
+    key("chord_timeout_ms", ValueKind::Integer),
+    key("undo_levels", ValueKind::Integer),
+    key("persistent_undo", ValueKind::Bool),
+    key("line_numbers", ValueKind::Text),
+    key("footer_format", ValueKind::Text),
+    key("theme.preset", ValueKind::Text),
+    key("theme.header", ValueKind::Text),
+    key("theme.text", ValueKind::Text),
+    key("theme.note", ValueKind::Text),
+    key("theme.completed", ValueKind::Text),
+    key("theme.completed_strikethrough", ValueKind::Bool),
+    key("theme.in_progress", ValueKind::Text),
+    key("theme.code", ValueKind::Text),
+    key("theme.headings", ValueKind::List),
+    key("theme.selection_fg", ValueKind::Text),
+    key("theme.selection_bg", ValueKind::Text),
+    key("theme.edit_fg", ValueKind::Text),
+    key("theme.edit_bg", ValueKind::Text),
+    key("hooks.on_save", ValueKind::Text),
+    key("hooks.on_complete", ValueKind::Text),
+    key("hooks.on_add", ValueKind::Text),
+    key("templates.todo", ValueKind::Text),
+    key("templates.section", ValueKind::Text),
+    key("templates.file", ValueKind::Text),
+];
+
+const FILES_PREFIX: &str = "files.";
+const TAG_COLORS_PREFIX: &str = "tag_colors.";
+const KEYS_PREFIX: &str = "keys.";
+
+/// Where an effective value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    /// The active profile's table in the config file
+    Profile,
+    Environment(&'static str),
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Default => "default",
+            Source::File => "config file",
+            Source::Profile => "profile",
+            Source::Environment(variable) => variable,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    /// As TOML, or `None` for an optional setting that isn't set
+    pub value: Option<String>,
+    pub source: Source,
+}
+
+/// The type of `key`, or an error suggesting the closest known key
+pub fn kind_of(key: &str) -> Result<ValueKind, ConfigError> {
+    if let Some(name) = key.strip_prefix(FILES_PREFIX) {
+        return if name.is_empty() { Err(unknown_key(key)) } else { Ok(ValueKind::Path) };
+    }
+    if let Some(tag) = key.strip_prefix(TAG_COLORS_PREFIX) {
+        return if tag.is_empty() { Err(unknown_key(key)) } else { Ok(ValueKind::Text) };
+    }
+    if let Some(keys) = key.strip_prefix(KEYS_PREFIX) {
+        return if keys.is_empty() { Err(unknown_key(key)) } else { Ok(ValueKind::Text) };
+    }
+    KEYS.iter().find(|spec| spec.key == key).map(|spec| spec.kind).ok_or_else(|| unknown_key(key))
+}
+
+fn unknown_key(key: &str) -> ConfigError {
+    let suggestion = KEYS
+        .iter()
+        .map(|spec| (strsim::jaro_winkler(key, spec.key), spec.key))
+        .filter(|&(similarity, _)| similarity > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, suggestion)| suggestion.to_string());
+    ConfigError::UnknownKey { key: key.to_string(), suggestion }
+}
+
+pub fn parse_value(key: &str, kind: ValueKind, input: &str) -> Result<Value, ConfigError> {
+    let invalid = |message: String| ConfigError::InvalidValue { key: key.to_string(), message };
+    match kind {
+        ValueKind::Bool => match input.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" => Ok(Value::Boolean(true)),
+            "false" | "no" | "off" => Ok(Value::Boolean(false)),
+            _ => Err(invalid(format!("expected true or false, got '{}'", input))),
+        },
+        ValueKind::Integer => input
+            .trim()
+            .parse::<u32>()
+            .map(|number| Value::Integer(number.into()))
+            .map_err(|_| invalid(format!("expected a whole number, got '{}'", input))),
+        ValueKind::Text => Ok(Value::String(input.to_string())),
+        ValueKind::Path => Ok(Value::String(absolute_path(input))),
+        ValueKind::PathList => match parse_value(key, ValueKind::List, input)? {
+            Value::Array(paths) => Ok(Value::Array(
+                paths.into_iter().map(|path| path.as_str().map_or(path.clone(), |path| Value::String(absolute_path(path)))).collect(),
+            )),
+            other => Ok(other),
+        },
+        ValueKind::List if input.trim_start().starts_with('[') => {
+            let table: Table = toml::from_str(&format!("list = {}", input))
+                .map_err(|_| invalid(format!("not a TOML array: {}", input)))?;
+            Ok(table["list"].clone())
+        }
+        ValueKind::List => Ok(Value::Array(
+            input
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+// `~` and variables are kept as written, so the config still works if they change
+fn absolute_path(path: &str) -> String {
+    if path.is_empty() || path.starts_with('~') || path.starts_with('$') || Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    match std::env::current_dir() {
+        Ok(dir) => dir.join(path).to_string_lossy().to_string(),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Sets `key` in the config file's `table`, or in `profile` within it,
+/// checking that the result is still a valid config. Returns the value as
+/// stored.
+pub fn set(table: &mut Table, profile: Option<&str>, key: &str, input: &str) -> Result<Value, ConfigError> {
+    let value = parse_value(key, kind_of(key)?, input)?;
+    let mut updated = table.clone();
+    let (parent, name) = parent_table(scope(&mut updated, profile)?, key)?;
+    parent.insert(name.to_string(), value.clone());
+    validate(key, &apply_profile(updated.clone(), profile)?)?;
+    *table = updated;
+    Ok(value)
+}
+
+/// The files named by a `Path` or `PathList` value that don't exist, expanded
+pub fn missing_paths(value: &Value) -> Vec<String> {
+    let paths = match value {
+        Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+        other => other.as_str().into_iter().collect::<Vec<_>>(),
+    };
+    paths
+        .into_iter()
+        .map(expand_path)
+        // A daily file's name changes every day, so there's no one file to look for
+        .filter(|path| !daily::is_dated(path) && !Path::new(path).exists())
+        .collect()
+}
+
+/// Removes `key` from the config file's `table`, or from `profile` within
+/// it, so its default (or the value outside the profile) applies again
+pub fn unset(table: &mut Table, profile: Option<&str>, key: &str) -> Result<bool, ConfigError> {
+    kind_of(key)?;
+    let (parent, name) = parent_table(scope(table, profile)?, key)?;
+    Ok(parent.remove(name).is_some())
+}
+
+/// The effective value of `key` as it would be written in the config file
+pub fn get(config: &Config, key: &str) -> Result<Option<Value>, ConfigError> {
+    kind_of(key)?;
+    let effective = effective_table(config)?;
+    Ok(lookup(&effective, key).cloned())
+}
+
+/// Every known key with its effective value and where it comes from
+pub fn list(config: &Config, file: &Table, profile: Option<&str>) -> Result<Vec<Entry>, ConfigError> {
+    let overrides = profile.and_then(|name| lookup(file, &format!("{}.{}", PROFILES_KEY, name))).and_then(Value::as_table);
+    let effective = effective_table(config)?;
+    let files = config.files.keys().map(|name| format!("{}{}", FILES_PREFIX, name));
+    let tag_colors = config.tag_colors.keys().map(|tag| format!("{}{}", TAG_COLORS_PREFIX, tag));
+    let bound_keys = config.keys.keys().map(|keys| format!("{}{}", KEYS_PREFIX, keys));
+    let keys = KEYS.iter().map(|spec| spec.key.to_string()).chain(files).chain(tag_colors).chain(bound_keys);
+    let from_environment = file_from_environment().is_some();
+    Ok(keys
+        .map(|key| Entry {
+            value: lookup(&effective, &key).map(ToString::to_string),
+            source: match key.as_str() {
+                "file_path" if from_environment => Source::Environment(FILE_ENV),
+                _ if overrides.is_some_and(|overrides| lookup(overrides, &key).is_some()) => Source::Profile,
+                _ if lookup(file, &key).is_some() => Source::File,
+                _ => Source::Default,
+            },
+            key,
+        })
+        .collect())
+}
+
+/// How a value is printed by `config get`: strings bare, everything else as TOML
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn effective_table(config: &Config) -> Result<Table, ConfigError> {
+    Table::try_from(config).map_err(|e| ConfigError::SerializeError(e.to_string()))
+}
+
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    match key.split_once('.') {
+        Some((parent, name)) => table.get(parent)?.as_table()?.get(name),
+        None => table.get(key),
+    }
+}
+
+// The table keys are set in: the whole file, or the profile's table in it
+fn scope<'a>(table: &'a mut Table, profile: Option<&str>) -> Result<&'a mut Table, ConfigError> {
+    let Some(name) = profile else {
+        return Ok(table);
+    };
+    let not_a_table = |what: String| ConfigError::ParseError(format!("{} in the config file isn't a table", what));
+    table
+        .entry(PROFILES_KEY)
+        .or_insert_with(|| Value::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| not_a_table(format!("'{}'", PROFILES_KEY)))?
+        .entry(name)
+        .or_insert_with(|| Value::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| not_a_table(format!("Profile '{}'", name)))
+}
+
+// The table holding `key` and the key's name within it, creating the table
+// for a dotted key if the file doesn't have it yet
+fn parent_table<'a, 'k>(table: &'a mut Table, key: &'k str) -> Result<(&'a mut Table, &'k str), ConfigError> {
+    let Some((parent, name)) = key.split_once('.') else {
+        return Ok((table, key));
+    };
+    let parent_value = table.entry(parent).or_insert_with(|| Value::Table(Table::new()));
+    let parent_table = parent_value.as_table_mut().ok_or_else(|| ConfigError::InvalidValue {
+        key: key.to_string(),
+        message: format!("'{}' in the config file isn't a table", parent),
+    })?;
+    Ok((parent_table, name))
+}
+
+fn validate(key: &str, table: &Table) -> Result<(), ConfigError> {
+    let invalid = |message: String| ConfigError::InvalidValue { key: key.to_string(), message };
+    let config: Config = Value::Table(table.clone()).try_into().map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+    Theme::from_config(&config.theme).map_err(invalid)?.with_tag_colors(&config.tag_colors).map_err(invalid)?;
+    daily::check(&config.file_path).map_err(invalid)?;
+    for template in [&config.templates.todo, &config.templates.section, &config.templates.file].into_iter().flatten() {
+        templates::check(template).map_err(invalid)?;
+    }
+    if let Some(format) = &config.footer_format {
+        footer::check_format(format).map_err(invalid)?;
+    }
+    if let Some(error) = Keymap::load(&config.keys).1.into_iter().next() {
+        return Err(invalid(error));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_key_suggestions() {
+        assert_eq!(kind_of("theme.preset").unwrap(), ValueKind::Text);
+        assert_eq!(kind_of("files.work").unwrap(), ValueKind::Path);
+        match kind_of("autosave_interval").unwrap_err() {
+            ConfigError::UnknownKey { suggestion, .. } => assert_eq!(suggestion.as_deref(), Some("autosave_interval_ms")),
+            e => panic!("unexpected error: {}", e),
+        }
+        match kind_of("colour").unwrap_err() {
+            ConfigError::UnknownKey { suggestion, .. } => assert_eq!(suggestion, None),
+            e => panic!("unexpected error: {}", e),
+        }
+        assert!(kind_of("files.").is_err());
+        assert_eq!(kind_of("tag_colors.urgent").unwrap(), ValueKind::Text);
+        assert_eq!(kind_of("keys.ctrl+n").unwrap(), ValueKind::Text);
+        assert!(kind_of("keys.").is_err());
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("backup", ValueKind::Bool, "yes").unwrap(), Value::Boolean(true));
+        assert!(parse_value("backup", ValueKind::Bool, "maybe").is_err());
+        assert_eq!(parse_value("autosave_interval_ms", ValueKind::Integer, "250").unwrap(), Value::Integer(250));
+        assert!(parse_value("autosave_interval_ms", ValueKind::Integer, "-1").is_err());
+        let expected = Value::Array(vec![Value::String("red".to_string()), Value::String("blue".to_string())]);
+        assert_eq!(parse_value("theme.headings", ValueKind::List, "red, blue").unwrap(), expected);
+        assert_eq!(parse_value("theme.headings", ValueKind::List, r#"["red", "blue"]"#).unwrap(), expected);
+
+        let cwd = std::env::current_dir().unwrap();
+        let relative = parse_value("file_path", ValueKind::Path, "notes/TODO.md").unwrap();
+        assert_eq!(relative.as_str(), Some(cwd.join("notes/TODO.md").to_str().unwrap()));
+        assert_eq!(parse_value("file_path", ValueKind::Path, "~/TODO.md").unwrap().as_str(), Some("~/TODO.md"));
+        assert_eq!(parse_value("file_path", ValueKind::Path, "/TODO.md").unwrap().as_str(), Some("/TODO.md"));
+        let plugins = parse_value("plugins", ValueKind::PathList, "a.rhai, /b.rhai").unwrap();
+        assert_eq!(plugins[0].as_str(), Some(cwd.join("a.rhai").to_str().unwrap()));
+        assert_eq!(plugins[1].as_str(), Some("/b.rhai"));
+    }
+
+    #[test]
+    fn test_missing_paths() {
+        let cwd = std::env::current_dir().unwrap().to_string_lossy().to_string();
+        let value = Value::Array(vec![Value::String(cwd.clone()), Value::String("/surely/not/here.md".to_string())]);
+        assert_eq!(missing_paths(&value), vec!["/surely/not/here.md"]);
+        assert!(missing_paths(&Value::String(cwd)).is_empty());
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let mut table: Table = toml::from_str("file_path = \"/notes/TODO.md\"\n").unwrap();
+        set(&mut table, None, "theme.preset", "light").unwrap();
+        set(&mut table, None, "files.work", "/notes/work.md").unwrap();
+        set(&mut table, None, "backup", "true").unwrap();
+        assert_eq!(table["theme"]["preset"].as_str(), Some("light"));
+        assert_eq!(table["files"]["work"].as_str(), Some("/notes/work.md"));
+
+        // Values the app would reject leave the file alone
+        assert!(matches!(set(&mut table, None, "theme.preset", "neon"), Err(ConfigError::InvalidValue { .. })));
+        assert!(set(&mut table, None, "theme.header", "not a color").is_err());
+        assert_eq!(table["theme"]["preset"].as_str(), Some("light"));
+
+        assert!(unset(&mut table, None, "backup").unwrap());
+        assert!(!unset(&mut table, None, "backup").unwrap());
+        assert!(unset(&mut table, None, "nope").is_err());
+    }
+
+    #[test]
+    fn test_set_and_list_keys() {
+        let mut table: Table = toml::from_str("file_path = \"/notes/TODO.md\"\n").unwrap();
+        set(&mut table, None, "keys.ctrl+n", "move_down").unwrap();
+        set(&mut table, None, "keys.space f", "search").unwrap();
+        assert_eq!(table["keys"]["space f"].as_str(), Some("search"));
+        assert!(set(&mut table, None, "keys.x", "fly_away").is_err());
+        assert!(set(&mut table, None, "keys.hyper+x", "search").is_err());
+        assert!(table["keys"].get("x").is_none());
+
+        let config: Config = Value::Table(table.clone()).try_into().unwrap();
+        assert_eq!(get(&config, "keys.ctrl+n").unwrap(), Some(Value::String("move_down".to_string())));
+        let entries = list(&config, &table, None).unwrap();
+        let entry = entries.iter().find(|entry| entry.key == "keys.space f").unwrap();
+        assert_eq!(entry.value.as_deref(), Some("\"search\""));
+        assert_eq!(entry.source, Source::File);
+    }
+
+    #[test]
+    fn test_set_in_profile() {
+        let mut table: Table = toml::from_str("file_path = \"/notes/TODO.md\"\n").unwrap();
+        set(&mut table, Some("work"), "file_path", "/work/TODO.md").unwrap();
+        set(&mut table, Some("work"), "theme.preset", "light").unwrap();
+        assert!(set(&mut table, Some("work"), "theme.preset", "neon").is_err());
+        assert_eq!(table["file_path"].as_str(), Some("/notes/TODO.md"));
+        assert_eq!(table["profiles"]["work"]["file_path"].as_str(), Some("/work/TODO.md"));
+        assert_eq!(table["profiles"]["work"]["theme"]["preset"].as_str(), Some("light"));
+
+        let config: Config = Value::Table(apply_profile(table.clone(), Some("work")).unwrap()).try_into().unwrap();
+        let entries = list(&config, &table, Some("work")).unwrap();
+        let entry = |key: &str| entries.iter().find(|entry| entry.key == key).unwrap();
+        assert_eq!(entry("file_path").source, Source::Profile);
+        assert_eq!(entry("file_path").value.as_deref(), Some("\"/work/TODO.md\""));
+        assert_eq!(entry("theme.preset").source, Source::Profile);
+        assert_eq!(entry("backup").source, Source::Default);
+
+        assert!(unset(&mut table, Some("work"), "theme.preset").unwrap());
+        assert!(!unset(&mut table, Some("work"), "backup").unwrap());
+    }
+
+    #[test]
+    fn test_get_and_list() {
+        let file: Table = toml::from_str("file_path = \"/notes/TODO.md\"\n[theme]\npreset = \"light\"\n[files]\nwork = \"/notes/work.md\"\n").unwrap();
+        let config: Config = Value::Table(file.clone()).try_into().unwrap();
+        assert_eq!(get(&config, "theme.preset").unwrap(), Some(Value::String("light".to_string())));
+        assert_eq!(get(&config, "autosave_interval_ms").unwrap(), Some(Value::Integer(500)));
+        assert_eq!(get(&config, "todoist_api_token").unwrap(), None);
+        assert!(get(&config, "nope").is_err());
+
+        let entries = list(&config, &file, None).unwrap();
+        let entry = |key: &str| entries.iter().find(|entry| entry.key == key).unwrap();
+        assert_eq!(entry("file_path").source, Source::File);
+        assert_eq!(entry("file_path").value.as_deref(), Some("\"/notes/TODO.md\""));
+        assert_eq!(entry("backup").source, Source::Default);
+        assert_eq!(entry("backup").value.as_deref(), Some("false"));
+        assert_eq!(entry("age_identity_file").value, None);
+        assert_eq!(entry("files.work").source, Source::File);
+        assert_eq!(entries.len(), KEYS.len() + 1);
+    }
+}
@@ -0,0 +1,232 @@
+//! Encryption of TODO files at rest with age. An encrypted file is decrypted
+//! when it's read and encrypted again on every save, so the list never
+//! touches the disk in the clear.
+
+use crate::config::Config;
+use age::secrecy::SecretString;
+use age::{scrypt, x25519};
+use anyhow::{bail, Context, Result};
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use todo_core::activity::{self, LogEntry};
+use todo_core::models::TodoList;
+use todo_core::parser::{parse_todo_content, parse_todo_file};
+use todo_core::sync::{self, SyncState};
+use todo_core::writer::{self, WriteOptions};
+
+enum Key {
+    Passphrase(SecretString),
+    Identity(x25519::Identity),
+}
+
+pub struct Encryption {
+    key: Key,
+    // Set by `encrypt = true`, which applies to every file rather than just `.age` ones
+    all_files: bool,
+    // The last ciphertext read or written and its plaintext, so reloading the
+    // file after our own save doesn't decrypt it again
+    known: RefCell<Option<(Vec<u8>, String)>>,
+}
+
+/// Whether the file at `path` is kept encrypted: it ends in `.age`, or
+/// `encrypt` is set in the config
+pub fn is_encrypted(config: &Config, path: &str) -> bool {
+    config.encrypt || path.ends_with(".age")
+}
+
+impl Encryption {
+    /// The key for the file at `path` if it's encrypted: the configured
+    /// identity file, or else a passphrase typed at the terminal
+    pub fn for_file(config: &Config, path: &str) -> Result<Option<Self>> {
+        if !is_encrypted(config, path) {
+            return Ok(None);
+        }
+        let encryption = match &config.age_identity_file {
+            Some(identity_file) => Self::from_identity_file(identity_file)?,
+            None => Self::prompt_passphrase(path)?,
+        };
+        Ok(Some(Self { all_files: config.encrypt, ..encryption }))
+    }
+
+    pub fn with_passphrase(passphrase: SecretString) -> Self {
+        Self::new(Key::Passphrase(passphrase))
+    }
+
+    /// Uses the first key in an identity file made by `age-keygen`
+    pub fn from_identity_file(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read identity file: {}", path))?;
+        let identity = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .with_context(|| format!("No key in identity file: {}", path))?
+            .parse::<x25519::Identity>()
+            .map_err(|e| anyhow::anyhow!("Invalid identity file {}: {}", path, e))?;
+        Ok(Self::new(Key::Identity(identity)))
+    }
+
+    // A new file gets its passphrase typed twice, since a typo would lock it
+    fn prompt_passphrase(path: &str) -> Result<Self> {
+        let passphrase = rpassword::prompt_password(format!("Passphrase for {}: ", path))?;
+        if !Path::new(path).exists() && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+            bail!("Passphrases don't match");
+        }
+        Ok(Self::with_passphrase(SecretString::from(passphrase)))
+    }
+
+    fn new(key: Key) -> Self {
+        Self {
+            key,
+            all_files: false,
+            known: RefCell::new(None),
+        }
+    }
+
+    /// Whether the file at `path` is encrypted with this key, e.g. when
+    /// switching to another file
+    pub fn applies_to(&self, path: &str) -> bool {
+        self.all_files || path.ends_with(".age")
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<Vec<u8>> {
+        let ciphertext = self.seal(plaintext)?;
+        self.known.replace(Some((ciphertext.clone(), plaintext.to_string())));
+        Ok(ciphertext)
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<String> {
+        if let Some((known, plaintext)) = &*self.known.borrow()
+            && known == ciphertext
+        {
+            return Ok(plaintext.clone());
+        }
+        let plaintext = self.open(ciphertext)?;
+        self.known.replace(Some((ciphertext.to_vec(), plaintext.clone())));
+        Ok(plaintext)
+    }
+
+    // Encrypts without remembering the result, for files other than the list
+    fn seal(&self, plaintext: &str) -> Result<Vec<u8>> {
+        match &self.key {
+            Key::Passphrase(passphrase) => age::encrypt(&scrypt::Recipient::new(passphrase.clone()), plaintext.as_bytes()),
+            Key::Identity(identity) => age::encrypt(&identity.to_public(), plaintext.as_bytes()),
+        }
+        .context("Failed to encrypt")
+    }
+
+    fn open(&self, ciphertext: &[u8]) -> Result<String> {
+        let plaintext = match &self.key {
+            Key::Passphrase(passphrase) => age::decrypt(&scrypt::Identity::new(passphrase.clone()), ciphertext),
+            Key::Identity(identity) => age::decrypt(identity, ciphertext),
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to decrypt: {}", e))?;
+        String::from_utf8(plaintext).context("Decrypted file isn't text")
+    }
+}
+
+/// Reads the TODO file at `path`, decrypting it if the key applies to it
+pub fn read_todo_file(path: &str, encryption: Option<&Encryption>) -> Result<TodoList> {
+    let Some(encryption) = encryption.filter(|encryption| encryption.applies_to(path)) else {
+        if path.ends_with(".age") {
+            bail!("{} is encrypted; open it directly to enter its key", path);
+        }
+        return parse_todo_file(path);
+    };
+    let ciphertext = fs::read(path).with_context(|| format!("Failed to read TODO file: {}", path))?;
+    let content = encryption.decrypt(&ciphertext).with_context(|| format!("Failed to read TODO file: {}", path))?;
+    Ok(parse_todo_content(path, &content))
+}
+
+/// Writes the list to its file, encrypting it if the key applies to it
+pub fn write_todo_file(todo_list: &TodoList, options: &WriteOptions, encryption: Option<&Encryption>) -> Result<()> {
+    match encryption.filter(|encryption| encryption.applies_to(&todo_list.file_path)) {
+        Some(encryption) => writer::write_todo_file_with(todo_list, options, |content| encryption.encrypt(&content)),
+        None => writer::write_todo_file(todo_list, options),
+    }
+}
+
+/// Adds `entries` to the activity log, except for an encrypted file: the log
+/// is plain text, so it would give away what's in the list
+pub fn append_activity(todo_path: &str, entries: &[LogEntry], encryption: Option<&Encryption>) -> Result<()> {
+    if encryption.is_some_and(|encryption| encryption.applies_to(todo_path)) {
+        if !entries.is_empty() {
+            eprintln!("Activity isn't logged for encrypted files");
+        }
+        return Ok(());
+    }
+    activity::append_entries(todo_path, entries)
+}
+
+/// The state of the last sync, kept encrypted next to an encrypted file
+pub fn load_sync_state(todo_path: &str, service: &str, encryption: Option<&Encryption>) -> Result<SyncState> {
+    match encryption.filter(|encryption| encryption.applies_to(todo_path)) {
+        // State saved before the file was encrypted is read as it is, and
+        // encrypted when it's saved again
+        Some(encryption) => sync::load_state_with(todo_path, service, |content| match content.first() {
+            Some(b'{') => Ok(String::from_utf8(content)?),
+            _ => encryption.open(&content),
+        }),
+        None => sync::load_state(todo_path, service),
+    }
+}
+
+pub fn save_sync_state(todo_path: &str, service: &str, state: &SyncState, encryption: Option<&Encryption>) -> Result<()> {
+    match encryption.filter(|encryption| encryption.applies_to(todo_path)) {
+        Some(encryption) => sync::save_state_with(todo_path, service, state, |json| encryption.seal(&json)),
+        None => sync::save_state(todo_path, service, state),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let encryption = Encryption::with_passphrase(SecretString::from("correct horse".to_string()));
+        let ciphertext = encryption.encrypt("- [ ] Secret plans\n").unwrap();
+        assert!(!String::from_utf8_lossy(&ciphertext).contains("Secret plans"));
+
+        let fresh = Encryption::with_passphrase(SecretString::from("correct horse".to_string()));
+        assert_eq!(fresh.decrypt(&ciphertext).unwrap(), "- [ ] Secret plans\n");
+        let wrong = Encryption::with_passphrase(SecretString::from("wrong".to_string()));
+        assert!(wrong.decrypt(&ciphertext).is_err());
+    }
+
+    #[test]
+    fn test_read_and_write_file() {
+        let dir = std::env::temp_dir().join(format!("todo_encryption_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let identity_path = dir.join("key.txt");
+        let identity = x25519::Identity::generate();
+        fs::write(&identity_path, format!("# created: today\n{}\n", age::secrecy::ExposeSecret::expose_secret(&identity.to_string()))).unwrap();
+        let encryption = Encryption::from_identity_file(&identity_path.to_string_lossy()).unwrap();
+
+        let path = dir.join("TODO.md.age").to_string_lossy().to_string();
+        let list = parse_todo_content(&path, "# Work\n- [ ] Ship it\n");
+        write_todo_file(&list, &WriteOptions::default(), Some(&encryption)).unwrap();
+        assert!(!fs::read(&path).unwrap().starts_with(b"# Work"));
+        assert_eq!(read_todo_file(&path, Some(&encryption)).unwrap().items, list.items);
+
+        let config = Config::default();
+        assert!(is_encrypted(&config, &path));
+        assert!(!is_encrypted(&config, "TODO.md"));
+        assert!(!encryption.applies_to("TODO.md"));
+        assert!(read_todo_file(&path, None).is_err());
+
+        // What's kept next to the list doesn't give its contents away either
+        let state = SyncState { links: vec![sync::Link { task_id: "1".to_string(), content: "Ship it".to_string(), completed: false }] };
+        save_sync_state(&path, "todoist", &state, Some(&encryption)).unwrap();
+        assert!(!String::from_utf8_lossy(&fs::read(sync::state_path(&path, "todoist")).unwrap()).contains("Ship it"));
+        assert_eq!(load_sync_state(&path, "todoist", Some(&encryption)).unwrap(), state);
+        sync::save_state(&path, "todoist", &state).unwrap();
+        assert_eq!(load_sync_state(&path, "todoist", Some(&encryption)).unwrap(), state);
+
+        append_activity(&path, &[LogEntry::now(activity::Activity::Added, "Ship it")], Some(&encryption)).unwrap();
+        assert!(!activity::log_path(&path).exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
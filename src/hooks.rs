@@ -0,0 +1,139 @@
+//! Commands from the `[hooks]` config table, run through `sh -c` when the
+//! list is saved or a todo is added or checked off. The item is described in
+//! environment variables (`TODO_CONTENT`, `TODO_DUE`, `TODO_TAGS`) and given on
+//! stdin as a line of JSON, as in `todo export --format json`. Every hook
+//! also gets `TODO_EVENT` and `TODO_FILE`.
+//!
+//! Hooks run in the background with their output discarded, so a slow or
+//! noisy one can't hold up or garble the TUI.
+
+use crate::config::HooksConfig;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use todo_core::{export, metadata, ListItem};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Save,
+    Complete,
+    Add,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Save => "save",
+            HookEvent::Complete => "complete",
+            HookEvent::Add => "add",
+        }
+    }
+
+    fn command(self, hooks: &HooksConfig) -> Option<&str> {
+        match self {
+            HookEvent::Save => hooks.on_save.as_deref(),
+            HookEvent::Complete => hooks.on_complete.as_deref(),
+            HookEvent::Add => hooks.on_add.as_deref(),
+        }
+    }
+}
+
+/// Runs the hook for `event`, if one is configured, without waiting for it
+pub fn run(hooks: &HooksConfig, event: HookEvent, file_path: &str, item: Option<&ListItem>) -> Result<()> {
+    let Some(command) = event.command(hooks) else {
+        return Ok(());
+    };
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(environment(event, file_path, item))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run on_{} hook", event.name()))?;
+    let input = match item {
+        Some(item) => export::item_to_json(item)? + "\n",
+        None => String::new(),
+    };
+    // A hook that doesn't read its input may have closed it already
+    child.stdin.take().map(|mut stdin| stdin.write_all(input.as_bytes()));
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Runs the `on_complete` hook for each todo checked off since `before`, a
+/// copy of the list from before a change that didn't add or remove items
+pub fn run_for_completions(hooks: &HooksConfig, file_path: &str, items: &[ListItem], before: &[ListItem]) -> Result<()> {
+    if hooks.on_complete.is_none() || items.len() != before.len() {
+        return Ok(());
+    }
+    items
+        .iter()
+        .zip(before)
+        .filter(|(item, previous)| matches!(item, ListItem::Todo { .. }) && item.is_completed() && !previous.is_completed())
+        .try_for_each(|(item, _)| run(hooks, HookEvent::Complete, file_path, Some(item)))
+}
+
+fn environment(event: HookEvent, file_path: &str, item: Option<&ListItem>) -> Vec<(&'static str, String)> {
+    let mut variables = vec![("TODO_EVENT", event.name().to_string()), ("TODO_FILE", file_path.to_string())];
+    if let Some(item) = item {
+        let content = item.content();
+        variables.push(("TODO_CONTENT", content.to_string()));
+        if let Some(due) = metadata::due_date(content) {
+            variables.push(("TODO_DUE", due.to_string()));
+        }
+        variables.push(("TODO_TAGS", metadata::tags(content).join(" ")));
+    }
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_environment() {
+        let item = ListItem::new_todo("Pay rent #home #bills 📅 2024-05-01".to_string(), true, 0);
+        assert_eq!(environment(HookEvent::Complete, "TODO.md", Some(&item)), vec![
+            ("TODO_EVENT", "complete".to_string()),
+            ("TODO_FILE", "TODO.md".to_string()),
+            ("TODO_CONTENT", "Pay rent #home #bills 📅 2024-05-01".to_string()),
+            ("TODO_DUE", "2024-05-01".to_string()),
+            ("TODO_TAGS", "home bills".to_string()),
+        ]);
+        assert_eq!(environment(HookEvent::Save, "TODO.md", None).len(), 2);
+    }
+
+    #[test]
+    fn test_run_for_completions() {
+        let dir = std::env::temp_dir().join(format!("todo_hooks_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("completed.txt");
+        let hooks = HooksConfig {
+            on_complete: Some(format!("cat >> '{}'", output.display())),
+            ..HooksConfig::default()
+        };
+        let before = vec![
+            ListItem::new_todo("Done already".to_string(), true, 0),
+            ListItem::new_todo("Ship it".to_string(), false, 0),
+            ListItem::new_todo("Later".to_string(), false, 0),
+        ];
+        let mut items = before.clone();
+        items[1] = ListItem::new_todo("Ship it".to_string(), true, 0);
+        run_for_completions(&hooks, "TODO.md", &items, &before).unwrap();
+
+        // The hook runs in the background
+        let start = Instant::now();
+        while !fs::read_to_string(&output).is_ok_and(|json| json.ends_with('\n')) && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(json["content"], "Ship it");
+        assert_eq!(json["completed"], true);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
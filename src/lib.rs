@@ -1,3 +1,9 @@
 pub mod config;
-pub mod todo;
+pub mod encryption;
+pub mod hooks;
+pub mod lock;
+pub mod server;
+pub mod setup;
+pub mod templates;
+pub mod todoist;
 pub mod tui;
\ No newline at end of file
@@ -0,0 +1,144 @@
+//! Keeps two `todo`s from overwriting each other's changes to the same file.
+//! Whoever writes it holds an advisory lock on a file next to it
+//! (`TODO.md.pid`) holding its PID: the TUI for as long as the file is open,
+//! `todo serve` and commands like `todo done` while they change it. The lock
+//! goes away with the process, so a pid file left behind by one that crashed
+//! doesn't count.
+
+use anyhow::{bail, Context, Result};
+use fs4::fs_std::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often `wait` tries the lock again
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a command waits for another one to finish changing the file
+pub const CHANGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn lock_path(todo_path: &str) -> String {
+    format!("{}.pid", todo_path)
+}
+
+/// The lock for a command that reads the file, changes it and writes it
+/// back; fails while a todo window has the file open
+pub fn lock_for_change(todo_path: &str) -> Result<InstanceLock> {
+    let lock = InstanceLock::wait(todo_path, CHANGE_TIMEOUT)?;
+    if let Some(warning) = lock.warning() {
+        bail!("{} is {}; make the change there, or quit it first", todo_path, warning);
+    }
+    Ok(lock)
+}
+
+pub enum InstanceLock {
+    /// This process may write the file; released when dropped
+    Held { file: File },
+    /// Another process has the file open, with its PID if it could be read
+    HeldElsewhere(Option<u32>),
+}
+
+impl InstanceLock {
+    pub fn acquire(todo_path: &str) -> Result<Self> {
+        let path = lock_path(todo_path);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file: {}", path))?;
+        if !file.try_lock_exclusive().with_context(|| format!("Failed to lock {}", path))? {
+            let mut pid = String::new();
+            file.read_to_string(&mut pid).ok();
+            return Ok(InstanceLock::HeldElsewhere(pid.trim().parse().ok()));
+        }
+        // Whatever was there belonged to a process that's gone
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        Ok(InstanceLock::Held { file })
+    }
+
+    /// Tries for the lock until `timeout` has passed, for a change that
+    /// only takes a moment, such as another command's
+    pub fn wait(todo_path: &str, timeout: Duration) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let lock = Self::acquire(todo_path)?;
+            if lock.is_held() || Instant::now() >= deadline {
+                return Ok(lock);
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    pub fn is_held(&self) -> bool {
+        matches!(self, InstanceLock::Held { .. })
+    }
+
+    /// What to tell the user when another process holds the lock
+    pub fn warning(&self) -> Option<String> {
+        match self {
+            InstanceLock::Held { .. } => None,
+            InstanceLock::HeldElsewhere(Some(pid)) => Some(format!("open in another todo (PID {})", pid)),
+            InstanceLock::HeldElsewhere(None) => Some("open in another todo".to_string()),
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if let InstanceLock::Held { file } = self {
+            // The file stays: removing it would let a process waiting on it
+            // and one creating it afresh both hold "the" lock
+            file.set_len(0).ok();
+            FileExt::unlock(file).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn todo_path(test: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("todo_lock_{}_{}", test, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("TODO.md").to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_second_lock_sees_first() {
+        let todo_path = todo_path("second");
+        let first = InstanceLock::acquire(&todo_path).unwrap();
+        assert!(first.is_held());
+        assert_eq!(fs::read_to_string(lock_path(&todo_path)).unwrap(), std::process::id().to_string());
+
+        let second = InstanceLock::acquire(&todo_path).unwrap();
+        assert!(!second.is_held());
+        assert_eq!(second.warning(), Some(format!("open in another todo (PID {})", std::process::id())));
+        assert!(!InstanceLock::wait(&todo_path, Duration::from_millis(100)).unwrap().is_held());
+
+        drop(first);
+        assert_eq!(fs::read_to_string(lock_path(&todo_path)).unwrap(), "");
+        assert!(InstanceLock::wait(&todo_path, Duration::ZERO).unwrap().is_held());
+        fs::remove_dir_all(Path::new(&todo_path).parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_stale_pid_file_is_taken_over() {
+        let todo_path = todo_path("stale");
+        fs::write(lock_path(&todo_path), "999999999").unwrap();
+
+        let lock = InstanceLock::acquire(&todo_path).unwrap();
+        assert!(lock.is_held());
+        assert_eq!(fs::read_to_string(lock_path(&todo_path)).unwrap(), std::process::id().to_string());
+        drop(lock);
+        fs::remove_dir_all(Path::new(&todo_path).parent().unwrap()).ok();
+    }
+}
@@ -1,15 +1,27 @@
 mod config;
-mod todo;
+mod encryption;
+mod hooks;
+mod lock;
+mod server;
+mod setup;
+mod templates;
+mod todoist;
 mod tui;
 
 use tui::handlers::KeyEventHandler;
 
-use clap::{Parser, Subcommand, ValueHint, Command, CommandFactory};
+use clap::{error::ErrorKind, Parser, Subcommand, ValueEnum, ValueHint, Command, CommandFactory};
 use clap_complete::{generate, Generator, Shell};
-use config::{Config, ConfigError};
-use std::io;
-use anyhow::Result;
+use config::{daily, schema, Config, ConfigError};
+use encryption::Encryption;
+use hooks::HookEvent;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use chrono::Local;
 use crossterm::{
+    cursor::Show,
     event::{self, DisableMouseCapture, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -18,8 +30,13 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
-use todo::parser::parse_todo_file;
-use tui::{app::App, ui};
+use regex::RegexBuilder;
+use todo_core::{activity, export, metadata, script, sync};
+use todo_core::actions::ItemActions;
+use todo_core::models::{ListItem, TodoList};
+use todo_core::writer::{serialize_item_with_style, WriteOptions};
+use todoist::TodoistClient;
+use tui::{agenda::today, app::App, editor, reminders, ui};
 
 #[derive(Parser)]
 #[command(name = "todo")]
@@ -29,17 +46,85 @@ struct Cli {
     #[arg(value_hint = ValueHint::FilePath)]
     file: Option<String>,
     
+    /// Markdown file to use instead of the configured `file_path`
+    #[arg(long = "file", short = 'f', value_name = "PATH", global = true, value_hint = ValueHint::FilePath)]
+    file_option: Option<String>,
+    
+    /// Config file to use instead of ~/.config/todo/config.toml (or $TODO_CONFIG)
+    #[arg(long = "config", value_name = "PATH", global = true, value_hint = ValueHint::FilePath)]
+    config_path: Option<String>,
+    
+    /// Profile from the config's [profiles.<name>] tables to use; `config` commands change its settings
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+    
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    #[command(about = "Set up the TODO file and config, asking where the list should live")]
+    Init {
+        #[arg(long, value_enum, help = "Just create the file (the configured one, or --file) from a template")]
+        template: Option<setup::Template>,
+    },
     #[command(about = "Configuration management")]
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    #[command(about = "Print the TODO list to stdout")]
+    List {
+        #[arg(long, conflicts_with = "completed", help = "Only show incomplete todos")]
+        pending: bool,
+        #[arg(long, help = "Only show completed todos")]
+        completed: bool,
+        #[arg(long, value_name = "NAME", help = "Only show items under the given heading")]
+        heading: Option<String>,
+    },
+    #[command(about = "Check off a todo matching a pattern without opening the TUI")]
+    Done {
+        #[arg(help = "Text to look for in the todo (ignores case)")]
+        pattern: String,
+        #[arg(long, help = "Treat the pattern as a regular expression")]
+        regex: bool,
+        #[arg(long, help = "Check off every matching todo instead of the first")]
+        all: bool,
+    },
+    #[command(about = "Move open todos under earlier dated headings (## 2024-05-01) to today's")]
+    Rollover,
+    #[command(about = "Print the list in another format")]
+    Export {
+        #[arg(long, value_enum, help = "Format to write")]
+        format: ExportFormat,
+    },
+    #[command(about = "Add the items of an exported JSON list to the end of the list")]
+    Import {
+        #[arg(help = "JSON file written by `todo export --format json`", value_hint = ValueHint::FilePath)]
+        source: String,
+    },
+    #[command(about = "Apply operations read from stdin (a line per operation, or JSON) and print the items changed")]
+    Exec {
+        #[arg(long, help = "Print the results without saving the file")]
+        dry_run: bool,
+    },
+    #[command(about = "Print the activity log of changes made in the TUI")]
+    Log,
+    #[command(about = "Send desktop notifications for todos due today or overdue, e.g. from cron")]
+    Notify,
+    #[command(about = "Serve a small HTTP API for reading and changing the list")]
+    Serve {
+        #[arg(long, default_value_t = 8080, help = "Port to listen on")]
+        port: u16,
+        #[arg(long, default_value = "127.0.0.1", help = "Address to listen on; 0.0.0.0 allows other devices")]
+        host: String,
+    },
+    #[command(about = "Sync todos both ways with a task service, mapping headings to projects")]
+    Sync {
+        #[arg(value_enum, help = "Service to sync with")]
+        service: SyncService,
+    },
     #[command(about = "Generate shell completion scripts")]
     Completion {
         #[arg(help = "Shell to generate completions for")]
@@ -47,13 +132,25 @@ enum Commands {
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Html,
+    Ics,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SyncService {
+    Todoist,
+}
+
 #[derive(Subcommand)]
 enum ConfigAction {
     #[command(about = "Set a configuration value")]
     Set {
-        #[arg(help = "Configuration key (currently only 'file_path' is supported)")]
+        #[arg(help = "Configuration key, e.g. file_path or theme.preset (see `todo config list`)")]
         key: String,
-        #[arg(help = "Configuration value", value_hint = ValueHint::FilePath)]
+        #[arg(help = "Configuration value; lists are comma-separated", value_hint = ValueHint::FilePath)]
         value: String,
     },
     #[command(about = "Get a configuration value")]
@@ -61,26 +158,112 @@ enum ConfigAction {
         #[arg(help = "Configuration key")]
         key: String,
     },
-    #[command(about = "List all configuration values")]
+    #[command(about = "Remove a value from the config file so its default applies")]
+    Unset {
+        #[arg(help = "Configuration key")]
+        key: String,
+    },
+    #[command(about = "List every configuration value and where it comes from")]
     List,
 }
 
 fn main() {
     let cli = Cli::parse();
+    // `--file` is global so it also works after a subcommand, which clap can't
+    // combine with `conflicts_with`, so check for both forms here
+    if cli.file.is_some() && cli.file_option.is_some() {
+        Cli::command()
+            .error(ErrorKind::ArgumentConflict, "the file can be given as an argument or with --file, but not both")
+            .exit();
+    }
+    // The command line wins over $TODO_FILE, which wins over the config file
+    let file = cli.file.or(cli.file_option).or_else(config::file_from_environment);
+    if let Some(path) = cli.config_path {
+        config::use_config_file(path.into());
+    }
+    if let Some(name) = cli.profile {
+        config::use_profile(name);
+    }
 
     match cli.command {
+        Some(Commands::Init { template }) => {
+            if let Err(e) = handle_init_command(file, template) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
         Some(Commands::Config { action }) => {
             if let Err(e) = handle_config_command(action) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
+        Some(Commands::List { pending, completed, heading }) => {
+            if let Err(e) = handle_list_command(file, pending, completed, heading) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Done { pattern, regex, all }) => {
+            if let Err(e) = handle_done_command(file, &pattern, regex, all) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Rollover) => {
+            if let Err(e) = handle_rollover_command(file) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Export { format }) => {
+            if let Err(e) = handle_export_command(file, format) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Import { source }) => {
+            if let Err(e) = handle_import_command(file, &source) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Exec { dry_run }) => {
+            if let Err(e) = handle_exec_command(file, dry_run) {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Log) => {
+            if let Err(e) = handle_log_command(file) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Notify) => {
+            if let Err(e) = handle_notify_command(file) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Serve { port, host }) => {
+            if let Err(e) = handle_serve_command(file, &host, port) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Some(Commands::Sync { service }) => {
+            if let Err(e) = handle_sync_command(file, service) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
         Some(Commands::Completion { shell }) => {
             let mut cmd = Cli::command();
             print_completions(shell, &mut cmd);
         }
         None => {
-            if let Err(e) = run_main_app(cli.file) {
+            if let Err(e) = run_main_app(file) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
@@ -88,79 +271,477 @@ fn main() {
     }
 }
 
+fn handle_init_command(file_path: Option<String>, template: Option<setup::Template>) -> Result<()> {
+    if let Some(template) = template {
+        let config = load_config_or_default()?;
+        let todo_file_path = resolve_todo_file_path(file_path)?;
+        if Path::new(&todo_file_path).exists() {
+            anyhow::bail!("{} already exists", todo_file_path);
+        }
+        setup::create_file(&config, &todo_file_path, template)?;
+        println!("Created {}.", todo_file_path);
+        return Ok(());
+    }
+    let current = file_path.or_else(|| Config::load().ok().map(|config| config.file_path));
+    let setup = setup::ask(&mut io::stdin().lock(), &mut io::stdout(), current.as_deref())?;
+    let path = setup::apply(&setup)?;
+    println!("Your list is at {}. Run 'todo' to open it.", path);
+    Ok(())
+}
+
 fn handle_config_command(action: ConfigAction) -> Result<(), ConfigError> {
     match action {
         ConfigAction::Set { key, value } => {
-            if key != "file_path" {
-                eprintln!("Error: Unknown configuration key '{}'. Only 'file_path' is supported.", key);
-                std::process::exit(1);
-            }
-            
-            let mut config = match Config::load() {
-                Ok(config) => config,
-                Err(ConfigError::ConfigNotFound) => Config {
-                    file_path: String::new(),
-                },
-                Err(e) => return Err(e),
-            };
-            
-            config.set_file_path(value);
-            config.save()?;
+            let mut table = load_config_table()?;
+            let value = schema::set(&mut table, config::active_profile(), &key, &value)?;
+            Config::save_table(&table)?;
             println!("Configuration saved successfully.");
+            if matches!(schema::kind_of(&key)?, schema::ValueKind::Path | schema::ValueKind::PathList) {
+                for path in schema::missing_paths(&value) {
+                    println!("Note: {} doesn't exist yet.", path);
+                }
+            }
         }
         ConfigAction::Get { key } => {
-            if key != "file_path" {
-                eprintln!("Error: Unknown configuration key '{}'. Only 'file_path' is supported.", key);
-                std::process::exit(1);
+            let config = Config::from_table(load_config_table()?)?;
+            match schema::get(&config, &key)? {
+                Some(value) => println!("{}", schema::display_value(&value)),
+                None => {
+                    eprintln!("'{}' is not set", key);
+                    std::process::exit(1);
+                }
+            }
+        }
+        ConfigAction::Unset { key } => {
+            let mut table = load_config_table()?;
+            if schema::unset(&mut table, config::active_profile(), &key)? {
+                Config::save_table(&table)?;
+                println!("'{}' now uses its default.", key);
+            } else {
+                println!("'{}' isn't set in the config file.", key);
             }
-            
-            let config = Config::load()?;
-            println!("{}", config.file_path);
         }
         ConfigAction::List => {
-            let config = Config::load()?;
-            println!("file_path = {}", config.file_path);
+            let table = load_config_table()?;
+            let config = Config::from_table(table.clone())?;
+            let lines: Vec<(String, &str)> = schema::list(&config, &table, config::active_profile())?
+                .into_iter()
+                .map(|entry| {
+                    let line = match entry.value {
+                        Some(value) => format!("{} = {}", entry.key, value),
+                        None => format!("{} (not set)", entry.key),
+                    };
+                    (line, entry.source.label())
+                })
+                .collect();
+            let width = lines.iter().map(|(line, _)| line.chars().count()).max().unwrap_or(0);
+            for (line, source) in lines {
+                println!("{:<width$}  # {}", line, source, width = width);
+            }
         }
     }
     Ok(())
 }
 
-fn run_main_app(file_path: Option<String>) -> Result<()> {
-    let todo_file_path = if let Some(path) = file_path {
-        path
+// The config file's own keys; a missing file has none
+fn load_config_table() -> Result<toml::Table, ConfigError> {
+    match Config::load_table() {
+        Err(ConfigError::ConfigNotFound) => Ok(toml::Table::new()),
+        result => result,
+    }
+}
+
+fn resolve_todo_file_path(file_path: Option<String>) -> Result<String> {
+    if let Some(path) = file_path {
+        return Ok(path);
+    }
+    
+    let config = Config::load()
+        .map_err(|e| anyhow::anyhow!("Configuration error: {}", e))?;
+    if config.discover_local_file
+        && let Some(path) = config::find_local_file(&std::env::current_dir()?)
+    {
+        return Ok(path.to_string_lossy().to_string());
+    }
+    Ok(daily::path_for(&config.file_path, today()))
+}
+
+// Opening a file directly shouldn't require a config file to exist
+fn load_config_or_default() -> Result<Config> {
+    match Config::load() {
+        Ok(config) => Ok(config),
+        Err(ConfigError::ConfigNotFound) => Ok(Config::default().with_environment()),
+        Err(e) => Err(anyhow::anyhow!("Configuration error: {}", e)),
+    }
+}
+
+// An encrypted file can only be read once its key is known, which may mean
+// asking for a passphrase. A missing file is offered to be created when
+// there's someone at the terminal to ask.
+fn open_todo_file(config: &Config, todo_file_path: &str) -> Result<(TodoList, Option<Encryption>)> {
+    if !Path::new(todo_file_path).exists() {
+        // Today's daily file is started without asking
+        if daily::date_of(&config.file_path, todo_file_path) == Some(today()) {
+            return setup::create_file(config, todo_file_path, setup::Template::Daily);
+        }
+        if io::stdin().is_terminal()
+            && io::stdout().is_terminal()
+            && let Some(template) = setup::offer_to_create(&mut io::stdin().lock(), &mut io::stdout(), todo_file_path)?
+        {
+            return setup::create_file(config, todo_file_path, template);
+        }
+        anyhow::bail!(
+            "TODO file not found: {}. Create it, or point to another with 'todo config set file_path <path>'.",
+            todo_file_path
+        );
+    }
+    let encryption = Encryption::for_file(config, todo_file_path)?;
+    let todo_list = encryption::read_todo_file(todo_file_path, encryption.as_ref())?;
+    Ok((todo_list, encryption))
+}
+
+fn handle_list_command(
+    file_path: Option<String>,
+    pending: bool,
+    completed: bool,
+    heading: Option<String>,
+) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let (todo_list, _) = open_todo_file(&config, &todo_file_path)?;
+    
+    let range = match heading {
+        Some(name) => todo_list
+            .section_range(&name)
+            .ok_or_else(|| anyhow::anyhow!("Heading '{}' not found", name))?,
+        None => 0..todo_list.items.len(),
+    };
+    
+    let filtering = pending || completed;
+    for item in &todo_list.items[range] {
+        let include = match item {
+            ListItem::Todo { .. } => {
+                !filtering || (pending && !item.is_completed()) || (completed && item.is_completed())
+            }
+            ListItem::Note { .. } | ListItem::Heading { .. } => !filtering,
+            // Paragraphs, blank lines etc. aren't part of the list itself
+            ListItem::Raw { .. } => false,
+        };
+        if include {
+            println!("{}", serialize_item_with_style(item, todo_list.indent_style));
+        }
+    }
+    
+    Ok(())
+}
+
+fn handle_done_command(file_path: Option<String>, pattern: &str, regex: bool, all: bool) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let _lock = lock::lock_for_change(&todo_file_path)?;
+    let (mut todo_list, encryption) = open_todo_file(&config, &todo_file_path)?;
+    
+    let source = if regex { pattern.to_string() } else { regex::escape(pattern) };
+    let matcher = RegexBuilder::new(&source)
+        .case_insensitive(!regex)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid regex: {}", e))?;
+    let matches: Vec<usize> = todo_list
+        .items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches!(item, ListItem::Todo { .. }) && !item.is_completed() && matcher.is_match(item.content()))
+        .map(|(index, _)| index)
+        .take(if all { usize::MAX } else { 1 })
+        .collect();
+    if matches.is_empty() {
+        anyhow::bail!("No open todo matches '{}'", pattern);
+    }
+    
+    // Checked off the same way as in the TUI, honoring the same settings
+    let before = todo_list.items.clone();
+    for &index in &matches {
+        ItemActions::toggle_todo_completion(&mut todo_list.items, index);
+        if config.auto_complete_parents {
+            ItemActions::update_parent_completion(&mut todo_list.items, index);
+        }
+    }
+    if config.activity_log {
+        let entries = activity::completion_entries(&todo_list.items, &before);
+        encryption::append_activity(&todo_file_path, &entries, encryption.as_ref())?;
+    }
+    if config.stamp_done_date {
+        ItemActions::record_done_dates(&mut todo_list.items, &before, today());
+    }
+    encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption.as_ref())?;
+    hooks::run_for_completions(&config.hooks, &todo_file_path, &todo_list.items, &before)?;
+    hooks::run(&config.hooks, HookEvent::Save, &todo_file_path, None)?;
+    
+    for index in matches {
+        println!("{}", serialize_item_with_style(&todo_list.items[index], todo_list.indent_style));
+    }
+    Ok(())
+}
+
+fn handle_export_command(file_path: Option<String>, format: ExportFormat) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let (todo_list, _) = open_todo_file(&config, &todo_file_path)?;
+    let output = match format {
+        ExportFormat::Json => export::to_json(&todo_list)?,
+        ExportFormat::Html => export::to_html(&todo_list),
+        ExportFormat::Ics => export::to_ics(&todo_list, chrono::Utc::now().naive_utc()),
+    };
+    print!("{}", output);
+    Ok(())
+}
+
+fn handle_import_command(file_path: Option<String>, source: &str) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let json = std::fs::read_to_string(source).with_context(|| format!("Failed to read {}", source))?;
+    let imported = export::from_json(&json, &todo_file_path)?;
+    
+    let _lock = lock::lock_for_change(&todo_file_path)?;
+    let (mut todo_list, encryption) = if Path::new(&todo_file_path).exists() {
+        open_todo_file(&config, &todo_file_path)?
     } else {
-        let config = Config::load()
-            .map_err(|e| anyhow::anyhow!("Configuration error: {}", e))?;
-        config.file_path
+        (TodoList::new(todo_file_path.clone()), Encryption::for_file(&config, &todo_file_path)?)
     };
+    let count = imported.items.len();
+    todo_list.items.extend(imported.items);
+    encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption.as_ref())?;
+    println!("Imported {} items into {}", count, todo_file_path);
+    Ok(())
+}
+
+fn handle_exec_command(file_path: Option<String>, dry_run: bool) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let input = io::read_to_string(io::stdin()).context("Failed to read operations from stdin")?;
+    let operations = script::parse_script(&input)?;
+    let _lock = if dry_run { None } else { Some(lock::lock_for_change(&todo_file_path)?) };
+    let (mut todo_list, encryption) = open_todo_file(&config, &todo_file_path)?;
+    let before = todo_list.items.clone();
     
-    let todo_list = parse_todo_file(&todo_file_path)?;
-    let mut app = App::new(todo_list);
+    // Nothing is saved unless every operation applies
+    let mut changed = Vec::with_capacity(operations.len());
+    // Items to run hooks for, kept as they were since later operations can move them
+    let mut hooked = Vec::new();
+    let mut log_entries = Vec::new();
+    for (number, mut operation) in operations.into_iter().enumerate() {
+        if let script::Operation::Add { content, .. } = &mut operation
+            && config.stamp_created_date
+        {
+            *content = metadata::stamp_created_date(content, today());
+        }
+        // A toggle is dated and logged as it's applied, since later operations
+        // can move the items it changed
+        let toggled = matches!(operation, script::Operation::Toggle { .. }).then(|| todo_list.items.clone());
+        let index = script::apply(&mut todo_list, &operation).with_context(|| format!("Operation {}", number + 1))?;
+        if let Some(previous) = toggled {
+            if config.auto_complete_parents {
+                ItemActions::update_parent_completion(&mut todo_list.items, index);
+            }
+            if config.stamp_done_date {
+                ItemActions::record_done_dates(&mut todo_list.items, &previous, today());
+            }
+            log_entries.extend(activity::completion_entries(&todo_list.items, &previous));
+        }
+        match operation {
+            script::Operation::Add { .. } => hooked.push((HookEvent::Add, todo_list.items[index].clone())),
+            script::Operation::Toggle { .. } if todo_list.items[index].is_completed() => {
+                hooked.push((HookEvent::Complete, todo_list.items[index].clone()))
+            }
+            _ => {}
+        }
+        changed.push(index);
+    }
+    if !dry_run && todo_list.items != before {
+        if config.activity_log {
+            encryption::append_activity(&todo_file_path, &log_entries, encryption.as_ref())?;
+        }
+        encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption.as_ref())?;
+        for (event, item) in &hooked {
+            hooks::run(&config.hooks, *event, &todo_file_path, Some(item))?;
+        }
+        hooks::run(&config.hooks, HookEvent::Save, &todo_file_path, None)?;
+    }
     
-    run_tui(&mut app)?;
+    // Later operations can shift earlier items, so each is printed as it is now
+    for index in changed {
+        if let Some(item) = todo_list.items.get(index) {
+            println!("{}\t{}", index, serialize_item_with_style(item, todo_list.indent_style));
+        }
+    }
+    Ok(())
+}
+
+fn handle_log_command(file_path: Option<String>) -> Result<()> {
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    for entry in activity::read_log(&todo_file_path)? {
+        println!("{}  {:<9}  {}", entry.timestamp.format("%Y-%m-%d %H:%M"), entry.activity.label(), entry.content);
+    }
+    Ok(())
+}
+
+fn handle_rollover_command(file_path: Option<String>) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let _lock = lock::lock_for_change(&todo_file_path)?;
+    let (mut todo_list, encryption) = open_todo_file(&config, &todo_file_path)?;
+    let today = today();
+    let section = templates::section_items(&config.templates, Local::now().naive_local());
+    let count = ItemActions::rollover(&mut todo_list.items, today, &section);
+    if count == 0 {
+        println!("Nothing to roll over.");
+        return Ok(());
+    }
+    encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption.as_ref())?;
+    hooks::run(&config.hooks, HookEvent::Save, &todo_file_path, None)?;
+    println!("Moved {} {} to {}.", count, if count == 1 { "todo" } else { "todos" }, today.format("%Y-%m-%d"));
+    Ok(())
+}
+
+fn handle_notify_command(file_path: Option<String>) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let (todo_list, _) = open_todo_file(&config, &todo_file_path)?;
+    for reminder in reminders::due_reminders(&todo_list.items, today()) {
+        reminder.send()?;
+    }
+    Ok(())
+}
+
+fn handle_serve_command(file_path: Option<String>, host: &str, port: u16) -> Result<()> {
+    let config = load_config_or_default()?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    // Read once up front so a missing file or wrong key fails right away
+    let (_, encryption) = open_todo_file(&config, &todo_file_path)?;
+    server::serve(&config, &todo_file_path, encryption.as_ref(), &format!("{}:{}", host, port))
+}
+
+fn handle_sync_command(file_path: Option<String>, service: SyncService) -> Result<()> {
+    let SyncService::Todoist = service;
+    let config = load_config_or_default()?;
+    let api_token = config
+        .todoist_api_token
+        .as_deref()
+        .context("Set todoist_api_token in the config file to sync with Todoist")?;
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    let _lock = lock::lock_for_change(&todo_file_path)?;
+    let (mut todo_list, encryption) = open_todo_file(&config, &todo_file_path)?;
+    let local_modified = std::fs::metadata(&todo_file_path)?.modified()?.into();
+    let state = encryption::load_sync_state(&todo_file_path, "todoist", encryption.as_ref())?;
     
+    let mut client = TodoistClient::connect(api_token)?;
+    let remote = client.fetch_tasks()?;
+    let plan = sync::plan_sync(&mut todo_list, &state, &remote, local_modified);
+    if plan.local_changes > 0 {
+        encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption.as_ref())?;
+    }
+    
+    // Keep the links made so far even if a change fails, so a retry doesn't
+    // create the same tasks again
+    let mut links = plan.links;
+    let result = plan.changes.iter().try_for_each(|change| {
+        if let (Some(task_id), sync::RemoteChange::Create { content, .. }) = (client.apply(change)?, change) {
+            links.push(sync::Link { task_id, content: content.clone(), completed: false });
+        }
+        Ok::<_, anyhow::Error>(())
+    });
+    encryption::save_sync_state(&todo_file_path, "todoist", &sync::SyncState { links }, encryption.as_ref())?;
+    result?;
+    
+    println!(
+        "Synced with Todoist: {} changes in {}, {} on Todoist",
+        plan.local_changes,
+        todo_file_path,
+        plan.changes.len()
+    );
     Ok(())
 }
 
+fn run_main_app(file_path: Option<String>) -> Result<()> {
+    // Without a config there's no list to open, so set one up first
+    if file_path.is_none() && matches!(Config::load(), Err(ConfigError::ConfigNotFound)) && io::stdin().is_terminal() {
+        setup::apply(&setup::ask(&mut io::stdin().lock(), &mut io::stdout(), None)?)?;
+    }
+    let is_adhoc_file = file_path.is_some();
+    let todo_file_path = resolve_todo_file_path(file_path)?;
+    
+    let config = load_config_or_default()?;
+    // A file named on the command line may not exist yet; it's created on the first save
+    let (todo_list, encryption) = if is_adhoc_file && !Path::new(&todo_file_path).exists() {
+        let encryption = Encryption::for_file(&config, &todo_file_path)?;
+        (TodoList::new(todo_file_path), encryption)
+    } else {
+        open_todo_file(&config, &todo_file_path)?
+    };
+    let mut app = App::new(todo_list, &config, encryption);
+    app.lock_file();
+    app.auto_archive();
+    
+    let result = run_tui(&mut app);
+    
+    // Write out anything the autosave hadn't gotten to yet, even if the TUI failed
+    app.flush_changes()?;
+    if app.has_conflict() {
+        anyhow::bail!("Unsaved changes weren't written: {} was changed by another program", app.todo_list.file_path);
+    }
+    app.save_undo_history()?;
+    result
+}
+
 fn run_tui(app: &mut App) -> Result<()> {
-    // Setup terminal
+    install_panic_hook();
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let _guard = TerminalGuard;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    let result = run_app(&mut terminal, app);
+    run_app(&mut terminal, app)
+}
 
-    // Restore terminal
+// Puts the terminal back the way the shell had it
+fn restore_terminal() -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show)
+}
+
+/// Restores the terminal when the TUI ends, however it ends: normally, with
+/// an error, or by unwinding from a panic
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal().ok();
+    }
+}
 
+// The default hook prints the panic message right away, before the guard has
+// run, so it would land on the alternate screen in raw mode and be lost.
+// Restore the terminal first so it's readable.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal().ok();
+        default_hook(info);
+    }));
+}
+
+// Gives the terminal back to the shell while `run` executes, e.g. to run an
+// editor, and restores the TUI afterwards even if `run` failed
+fn suspend_tui<B: Backend>(terminal: &mut Terminal<B>, run: impl FnOnce() -> Result<()>) -> Result<()> {
+    restore_terminal()?;
+
+    let result = run();
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    // Redraw everything, since the screen contents are gone
+    terminal.clear()?;
     result
 }
 
@@ -168,12 +749,23 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()>
     loop {
         terminal.draw(|f| ui::draw(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
+        // Poll with a timeout so external file changes are picked up while idle
+        if event::poll(Duration::from_millis(250))?
+            && let Event::Key(key) = event::read()?
+        {
             app.handle_key_event(key)?;
             if app.should_quit {
                 break;
             }
         }
+        
+        if let Some(edit) = app.take_external_edit() {
+            let result = suspend_tui(terminal, || editor::run(&edit));
+            app.finish_external_edit(result);
+        }
+        
+        app.check_for_external_changes()?;
+        app.tick();
     }
     Ok(())
 }
@@ -0,0 +1,273 @@
+//! `todo serve`: a small HTTP API over the TODO file, for scripts and other
+//! devices. Every request reads the file afresh, and one that changes it
+//! writes it back while holding the same lock as the TUI and the other
+//! commands (`TODO.md.pid`); while a todo window has the file open, changes
+//! are refused with 409. Changes must be sent as `application/json`, which a
+//! web page can't do to another site without asking first, so other sites
+//! can't use a browser to edit the list.
+//!
+//! - `GET /` renders the list as a page
+//! - `GET /items` lists the items as in `todo export --format json`
+//! - `POST /items` adds `{"content": "...", "heading": "Work", "note": false}`
+//! - `POST /items/<index>/toggle` checks off or reopens a todo
+//! - `DELETE /items/<index>` deletes an item
+
+use crate::config::Config;
+use crate::encryption::{self, Encryption};
+use crate::hooks::{self, HookEvent};
+use crate::lock::{self, InstanceLock};
+use crate::tui::agenda::today;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::io::Read;
+use todo_core::{activity, export, metadata, ItemActions, ItemCreator, ListItem, TodoList};
+use todo_core::writer::WriteOptions;
+
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Reply {
+    fn json(status: u16, body: String) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, serde_json::json!({ "error": message }).to_string())
+    }
+}
+
+// Bodies hold a single item, so anything bigger isn't a real request
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Deserialize)]
+struct NewItem {
+    content: String,
+    #[serde(default)]
+    heading: Option<String>,
+    #[serde(default)]
+    note: bool,
+}
+
+/// Serves the file at `todo_path` on `address` until the process is stopped
+pub fn serve(config: &Config, todo_path: &str, encryption: Option<&Encryption>, address: &str) -> Result<()> {
+    let server = tiny_http::Server::http(address).map_err(|e| anyhow!("Failed to listen on {}: {}", address, e))?;
+    println!("Serving {} on http://{}", todo_path, address);
+
+    for mut request in server.incoming_requests() {
+        let method = request.method().as_str().to_string();
+        let content_type = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Content-Type"))
+            .map(|header| header.value.as_str().to_string());
+        let reply = match (check_content_type(&method, content_type.as_deref()), read_body(&mut request)) {
+            (Some(reply), _) | (None, Err(reply)) => reply,
+            (None, Ok(body)) => handle(config, todo_path, encryption, &method, request.url(), &body)
+                .unwrap_or_else(|e| Reply::error(500, &format!("{:#}", e))),
+        };
+        let content_type = tiny_http::Header::from_bytes("Content-Type", reply.content_type).expect("valid header");
+        let response = tiny_http::Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+        // The client may have gone away already; that's its business
+        request.respond(response).ok();
+    }
+    Ok(())
+}
+
+fn read_body(request: &mut tiny_http::Request) -> Result<String, Reply> {
+    let mut body = String::new();
+    match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_string(&mut body) {
+        Ok(len) if len as u64 > MAX_BODY_BYTES => Err(Reply::error(413, "Request body is too large")),
+        Ok(_) => Ok(body),
+        Err(_) => Err(Reply::error(400, "Request body isn't text")),
+    }
+}
+
+/// The reply refusing a request that would change the list without saying
+/// it's JSON, e.g. a form or `text/plain` post from a web page
+pub fn check_content_type(method: &str, content_type: Option<&str>) -> Option<Reply> {
+    if method == "GET" {
+        return None;
+    }
+    let media_type = content_type.and_then(|value| value.split(';').next()).unwrap_or_default();
+    if media_type.trim().eq_ignore_ascii_case("application/json") {
+        return None;
+    }
+    Some(Reply::error(415, "Send changes with Content-Type: application/json"))
+}
+
+fn handle(config: &Config, todo_path: &str, encryption: Option<&Encryption>, method: &str, url: &str, body: &str) -> Result<Reply> {
+    let _lock = match method {
+        "GET" => None,
+        _ => {
+            let lock = InstanceLock::wait(todo_path, lock::CHANGE_TIMEOUT)?;
+            if let Some(warning) = lock.warning() {
+                return Ok(Reply::error(409, &format!("{} is {}", todo_path, warning)));
+            }
+            Some(lock)
+        }
+    };
+    let mut todo_list = encryption::read_todo_file(todo_path, encryption)?;
+    let before = todo_list.items.clone();
+    let reply = route(config, &mut todo_list, method, url, body);
+    if todo_list.items != before {
+        if config.activity_log {
+            encryption::append_activity(todo_path, &activity::completion_entries(&todo_list.items, &before), encryption)?;
+        }
+        if config.stamp_done_date {
+            ItemActions::record_done_dates(&mut todo_list.items, &before, today());
+        }
+        encryption::write_todo_file(&todo_list, &WriteOptions { backup: config.backup }, encryption)?;
+        run_hooks(config, todo_path, &todo_list.items, &before)?;
+    }
+    Ok(reply)
+}
+
+// The only request that adds an item adds just the one, so it's where the
+// lists first differ
+fn run_hooks(config: &Config, todo_path: &str, items: &[ListItem], before: &[ListItem]) -> Result<()> {
+    if items.len() == before.len() + 1 {
+        let index = items.iter().zip(before).position(|(item, previous)| item != previous).unwrap_or(before.len());
+        hooks::run(&config.hooks, HookEvent::Add, todo_path, Some(&items[index]))?;
+    }
+    hooks::run_for_completions(&config.hooks, todo_path, items, before)?;
+    hooks::run(&config.hooks, HookEvent::Save, todo_path, None)
+}
+
+/// Answers one request, changing `todo_list` in place
+pub fn route(config: &Config, todo_list: &mut TodoList, method: &str, url: &str, body: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let items = &mut todo_list.items;
+    let item_index = |index: &str, items: &[ListItem]| index.parse::<usize>().ok().filter(|&index| index < items.len());
+
+    match (method, segments.as_slice()) {
+        ("GET", []) => Reply {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: export::to_html(todo_list),
+        },
+        ("GET", ["items"]) => match export::to_json(todo_list) {
+            Ok(json) => Reply::json(200, json),
+            Err(e) => Reply::error(500, &e.to_string()),
+        },
+        ("POST", ["items"]) => {
+            let new_item: NewItem = match serde_json::from_str(body) {
+                Ok(new_item) => new_item,
+                Err(e) => return Reply::error(400, &format!("Invalid item: {}", e)),
+            };
+            let content = new_item.content.trim();
+            if content.is_empty() {
+                return Reply::error(400, "Item content is empty");
+            }
+            let Some(index) = ItemCreator::end_of_section(items, new_item.heading.as_deref()) else {
+                return Reply::error(404, "No such heading");
+            };
+            let mut content = content.to_string();
+            if config.stamp_created_date {
+                content = metadata::stamp_created_date(&content, today());
+            }
+            let item = if new_item.note {
+                ListItem::new_note(content, 0)
+            } else {
+                ListItem::new_todo(content, false, 0)
+            };
+            items.insert(index, item);
+            Reply::json(201, serde_json::json!({ "index": index }).to_string())
+        }
+        ("POST", ["items", index, "toggle"]) => {
+            let Some(index) = item_index(index, items) else {
+                return Reply::error(404, "No such item");
+            };
+            if !matches!(items[index], ListItem::Todo { .. }) {
+                return Reply::error(400, "Only todos can be checked off");
+            }
+            ItemActions::toggle_todo_completion(items, index);
+            if config.auto_complete_parents {
+                ItemActions::update_parent_completion(items, index);
+            }
+            Reply::json(200, serde_json::json!({ "completed": items[index].is_completed() }).to_string())
+        }
+        ("DELETE", ["items", index]) => {
+            let Some(index) = item_index(index, items) else {
+                return Reply::error(404, "No such item");
+            };
+            if !ItemActions::delete_item(items, index) {
+                return Reply::error(400, "Headings can't be deleted");
+            }
+            Reply::json(200, serde_json::json!({ "deleted": index }).to_string())
+        }
+        _ => Reply::error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_core::{parse_todo_content, serialize_todo_list};
+
+    fn create_test_list() -> TodoList {
+        parse_todo_content("TODO.md", "- [ ] Loose end\n\n# Work\n- [ ] Report\n")
+    }
+
+    #[test]
+    fn test_read_routes() {
+        let config = Config::default();
+        let mut list = create_test_list();
+        let reply = route(&config, &mut list, "GET", "/items", "");
+        assert_eq!(reply.status, 200);
+        let json: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+        assert_eq!(json["items"][3]["content"], "Report");
+
+        assert!(route(&config, &mut list, "GET", "/", "").body.contains("<h1>Work</h1>"));
+        assert_eq!(route(&config, &mut list, "GET", "/nope", "").status, 404);
+        assert_eq!(route(&config, &mut list, "PUT", "/items", "").status, 404);
+    }
+
+    #[test]
+    fn test_add_item() {
+        let config = Config::default();
+        let mut list = create_test_list();
+        let reply = route(&config, &mut list, "POST", "/items", r#"{"content": "Slides", "heading": "work"}"#);
+        assert_eq!(reply, Reply::json(201, r#"{"index":4}"#.to_string()));
+        let reply = route(&config, &mut list, "POST", "/items", r#"{"content": "An idea", "note": true}"#);
+        assert_eq!(reply.status, 201);
+        assert_eq!(serialize_todo_list(&list), "- [ ] Loose end\n- An idea\n\n# Work\n- [ ] Report\n- [ ] Slides\n");
+
+        assert_eq!(route(&config, &mut list, "POST", "/items", r#"{"content": " "}"#).status, 400);
+        assert_eq!(route(&config, &mut list, "POST", "/items", "not json").status, 400);
+        assert_eq!(route(&config, &mut list, "POST", "/items", r#"{"content": "x", "heading": "Home"}"#).status, 404);
+    }
+
+    #[test]
+    fn test_check_content_type() {
+        assert_eq!(check_content_type("GET", None), None);
+        assert_eq!(check_content_type("POST", Some("application/json; charset=utf-8")), None);
+        assert_eq!(check_content_type("DELETE", Some("Application/JSON")), None);
+        assert_eq!(check_content_type("POST", Some("text/plain")).unwrap().status, 415);
+        assert_eq!(check_content_type("POST", None).unwrap().status, 415);
+    }
+
+    #[test]
+    fn test_toggle_and_delete() {
+        let config = Config::default();
+        let mut list = create_test_list();
+        let reply = route(&config, &mut list, "POST", "/items/3/toggle", "");
+        assert_eq!(reply.body, r#"{"completed":true}"#);
+        assert_eq!(route(&config, &mut list, "POST", "/items/2/toggle", "").status, 400);
+        assert_eq!(route(&config, &mut list, "POST", "/items/9/toggle", "").status, 404);
+
+        assert_eq!(route(&config, &mut list, "DELETE", "/items/2", "").status, 400);
+        assert_eq!(route(&config, &mut list, "DELETE", "/items/0", "").status, 200);
+        assert_eq!(serialize_todo_list(&list), "\n# Work\n- [x] Report\n");
+    }
+}
@@ -0,0 +1,198 @@
+//! First-run setup: `todo init`, also offered when `todo` starts without a
+//! config. It asks where the list should live, creates the file if needed and
+//! writes the config. New files start from a [`Template`], which is also how
+//! a missing file gets created when it's opened.
+
+use crate::config::{active_profile, expand_path, schema, Config, ConfigError, TemplatesConfig};
+use crate::encryption::{self, Encryption};
+use crate::templates;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use todo_core::parse_todo_content;
+use todo_core::writer::WriteOptions;
+use todo_core::TodoList;
+
+const DEFAULT_FILE_PATH: &str = "~/TODO.md";
+
+/// A short tour of the list format and the main keys
+pub const STARTER_TEMPLATE: &str = "# Inbox
+- [ ] Check this off with Enter
+- [ ] Add a todo below with a, or edit this one with e
+  - [ ] Nest todos with Shift+→ (or L), and fold them away with z
+- Notes like this one have no checkbox; add one with n
+
+# Later
+- [ ] Press ? for every key, and : for commands like :sort and :archive
+";
+
+/// What to put in a file that's created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Template {
+    /// Nothing at all
+    Empty,
+    /// A few example todos that explain the main keys
+    Starter,
+    /// A heading with today's date, or `templates.file` from the config
+    Daily,
+}
+
+impl Template {
+    pub fn content(self, templates: &TemplatesConfig, now: NaiveDateTime) -> String {
+        match self {
+            Template::Empty => String::new(),
+            Template::Starter => STARTER_TEMPLATE.to_string(),
+            Template::Daily => match &templates.file {
+                Some(file) => templates::expand(file, now).0,
+                None => format!("# {}\n", now.format("%Y-%m-%d")),
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Setup {
+    /// As typed, so `~` stays portable; relative paths are made absolute
+    /// when the config is written
+    pub file_path: String,
+    /// How to create the file, if it doesn't exist yet
+    pub template: Option<Template>,
+}
+
+/// Asks the setup questions on `output`, reading answers from `input`
+pub fn ask(input: &mut impl BufRead, output: &mut impl Write, default_path: Option<&str>) -> Result<Setup> {
+    writeln!(output, "Let's set up your todo list.")?;
+    let file_path = prompt(input, output, "Where should it live?", default_path.unwrap_or(DEFAULT_FILE_PATH))?;
+    let template = if Path::new(&expand_path(&file_path)).exists() {
+        None
+    } else if confirm(input, output, "That file doesn't exist yet. Start it with a few example todos?", true)? {
+        Some(Template::Starter)
+    } else {
+        Some(Template::Empty)
+    };
+    Ok(Setup { file_path, template })
+}
+
+/// Creates the file if needed and saves its path in the config, keeping any
+/// other settings. Returns the expanded path.
+pub fn apply(setup: &Setup) -> Result<String> {
+    let mut table = match Config::load_table() {
+        Err(ConfigError::ConfigNotFound) => toml::Table::new(),
+        result => result?,
+    };
+    schema::set(&mut table, active_profile(), "file_path", &setup.file_path)?;
+    let config = Config::from_table(table.clone())?;
+    if let Some(template) = setup.template {
+        create_file(&config, &config.file_path, template)?;
+    }
+    Config::save_table(&table)?;
+    Ok(config.file_path)
+}
+
+/// Writes a new TODO file from `template`, creating its directory if needed
+/// and encrypting it if it's an `.age` file
+pub fn create_file(config: &Config, path: &str, template: Template) -> Result<(TodoList, Option<Encryption>)> {
+    if let Some(parent) = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let encryption = Encryption::for_file(config, path)?;
+    let list = parse_todo_content(path, &template.content(&config.templates, Local::now().naive_local()));
+    encryption::write_todo_file(&list, &WriteOptions { backup: false }, encryption.as_ref())?;
+    Ok((list, encryption))
+}
+
+/// Asks whether to create the missing file at `path`, and how
+pub fn offer_to_create(input: &mut impl BufRead, output: &mut impl Write, path: &str) -> Result<Option<Template>> {
+    if !confirm(input, output, &format!("{} doesn't exist. Create it?", path), true)? {
+        return Ok(None);
+    }
+    Ok(Some(if confirm(input, output, "Start it with a heading for today?", false)? {
+        Template::Daily
+    } else {
+        Template::Empty
+    }))
+}
+
+/// Asks `question`, returning `default` for an empty answer
+pub fn prompt(input: &mut impl BufRead, output: &mut impl Write, question: &str, default: &str) -> Result<String> {
+    write!(output, "{} [{}] ", question, default)?;
+    output.flush()?;
+    let answer = read_answer(input)?;
+    Ok(if answer.is_empty() { default.to_string() } else { answer })
+}
+
+/// Asks a yes/no question until it gets an answer it understands
+pub fn confirm(input: &mut impl BufRead, output: &mut impl Write, question: &str, default: bool) -> Result<bool> {
+    loop {
+        write!(output, "{} [{}] ", question, if default { "Y/n" } else { "y/N" })?;
+        output.flush()?;
+        match read_answer(input)?.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => writeln!(output, "Please answer y or n.")?,
+        }
+    }
+}
+
+fn read_answer(input: &mut impl BufRead) -> Result<String> {
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        anyhow::bail!("Setup cancelled");
+    }
+    Ok(answer.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_ask_for_new_file() {
+        let mut output = Vec::new();
+        let setup = ask(&mut Cursor::new("/surely/not/here/TODO.md\nmaybe\nn\n"), &mut output, None).unwrap();
+        assert_eq!(setup, Setup { file_path: "/surely/not/here/TODO.md".to_string(), template: Some(Template::Empty) });
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[~/TODO.md]"));
+        assert!(output.contains("Please answer y or n."));
+    }
+
+    #[test]
+    fn test_ask_with_defaults() {
+        let existing = std::env::current_dir().unwrap().to_string_lossy().to_string();
+        let setup = ask(&mut Cursor::new("\n"), &mut Vec::new(), Some(&existing)).unwrap();
+        assert_eq!(setup, Setup { file_path: existing, template: None });
+
+        let setup = ask(&mut Cursor::new("/surely/not/here.md\n\n"), &mut Vec::new(), None).unwrap();
+        assert_eq!(setup.template, Some(Template::Starter));
+        assert!(ask(&mut Cursor::new(""), &mut Vec::new(), None).is_err());
+    }
+
+    #[test]
+    fn test_offer_to_create() {
+        let offer = |answers: &str| offer_to_create(&mut Cursor::new(answers.to_string()), &mut Vec::new(), "TODO.md").unwrap();
+        assert_eq!(offer("\n\n"), Some(Template::Empty));
+        assert_eq!(offer("y\ny\n"), Some(Template::Daily));
+        assert_eq!(offer("n\n"), None);
+    }
+
+    #[test]
+    fn test_daily_template() {
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(Template::Daily.content(&TemplatesConfig::default(), now), "# 2024-05-01\n");
+        assert_eq!(Template::Empty.content(&TemplatesConfig::default(), now), "");
+        let templates = TemplatesConfig { file: Some("# {date}\n\n## Focus\n- [ ] ".to_string()), ..Default::default() };
+        assert_eq!(Template::Daily.content(&templates, now), "# 2024-05-01\n\n## Focus\n- [ ] ");
+    }
+
+    #[test]
+    fn test_starter_template_parses() {
+        let list = parse_todo_content("TODO.md", STARTER_TEMPLATE);
+        let todos = list.items.iter().filter(|item| matches!(item, todo_core::ListItem::Todo { .. })).count();
+        let notes = list.items.iter().filter(|item| matches!(item, todo_core::ListItem::Note { .. })).count();
+        assert_eq!((todos, notes), (4, 1));
+    }
+}
@@ -0,0 +1,102 @@
+//! `[templates]` in the config: what new todos start as, the items added
+//! under a new heading and the content of new daily files. `{date}` and
+//! `{time}` are filled in when a template is used, and `{cursor}` marks where
+//! typing starts in a todo.
+
+use crate::config::TemplatesConfig;
+use chrono::NaiveDateTime;
+use todo_core::{parse_todo_content, ListItem};
+
+// `{{` and `}}` stand for literal braces. Returns the text and where
+// `{cursor}` was, if anywhere; without `now`, dates are left empty.
+fn fill(template: &str, now: Option<NaiveDateTime>) -> Result<(String, Option<usize>), String> {
+    let mut text = String::new();
+    let mut cursor = None;
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        text.push_str(&rest[..start]);
+        let brace = &rest[start..start + 1];
+        if rest[start + 1..].starts_with(brace) {
+            text.push_str(brace);
+            rest = &rest[start + 2..];
+            continue;
+        }
+        if brace == "}" {
+            return Err(format!("unmatched '}}' in template: {}", template));
+        }
+        let end = rest[start..].find('}').ok_or_else(|| format!("unclosed '{{' in template: {}", template))?;
+        let format = match &rest[start + 1..start + end] {
+            "date" => "%Y-%m-%d",
+            "time" => "%H:%M",
+            "cursor" => {
+                cursor = Some(text.len());
+                ""
+            }
+            name => return Err(format!("unknown template placeholder {{{}}}; use {{date}}, {{time}} or {{cursor}}", name)),
+        };
+        if let Some(now) = now.filter(|_| !format.is_empty()) {
+            text.push_str(&now.format(format).to_string());
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    Ok((text, cursor))
+}
+
+/// Checks that `template` only uses known placeholders
+pub fn check(template: &str) -> Result<(), String> {
+    fill(template, None).map(|_| ())
+}
+
+/// `template` filled in for `now`, and where the cursor goes: at `{cursor}`,
+/// or the end. An invalid template is used as written.
+pub fn expand(template: &str, now: NaiveDateTime) -> (String, usize) {
+    match fill(template, Some(now)) {
+        Ok((text, cursor)) => {
+            let cursor = cursor.unwrap_or(text.len());
+            (text, cursor)
+        }
+        Err(_) => (template.to_string(), template.len()),
+    }
+}
+
+/// The items a new heading starts with
+pub fn section_items(templates: &TemplatesConfig, now: NaiveDateTime) -> Vec<ListItem> {
+    match &templates.section {
+        Some(section) => parse_todo_content("", &expand(section, now).0).items,
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand("{cursor} ➕ {date}", now()), (" ➕ 2024-05-01".to_string(), 0));
+        assert_eq!(expand("Standup {time} {{x}}", now()), ("Standup 09:30 {x}".to_string(), 17));
+        assert_eq!(expand("{nope}", now()), ("{nope}".to_string(), 6));
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(check("- [ ] Review {date}\n- [ ] Plan").is_ok());
+        assert!(check("{when}").unwrap_err().contains("{when}"));
+        assert!(check("{date").unwrap_err().contains("unclosed"));
+    }
+
+    #[test]
+    fn test_section_items() {
+        let templates = TemplatesConfig { section: Some("- [ ] Review\n  - [ ] Inbox {date}".to_string()), ..Default::default() };
+        let items = section_items(&templates, now());
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].content(), "Inbox 2024-05-01");
+        assert!(section_items(&TemplatesConfig::default(), now()).is_empty());
+    }
+}
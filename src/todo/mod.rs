@@ -1,3 +0,0 @@
-pub mod models;
-pub mod parser;
-pub mod writer;
\ No newline at end of file
@@ -1,76 +0,0 @@
-#[derive(Debug, Clone)]
-pub enum ListItem {
-    Todo {
-        content: String,
-        completed: bool,
-        indent_level: usize,
-    },
-    Note {
-        content: String,
-        indent_level: usize,
-    },
-    Heading {
-        content: String,
-        level: usize, // 1 for #, 2 for ##, etc.
-    },
-}
-
-impl ListItem {
-    pub fn new_todo(content: String, completed: bool, indent_level: usize) -> Self {
-        Self::Todo {
-            content,
-            completed,
-            indent_level,
-        }
-    }
-
-    pub fn new_note(content: String, indent_level: usize) -> Self {
-        Self::Note {
-            content,
-            indent_level,
-        }
-    }
-
-    pub fn new_heading(content: String, level: usize) -> Self {
-        Self::Heading {
-            content,
-            level,
-        }
-    }
-
-    pub fn is_completed(&self) -> bool {
-        match self {
-            Self::Todo { completed, .. } => *completed,
-            Self::Note { .. } => false,
-            Self::Heading { .. } => false,
-        }
-    }
-
-}
-
-#[derive(Debug, Clone)]
-pub struct TodoList {
-    pub items: Vec<ListItem>,
-    pub file_path: String,
-}
-
-impl TodoList {
-    pub fn new(file_path: String) -> Self {
-        Self {
-            items: Vec::new(),
-            file_path,
-        }
-    }
-
-    pub fn add_item(&mut self, item: ListItem) {
-        self.items.push(item);
-    }
-
-    pub fn total_items(&self) -> usize {
-        self.items.iter().filter(|item| matches!(item, ListItem::Todo { .. })).count()
-    }
-
-    pub fn completed_items(&self) -> usize {
-        self.items.iter().filter(|item| item.is_completed()).count()
-    }
-}
\ No newline at end of file
@@ -0,0 +1,157 @@
+//! The Todoist side of `todo sync todoist`, over Todoist's REST API
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use todo_core::sync::{RemoteChange, RemoteTask};
+
+const API_URL: &str = "https://api.todoist.com/api/v1";
+
+#[derive(Deserialize)]
+struct Page<T> {
+    results: Vec<T>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Project {
+    id: String,
+    name: String,
+    #[serde(default, alias = "is_inbox_project")]
+    inbox_project: bool,
+}
+
+#[derive(Deserialize)]
+struct Task {
+    id: String,
+    content: String,
+    project_id: String,
+    updated_at: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NewTask<'a> {
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project_id: Option<&'a str>,
+}
+
+pub struct TodoistClient {
+    agent: ureq::Agent,
+    authorization: String,
+    projects: Vec<Project>,
+}
+
+impl TodoistClient {
+    pub fn connect(api_token: &str) -> Result<Self> {
+        let mut client = Self {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(30)).build(),
+            authorization: format!("Bearer {}", api_token),
+            projects: Vec::new(),
+        };
+        client.projects = client.fetch_all("projects")?;
+        Ok(client)
+    }
+
+    /// The open tasks, with their projects named by the heading they map to
+    pub fn fetch_tasks(&self) -> Result<Vec<RemoteTask>> {
+        let tasks: Vec<Task> = self.fetch_all("tasks")?;
+        Ok(tasks
+            .into_iter()
+            .map(|task| RemoteTask {
+                project: self
+                    .projects
+                    .iter()
+                    .find(|project| project.id == task.project_id && !project.inbox_project)
+                    .map(|project| project.name.clone()),
+                updated_at: task
+                    .updated_at
+                    .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                    .map(|time| time.with_timezone(&Utc)),
+                id: task.id,
+                content: task.content,
+            })
+            .collect())
+    }
+
+    /// Makes the change, returning the new task's id for a created task
+    pub fn apply(&mut self, change: &RemoteChange) -> Result<Option<String>> {
+        match change {
+            RemoteChange::Create { project, content } => {
+                let project_id = match project {
+                    Some(name) => Some(self.project_id(name)?),
+                    None => None,
+                };
+                let task: Task = self
+                    .post("tasks")
+                    .send_json(NewTask { content, project_id: project_id.as_deref() })
+                    .context("Failed to create Todoist task")?
+                    .into_json()?;
+                return Ok(Some(task.id));
+            }
+            RemoteChange::Update { task_id, content } => {
+                self.post(&format!("tasks/{}", task_id))
+                    .send_json(NewTask { content, project_id: None })
+                    .context("Failed to update Todoist task")?;
+            }
+            RemoteChange::Complete { task_id } => {
+                self.post(&format!("tasks/{}/close", task_id)).call().context("Failed to complete Todoist task")?;
+            }
+            RemoteChange::Reopen { task_id } => {
+                self.post(&format!("tasks/{}/reopen", task_id)).call().context("Failed to reopen Todoist task")?;
+            }
+            RemoteChange::Delete { task_id } => {
+                self.request("DELETE", &format!("tasks/{}", task_id))
+                    .call()
+                    .context("Failed to delete Todoist task")?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// The id of the project for the heading `name`, creating it if needed
+    fn project_id(&mut self, name: &str) -> Result<String> {
+        if let Some(project) = self.projects.iter().find(|project| project.name.eq_ignore_ascii_case(name)) {
+            return Ok(project.id.clone());
+        }
+        let project: Project = self
+            .post("projects")
+            .send_json(ureq::json!({ "name": name }))
+            .context("Failed to create Todoist project")?
+            .into_json()?;
+        let id = project.id.clone();
+        self.projects.push(project);
+        Ok(id)
+    }
+
+    fn fetch_all<T: for<'de> Deserialize<'de>>(&self, endpoint: &str) -> Result<Vec<T>> {
+        let mut results = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut request = self.request("GET", endpoint);
+            if let Some(cursor) = &cursor {
+                request = request.query("cursor", cursor);
+            }
+            let page: Page<T> = request
+                .call()
+                .with_context(|| format!("Failed to fetch Todoist {}", endpoint))?
+                .into_json()?;
+            results.extend(page.results);
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                return Ok(results);
+            }
+        }
+    }
+
+    fn post(&self, endpoint: &str) -> ureq::Request {
+        self.request("POST", endpoint)
+    }
+
+    fn request(&self, method: &str, endpoint: &str) -> ureq::Request {
+        self.agent
+            .request(method, &format!("{}/{}", API_URL, endpoint))
+            .set("Authorization", &self.authorization)
+    }
+}
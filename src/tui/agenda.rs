@@ -0,0 +1,162 @@
+use chrono::{Local, NaiveDate};
+use todo_core::{metadata, models::ListItem};
+
+// Tasks due within this many days from today show up under "This week"
+const WEEK_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AgendaGroup {
+    Overdue,
+    Today,
+    ThisWeek,
+}
+
+impl AgendaGroup {
+    pub fn title(&self) -> &'static str {
+        match self {
+            AgendaGroup::Overdue => "Overdue",
+            AgendaGroup::Today => "Today",
+            AgendaGroup::ThisWeek => "This week",
+        }
+    }
+}
+
+/// An open todo with a due date, pointing back at its item in the list
+#[derive(Debug, Clone, PartialEq)]
+pub struct AgendaEntry {
+    pub index: usize,
+    pub due: NaiveDate,
+    pub group: AgendaGroup,
+}
+
+pub struct AgendaState {
+    pub agenda_mode: bool,
+    pub entries: Vec<AgendaEntry>,
+    pub selected_index: usize,
+}
+
+impl AgendaState {
+    pub fn new() -> Self {
+        Self {
+            agenda_mode: false,
+            entries: Vec::new(),
+            selected_index: 0,
+        }
+    }
+
+    pub fn open(&mut self, items: &[ListItem], today: NaiveDate) {
+        self.agenda_mode = true;
+        self.selected_index = 0;
+        self.refresh(items, today);
+    }
+
+    pub fn close(&mut self) {
+        self.agenda_mode = false;
+    }
+
+    /// Rebuilds the entries after the list changed, keeping the selection in range
+    pub fn refresh(&mut self, items: &[ListItem], today: NaiveDate) {
+        self.entries = build_agenda(items, today);
+        self.selected_index = self.selected_index.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selected_index < self.entries.len().saturating_sub(1) {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&AgendaEntry> {
+        self.entries.get(self.selected_index)
+    }
+}
+
+impl Default for AgendaState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The local date that due dates are compared against
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Collects the unchecked todos that are overdue or due within the next week,
+/// sorted by due date and then by their position in the file.
+pub fn build_agenda(items: &[ListItem], today: NaiveDate) -> Vec<AgendaEntry> {
+    let mut entries: Vec<AgendaEntry> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches!(item, ListItem::Todo { .. }) && !item.is_completed())
+        .filter_map(|(index, item)| {
+            let due = metadata::due_date(item.content())?;
+            let days_left = (due - today).num_days();
+            let group = match days_left {
+                ..0 => AgendaGroup::Overdue,
+                0 => AgendaGroup::Today,
+                1..WEEK_DAYS => AgendaGroup::ThisWeek,
+                _ => return None,
+            };
+            Some(AgendaEntry { index, due, group })
+        })
+        .collect();
+
+    entries.sort_by_key(|entry| (entry.due, entry.index));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 10).unwrap()
+    }
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report 📅 2024-05-12".to_string(), false, 0),
+            ListItem::new_todo("Invoice 📅 2024-05-01".to_string(), false, 0),
+            ListItem::new_todo("Done already 📅 2024-05-01".to_string(), true, 0),
+            ListItem::new_todo("Standup 📅 2024-05-10".to_string(), false, 1),
+            ListItem::new_todo("Next month 📅 2024-06-10".to_string(), false, 0),
+            ListItem::new_todo("No date".to_string(), false, 0),
+            ListItem::new_note("Note 📅 2024-05-10".to_string(), 0),
+        ]
+    }
+
+    #[test]
+    fn test_build_agenda_groups_and_sorts_by_date() {
+        let entries = build_agenda(&create_test_items(), today());
+        let summary: Vec<(usize, AgendaGroup)> = entries.iter().map(|entry| (entry.index, entry.group)).collect();
+        assert_eq!(summary, vec![
+            (2, AgendaGroup::Overdue),
+            (4, AgendaGroup::Today),
+            (1, AgendaGroup::ThisWeek),
+        ]);
+    }
+
+    #[test]
+    fn test_agenda_selection_stays_in_range() {
+        let mut agenda = AgendaState::new();
+        agenda.open(&create_test_items(), today());
+        agenda.move_selection_up();
+        assert_eq!(agenda.selected_index, 0);
+
+        agenda.move_selection_down();
+        agenda.move_selection_down();
+        agenda.move_selection_down();
+        assert_eq!(agenda.selected_entry().unwrap().index, 1);
+
+        agenda.refresh(&create_test_items()[..2], today());
+        assert_eq!(agenda.selected_index, 0);
+    }
+}
@@ -1,88 +1,1519 @@
-use crate::todo::models::{TodoList, ListItem};
+use crate::config::{daily, Config, HooksConfig, LineNumbers, TemplatesConfig};
+use crate::encryption::{self, Encryption};
+use crate::hooks::{self, HookEvent};
+use crate::lock::InstanceLock;
+use crate::templates;
 use crate::tui::{
-    actions::{ItemActions, ActionPerformer},
-    edit::{EditState, Editable},
-    handlers::{KeyHandler, KeyEventHandler, NormalModeAction, HelpModeAction, SearchModeAction, EditModeAction},
-    navigation::{NavigationState, ItemCreator},
-    persistence::Persistence,
-    search::SearchState,
+    actions::ActionPerformer,
+    agenda::{today, AgendaEntry, AgendaState},
+    board::{BoardColumn, BoardLayout, BoardState, ColumnTarget},
+    browser::open_url,
+    calendar::CalendarState,
+    clipboard::{copy_to_system_clipboard, items_to_markdown, reindent, Register},
+    command::CommandLineState,
+    completion::CompletionState,
+    confirm::PendingDelete,
+    edit::{normalize_multiline, EditState, Editable},
+    editor::{self, ExternalEdit},
+    files::{build_file_entries, resolve_file_argument, FileEntry, FilePickerState, FileSession},
+    fold::FoldState,
+    handlers::{parse_command, Command, CommandModeAction, KeyHandler, KeyEventHandler, NormalModeAction, HelpModeAction, PromptAction, AgendaAction, BoardAction, CalendarAction, OutlineAction, SearchModeAction, EditModeAction, FilePickerAction, MovePickerAction, TrashPickerAction, TagPanelAction, ConfirmAction, ConflictAction},
+    help::{help_lines, HelpState},
+    footer::{self, FooterValues, HintContext},
+    chord::ChordState,
+    keymap::{Chord, KeyPress, Keymap},
+    macros::MacroState,
+    marks::MarkState,
+    pomodoro::PomodoroState,
+    history::SearchHistory,
+    move_picker::MovePickerState,
+    navigation::{ItemAnchor, NavigationState},
+    outline::{build_outline, current_entry, OutlineEntry, OutlineState},
+    persistence::{Autosave, Persistence},
+    plugins::{PluginEvent, PluginHost, PluginOutcome},
+    reminders::ReminderState,
+    replace::Substitution,
+    search::{CaseSensitivity, QueryKind, SearchOrigin, SearchState},
     state::AppState,
+    status::{StatusBar, StatusMessage},
+    theme::Theme,
+    tags::{self, TagPanelState},
+    trash::{TrashEntry, TrashState},
     undo::{UndoManager, UndoableApp},
+    watcher::FileWatcher,
+    zoom::ZoomState,
 };
 use anyhow::Result;
-use crossterm::event::KeyEvent;
+use chrono::{DateTime, Days, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use todo_core::{
+    actions::ItemActions,
+    activity::{self, Activity, LogEntry},
+    links::{find_links, find_references, resolve_reference},
+    merge::merge,
+    metadata,
+    models::{ListItem, TodoList, TodoState},
+    structure::ItemCreator,
+    writer::WriteOptions,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct App {
+    pub todo_list: TodoList,
+    pub should_quit: bool,
+    pub help_mode: bool,
+    status: StatusBar,
+    theme: Theme,
+    
+    // Component states
+    navigation: NavigationState,
+    edit_state: EditState,
+    search_state: SearchState,
+    search_history: SearchHistory,
+    undo_manager: UndoManager,
+    folds: FoldState,
+    zoom: ZoomState,
+    marks: MarkState,
+    macros: MacroState,
+    completion: CompletionState,
+    pomodoro: PomodoroState,
+    hide_completed: bool,
+    // Only open todos and their headings are shown (`F`)
+    pending_only: bool,
+    hide_blocked: bool,
+    /// `file_path` when it has date fields, for stepping between days
+    daily_file_path: Option<String>,
+    line_numbers: LineNumbers,
+    confirm_delete: bool,
+    auto_complete_parents: bool,
+    continue_lists: bool,
+    stamp_created_date: bool,
+    templates: TemplatesConfig,
+    stamp_done_date: bool,
+    activity_log: bool,
+    auto_archive_after_days: Option<u32>,
+    notifications: bool,
+    reminders: ReminderState,
+    hooks: HooksConfig,
+    plugins: PluginHost,
+    pending_delete: Option<PendingDelete>,
+    // The file as another program left it, when that conflicts with changes
+    // not yet written; nothing is saved until the user picks what to keep
+    pending_conflict: Option<TodoList>,
+    register: Register,
+    file_picker: FilePickerState,
+    agenda: AgendaState,
+    board: BoardState,
+    calendar: CalendarState,
+    outline: OutlineState,
+    move_picker: MovePickerState,
+    trash: TrashState,
+    tags: TagPanelState,
+    command_line: CommandLineState,
+    help: HelpState,
+    keymap: Keymap,
+    footer_format: String,
+    // Keys of a multi-key command, waiting for the rest
+    chord: ChordState,
+    // Digits typed before a motion, e.g. the 5 of `5j`
+    pending_count: Option<usize>,
+    // Set when the file should be opened in $EDITOR on the next loop iteration
+    external_edit: Option<ExternalEdit>,
+    // Cursor and undo history of files that aren't currently open, keyed by path
+    file_sessions: HashMap<String, FileSession>,
+    watcher: Option<FileWatcher>,
+    write_options: WriteOptions,
+    // The key for encrypted files, asked for before the TUI starts
+    encryption: Option<Encryption>,
+    autosave: Autosave,
+    // When this session last wrote the file
+    last_saved: Option<DateTime<Local>>,
+    // What was last written to (or read from) disk, so our own writes can be
+    // told apart from edits made by other programs
+    saved_items: Vec<ListItem>,
+    persistent_undo: bool,
+    // Set once the file is locked against other instances; None in tests
+    // and other callers that don't lock
+    instance_lock: Option<InstanceLock>,
+}
+
+impl App {
+    pub fn new(todo_list: TodoList, config: &Config, encryption: Option<Encryption>) -> Self {
+        // Live reload is best-effort; the app still works without a watcher
+        let watcher = FileWatcher::new(&todo_list.file_path).ok();
+        let file_entries = build_file_entries(&todo_list.file_path, &config.file_path, &config.files);
+        
+        // A broken theme shouldn't keep the list from opening; fall back and say why
+        let mut status = StatusBar::new();
+        let theme = Theme::from_config(&config.theme).unwrap_or_else(|e| {
+            status.error(e);
+            Theme::default()
+        });
+        let theme = theme.clone().with_tag_colors(&config.tag_colors).unwrap_or_else(|e| {
+            status.error(e);
+            theme
+        });
+        let (plugins, plugin_errors) = PluginHost::load(&config.plugins);
+        for error in plugin_errors {
+            status.error(error);
+        }
+        let (keymap, keymap_errors) = Keymap::load(&config.keys);
+        for error in keymap_errors {
+            status.error(error);
+        }
+        let footer_format = match &config.footer_format {
+            Some(format) => match crate::config::footer::check_format(format) {
+                Ok(()) => format.clone(),
+                Err(e) => {
+                    status.error(e);
+                    footer::DEFAULT_FORMAT.to_string()
+                }
+            },
+            None => footer::DEFAULT_FORMAT.to_string(),
+        };
+        
+        let saved_items = todo_list.items.clone();
+        
+        let mut app = Self {
+            todo_list,
+            should_quit: false,
+            help_mode: false,
+            status,
+            theme,
+            navigation: NavigationState::new(),
+            edit_state: EditState::new(),
+            search_state: SearchState::new(),
+            search_history: SearchHistory::load(SearchHistory::default_path()),
+            undo_manager: UndoManager::new(config.undo_levels),
+            folds: FoldState::new(),
+            zoom: ZoomState::new(),
+            marks: MarkState::new(),
+            macros: MacroState::new(),
+            completion: CompletionState::new(),
+            pomodoro: PomodoroState::new(config.pomodoro_minutes),
+            hide_completed: config.hide_completed,
+            pending_only: false,
+            hide_blocked: config.hide_blocked,
+            daily_file_path: daily::is_dated(&config.file_path).then(|| config.file_path.clone()),
+            line_numbers: config.line_numbers,
+            confirm_delete: config.confirm_delete,
+            auto_complete_parents: config.auto_complete_parents,
+            continue_lists: config.continue_lists,
+            stamp_created_date: config.stamp_created_date,
+            templates: config.templates.clone(),
+            stamp_done_date: config.stamp_done_date,
+            activity_log: config.activity_log,
+            auto_archive_after_days: config.auto_archive_after_days,
+            notifications: config.notifications,
+            reminders: ReminderState::new(),
+            hooks: config.hooks.clone(),
+            plugins,
+            pending_delete: None,
+            pending_conflict: None,
+            register: Register::new(),
+            file_picker: FilePickerState::new(file_entries),
+            agenda: AgendaState::new(),
+            board: BoardState::new(),
+            calendar: CalendarState::new(today()),
+            outline: OutlineState::new(),
+            move_picker: MovePickerState::new(),
+            trash: TrashState::new(),
+            tags: TagPanelState::new(),
+            command_line: CommandLineState::new(),
+            help: HelpState::new(help_lines(&keymap)),
+            keymap,
+            footer_format,
+            chord: ChordState::new(config.chord_timeout_ms),
+            pending_count: None,
+            external_edit: None,
+            file_sessions: HashMap::new(),
+            watcher,
+            write_options: WriteOptions {
+                backup: config.backup,
+            },
+            encryption,
+            autosave: Autosave::new(Duration::from_millis(config.autosave_interval_ms)),
+            last_saved: None,
+            saved_items,
+            persistent_undo: config.persistent_undo,
+            instance_lock: None,
+        };
+        app.load_undo_history();
+        app
+    }
+
+    // Undo history is kept next to the file only when asked for, and never
+    // for encrypted files, since it holds their contents
+    fn keeps_undo_history(&self) -> bool {
+        self.persistent_undo
+            && !self.is_read_only()
+            && !self.is_encrypted()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.encryption.as_ref().is_some_and(|encryption| encryption.applies_to(&self.todo_list.file_path))
+    }
+
+    fn load_undo_history(&mut self) {
+        if !self.keeps_undo_history() {
+            return;
+        }
+        if let Err(e) = self.undo_manager.load(&self.todo_list.file_path, &self.todo_list) {
+            self.status.error(format!("{:#}", e));
+        }
+    }
+
+    /// Writes the undo history next to the file for the next session, if
+    /// `persistent_undo` is on
+    pub fn save_undo_history(&mut self) -> Result<()> {
+        if !self.keeps_undo_history() {
+            return Ok(());
+        }
+        let current = self.current_state();
+        self.undo_manager.save(&self.todo_list.file_path, current)
+    }
+
+    /// Locks the file against other instances of the app. If one already has
+    /// it open, this one can't save until that one quits.
+    pub fn lock_file(&mut self) {
+        // Release the lock on the previous file first, in case it's the same one
+        self.instance_lock = None;
+        match InstanceLock::acquire(&self.todo_list.file_path) {
+            Ok(lock) => {
+                if let Some(warning) = lock.warning() {
+                    self.status.error(format!("{} is {}; changes are saved once it quits", self.todo_list.file_path, warning));
+                }
+                self.instance_lock = Some(lock);
+            }
+            // E.g. a read-only directory, where saving will fail anyway
+            Err(e) => self.status.error(format!("{:#}", e)),
+        }
+    }
+
+    /// Why the file can't be saved right now, if another instance has it open
+    pub fn read_only_warning(&self) -> Option<String> {
+        self.instance_lock.as_ref().and_then(InstanceLock::warning)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.instance_lock.as_ref().is_some_and(|lock| !lock.is_held())
+    }
+
+    // Takes the lock over if the instance holding it has quit
+    fn retry_lock(&mut self) {
+        if !self.is_read_only() {
+            return;
+        }
+        if let Ok(lock) = InstanceLock::acquire(&self.todo_list.file_path)
+            && lock.is_held()
+        {
+            self.instance_lock = Some(lock);
+            self.status.info("The other todo has quit; saving changes again");
+        }
+    }
+
+    // Record a change; it's written out once edits pause for the autosave interval
+    fn save_to_file(&mut self) -> Result<()> {
+        self.autosave.mark_dirty();
+        if self.autosave.is_due(Instant::now()) {
+            self.flush_changes()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Periodic housekeeping, called from the event loop between key presses
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.autosave(now);
+        self.send_reminders(now);
+        self.finish_pomodoro(now);
+        if self.chord.expire(now) {
+            self.pending_count = None;
+        }
+        self.status.tick(now);
+    }
+
+    // Logs a pomodoro that's run its course on its todo and says it's over.
+    // While an item is being edited, that waits until it's done.
+    fn finish_pomodoro(&mut self, now: Instant) {
+        if self.edit_state.edit_mode {
+            return;
+        }
+        let Some(finished) = self.pomodoro.take_finished(&self.todo_list.items, now) else {
+            return;
+        };
+        match finished.index {
+            Some(index) => {
+                self.save_current_state();
+                let item = &mut self.todo_list.items[index];
+                *item.content_mut() = metadata::add_time_spent(item.content(), finished.minutes);
+                self.refresh_visibility();
+                match self.save_to_file() {
+                    Ok(()) => self.status.info(format!(
+                        "Pomodoro done: logged {} on {}",
+                        metadata::format_minutes(finished.minutes),
+                        finished.content.lines().next().unwrap_or_default()
+                    )),
+                    Err(e) => self.status.error(format!("Failed to save file: {:#}", e)),
+                }
+            }
+            None => self.status.info("Pomodoro done; its todo is gone, so the time wasn't logged"),
+        }
+        finished.send();
+    }
+
+    // Notify about todos that are due, once a day each, while the list is open
+    fn send_reminders(&mut self, now: Instant) {
+        if !self.notifications {
+            return;
+        }
+        for reminder in self.reminders.take_new(&self.todo_list.items, today(), now) {
+            if let Err(e) = reminder.send() {
+                self.status.error(format!("{:#}", e));
+                break;
+            }
+        }
+    }
+
+    // Write pending changes if the autosave interval has passed
+    fn autosave(&mut self, now: Instant) {
+        // An item being added or edited isn't ready to be written yet
+        if self.edit_state.edit_mode || !self.autosave.is_due(now) {
+            return;
+        }
+        match self.flush_changes() {
+            // Waiting for a decision about changes on disk
+            Ok(()) if self.autosave.is_dirty() => {}
+            Ok(()) => self.status.info("Saved"),
+            Err(e) => self.status.error(format!("Failed to save file: {:#}", e)),
+        }
+    }
+
+    /// Writes pending changes right away, e.g. before quitting
+    pub fn flush_changes(&mut self) -> Result<()> {
+        if !self.autosave.is_dirty() || self.pending_conflict.is_some() {
+            return Ok(());
+        }
+        self.retry_lock();
+        if let Some(warning) = self.read_only_warning() {
+            anyhow::bail!("Not saved: {} is {}", self.todo_list.file_path, warning);
+        }
+        if let Some(disk) = self.changed_on_disk() {
+            self.pending_conflict = Some(disk);
+            return Ok(());
+        }
+        self.todo_list.save_to_file(&self.write_options, self.encryption.as_ref())?;
+        self.saved_items = self.todo_list.items.clone();
+        self.autosave.mark_saved();
+        self.last_saved = Some(Local::now());
+        self.run_hook(HookEvent::Save, None);
+        Ok(())
+    }
+
+    // The file's contents if something else changed it since we last read or
+    // wrote it. A file that's gone or can't be read is simply written.
+    fn changed_on_disk(&self) -> Option<TodoList> {
+        if !Path::new(&self.todo_list.file_path).exists() {
+            return None;
+        }
+        encryption::read_todo_file(&self.todo_list.file_path, self.encryption.as_ref())
+            .ok()
+            .filter(|disk| disk.items != self.saved_items)
+    }
+
+    pub fn has_conflict(&self) -> bool {
+        self.pending_conflict.is_some()
+    }
+
+    // Quitting writes pending changes first, unless they conflict with the
+    // file on disk, which needs a decision. A failed write is reported once
+    // the TUI has closed.
+    fn quit(&mut self) {
+        if self.flush_changes().is_ok() && self.pending_conflict.is_some() {
+            return;
+        }
+        self.should_quit = true;
+    }
+
+    fn resolve_conflict(&mut self, action: ConflictAction) -> Result<()> {
+        if action == ConflictAction::None {
+            return Ok(());
+        }
+        let Some(disk) = self.pending_conflict.take() else {
+            return Ok(());
+        };
+        let base = std::mem::replace(&mut self.saved_items, disk.items.clone());
+        match action {
+            ConflictAction::Reload => {
+                // Our version stays one undo away
+                self.autosave.mark_saved();
+                self.apply_external_change(disk);
+                self.status.info("Reloaded the file; u brings your changes back");
+            }
+            ConflictAction::Overwrite => {
+                self.flush_changes()?;
+                self.status.info("Saved over the changes on disk");
+            }
+            ConflictAction::Merge => {
+                let merged = merge(&base, &self.todo_list.items, &disk.items);
+                self.apply_external_change(TodoList { items: merged.items, ..disk });
+                self.flush_changes()?;
+                match merged.conflicts {
+                    0 => self.status.info("Merged the changes on disk"),
+                    conflicts => self.status.error(format!(
+                        "Merged the changes on disk; {} conflicting change(s) kept both versions",
+                        conflicts
+                    )),
+                }
+            }
+            ConflictAction::None => {}
+        }
+        Ok(())
+    }
+
+    pub fn total_items(&self) -> usize {
+        self.todo_list.total_items()
+    }
+
+    pub fn completed_items(&self) -> usize {
+        self.todo_list.completed_items()
+    }
+
+    // Delegate to navigation state
+    pub fn selected_index(&self) -> usize {
+        self.navigation.selected_index
+    }
+
+
+    pub fn scroll_offset(&self) -> usize {
+        self.navigation.scroll_offset
+    }
+
+    pub fn fit_scroll(&mut self, viewport_height: usize, selected_row: usize, total_rows: usize) {
+        self.navigation.set_viewport_height(viewport_height);
+        self.navigation.scroll_to_row(selected_row, total_rows);
+    }
+
+    pub fn selected_items(&self) -> &std::collections::HashSet<usize> {
+        &self.navigation.selected_items
+    }
+
+    // Delegate to edit state
+    pub fn visual_mode(&self) -> bool {
+        self.navigation.is_visual_mode()
+    }
+
+    pub fn pending_delete(&self) -> Option<&PendingDelete> {
+        self.pending_delete.as_ref()
+    }
+
+    pub fn agenda_mode(&self) -> bool {
+        self.agenda.agenda_mode
+    }
+
+    pub fn agenda_entries(&self) -> &[AgendaEntry] {
+        &self.agenda.entries
+    }
+
+    pub fn agenda_selected_index(&self) -> usize {
+        self.agenda.selected_index
+    }
+
+    pub fn calendar_mode(&self) -> bool {
+        self.calendar.calendar_mode
+    }
+
+    pub fn calendar_selected_date(&self) -> chrono::NaiveDate {
+        self.calendar.selected_date
+    }
+
+    /// The todos of the day opened with Enter, if one is
+    pub fn calendar_day_todos(&self) -> Option<&[usize]> {
+        self.calendar.day_todos.as_deref()
+    }
+
+    pub fn calendar_day_selected_index(&self) -> usize {
+        self.calendar.day_selected_index
+    }
+
+    pub fn board_mode(&self) -> bool {
+        self.board.board_mode
+    }
+
+    pub fn board_layout(&self) -> BoardLayout {
+        self.board.layout
+    }
+
+    pub fn board_columns(&self) -> &[BoardColumn] {
+        &self.board.columns
+    }
+
+    /// The selected column and card within it
+    pub fn board_selection(&self) -> (usize, usize) {
+        (self.board.selected_column, self.board.selected_card)
+    }
+
+    // Moves the selected card one column over: to the end of that heading's
+    // section, or into that state
+    fn move_board_card(&mut self, delta: isize) {
+        let (Some(index), Some(target)) = (self.board.selected_card(), self.board.neighbour_target(delta)) else {
+            return;
+        };
+        let moved = match target {
+            ColumnTarget::Heading(heading) => self.perform_move_to_section(&HashSet::from([index]), heading),
+            ColumnTarget::State(state) => self.perform_set_state(index, state).then_some(index),
+        };
+        self.board.refresh(&self.todo_list.items);
+        if let Some(new_index) = moved {
+            self.board.select_item(new_index);
+            self.navigation.selected_index = new_index;
+            self.navigation.update_scroll();
+        }
+    }
+
+    pub fn outline_visible(&self) -> bool {
+        self.outline.visible
+    }
+
+    pub fn outline_focused(&self) -> bool {
+        self.outline.focused
+    }
+
+    pub fn outline_entries(&self) -> Vec<OutlineEntry> {
+        build_outline(&self.todo_list.items)
+    }
+
+    /// Headings the move picker offers: those in the zoomed section while
+    /// zoomed, so nothing is moved out of it
+    pub fn move_picker_entries(&self) -> Vec<OutlineEntry> {
+        let mut entries = self.outline_entries();
+        if let Some(range) = self.zoom.range(&self.todo_list.items) {
+            entries.retain(|entry| range.contains(&entry.index));
+        }
+        entries
+    }
+
+    pub fn move_picker_mode(&self) -> bool {
+        self.move_picker.picker_mode
+    }
+
+    pub fn move_picker_selected_index(&self) -> usize {
+        self.move_picker.selected_index
+    }
+
+    pub fn trash_picker_mode(&self) -> bool {
+        self.trash.picker_mode
+    }
+
+    pub fn trash_picker_selected_index(&self) -> usize {
+        self.trash.selected_index
+    }
+
+    pub fn trash_entries(&self) -> impl Iterator<Item = &TrashEntry> {
+        self.trash.entries.iter()
+    }
+
+    /// The tag panel, while it's open
+    pub fn tag_panel(&self) -> Option<&TagPanelState> {
+        self.tags.open.then_some(&self.tags)
+    }
+
+    /// The tag the list is narrowed to
+    pub fn tag_filter(&self) -> Option<&str> {
+        self.tags.filter.as_deref()
+    }
+
+    fn open_tag_panel(&mut self) {
+        self.tags.open(&self.todo_list.items);
+        if self.tags.entries.is_empty() {
+            self.tags.close();
+            self.status.info("No #tags in this file");
+        }
+    }
+
+    // Shows only the items with the picked tag, from the first of them
+    fn filter_by_tag(&mut self) {
+        self.tags.filter = self.tags.selected_tag().map(str::to_string);
+        self.tags.close();
+        self.navigation.clear_selection();
+        if let Some(&first) = self.visible_indices().first() {
+            self.navigation.selected_index = first;
+            self.navigation.update_scroll();
+        }
+    }
+
+    // Renames the picked tag to `to` on every item, or without `to` removes
+    // it, as one undo step
+    fn retag(&mut self, to: Option<String>) -> Result<()> {
+        let Some(from) = self.tags.selected_tag().map(str::to_string) else {
+            return Ok(());
+        };
+        let to = match to {
+            Some(name) => Some(metadata::normalize_tag(&name).ok_or_else(|| anyhow::anyhow!("Not a tag name: {}", name))?.to_string()),
+            None => None,
+        };
+        if to.as_ref() == Some(&from) {
+            return Ok(());
+        }
+
+        let retagged: Vec<(usize, String)> = self
+            .todo_list
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                let content = match &to {
+                    Some(to) => metadata::rename_tag(item.content(), &from, to),
+                    None => metadata::remove_tag(item.content(), &from),
+                };
+                content.map(|content| (index, content))
+            })
+            .collect();
+        if retagged.is_empty() {
+            return Ok(());
+        }
+
+        self.save_current_state();
+        let changed = retagged.len();
+        for (index, content) in retagged {
+            *self.todo_list.items[index].content_mut() = content;
+        }
+        if self.tags.filter.as_ref() == Some(&from) {
+            self.tags.filter = to.clone();
+        }
+        self.tags.refresh(&self.todo_list.items);
+        if self.tags.entries.is_empty() {
+            self.tags.close();
+        }
+        self.search_state.clear_results();
+        self.save_to_file()?;
+        let items = if changed == 1 { "item" } else { "items" };
+        self.status.info(match to {
+            Some(to) => format!("Renamed #{} to #{} on {} {}", from, to, changed, items),
+            None => format!("Removed #{} from {} {}", from, changed, items),
+        });
+        Ok(())
+    }
+
+    // Puts the picked deletion back below the current item, like a paste
+    fn restore_from_trash(&mut self) -> Result<()> {
+        let Some(entry) = self.trash.take_selected() else {
+            return Ok(());
+        };
+        self.save_current_state();
+        let (position, base_indent) = self.paste_position();
+        let count = entry.items.len();
+        self.todo_list.items.splice(position..position, reindent(&entry.items, base_indent));
+
+        self.navigation.selected_index = position;
+        self.navigation.clear_selection();
+        self.search_state.clear_results();
+        self.navigation.update_scroll();
+        self.status.info(format!("Restored {} item(s)", count));
+        self.save_to_file()
+    }
+
+    // Moves the bulk selection, or the current item, starting on its own section
+    fn open_move_picker(&mut self) -> Result<()> {
+        let entries = self.move_picker_entries();
+        if entries.is_empty() {
+            anyhow::bail!("No headings to move to");
+        }
+        let targets = if self.navigation.selected_items.is_empty() {
+            HashSet::from([self.navigation.selected_index])
+        } else {
+            self.selection_in_zoom()
+        };
+        let start = current_entry(&entries, self.navigation.selected_index).unwrap_or(0);
+        self.move_picker.open(targets, start);
+        Ok(())
+    }
+
+    fn move_items_to_picked_heading(&mut self) -> Result<()> {
+        let entries = self.move_picker_entries();
+        let targets = std::mem::take(&mut self.move_picker.targets);
+        let picked = entries.get(self.move_picker.selected_index).map(|entry| entry.index);
+        self.move_picker.close();
+        let Some(heading_index) = picked else {
+            return Ok(());
+        };
+
+        let heading = self.todo_list.items[heading_index].content().to_string();
+        match self.perform_move_to_section(&targets, heading_index) {
+            Some(new_index) => {
+                self.navigation.selected_index = new_index;
+                self.navigation.clear_selection();
+                self.navigation.update_scroll();
+                self.status.info(format!("Moved to {}", heading));
+                Ok(())
+            }
+            None => anyhow::bail!("Nothing to move"),
+        }
+    }
+
+    // The bulk selection, less any items selected before zooming in that are
+    // outside the zoomed section
+    fn selection_in_zoom(&self) -> HashSet<usize> {
+        self.navigation
+            .selected_items
+            .iter()
+            .copied()
+            .filter(|&index| self.zoom.contains(&self.todo_list.items, index))
+            .collect()
+    }
+
+    // Moves the list cursor to the next or previous heading in the outline
+    fn move_outline_selection(&mut self, down: bool) {
+        let entries = self.outline_entries();
+        let target = match current_entry(&entries, self.navigation.selected_index) {
+            Some(current) if down => Some(current + 1),
+            Some(current) => current.checked_sub(1),
+            // Above the first heading, there's only one way to go
+            None if down => Some(0),
+            None => None,
+        };
+        if let Some(entry) = target.and_then(|target| entries.get(target)) {
+            self.navigation.selected_index = entry.index;
+            self.navigation.clear_selection();
+            self.navigation.update_scroll();
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn status_message(&self) -> Option<&StatusMessage> {
+        self.status.current()
+    }
+
+    pub fn edit_mode(&self) -> bool {
+        self.edit_state.edit_mode
+    }
+
+    /// The edit buffer as wrapped rows, with the cursor drawn in
+    pub fn edit_display_rows(&self) -> Vec<String> {
+        self.edit_state.display_rows()
+    }
+
+    pub fn set_edit_wrap_width(&mut self, width: usize) {
+        self.edit_state.wrap_width = width;
+    }
+
+    // Delegate to search state
+    pub fn command_mode(&self) -> bool {
+        self.command_line.command_mode
+    }
+
+    pub fn command_input(&self) -> &str {
+        &self.command_line.input
+    }
+
+    /// The substitution being typed at the `:` prompt, for previewing its effect
+    pub fn substitution_preview(&self) -> Option<Substitution> {
+        if !self.command_line.command_mode {
+            return None;
+        }
+        Substitution::parse(&self.command_line.input)?.ok()
+    }
+
+    /// The items a substitution applies to
+    pub fn substitution_range(&self, substitution: &Substitution) -> std::ops::Range<usize> {
+        if substitution.whole_file {
+            0..self.todo_list.items.len()
+        } else {
+            let index = self.navigation.selected_index.min(self.todo_list.items.len());
+            index..(index + 1).min(self.todo_list.items.len())
+        }
+    }
+
+    /// The help window, fitted to `height` lines
+    pub fn help_view(&mut self, height: usize) -> &HelpState {
+        self.help.set_viewport_height(height);
+        &self.help
+    }
+
+    pub fn search_mode(&self) -> bool {
+        self.search_state.search_mode
+    }
+
+    pub fn search_query(&self) -> &str {
+        &self.search_state.search_query
+    }
+
+    pub fn search_matches(&self) -> &[usize] {
+        &self.search_state.search_matches
+    }
+
+    pub fn search_kind(&self) -> QueryKind {
+        self.search_state.kind
+    }
+
+    fn file_name(&self) -> String {
+        Path::new(&self.todo_list.file_path)
+            .file_name()
+            .map_or_else(|| self.todo_list.file_path.clone(), |name| name.to_string_lossy().to_string())
+    }
+
+    // Headings the cursor is under, outermost first
+    fn current_headings(&self) -> Vec<String> {
+        ItemCreator::heading_trail(&self.todo_list.items, self.navigation.selected_index)
+            .into_iter()
+            .map(|heading| self.todo_list.items[heading].content().to_string())
+            .collect()
+    }
+
+    /// The file name followed by the headings the cursor is under, for the header
+    pub fn breadcrumb(&self) -> Vec<String> {
+        let mut breadcrumb = vec![self.file_name()];
+        breadcrumb.extend(self.current_headings());
+        breadcrumb
+    }
+
+    /// Whether there are changes not yet written to the file
+    pub fn is_modified(&self) -> bool {
+        self.autosave.is_dirty()
+    }
+
+    pub fn last_saved(&self) -> Option<DateTime<Local>> {
+        self.last_saved
+    }
+
+    /// The footer's summary of the list, as laid out by `footer_format`
+    pub fn footer_summary(&self) -> String {
+        let values = FooterValues {
+            total: self.total_items(),
+            done: self.completed_items(),
+            file: self.file_name(),
+            section: self.current_headings().pop(),
+            selection: self.navigation.selected_items.len(),
+        };
+        footer::expand(&self.footer_format, &values)
+    }
+
+    /// Hints like `/: search` for what can be done now, with the keys as bound
+    pub fn footer_hints(&self) -> Vec<String> {
+        let context = if !self.navigation.selected_items.is_empty() {
+            HintContext::Selection
+        } else if self.search_state.current_match_index.is_some() && !self.search_state.search_matches.is_empty() {
+            HintContext::SearchResults
+        } else if self.zoom.is_zoomed() {
+            HintContext::Zoomed
+        } else {
+            HintContext::List
+        };
+        // Hidden todos are easy to forget about
+        let show_completed = (NormalModeAction::ToggleHideCompleted, "show completed");
+        let show_blocked = (NormalModeAction::ToggleHideBlocked, "show blocked");
+        let show_all = (NormalModeAction::TogglePendingOnly, "show all");
+        footer::hints(context)
+            .iter()
+            .chain(self.hide_completed.then_some(&show_completed))
+            .chain(self.hide_blocked.then_some(&show_blocked))
+            .chain(self.pending_only.then_some(&show_all))
+            .filter_map(|(action, hint)| Some(format!("{}: {}", self.keymap.keys_for(action).first()?, hint)))
+            .collect()
+    }
+
+    /// Time left in the running pomodoro, and the todo it's on
+    pub fn pomodoro(&self) -> Option<(Duration, &str)> {
+        self.pomodoro.remaining(Instant::now())
+    }
+
+    /// The tags offered for the word being typed, and the picked one
+    pub fn tag_completion(&self) -> Option<(&[String], usize)> {
+        self.completion.is_open().then(|| (self.completion.candidates(), self.completion.selected()))
+    }
+
+    /// Letters of the marks on the item at `index`
+    pub fn marks_on(&self, index: usize) -> Vec<char> {
+        self.marks.marks_on(index)
+    }
+
+    pub fn line_numbers(&self) -> LineNumbers {
+        self.line_numbers
+    }
+
+    /// The count typed so far for the next motion
+    pub fn pending_count(&self) -> Option<usize> {
+        self.pending_count
+    }
+
+    /// The keys of a multi-key command typed so far, e.g. `g`
+    pub fn pending_keys(&self) -> Option<String> {
+        self.chord.is_pending().then(|| self.chord.label())
+    }
+
+    pub fn search_active(&self) -> bool {
+        self.search_state.is_active()
+    }
+
+    /// Byte ranges to highlight in `content` while a search is active
+    pub fn search_highlights(&self, content: &str) -> Vec<std::ops::Range<usize>> {
+        if !self.search_state.is_active() {
+            return Vec::new();
+        }
+        self.search_state.match_ranges(content)
+    }
+
+    pub fn search_case_sensitivity(&self) -> CaseSensitivity {
+        self.search_state.case_sensitivity
+    }
+
+    pub fn search_in_section(&self) -> bool {
+        self.search_state.section.is_some()
+    }
+
+    pub fn search_error(&self) -> Option<&str> {
+        self.search_state.error.as_deref()
+    }
+
+    pub fn current_match_index(&self) -> Option<usize> {
+        self.search_state.current_match_index
+    }
+
+    // Items that are shown, i.e. not inside a fold or filtered out
+    pub fn visible_indices(&self) -> Vec<usize> {
+        let mut indices = self.folds.visible_indices(&self.todo_list.items);
+        if let Some(range) = self.zoom.range(&self.todo_list.items) {
+            indices.retain(|i| range.contains(i));
+        }
+        if self.hide_completed {
+            indices.retain(|&i| !self.todo_list.items[i].is_completed());
+        }
+        if self.hide_blocked {
+            let blocked = self.blocked_todos();
+            indices.retain(|i| !blocked.contains(i));
+        }
+        // An item being written stays in view until it's done
+        let editing = self.edit_state.edit_mode.then_some(self.navigation.selected_index);
+        if self.pending_only {
+            let shown = self.pending_indices();
+            indices.retain(|i| shown.contains(i) || Some(*i) == editing);
+        }
+        if let Some(tag) = &self.tags.filter {
+            let shown = tags::filter_indices(&self.todo_list.items, tag);
+            indices.retain(|i| shown.contains(i) || Some(*i) == editing);
+        }
+        indices
+    }
+
+    /// Headings leading to the zoomed section, empty when not zoomed
+    pub fn zoom_breadcrumb(&self) -> Vec<String> {
+        self.zoom.breadcrumb(&self.todo_list.items)
+    }
+
+    fn on_heading(&self) -> bool {
+        matches!(self.todo_list.items.get(self.navigation.selected_index), Some(ListItem::Heading { .. }))
+    }
+
+    // Changes the level of the heading under the cursor and its sub-headings
+    fn shift_section(&mut self, delta: isize) -> Result<()> {
+        if !self.on_heading() {
+            anyhow::bail!("Not on a heading");
+        }
+        self.perform_shift_heading(self.navigation.selected_index, delta, true);
+        Ok(())
+    }
+
+    fn toggle_zoom(&mut self) -> Result<()> {
+        if self.zoom.is_zoomed() {
+            self.zoom.unzoom();
+        } else if !self.zoom.zoom(&self.todo_list.items, self.navigation.selected_index) {
+            anyhow::bail!("Not in a section");
+        }
+        Ok(())
+    }
+
+    pub fn hide_completed(&self) -> bool {
+        self.hide_completed
+    }
+
+    pub fn hide_blocked(&self) -> bool {
+        self.hide_blocked
+    }
+
+    pub fn pending_only(&self) -> bool {
+        self.pending_only
+    }
+
+    /// The register a macro is being recorded into
+    pub fn recording_macro(&self) -> Option<char> {
+        self.macros.recording()
+    }
+
+    // Open todos and the headings they're under
+    fn pending_indices(&self) -> HashSet<usize> {
+        let items = &self.todo_list.items;
+        let open = (0..items.len()).filter(|&i| matches!(items[i], ListItem::Todo { .. }) && !items[i].is_completed());
+        ItemCreator::with_headings(items, open)
+    }
+
+    /// Open todos waiting on another open todo
+    pub fn blocked_todos(&self) -> HashSet<usize> {
+        ItemCreator::blocked_todos(&self.todo_list.items)
+    }
+
+    pub fn hidden_completed_count(&self) -> usize {
+        if self.hide_completed {
+            self.todo_list.completed_items()
+        } else {
+            0
+        }
+    }
+
+    pub fn folded_child_count(&self, index: usize) -> Option<usize> {
+        self.folds.hidden_count(&self.todo_list.items, index)
+    }
+
+    // Keep folds attached to the right items after the list changed, and make
+    // sure the cursor never ends up on a hidden item
+    fn refresh_visibility(&mut self) {
+        self.folds.refresh(&self.todo_list.items);
+        self.zoom.refresh(&self.todo_list.items);
+        self.marks.refresh(&self.todo_list.items);
+        self.pomodoro.refresh(&self.todo_list.items);
+        if self.folds.is_hidden(&self.todo_list.items, self.navigation.selected_index) {
+            self.folds.reveal(&self.todo_list.items, self.navigation.selected_index);
+        }
+        
+        // Filtered-out items can't be revealed, so move to the next shown item instead
+        let visible_indices = self.visible_indices();
+        let selected = self.navigation.selected_index;
+        if !visible_indices.is_empty() && !visible_indices.contains(&selected) {
+            self.navigation.selected_index = visible_indices
+                .iter()
+                .copied()
+                .find(|&i| i > selected)
+                .or_else(|| visible_indices.last().copied())
+                .unwrap_or(selected);
+            self.navigation.update_scroll();
+        }
+    }
+
+    // Delegate to file picker state
+    pub fn file_picker_mode(&self) -> bool {
+        self.file_picker.picker_mode
+    }
+
+    pub fn file_entries(&self) -> &[FileEntry] {
+        &self.file_picker.entries
+    }
+
+    pub fn file_picker_selected_index(&self) -> usize {
+        self.file_picker.selected_index
+    }
+
+    pub fn file_picker_error(&self) -> Option<&str> {
+        self.file_picker.error.as_deref()
+    }
+
+    fn open_selected_file(&mut self) {
+        let Some(entry) = self.file_picker.selected_entry().cloned() else {
+            return;
+        };
+        
+        if entry.path != self.todo_list.file_path
+            && let Err(e) = self.switch_to_file(&entry.path)
+        {
+            // Keep the picker open so the error is visible
+            self.file_picker.error = Some(e.to_string());
+            return;
+        }
+        self.file_picker.close();
+    }
 
-pub struct App {
-    pub todo_list: TodoList,
-    pub should_quit: bool,
-    pub help_mode: bool,
-    
-    // Component states
-    navigation: NavigationState,
-    edit_state: EditState,
-    search_state: SearchState,
-    undo_manager: UndoManager,
-}
+    // Open another file, remembering the cursor and undo history of the current one
+    fn switch_to_file(&mut self, file_path: &str) -> Result<()> {
+        let new_list = encryption::read_todo_file(file_path, self.encryption.as_ref())?;
+        self.flush_changes()?;
+        if self.pending_conflict.is_some() {
+            anyhow::bail!("The file changed on disk; decide what to keep first");
+        }
+        self.save_undo_history()?;
+        self.saved_items = new_list.items.clone();
+        
+        let previous_session = FileSession {
+            selected_index: self.navigation.selected_index,
+            scroll_offset: self.navigation.scroll_offset,
+            undo_manager: self.undo_manager.take(),
+        };
+        self.file_sessions.insert(self.todo_list.file_path.clone(), previous_session);
+        
+        let session = self.file_sessions.remove(file_path);
+        self.todo_list = new_list;
+        self.watcher = FileWatcher::new(file_path).ok();
+        if self.instance_lock.is_some() {
+            self.lock_file();
+        }
+        
+        self.navigation = NavigationState::new();
+        self.folds.unfold_all();
+        self.zoom.unzoom();
+        self.marks.clear();
+        self.tags.close();
+        self.tags.filter = None;
+        // Its todo is in the other file
+        self.pomodoro.stop();
+        if let Some(session) = session {
+            self.navigation.selected_index = session.selected_index.min(self.todo_list.items.len().saturating_sub(1));
+            self.navigation.scroll_offset = session.scroll_offset;
+            self.undo_manager = session.undo_manager;
+        } else {
+            self.load_undo_history();
+        }
+        self.navigation.update_scroll();
+        self.search_state.clear_results();
+        Ok(())
+    }
 
-impl App {
-    pub fn new(todo_list: TodoList) -> Self {
-        Self {
-            todo_list,
-            should_quit: false,
-            help_mode: false,
-            navigation: NavigationState::new(),
-            edit_state: EditState::new(),
-            search_state: SearchState::new(),
-            undo_manager: UndoManager::new(),
+    // Reload the list if the file was modified by another program
+    pub fn check_for_external_changes(&mut self) -> Result<()> {
+        let changed = self.watcher.as_ref().is_some_and(|watcher| watcher.has_changed());
+        if !changed {
+            return Ok(());
+        }
+        
+        // The file may be missing or half-written; we'll get another event once it settles
+        let Ok(new_list) = encryption::read_todo_file(&self.todo_list.file_path, self.encryption.as_ref()) else {
+            return Ok(());
+        };
+        
+        // Our own saves also trigger events, but leave the contents unchanged
+        if new_list.items == self.todo_list.items || new_list.items == self.saved_items {
+            return Ok(());
         }
+        
+        // Changes we haven't written yet would be lost, so ask what to keep
+        if self.autosave.is_dirty() {
+            self.pending_conflict = Some(new_list);
+            return Ok(());
+        }
+        self.saved_items = new_list.items.clone();
+        self.autosave.mark_saved();
+        self.apply_external_change(new_list);
+        self.status.info("Reloaded changes from disk");
+        Ok(())
     }
 
-    pub fn total_items(&self) -> usize {
-        self.todo_list.total_items()
+    fn open_help(&mut self, line: usize) {
+        self.help_mode = true;
+        self.help.open(line);
     }
 
-    pub fn completed_items(&self) -> usize {
-        self.todo_list.completed_items()
+    fn next_help_match(&mut self, forward: bool) {
+        if !self.help.query.is_empty() && !self.help.next_match(forward) {
+            self.status.info(format!("Nothing in the help matches '{}'", self.help.query));
+        }
     }
 
-    // Delegate to navigation state
-    pub fn selected_index(&self) -> usize {
-        self.navigation.selected_index
+    fn execute_command(&mut self, input: &str) -> Result<()> {
+        if input.trim().is_empty() {
+            return Ok(());
+        }
+        let command = match parse_command(input) {
+            Ok(command) => command,
+            Err(message) => {
+                // Built-in commands can't be replaced by plugins
+                let (name, argument) = input.trim().split_once(char::is_whitespace).unwrap_or((input.trim(), ""));
+                if !self.plugins.has_command(name) {
+                    anyhow::bail!(message);
+                }
+                let outcome = self.plugins.run_command(name, argument.trim(), &self.todo_list.items, self.navigation.selected_index);
+                return self.apply_plugin_outcome(outcome);
+            }
+        };
+        match command {
+            Command::Write => {
+                // Written even without changes, like :w in vim
+                self.autosave.mark_dirty();
+                self.flush_changes()?;
+                self.status.info("Saved");
+            }
+            Command::Quit => self.quit(),
+            Command::Open(argument) => {
+                let path = resolve_file_argument(&argument, &self.file_picker.entries);
+                if path != self.todo_list.file_path {
+                    self.switch_to_file(&path)?;
+                }
+            }
+            Command::Sort(key) => {
+                self.save_current_state();
+                let index = self.navigation.selected_index;
+                let anchor = ItemAnchor::new(&self.todo_list.items, index);
+                if ItemActions::sort_section(&mut self.todo_list.items, index, key) {
+                    self.follow_anchor(anchor);
+                    self.search_state.clear_results();
+                    self.save_to_file()?;
+                } else {
+                    self.status.info("Section is already sorted");
+                }
+            }
+            Command::Archive { older_than_days } => {
+                let count = self.archive_done_todos(older_than_days)?;
+                if count == 0 {
+                    self.status.info("Nothing to archive");
+                    return Ok(());
+                }
+                self.status.info(format!("Archived {} {}", count, if count == 1 { "todo" } else { "todos" }));
+            }
+            Command::Rollover => {
+                self.save_current_state();
+                let anchor = ItemAnchor::new(&self.todo_list.items, self.navigation.selected_index);
+                let section = templates::section_items(&self.templates, Local::now().naive_local());
+                let count = ItemActions::rollover(&mut self.todo_list.items, today(), &section);
+                if count == 0 {
+                    self.status.info("Nothing to roll over");
+                    return Ok(());
+                }
+                self.follow_anchor(anchor);
+                self.search_state.clear_results();
+                self.save_to_file()?;
+                self.status.info(format!("Moved {} {} to today", count, if count == 1 { "todo" } else { "todos" }));
+            }
+            Command::Section(name) => {
+                self.save_current_state();
+                let section = templates::section_items(&self.templates, Local::now().naive_local());
+                let heading = ItemActions::add_section(&mut self.todo_list.items, self.navigation.selected_index, &name, section);
+                self.search_state.clear_results();
+                self.save_to_file()?;
+                self.jump_to_item(heading);
+            }
+            Command::Substitute(substitution) => {
+                let range = self.substitution_range(&substitution);
+                
+                // All replacements are one change, so a single undo reverts them
+                self.save_current_state();
+                let mut changed = 0;
+                for item in self.todo_list.items.iter_mut().take(range.end).skip(range.start) {
+                    if let Some(content) = substitution.apply(item.content()) {
+                        *item.content_mut() = content;
+                        changed += 1;
+                    }
+                }
+                if changed == 0 {
+                    anyhow::bail!("Pattern not found: {}", substitution.pattern);
+                }
+                self.search_state.clear_results();
+                self.save_to_file()?;
+                self.status.info(format!("Replaced in {} {}", changed, if changed == 1 { "item" } else { "items" }));
+            }
+            Command::Tag { tag, remove } => {
+                let changed = if remove {
+                    self.edit_target_todos(|content| metadata::remove_tag(content, &tag))?
+                } else {
+                    self.edit_target_todos(|content| metadata::add_tag(content, &tag))?
+                };
+                let verb = if remove { "Untagged" } else { "Tagged" };
+                self.status.info(format!("{} {} {}", verb, changed, if changed == 1 { "todo" } else { "todos" }));
+            }
+            Command::Due(date) => {
+                let changed = self.edit_target_todos(|content| {
+                    Some(metadata::set_due_date(content, date)).filter(|updated| updated != content)
+                })?;
+                let verb = if date.is_some() { "Set the due date of" } else { "Cleared the due date of" };
+                self.status.info(format!("{} {} {}", verb, changed, if changed == 1 { "todo" } else { "todos" }));
+            }
+            Command::Help(topic) => {
+                let line = match topic {
+                    Some(topic) => self.help.topic_line(&topic).ok_or_else(|| anyhow::anyhow!("No help for '{}'", topic))?,
+                    None => 0,
+                };
+                self.open_help(line);
+            }
+            Command::Search { section } => self.start_search(section)?,
+            Command::SetLineNumbers(line_numbers) => self.line_numbers = line_numbers,
+            Command::Undelete => {
+                if self.trash.entries.is_empty() {
+                    self.status.info("Nothing deleted this session");
+                } else {
+                    self.trash.open();
+                }
+            }
+        }
+        Ok(())
     }
 
+    // Keeps the cursor on the same item after the list was rearranged
+    fn follow_anchor(&mut self, anchor: Option<ItemAnchor>) {
+        let last_index = self.todo_list.items.len().saturating_sub(1);
+        self.navigation.selected_index = anchor
+            .and_then(|mut anchor| anchor.resolve(&self.todo_list.items))
+            .unwrap_or(self.navigation.selected_index.min(last_index));
+        self.navigation.clear_selection();
+        self.navigation.update_scroll();
+    }
 
-    pub fn selected_items(&self) -> &std::collections::HashSet<usize> {
-        &self.navigation.selected_items
+    // Opens the first link in the current item
+    fn open_link(&mut self) -> Result<()> {
+        let Some(item) = self.todo_list.items.get(self.navigation.selected_index) else {
+            return Ok(());
+        };
+        let Some(link) = find_links(item.content()).into_iter().next() else {
+            self.status.info("No link in this item");
+            return Ok(());
+        };
+        open_url(&link.url)?;
+        self.status.info(format!("Opened {}", link.url));
+        Ok(())
     }
 
-    // Delegate to edit state
-    pub fn edit_mode(&self) -> bool {
-        self.edit_state.edit_mode
+    // Opens the nearest earlier (or later) daily file that exists
+    fn open_neighbouring_day(&mut self, forward: bool) -> Result<()> {
+        let Some(path) = &self.daily_file_path else {
+            anyhow::bail!("file_path has no date fields, like ~/notes/%Y-%m-%d.md");
+        };
+        let Some(date) = daily::date_of(path, &self.todo_list.file_path) else {
+            anyhow::bail!("{} isn't one of the daily files", self.todo_list.file_path);
+        };
+        match daily::neighbour(path, date, forward) {
+            Some((_, file)) => self.switch_to_file(&file),
+            None if forward => anyhow::bail!("No daily file after {}", date),
+            None => anyhow::bail!("No daily file before {}", date),
+        }
     }
 
-    pub fn edit_buffer(&self) -> &str {
-        &self.edit_state.edit_buffer
+    // Jumps to the item the first `[[text]]` or `(#id)` in this one refers to
+    fn follow_reference(&mut self) -> Result<()> {
+        let index = self.navigation.selected_index;
+        let Some(item) = self.todo_list.items.get(index) else {
+            return Ok(());
+        };
+        let Some(reference) = find_references(item.content()).into_iter().next() else {
+            self.status.info("No reference in this item");
+            return Ok(());
+        };
+        match resolve_reference(&self.todo_list.items, &reference.target, index) {
+            Some(target) => self.jump_to_item(target),
+            None => anyhow::bail!("Nothing matches {}", &item.content()[reference.range]),
+        }
+        Ok(())
     }
 
-    pub fn edit_cursor_position(&self) -> usize {
-        self.edit_state.edit_cursor_position
+    // Moves the cursor to `index`, showing it if it's folded away or outside the zoom
+    fn jump_to_item(&mut self, index: usize) {
+        if !self.zoom.contains(&self.todo_list.items, index) {
+            self.zoom.unzoom();
+        }
+        self.navigation.selected_index = index;
+        self.refresh_visibility();
+        self.navigation.update_scroll();
     }
 
-    // Delegate to search state
-    pub fn search_mode(&self) -> bool {
-        self.search_state.search_mode
+    // The editor reads the file from disk, so it has to be up to date first
+    fn request_external_edit(&mut self) -> Result<()> {
+        if self.is_encrypted() {
+            anyhow::bail!("Encrypted files can't be opened in an external editor");
+        }
+        self.flush_changes()?;
+        self.external_edit = Some(ExternalEdit {
+            file_path: self.todo_list.file_path.clone(),
+            line: editor::line_number(&self.todo_list.items, self.navigation.selected_index),
+        });
+        Ok(())
     }
 
-    pub fn search_query(&self) -> &str {
-        &self.search_state.search_query
+    /// Hands a pending `$EDITOR` request to the event loop, which suspends the TUI to run it
+    pub fn take_external_edit(&mut self) -> Option<ExternalEdit> {
+        self.external_edit.take()
     }
 
-    pub fn search_matches(&self) -> &[usize] {
-        &self.search_state.search_matches
+    /// Picks up whatever the external editor changed, once it has exited
+    pub fn finish_external_edit(&mut self, result: Result<()>) {
+        if let Err(e) = result {
+            self.status.error(format!("{:#}", e));
+            return;
+        }
+        // Quitting the editor without saving a new file leaves nothing to load
+        if !Path::new(&self.todo_list.file_path).exists() {
+            return;
+        }
+        match encryption::read_todo_file(&self.todo_list.file_path, self.encryption.as_ref()) {
+            Ok(new_list) if new_list.items != self.todo_list.items => {
+                self.saved_items = new_list.items.clone();
+                self.apply_external_change(new_list);
+                self.status.info("Reloaded changes from editor");
+            }
+            Ok(_) => {}
+            Err(e) => self.status.error(format!("Failed to reload file: {:#}", e)),
+        }
     }
 
-    pub fn current_match_index(&self) -> Option<usize> {
-        self.search_state.current_match_index
+    fn apply_external_change(&mut self, new_list: TodoList) {
+        let selected = self.navigation.selected_index;
+        
+        // A todo being added hasn't been saved yet, so keep it and anchor on the item above it
+        let placeholder = if self.edit_state.edit_mode && self.edit_state.adding_new_todo && selected < self.todo_list.items.len() {
+            Some(self.todo_list.items.remove(selected))
+        } else {
+            None
+        };
+        let anchor_index = if placeholder.is_some() { selected.checked_sub(1) } else { Some(selected) };
+        let new_anchor_index = anchor_index.and_then(|index| {
+            let anchor = self.todo_list.items.get(index)?;
+            ItemCreator::find_nearest_matching_item(&new_list.items, anchor, index)
+        });
+        
+        self.save_current_state();
+        self.todo_list = new_list;
+        
+        let last_index = self.todo_list.items.len().saturating_sub(1);
+        self.navigation.selected_index = match placeholder {
+            Some(item) => {
+                let position = match new_anchor_index {
+                    Some(index) => index + 1,
+                    None if anchor_index.is_none() => 0,
+                    None => selected.min(self.todo_list.items.len()),
+                };
+                self.todo_list.items.insert(position, item);
+                position
+            }
+            None => new_anchor_index.unwrap_or(selected.min(last_index)),
+        };
+        
+        // Indices no longer refer to the same items
+        self.navigation.clear_selection();
+        self.pending_delete = None;
+        self.search_state.clear_results();
+        if self.agenda.agenda_mode {
+            self.agenda.refresh(&self.todo_list.items, today());
+        }
+        if self.board.board_mode {
+            self.board.refresh(&self.todo_list.items);
+        }
+        self.calendar.refresh(&self.todo_list.items);
+        self.refresh_visibility();
+        self.navigation.update_scroll();
+    }
+
+    // Opens the search prompt; a section search only looks under the heading
+    // containing the cursor, including its subsections
+    fn start_search(&mut self, in_section: bool) -> Result<()> {
+        let section = if in_section {
+            let items = &self.todo_list.items;
+            let heading = ItemCreator::find_current_heading_context(items, self.navigation.selected_index)
+                .checked_sub(1)
+                .ok_or_else(|| anyhow::anyhow!("Not in a section"))?;
+            let (_, end) = ItemCreator::get_fold_range(items, heading);
+            Some(heading + 1..end + 1)
+        } else {
+            None
+        };
+        let origin = SearchOrigin {
+            selected_index: self.navigation.selected_index,
+            scroll_offset: self.navigation.scroll_offset,
+        };
+        self.search_state.enter_search_mode(origin, section);
+        Ok(())
+    }
+
+    // Moves the cursor to the first match as the query is typed
+    fn preview_search(&mut self) {
+        self.navigation.selected_index = self.search_state.preview();
+        self.navigation.update_scroll();
     }
 
     // Handle escape key context
@@ -90,6 +1521,10 @@ impl App {
         if !self.search_state.search_matches.is_empty() {
             // Clear search results if they exist
             self.search_state.clear_results();
+        } else if self.navigation.selected_items.is_empty() && self.tags.filter.is_some() {
+            // Then the tag filter, once nothing is selected
+            self.tags.filter = None;
+            self.navigation.update_scroll();
         } else {
             // Otherwise clear bulk selection
             self.navigation.clear_selection();
@@ -122,6 +1557,283 @@ impl App {
         Ok(())
     }
 
+    // Items that yank and copy act on: the bulk selection, or the current item
+    // together with its children
+    fn target_indices(&self) -> Vec<usize> {
+        if !self.navigation.selected_items.is_empty() {
+            let mut indices: Vec<usize> = self.navigation.selected_items.iter().copied().collect();
+            indices.sort();
+            indices
+        } else if self.navigation.selected_index < self.todo_list.items.len() {
+            let (start, end) = ItemCreator::get_block_range(&self.todo_list.items, self.navigation.selected_index);
+            (start..=end).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Rewrites the content of the selected todos (or the current one) in a
+    // single undo step, returning how many of them changed
+    fn edit_target_todos(&mut self, edit: impl Fn(&str) -> Option<String>) -> Result<usize> {
+        let indices: Vec<usize> = if self.navigation.selected_items.is_empty() {
+            vec![self.navigation.selected_index]
+        } else {
+            self.navigation.selected_items.iter().copied().collect()
+        };
+
+        self.save_current_state();
+        let mut changed = 0;
+        for index in indices {
+            if let Some(item @ ListItem::Todo { .. }) = self.todo_list.items.get_mut(index)
+                && let Some(content) = edit(item.content())
+            {
+                *item.content_mut() = content;
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.search_state.clear_results();
+            self.save_to_file()?;
+        }
+        Ok(changed)
+    }
+
+    // A copy of the list to compare against after a toggle, taken only when
+    // completions are dated, logged or hooked
+    fn completion_snapshot(&self) -> Option<Vec<ListItem>> {
+        let wanted = self.stamp_done_date
+            || self.activity_log
+            || self.hooks.on_complete.is_some()
+            || self.plugins.handles(PluginEvent::Toggled);
+        wanted.then(|| self.todo_list.items.clone())
+    }
+
+    fn record_completions(&mut self, before: Option<Vec<ListItem>>) {
+        let Some(before) = before else {
+            return;
+        };
+        if self.activity_log {
+            let entries = activity::completion_entries(&self.todo_list.items, &before);
+            self.append_to_log(&entries);
+        }
+        if self.stamp_done_date {
+            ItemActions::record_done_dates(&mut self.todo_list.items, &before, today());
+        }
+        if let Err(e) = hooks::run_for_completions(&self.hooks, &self.todo_list.file_path, &self.todo_list.items, &before) {
+            self.status.error(format!("{:#}", e));
+        }
+        if self.plugins.handles(PluginEvent::Toggled) && before.len() == self.todo_list.items.len() {
+            let toggled: Vec<usize> = (0..before.len())
+                .filter(|&index| before[index].is_completed() != self.todo_list.items[index].is_completed())
+                .collect();
+            for index in toggled {
+                self.run_plugin_event(PluginEvent::Toggled, index);
+            }
+        }
+    }
+
+    fn run_plugin_event(&mut self, event: PluginEvent, index: usize) {
+        if !self.plugins.handles(event) {
+            return;
+        }
+        let outcome = self.plugins.run_event(event, index, &self.todo_list.items, self.navigation.selected_index);
+        if let Err(e) = self.apply_plugin_outcome(outcome) {
+            self.status.error(format!("{:#}", e));
+        }
+    }
+
+    // Takes on what a plugin did to its copy of the list
+    fn apply_plugin_outcome(&mut self, outcome: Result<PluginOutcome>) -> Result<()> {
+        let outcome = outcome?;
+        for message in outcome.messages {
+            self.status.info(message);
+        }
+        if let Some(items) = outcome.items {
+            self.save_current_state();
+            self.todo_list.items = items;
+            self.search_state.clear_results();
+            self.save_to_file()?;
+        }
+        self.navigation.selected_index = outcome.selected.min(self.todo_list.items.len().saturating_sub(1));
+        self.navigation.update_scroll();
+        Ok(())
+    }
+
+    fn run_hook(&mut self, event: HookEvent, index: Option<usize>) {
+        let item = index.and_then(|index| self.todo_list.items.get(index));
+        if let Err(e) = hooks::run(&self.hooks, event, &self.todo_list.file_path, item) {
+            self.status.error(format!("{:#}", e));
+        }
+    }
+
+    // Log entries for `activity` on the items at `indices`, or none when
+    // logging is off. They're made up front, as the items may move or go away.
+    fn activity_entries(&self, activity: Activity, indices: impl IntoIterator<Item = usize>) -> Vec<LogEntry> {
+        if !self.activity_log {
+            return Vec::new();
+        }
+        indices
+            .into_iter()
+            .filter_map(|index| self.todo_list.items.get(index))
+            .filter(|item| matches!(item, ListItem::Todo { .. } | ListItem::Note { .. }))
+            .map(|item| LogEntry::now(activity, item.content()))
+            .collect()
+    }
+
+    fn append_to_log(&mut self, entries: &[LogEntry]) {
+        // The log is plain text, so it would give away what's in the file
+        if self.is_encrypted() {
+            if !entries.is_empty() {
+                self.status.info("Activity isn't logged for encrypted files");
+            }
+            return;
+        }
+        if let Err(e) = activity::append_entries(&self.todo_list.file_path, entries) {
+            self.status.error(format!("{:#}", e));
+        }
+    }
+
+    /// Archives done todos, or with `older_than_days` only those stamped as
+    /// done more than that many days ago, as one undo step
+    fn archive_done_todos(&mut self, older_than_days: Option<u32>) -> Result<usize> {
+        let cutoff = older_than_days.and_then(|days| today().checked_sub_days(Days::new(days.into())));
+        self.save_current_state();
+        let anchor = ItemAnchor::new(&self.todo_list.items, self.navigation.selected_index);
+        let count = ItemActions::archive_completed(&mut self.todo_list.items, cutoff);
+        if count > 0 {
+            self.follow_anchor(anchor);
+            self.search_state.clear_results();
+            self.save_to_file()?;
+        }
+        Ok(count)
+    }
+
+    /// The startup pass for `auto_archive_after_days`
+    pub fn auto_archive(&mut self) {
+        let Some(days) = self.auto_archive_after_days else {
+            return;
+        };
+        // The instance that has the file open takes care of it
+        if self.is_read_only() {
+            return;
+        }
+        self.undo_manager.begin_group();
+        let result = self.archive_done_todos(Some(days));
+        self.undo_manager.end_group(&self.todo_list);
+        match result {
+            Ok(0) => {}
+            Ok(count) => self.status.info(format!(
+                "Archived {} {} done more than {} days ago",
+                count,
+                if count == 1 { "todo" } else { "todos" },
+                days
+            )),
+            Err(e) => self.status.error(format!("Failed to save file: {:#}", e)),
+        }
+    }
+
+    fn yank_items(&mut self) {
+        let indices = self.target_indices();
+        if indices.is_empty() {
+            return;
+        }
+        self.register.yank(&self.todo_list.items, &indices);
+        self.navigation.clear_selection();
+        self.status.info(format!("Yanked {} item(s)", self.register.len()));
+    }
+
+    fn paste_items(&mut self) -> Result<()> {
+        if self.register.is_empty() {
+            return Ok(());
+        }
+        self.save_current_state();
+        let (position, base_indent) = self.paste_position();
+        let pasted = self.register.items_for_paste(base_indent);
+        self.todo_list.items.splice(position..position, pasted);
+        
+        self.navigation.selected_index = position;
+        self.navigation.clear_selection();
+        self.search_state.clear_results();
+        self.navigation.update_scroll();
+        self.save_to_file()
+    }
+
+    // Pastes go after the current item's children, at the current item's level
+    fn paste_position(&self) -> (usize, usize) {
+        match self.todo_list.items.get(self.navigation.selected_index) {
+            Some(item) => {
+                let (_, block_end) = ItemCreator::get_block_range(&self.todo_list.items, self.navigation.selected_index);
+                (block_end + 1, item.indent_level().unwrap_or(0))
+            }
+            None => (self.todo_list.items.len(), 0),
+        }
+    }
+
+    fn copy_items_to_clipboard(&mut self) {
+        let indices = self.target_indices();
+        if indices.is_empty() {
+            return;
+        }
+        let items: Vec<ListItem> = indices.iter().map(|&i| self.todo_list.items[i].clone()).collect();
+        match copy_to_system_clipboard(&items_to_markdown(&items)) {
+            Ok(()) => self.status.info(format!("Copied {} item(s) to clipboard", items.len())),
+            Err(e) => self.status.error(format!("{:#}", e)),
+        }
+    }
+
+    // Delete the bulk selection or the current item (optionally with everything
+    // nested under it), asking first if configured to
+    fn request_delete(&mut self, with_children: bool) {
+        let indices: Vec<usize> = if !self.navigation.selected_items.is_empty() {
+            self.navigation.selected_items.iter().copied().collect()
+        } else {
+            vec![self.navigation.selected_index]
+        };
+        let pending = if with_children {
+            PendingDelete::with_children(&self.todo_list.items, &indices)
+        } else {
+            PendingDelete::new(&self.todo_list.items, &indices)
+        };
+        let Some(pending) = pending else {
+            return;
+        };
+        
+        if self.confirm_delete {
+            self.pending_delete = Some(pending);
+        } else {
+            self.delete_items(&pending.indices);
+        }
+    }
+
+    fn delete_items(&mut self, indices: &[usize]) {
+        let entries = self.activity_entries(Activity::Deleted, indices.iter().copied());
+        // Headings are never deleted, so they don't go to the trash either
+        let mut sorted = indices.to_vec();
+        sorted.sort();
+        let removed: Vec<ListItem> = sorted
+            .iter()
+            .filter_map(|&i| self.todo_list.items.get(i))
+            .filter(|item| !matches!(item, ListItem::Heading { .. }))
+            .cloned()
+            .collect();
+        let deleted_count = if let [index] = indices {
+            // Single item delete mode
+            usize::from(self.perform_delete_item(*index))
+        } else {
+            // Bulk delete mode
+            self.perform_bulk_delete(&indices.iter().copied().collect())
+        };
+        self.navigation.clear_selection();
+        if deleted_count > 0 {
+            self.append_to_log(&entries);
+            self.trash.push(removed);
+        }
+        
+        if deleted_count > 1 {
+            self.status.info(format!("{} items deleted", deleted_count));
+        }
+    }
+
     fn add_new_note(&mut self) -> Result<()> {
         self.save_current_state();
         self.edit_state.adding_new_todo = true;
@@ -170,9 +1882,103 @@ impl App {
             self.navigation.selected_index = position;
             self.enter_edit_mode_for_item(position);
         }
+        self.start_todo_template();
+        Ok(())
+    }
+
+    // Unlike `a`, never nests the new item under the current one
+    fn add_sibling(&mut self, above: bool, note: bool) -> Result<()> {
+        self.save_current_state();
+        self.edit_state.adding_new_todo = true;
+        
+        let (position, indent) = ItemCreator::determine_sibling_insert_position(&self.todo_list.items, self.navigation.selected_index, above);
+        let new_item = if note {
+            ItemCreator::create_new_note(String::new(), indent)
+        } else {
+            ItemCreator::create_new_todo(String::new(), false, indent)
+        };
+        self.todo_list.items.insert(position, new_item);
+        self.navigation.selected_index = position;
+        self.enter_edit_mode_for_item(position);
+        if !note {
+            self.start_todo_template();
+        }
         Ok(())
     }
 
+    // A new todo's text starts as `templates.todo`; the item itself stays
+    // empty until it's confirmed, so cancelling still removes it
+    fn start_todo_template(&mut self) {
+        if let Some(template) = &self.templates.todo {
+            let (text, cursor) = templates::expand(template, Local::now().naive_local());
+            self.edit_state.start_from_template(text, cursor);
+        }
+    }
+
+    // Splits the item being edited at the cursor and carries on editing the second half
+    fn split_edited_item(&mut self) -> Result<()> {
+        let index = self.navigation.selected_index;
+        if !self.todo_list.items.get(index).is_some_and(ListItem::allows_multiline) {
+            return Ok(());
+        }
+        // A new item's addition is already its undo step
+        let adding = self.edit_state.adding_new_todo;
+        if !adding {
+            self.save_current_state();
+        }
+        *self.todo_list.items[index].content_mut() = self.edit_state.edit_buffer.clone();
+        let Some(new_index) = ItemActions::split_item(&mut self.todo_list.items, index, self.edit_state.edit_cursor_position) else {
+            return Ok(());
+        };
+        // On a new item, the first half is added as it is and the second
+        // half is added in a step of its own
+        if adding {
+            let second = self.todo_list.items.remove(new_index);
+            self.finish_adding(index);
+            self.save_current_state();
+            self.todo_list.items.insert(new_index, second);
+        }
+
+        self.navigation.selected_index = new_index;
+        self.edit_state.exit_edit_mode();
+        self.enter_edit_mode_for_item(new_index);
+        self.edit_state.adding_new_todo = adding;
+        self.edit_state.move_cursor_home();
+        self.search_state.clear_results();
+        self.save_to_file()
+    }
+
+    // Backspace at the start of an item merges it into the item above
+    fn join_with_previous_item(&mut self) -> Result<()> {
+        let index = self.navigation.selected_index;
+        let joinable = |item: Option<&ListItem>| item.is_some_and(ListItem::allows_multiline);
+        if index == 0 || !joinable(self.todo_list.items.get(index)) || !joinable(self.todo_list.items.get(index - 1)) {
+            return Ok(());
+        }
+        // Not into an item hidden by a fold
+        if !self.visible_indices().contains(&(index - 1)) {
+            return Ok(());
+        }
+        // A new item joined onto the one above isn't added after all, and the
+        // addition's undo step already covers the join
+        if self.edit_state.adding_new_todo {
+            self.edit_state.adding_new_todo = false;
+        } else {
+            self.save_current_state();
+        }
+        *self.todo_list.items[index].content_mut() = self.edit_state.edit_buffer.clone();
+        let Some((previous, joined_at)) = ItemActions::join_with_previous(&mut self.todo_list.items, index) else {
+            return Ok(());
+        };
+
+        self.navigation.selected_index = previous;
+        self.edit_state.exit_edit_mode();
+        self.enter_edit_mode_for_item(previous);
+        self.edit_state.edit_cursor_position = joined_at;
+        self.search_state.clear_results();
+        self.save_to_file()
+    }
+
     fn add_new_todo_at_top(&mut self) -> Result<()> {
         self.save_current_state();
         self.edit_state.adding_new_todo = true;
@@ -183,100 +1989,615 @@ impl App {
         self.todo_list.items.insert(insert_position, new_todo);
         self.navigation.selected_index = insert_position;
         self.enter_edit_mode_for_item(insert_position);
+        self.start_todo_template();
+        Ok(())
+    }
+}
+
+// Implement all the traits
+impl KeyEventHandler for App {
+    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
+        // Everything a single key press changes is undone in one step
+        self.undo_manager.begin_group();
+        self.macros.record(key_event);
+        let result = self.dispatch_key_event(key_event);
+        self.undo_manager.end_group(&self.todo_list);
+        
+        self.refresh_visibility();
+        
+        // Errors are reported in the status line rather than ending the session
+        if let Err(e) = result {
+            self.status.error(format!("{:#}", e));
+        }
         Ok(())
     }
-}
+}
+
+impl App {
+    // Waits for the key after `prefix`, for a key that only sometimes starts
+    // a two-key command (`m` without a selection)
+    fn start_chord(&mut self, prefix: char) {
+        self.chord.push(KeyPress::new(KeyCode::Char(prefix), KeyModifiers::NONE), Instant::now());
+    }
 
-// Implement all the traits
-impl KeyEventHandler for App {
-    fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
-        if self.help_mode {
+    // Presses the keys of a macro `times` times over, stopping at the first
+    // error. It's all one key press to undo.
+    fn replay_macro(&mut self, register: char, times: usize) -> Result<()> {
+        let keys = self.macros.keys(register).map_err(anyhow::Error::msg)?;
+        if !self.macros.enter() {
+            anyhow::bail!("Macros replay each other too deeply");
+        }
+        let mut result = Ok(());
+        'replay: for _ in 0..times {
+            for &key_event in &keys {
+                result = self.dispatch_key_event(key_event);
+                self.refresh_visibility();
+                if result.is_err() || self.should_quit {
+                    break 'replay;
+                }
+            }
+        }
+        self.macros.exit();
+        result
+    }
+
+    fn dispatch_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
+        if self.help_mode && let Some(input) = self.help.search_input.clone() {
+            match KeyHandler::handle_prompt_key(key_event) {
+                PromptAction::Cancel => {
+                    self.help.finish_search();
+                    self.help.query.clear();
+                }
+                PromptAction::Confirm => self.help.finish_search(),
+                PromptAction::Backspace => {
+                    let mut input = input;
+                    input.pop();
+                    self.help.set_search_input(input);
+                }
+                PromptAction::InsertChar(c) => self.help.set_search_input(format!("{}{}", input, c)),
+                PromptAction::None => {}
+            }
+        } else if self.help_mode {
             match KeyHandler::handle_help_mode_key(key_event) {
                 HelpModeAction::ExitHelpMode => self.help_mode = false,
+                HelpModeAction::ScrollUp => self.help.scroll_by(-1),
+                HelpModeAction::ScrollDown => self.help.scroll_by(1),
+                HelpModeAction::PageUp => self.help.scroll_pages(-1),
+                HelpModeAction::PageDown => self.help.scroll_pages(1),
+                HelpModeAction::ScrollToTop => self.help.scroll = 0,
+                HelpModeAction::ScrollToBottom => self.help.scroll_to_end(),
+                HelpModeAction::StartSearch => self.help.start_search(),
+                HelpModeAction::NextMatch => self.next_help_match(true),
+                HelpModeAction::PreviousMatch => self.next_help_match(false),
                 HelpModeAction::None => {}
             }
+        } else if self.pending_conflict.is_some() {
+            self.resolve_conflict(KeyHandler::handle_conflict_key(key_event))?;
+        } else if self.pending_delete.is_some() {
+            match KeyHandler::handle_confirm_key(key_event) {
+                ConfirmAction::Confirm => {
+                    if let Some(pending) = self.pending_delete.take() {
+                        self.delete_items(&pending.indices);
+                    }
+                }
+                ConfirmAction::Cancel => self.pending_delete = None,
+                ConfirmAction::None => {}
+            }
+        } else if self.agenda.agenda_mode {
+            match KeyHandler::handle_agenda_key(key_event) {
+                AgendaAction::Close => self.agenda.close(),
+                AgendaAction::MoveSelectionUp => self.agenda.move_selection_up(),
+                AgendaAction::MoveSelectionDown => self.agenda.move_selection_down(),
+                AgendaAction::JumpToItem => {
+                    if let Some(entry) = self.agenda.selected_entry() {
+                        self.navigation.selected_index = entry.index;
+                        self.navigation.clear_selection();
+                        self.navigation.update_scroll();
+                        self.agenda.close();
+                    }
+                }
+                AgendaAction::None => {}
+            }
+        } else if self.calendar.day_todos.is_some() {
+            match KeyHandler::handle_agenda_key(key_event) {
+                AgendaAction::Close => self.calendar.close_day(),
+                AgendaAction::MoveSelectionUp => self.calendar.move_day_selection_up(),
+                AgendaAction::MoveSelectionDown => self.calendar.move_day_selection_down(),
+                AgendaAction::JumpToItem => {
+                    if let Some(index) = self.calendar.selected_todo() {
+                        self.navigation.selected_index = index;
+                        self.navigation.clear_selection();
+                        self.navigation.update_scroll();
+                        self.calendar.close();
+                    }
+                }
+                AgendaAction::None => {}
+            }
+        } else if self.calendar.calendar_mode {
+            match KeyHandler::handle_calendar_key(key_event) {
+                CalendarAction::Close => self.calendar.close(),
+                CalendarAction::PreviousDay => self.calendar.move_days(-1),
+                CalendarAction::NextDay => self.calendar.move_days(1),
+                CalendarAction::PreviousWeek => self.calendar.move_days(-7),
+                CalendarAction::NextWeek => self.calendar.move_days(7),
+                CalendarAction::PreviousMonth => self.calendar.move_months(-1),
+                CalendarAction::NextMonth => self.calendar.move_months(1),
+                CalendarAction::Today => self.calendar.selected_date = today(),
+                CalendarAction::OpenDay => self.calendar.open_day(&self.todo_list.items),
+                CalendarAction::None => {}
+            }
+        } else if self.board.board_mode {
+            match KeyHandler::handle_board_key(key_event) {
+                BoardAction::Close => self.board.close(),
+                BoardAction::PreviousColumn => self.board.move_column(-1),
+                BoardAction::NextColumn => self.board.move_column(1),
+                BoardAction::MoveSelectionUp => self.board.move_card_selection(-1),
+                BoardAction::MoveSelectionDown => self.board.move_card_selection(1),
+                BoardAction::MoveCardLeft => self.move_board_card(-1),
+                BoardAction::MoveCardRight => self.move_board_card(1),
+                BoardAction::ToggleLayout => self.board.toggle_layout(&self.todo_list.items),
+                BoardAction::Undo => {
+                    self.perform_undo()?;
+                    self.board.refresh(&self.todo_list.items);
+                }
+                BoardAction::JumpToItem => {
+                    if let Some(index) = self.board.selected_card() {
+                        self.navigation.selected_index = index;
+                        self.navigation.clear_selection();
+                        self.navigation.update_scroll();
+                        self.board.close();
+                    }
+                }
+                BoardAction::None => {}
+            }
+        } else if self.outline.focused {
+            match KeyHandler::handle_outline_key(key_event) {
+                OutlineAction::Close => self.outline.close(),
+                OutlineAction::MoveSelectionUp => self.move_outline_selection(false),
+                OutlineAction::MoveSelectionDown => self.move_outline_selection(true),
+                OutlineAction::FocusList => self.outline.focus_list(),
+                OutlineAction::None => {}
+            }
+        } else if self.file_picker.picker_mode {
+            match KeyHandler::handle_file_picker_key(key_event) {
+                FilePickerAction::Close => self.file_picker.close(),
+                FilePickerAction::MoveSelectionUp => self.file_picker.move_selection_up(),
+                FilePickerAction::MoveSelectionDown => self.file_picker.move_selection_down(),
+                FilePickerAction::OpenSelectedFile => self.open_selected_file(),
+                FilePickerAction::None => {}
+            }
+        } else if self.move_picker.picker_mode {
+            match KeyHandler::handle_move_picker_key(key_event) {
+                MovePickerAction::Close => self.move_picker.close(),
+                MovePickerAction::MoveSelectionUp => self.move_picker.move_selection_up(),
+                MovePickerAction::MoveSelectionDown => {
+                    let count = self.move_picker_entries().len();
+                    self.move_picker.move_selection_down(count);
+                }
+                MovePickerAction::MoveItems => self.move_items_to_picked_heading()?,
+                MovePickerAction::None => {}
+            }
+        } else if self.tags.open && let Some(input) = self.tags.rename_input.clone() {
+            match KeyHandler::handle_prompt_key(key_event) {
+                PromptAction::Cancel => self.tags.rename_input = None,
+                PromptAction::Confirm => {
+                    self.tags.rename_input = None;
+                    self.retag(Some(input))?;
+                }
+                PromptAction::Backspace => {
+                    let mut input = input;
+                    input.pop();
+                    self.tags.rename_input = Some(input);
+                }
+                PromptAction::InsertChar(c) => self.tags.rename_input = Some(format!("{}{}", input, c)),
+                PromptAction::None => {}
+            }
+        } else if self.tags.open {
+            match KeyHandler::handle_tag_panel_key(key_event) {
+                TagPanelAction::Close => self.tags.close(),
+                TagPanelAction::MoveSelectionUp => self.tags.move_selection_up(),
+                TagPanelAction::MoveSelectionDown => self.tags.move_selection_down(),
+                TagPanelAction::Filter => self.filter_by_tag(),
+                TagPanelAction::Rename => self.tags.start_rename(),
+                TagPanelAction::Remove => self.retag(None)?,
+                TagPanelAction::None => {}
+            }
+        } else if self.trash.picker_mode {
+            match KeyHandler::handle_trash_picker_key(key_event) {
+                TrashPickerAction::Close => self.trash.close(),
+                TrashPickerAction::MoveSelectionUp => self.trash.move_selection_up(),
+                TrashPickerAction::MoveSelectionDown => self.trash.move_selection_down(),
+                TrashPickerAction::Restore => self.restore_from_trash()?,
+                TrashPickerAction::None => {}
+            }
         } else if self.edit_state.edit_mode {
-            match KeyHandler::handle_edit_mode_key(key_event) {
+            let action = KeyHandler::handle_edit_mode_key(key_event);
+            let typing = matches!(
+                action,
+                EditModeAction::InsertChar(_) | EditModeAction::Backspace | EditModeAction::Delete | EditModeAction::DeleteWordBackward
+            );
+            let menu_open = self.completion.is_open();
+            match action {
+                // While the completion menu is open, Esc and ↑↓ are for it
+                EditModeAction::CancelEdit if menu_open => {}
+                EditModeAction::MoveCursorUp if menu_open => self.completion.move_selection(false),
+                EditModeAction::MoveCursorDown if menu_open => self.completion.move_selection(true),
+                EditModeAction::AcceptCompletion => {
+                    if let Some((range, word)) = self.completion.accept(self.edit_state.edit_cursor_position) {
+                        self.edit_state.replace(range, &word);
+                    }
+                }
                 EditModeAction::CancelEdit => self.cancel_edit()?,
                 EditModeAction::ConfirmEdit => self.confirm_edit()?,
+                EditModeAction::Backspace if self.edit_state.edit_cursor_position == 0 => self.join_with_previous_item()?,
                 EditModeAction::Backspace => self.edit_state.backspace(),
                 EditModeAction::Delete => self.edit_state.delete(),
                 EditModeAction::MoveCursorLeft => self.edit_state.move_cursor_left(),
                 EditModeAction::MoveCursorRight => self.edit_state.move_cursor_right(),
+                EditModeAction::MoveCursorUp => self.edit_state.move_cursor_up(),
+                EditModeAction::MoveCursorDown => self.edit_state.move_cursor_down(),
                 EditModeAction::MoveCursorHome => self.edit_state.move_cursor_home(),
                 EditModeAction::MoveCursorEnd => self.edit_state.move_cursor_end(),
                 EditModeAction::DeleteWordBackward => self.edit_state.delete_word_backward(),
+                EditModeAction::KillToLineStart => self.edit_state.kill_to_line_start(),
+                EditModeAction::KillToLineEnd => self.edit_state.kill_to_line_end(),
                 EditModeAction::MoveToPreviousWord => self.edit_state.move_to_previous_word(),
                 EditModeAction::MoveToNextWord => self.edit_state.move_to_next_word(),
+                EditModeAction::InsertNewline => {
+                    // Headings and raw lines have to stay on one line
+                    if self.todo_list.items.get(self.navigation.selected_index).is_some_and(ListItem::allows_multiline) {
+                        self.edit_state.insert_char('\n');
+                    }
+                }
+                EditModeAction::SplitItem => self.split_edited_item()?,
                 EditModeAction::InsertChar(c) => self.edit_state.insert_char(c),
                 EditModeAction::None => {}
             }
+            if typing && self.edit_state.edit_mode {
+                let editing = self.navigation.selected_index;
+                self.completion.update(&self.edit_state.edit_buffer, self.edit_state.edit_cursor_position, &self.todo_list.items, editing);
+            } else if !(menu_open && matches!(action, EditModeAction::MoveCursorUp | EditModeAction::MoveCursorDown)) {
+                self.completion.close();
+            }
+        } else if self.command_line.command_mode {
+            match KeyHandler::handle_command_mode_key(key_event) {
+                CommandModeAction::Cancel => self.command_line.cancel(),
+                CommandModeAction::Execute => {
+                    let input = self.command_line.take_input();
+                    self.execute_command(&input)?;
+                }
+                CommandModeAction::Complete => self.command_line.complete(),
+                CommandModeAction::Backspace => self.command_line.backspace(),
+                CommandModeAction::InsertChar(c) => self.command_line.insert_char(c),
+                CommandModeAction::None => {}
+            }
         } else if self.search_state.search_mode {
             match KeyHandler::handle_search_mode_key(key_event) {
-                SearchModeAction::CancelSearch => self.search_state.cancel_search(),
+                SearchModeAction::CancelSearch => {
+                    self.search_history.reset();
+                    let origin = self.search_state.cancel_search();
+                    self.navigation.selected_index = origin.selected_index.min(self.todo_list.items.len().saturating_sub(1));
+                    self.navigation.scroll_offset = origin.scroll_offset;
+                }
                 SearchModeAction::ConfirmSearch => {
+                    // Searches in an encrypted file would give away what's in it
+                    if !self.is_encrypted() {
+                        self.search_history.push(&self.search_state.search_query);
+                        if let Err(e) = self.search_history.save() {
+                            self.status.error(format!("{:#}", e));
+                        }
+                    }
                     if let Some(index) = self.search_state.confirm_search() {
                         self.navigation.selected_index = index;
                         self.navigation.update_scroll();
                     }
                 }
-                SearchModeAction::Backspace => self.search_state.backspace(&self.todo_list.items),
-                SearchModeAction::InsertChar(c) => self.search_state.insert_char(c, &self.todo_list.items),
+                SearchModeAction::ToggleRegex => {
+                    self.search_state.toggle_kind(QueryKind::Regex, &self.todo_list.items);
+                    self.preview_search();
+                }
+                SearchModeAction::ToggleFuzzy => {
+                    self.search_state.toggle_kind(QueryKind::Fuzzy, &self.todo_list.items);
+                    self.preview_search();
+                }
+                SearchModeAction::CycleCaseSensitivity => {
+                    self.search_state.cycle_case_sensitivity(&self.todo_list.items);
+                    self.preview_search();
+                }
+                SearchModeAction::HistoryPrevious => {
+                    if let Some(query) = self.search_history.older(&self.search_state.search_query) {
+                        self.search_state.set_query(query, &self.todo_list.items);
+                        self.preview_search();
+                    }
+                }
+                SearchModeAction::HistoryNext => {
+                    if let Some(query) = self.search_history.newer() {
+                        self.search_state.set_query(query, &self.todo_list.items);
+                        self.preview_search();
+                    }
+                }
+                SearchModeAction::Backspace => {
+                    self.search_state.backspace(&self.todo_list.items);
+                    self.preview_search();
+                }
+                SearchModeAction::InsertChar(c) => {
+                    self.search_state.insert_char(c, &self.todo_list.items);
+                    self.preview_search();
+                }
                 SearchModeAction::None => {}
             }
         } else {
-            match KeyHandler::handle_normal_mode_key(key_event) {
-                NormalModeAction::Quit => self.should_quit = true,
+            // Keys waited on too long don't combine with this one
+            if self.chord.expire(Instant::now()) {
+                self.pending_count = None;
+            }
+            // A count for the next motion; 0 only continues one, as in vim
+            if let KeyCode::Char(digit @ '0'..='9') = key_event.code
+                && key_event.modifiers.is_empty()
+                && (digit != '0' || self.pending_count.is_some())
+                && !self.chord.is_pending()
+                && self.keymap.action(key_event) == NormalModeAction::None
+                && !self.plugins.has_key(digit)
+            {
+                let count = self.pending_count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize - '0' as usize);
+                self.pending_count = Some(count);
+                return Ok(());
+            }
+            let count = self.pending_count.take();
+            // Keys pressed for the action, which a macro recording leaves out
+            // when it's the one that stops it
+            let pressed = self.chord.keys().len() + 1;
+            let action = match self.keymap.resolve(self.chord.keys(), key_event) {
+                Chord::Pending => {
+                    self.chord.push(KeyPress::new(key_event.code, key_event.modifiers), Instant::now());
+                    // `5gg` goes to the fifth row
+                    self.pending_count = count;
+                    return Ok(());
+                }
+                Chord::Action(action) => {
+                    let prefixed = !self.chord.take().is_empty();
+                    // Plugins can only bind keys that aren't taken
+                    if action == NormalModeAction::None
+                        && !prefixed
+                        && let KeyCode::Char(key) = key_event.code
+                        && self.plugins.has_key(key)
+                    {
+                        let outcome = self.plugins.run_key(key, &self.todo_list.items, self.navigation.selected_index);
+                        return self.apply_plugin_outcome(outcome);
+                    }
+                    action
+                }
+            };
+            match action {
+                NormalModeAction::Quit => self.quit(),
                 NormalModeAction::HandleEscape => self.handle_escape(),
-                NormalModeAction::MoveSelectionUp => self.navigation.move_selection_up(),
-                NormalModeAction::MoveSelectionDown => self.navigation.move_selection_down(self.todo_list.items.len()),
+                NormalModeAction::MoveSelectionUp => match count {
+                    Some(count) => self.navigation.move_by_rows(-(count as isize), &self.visible_indices()),
+                    None => self.navigation.move_selection_up(&self.visible_indices()),
+                },
+                NormalModeAction::MoveSelectionDown => match count {
+                    Some(count) => self.navigation.move_by_rows(count as isize, &self.visible_indices()),
+                    None => self.navigation.move_selection_down(&self.visible_indices()),
+                },
+                // Items can't be swapped past the edges of a zoomed section
                 NormalModeAction::MoveItemUp => {
-                    if let Some(new_index) = self.perform_move_item_up(self.navigation.selected_index) {
+                    let above = self.navigation.selected_index.checked_sub(1);
+                    if above.is_some_and(|above| self.zoom.contains(&self.todo_list.items, above))
+                        && let Some(new_index) = self.perform_move_item_up(self.navigation.selected_index)
+                    {
                         self.navigation.selected_index = new_index;
                         self.navigation.update_scroll();
                     }
                 }
                 NormalModeAction::MoveItemDown => {
-                    if let Some(new_index) = self.perform_move_item_down(self.navigation.selected_index) {
+                    let below = self.navigation.selected_index + 1;
+                    if self.zoom.contains(&self.todo_list.items, below)
+                        && let Some(new_index) = self.perform_move_item_down(self.navigation.selected_index)
+                    {
                         self.navigation.selected_index = new_index;
                         self.navigation.update_scroll();
                     }
                 }
                 NormalModeAction::IndentItem => {
-                    self.perform_indent_item(self.navigation.selected_index);
+                    if !self.navigation.selected_items.is_empty() {
+                        self.perform_bulk_indent(&self.navigation.selected_items.clone());
+                    } else if self.on_heading() {
+                        self.perform_shift_heading(self.navigation.selected_index, 1, false);
+                    } else {
+                        self.perform_indent_item(self.navigation.selected_index);
+                    }
                 }
                 NormalModeAction::UnindentItem => {
-                    self.perform_unindent_item(self.navigation.selected_index);
+                    if !self.navigation.selected_items.is_empty() {
+                        self.perform_bulk_unindent(&self.navigation.selected_items.clone());
+                    } else if self.on_heading() {
+                        self.perform_shift_heading(self.navigation.selected_index, -1, false);
+                    } else {
+                        self.perform_unindent_item(self.navigation.selected_index);
+                    }
                 }
                 NormalModeAction::ToggleSelectedItem => {
-                    self.perform_toggle_completion(self.navigation.selected_index);
+                    if !self.navigation.selected_items.is_empty() {
+                        self.perform_bulk_toggle_completion(&self.navigation.selected_items.clone());
+                    } else {
+                        self.perform_toggle_completion(self.navigation.selected_index);
+                    }
+                }
+                NormalModeAction::ToggleSubtree => {
+                    self.perform_toggle_subtree_completion(self.navigation.selected_index);
+                }
+                NormalModeAction::CycleState => {
+                    self.perform_cycle_state(self.navigation.selected_index);
                 }
                 NormalModeAction::EnterEditMode => self.enter_edit_mode_for_item(self.navigation.selected_index),
                 NormalModeAction::AddNewTodo => self.add_new_todo()?,
                 NormalModeAction::AddNewTodoAtTop => self.add_new_todo_at_top()?,
+                NormalModeAction::AddSiblingBelow => self.add_sibling(false, false)?,
+                NormalModeAction::AddSiblingAbove => self.add_sibling(true, false)?,
                 NormalModeAction::HandleN => self.handle_n()?,
                 NormalModeAction::HandleShiftN => self.handle_shift_n()?,
                 NormalModeAction::ToggleItemSelection => self.navigation.toggle_item_selection(self.todo_list.items.len()),
+                NormalModeAction::MoveSelectedItemsToCursor if self.navigation.selected_items.is_empty() => {
+                    self.start_chord('m');
+                }
+                NormalModeAction::SetMark(letter) => {
+                    if self.marks.set(letter, &self.todo_list.items, self.navigation.selected_index) {
+                        self.status.info(format!("Marked '{}", letter));
+                    }
+                }
+                NormalModeAction::JumpToMark(letter) => match self.marks.get(letter, &self.todo_list.items) {
+                    Some(index) => self.jump_to_item(index),
+                    None if MarkState::is_mark(letter) => self.status.error(format!("Mark '{} isn't set", letter)),
+                    None => {}
+                },
+                NormalModeAction::RecordMacro => match self.macros.stop(pressed) {
+                    Some((register, len)) => self.status.info(format!("Recorded {} keys into @{}", len, register)),
+                    None => self.start_chord('Q'),
+                },
+                NormalModeAction::StartRecording(register) => {
+                    if !self.macros.start(register) {
+                        anyhow::bail!("Macros are recorded into a letter, not {}", register);
+                    }
+                }
+                NormalModeAction::ReplayMacro(register) => self.replay_macro(register, count.unwrap_or(1))?,
                 NormalModeAction::MoveSelectedItemsToCursor => {
-                    if let Some(new_index) = self.perform_bulk_move(&self.navigation.selected_items.clone(), self.navigation.selected_index) {
+                    if let Some(new_index) = self.perform_bulk_move(&self.selection_in_zoom(), self.navigation.selected_index) {
                         self.navigation.selected_index = new_index;
                         self.navigation.clear_selection();
                         self.navigation.update_scroll();
                     }
                 }
-                NormalModeAction::ToggleHelpMode => self.help_mode = true,
+                NormalModeAction::ToggleHelpMode => self.open_help(0),
                 NormalModeAction::Undo => self.perform_undo()?,
-                NormalModeAction::EnterSearchMode => self.search_state.enter_search_mode(),
-                NormalModeAction::DeleteItem => {
-                    if !self.navigation.selected_items.is_empty() {
-                        // Bulk delete mode
-                        let deleted_count = self.perform_bulk_delete(&self.navigation.selected_items.clone());
-                        if deleted_count > 0 {
-                            self.navigation.clear_selection();
-                        }
+                NormalModeAction::Redo => self.perform_redo()?,
+                NormalModeAction::EnterSearchMode => self.start_search(false)?,
+                NormalModeAction::EnterSectionSearchMode => self.start_search(true)?,
+                NormalModeAction::DeleteItem => self.request_delete(false),
+                NormalModeAction::DeleteSubtree => self.request_delete(true),
+                NormalModeAction::DuplicateItem => {
+                    if let Some(new_index) = self.perform_duplicate_item(self.navigation.selected_index) {
+                        self.navigation.selected_index = new_index;
+                        self.navigation.update_scroll();
+                    }
+                }
+                NormalModeAction::OpenFilePicker => self.file_picker.open(&self.todo_list.file_path),
+                NormalModeAction::OpenMovePicker => self.open_move_picker()?,
+                NormalModeAction::ToggleZoom => self.toggle_zoom()?,
+                NormalModeAction::PromoteSection => self.shift_section(-1)?,
+                NormalModeAction::DemoteSection => self.shift_section(1)?,
+                NormalModeAction::YankItems => self.yank_items(),
+                NormalModeAction::PasteItems => self.paste_items()?,
+                NormalModeAction::CopyItemsToClipboard => self.copy_items_to_clipboard(),
+                NormalModeAction::ToggleHideCompleted => {
+                    self.hide_completed = !self.hide_completed;
+                    // Indices that are now hidden can't stay selected
+                    if self.hide_completed {
+                        let items = &self.todo_list.items;
+                        self.navigation.selected_items.retain(|&i| !items[i].is_completed());
+                    }
+                }
+                NormalModeAction::TogglePendingOnly => {
+                    self.pending_only = !self.pending_only;
+                    if self.pending_only {
+                        let shown = self.pending_indices();
+                        self.navigation.selected_items.retain(|i| shown.contains(i));
+                    }
+                }
+                NormalModeAction::ToggleHideBlocked => {
+                    self.hide_blocked = !self.hide_blocked;
+                    if self.hide_blocked {
+                        let blocked = self.blocked_todos();
+                        self.navigation.selected_items.retain(|i| !blocked.contains(i));
+                    }
+                }
+                NormalModeAction::ToggleVisualMode => {
+                    if self.navigation.is_visual_mode() {
+                        self.navigation.exit_visual_mode();
+                    } else {
+                        self.navigation.enter_visual_mode(&self.visible_indices());
+                    }
+                }
+                NormalModeAction::ToggleFold => {
+                    self.folds.toggle(&self.todo_list.items, self.navigation.selected_index);
+                }
+                NormalModeAction::CollapseItem => {
+                    self.folds.fold(&self.todo_list.items, self.navigation.selected_index);
+                }
+                NormalModeAction::ExpandItem => {
+                    self.folds.unfold(self.navigation.selected_index);
+                }
+                // The keymap leaves these pending rather than returning them
+                NormalModeAction::StartPrefix(_) => {}
+                NormalModeAction::OpenTagPanel => self.open_tag_panel(),
+                NormalModeAction::OpenAgenda => self.agenda.open(&self.todo_list.items, today()),
+                NormalModeAction::OpenCalendar => self.calendar.open(today()),
+                NormalModeAction::OpenBoard => self.board.open(&self.todo_list.items, self.navigation.selected_index),
+                NormalModeAction::OpenInEditor => self.request_external_edit()?,
+                NormalModeAction::OpenLink => self.open_link()?,
+                NormalModeAction::FollowReference => self.follow_reference()?,
+                NormalModeAction::OpenPreviousDay => self.open_neighbouring_day(false)?,
+                NormalModeAction::OpenNextDay => self.open_neighbouring_day(true)?,
+                NormalModeAction::TogglePomodoro => {
+                    if self.pomodoro.is_running() {
+                        self.pomodoro.stop();
+                        self.status.info("Pomodoro stopped");
+                    } else if self.pomodoro.start(&self.todo_list.items, self.navigation.selected_index, Instant::now()) {
+                        self.status.info("Pomodoro started");
                     } else {
-                        // Single item delete mode
-                        self.perform_delete_item(self.navigation.selected_index);
+                        anyhow::bail!("Not on a todo");
+                    }
+                }
+                // Opens the prompt ready for Enter, or for naming another order
+                NormalModeAction::SortSection => {
+                    self.command_line.enter_command_mode();
+                    self.command_line.input = "sort ".to_string();
+                }
+                NormalModeAction::FocusOutline => self.outline.focus(),
+                NormalModeAction::EnterCommandMode => self.command_line.enter_command_mode(),
+                NormalModeAction::MoveToPreviousHeading => {
+                    let visible_indices = self.visible_indices();
+                    for _ in 0..count.unwrap_or(1) {
+                        self.navigation.move_to_previous_heading(&self.todo_list.items, &visible_indices);
+                    }
+                }
+                // With a count, both go to that row, like vim's `5G`
+                NormalModeAction::MoveToFirstItem | NormalModeAction::MoveToLastItem if count.is_some() => {
+                    let visible_indices = self.visible_indices();
+                    self.navigation.move_to_first(&visible_indices);
+                    self.navigation.move_by_rows(count.unwrap_or(1) as isize - 1, &visible_indices);
+                }
+                NormalModeAction::MoveToFirstItem => self.navigation.move_to_first(&self.visible_indices()),
+                NormalModeAction::MoveToLastItem => self.navigation.move_to_last(&self.visible_indices()),
+                NormalModeAction::MoveHalfPageUp => {
+                    let rows = self.navigation.half_page();
+                    self.navigation.move_by_rows(-rows, &self.visible_indices());
+                }
+                NormalModeAction::MoveHalfPageDown => {
+                    let rows = self.navigation.half_page();
+                    self.navigation.move_by_rows(rows, &self.visible_indices());
+                }
+                NormalModeAction::MovePageUp => {
+                    let rows = self.navigation.page();
+                    self.navigation.move_by_rows(-rows, &self.visible_indices());
+                }
+                NormalModeAction::MovePageDown => {
+                    let rows = self.navigation.page();
+                    self.navigation.move_by_rows(rows, &self.visible_indices());
+                }
+                NormalModeAction::MoveToNextHeading => {
+                    let visible_indices = self.visible_indices();
+                    for _ in 0..count.unwrap_or(1) {
+                        self.navigation.move_to_next_heading(&self.todo_list.items, &visible_indices);
+                    }
+                }
+                NormalModeAction::MoveToPreviousOpenTodo | NormalModeAction::MoveToNextOpenTodo => {
+                    let forward = action == NormalModeAction::MoveToNextOpenTodo;
+                    let visible_indices = self.visible_indices();
+                    let moved = (0..count.unwrap_or(1))
+                        .take_while(|_| self.navigation.move_to_open_todo(&self.todo_list.items, &visible_indices, forward))
+                        .count();
+                    if moved == 0 {
+                        self.status.info(if forward { "No unchecked todos below" } else { "No unchecked todos above" });
                     }
                 }
                 NormalModeAction::None => {}
@@ -290,15 +2611,73 @@ impl ActionPerformer for App {
     fn perform_toggle_completion(&mut self, index: usize) -> bool {
         if matches!(self.todo_list.items.get(index), Some(ListItem::Todo { .. })) {
             self.save_current_state();
+            let before = self.completion_snapshot();
             let result = ItemActions::toggle_todo_completion(&mut self.todo_list.items, index);
             
             if result {
+                if self.auto_complete_parents {
+                    ItemActions::update_parent_completion(&mut self.todo_list.items, index);
+                }
+                self.record_completions(before);
+                
+                // Clear search results when items are modified
+                self.search_state.clear_results();
+                
+                // Save changes to file
+                if let Err(e) = self.save_to_file() {
+                    self.status.error(format!("Failed to save file: {:#}", e));
+                }
+            }
+            result
+        } else {
+            false
+        }
+    }
+
+    fn perform_toggle_subtree_completion(&mut self, index: usize) -> bool {
+        if matches!(self.todo_list.items.get(index), Some(ListItem::Todo { .. })) {
+            self.save_current_state();
+            let before = self.completion_snapshot();
+            let result = ItemActions::toggle_block_completion(&mut self.todo_list.items, index);
+            
+            if result {
+                if self.auto_complete_parents {
+                    ItemActions::update_parent_completion(&mut self.todo_list.items, index);
+                }
+                self.record_completions(before);
+                
+                // Clear search results when items are modified
+                self.search_state.clear_results();
+                
+                // Save changes to file
+                if let Err(e) = self.save_to_file() {
+                    self.status.error(format!("Failed to save file: {:#}", e));
+                }
+            }
+            result
+        } else {
+            false
+        }
+    }
+
+    fn perform_cycle_state(&mut self, index: usize) -> bool {
+        if matches!(self.todo_list.items.get(index), Some(ListItem::Todo { .. })) {
+            self.save_current_state();
+            let before = self.completion_snapshot();
+            let result = ItemActions::cycle_todo_state(&mut self.todo_list.items, index);
+            
+            if result {
+                if self.auto_complete_parents {
+                    ItemActions::update_parent_completion(&mut self.todo_list.items, index);
+                }
+                self.record_completions(before);
+                
                 // Clear search results when items are modified
                 self.search_state.clear_results();
                 
                 // Save changes to file
-                if let Err(e) = self.todo_list.save_to_file() {
-                    eprintln!("Failed to save file: {}", e);
+                if let Err(e) = self.save_to_file() {
+                    self.status.error(format!("Failed to save file: {:#}", e));
                 }
             }
             result
@@ -307,14 +2686,34 @@ impl ActionPerformer for App {
         }
     }
 
+    fn perform_set_state(&mut self, index: usize, state: TodoState) -> bool {
+        self.save_current_state();
+        let before = self.completion_snapshot();
+        let result = ItemActions::set_todo_state(&mut self.todo_list.items, index, state);
+
+        if result {
+            if self.auto_complete_parents {
+                ItemActions::update_parent_completion(&mut self.todo_list.items, index);
+            }
+            self.record_completions(before);
+            self.search_state.clear_results();
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
     fn perform_move_item_up(&mut self, index: usize) -> Option<usize> {
         self.save_current_state();
         let result = ItemActions::move_single_item_up(&mut self.todo_list.items, index);
         
-        if result.is_some() {
+        if let Some(new_index) = result {
+            let entries = self.activity_entries(Activity::Moved, [new_index]);
+            self.append_to_log(&entries);
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         result
@@ -324,10 +2723,12 @@ impl ActionPerformer for App {
         self.save_current_state();
         let result = ItemActions::move_single_item_down(&mut self.todo_list.items, index);
         
-        if result.is_some() {
+        if let Some(new_index) = result {
+            let entries = self.activity_entries(Activity::Moved, [new_index]);
+            self.append_to_log(&entries);
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         result
@@ -339,8 +2740,8 @@ impl ActionPerformer for App {
         
         if result {
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         result
@@ -352,8 +2753,55 @@ impl ActionPerformer for App {
         
         if result {
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
+    fn perform_bulk_toggle_completion(&mut self, selected_indices: &HashSet<usize>) -> bool {
+        self.save_current_state();
+        let before = self.completion_snapshot();
+        let result = ItemActions::toggle_selected_completion(&mut self.todo_list.items, selected_indices);
+
+        if result {
+            if self.auto_complete_parents {
+                for &index in selected_indices {
+                    ItemActions::update_parent_completion(&mut self.todo_list.items, index);
+                }
+            }
+            self.record_completions(before);
+
+            self.search_state.clear_results();
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
+    fn perform_bulk_indent(&mut self, selected_indices: &std::collections::HashSet<usize>) -> bool {
+        self.save_current_state();
+        let result = ItemActions::indent_selected_items(&mut self.todo_list.items, selected_indices);
+        
+        if result {
+            // Save changes to file
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
+    fn perform_bulk_unindent(&mut self, selected_indices: &std::collections::HashSet<usize>) -> bool {
+        self.save_current_state();
+        let result = ItemActions::unindent_selected_items(&mut self.todo_list.items, selected_indices);
+        
+        if result {
+            // Save changes to file
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         result
@@ -365,12 +2813,55 @@ impl ActionPerformer for App {
         }
 
         self.save_current_state();
+        let entries = self.activity_entries(Activity::Moved, selected_indices.iter().copied());
         let result = ItemActions::move_selected_items_to_position(&mut self.todo_list.items, selected_indices, target_index);
         
         if result.is_some() {
+            self.append_to_log(&entries);
+            // Save changes to file
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
+    fn perform_move_to_section(&mut self, indices: &HashSet<usize>, heading_index: usize) -> Option<usize> {
+        self.save_current_state();
+        let entries = self.activity_entries(Activity::Moved, indices.iter().copied());
+        let result = ItemActions::move_items_to_section(&mut self.todo_list.items, indices, heading_index);
+
+        if result.is_some() {
+            self.append_to_log(&entries);
+            self.search_state.clear_results();
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
+            }
+        }
+        result
+    }
+
+    fn perform_shift_heading(&mut self, index: usize, delta: isize, with_subheadings: bool) -> bool {
+        self.save_current_state();
+        let result = ItemActions::shift_heading_level(&mut self.todo_list.items, index, delta, with_subheadings);
+
+        if result && let Err(e) = self.save_to_file() {
+            self.status.error(format!("Failed to save file: {:#}", e));
+        }
+        result
+    }
+
+    fn perform_duplicate_item(&mut self, index: usize) -> Option<usize> {
+        self.save_current_state();
+        let result = ItemActions::duplicate_block(&mut self.todo_list.items, index);
+        
+        if result.is_some() {
+            // Clear search results when items are modified
+            self.search_state.clear_results();
+            
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         result
@@ -394,8 +2885,8 @@ impl ActionPerformer for App {
                 self.navigation.update_scroll();
                 
                 // Save changes to file
-                if let Err(e) = self.todo_list.save_to_file() {
-                    eprintln!("Failed to save file: {}", e);
+                if let Err(e) = self.save_to_file() {
+                    self.status.error(format!("Failed to save file: {:#}", e));
                 }
             }
             result
@@ -425,8 +2916,8 @@ impl ActionPerformer for App {
             self.navigation.update_scroll();
             
             // Save changes to file
-            if let Err(e) = self.todo_list.save_to_file() {
-                eprintln!("Failed to save file: {}", e);
+            if let Err(e) = self.save_to_file() {
+                self.status.error(format!("Failed to save file: {:#}", e));
             }
         }
         
@@ -436,15 +2927,8 @@ impl ActionPerformer for App {
 
 impl Editable for App {
     fn enter_edit_mode_for_item(&mut self, item_index: usize) {
-        if item_index < self.todo_list.items.len() {
-            if let Some(item) = self.todo_list.items.get(item_index) {
-                let content = match item {
-                    ListItem::Todo { content, .. } => content.clone(),
-                    ListItem::Note { content, .. } => content.clone(),
-                    ListItem::Heading { content, .. } => content.clone(),
-                };
-                self.edit_state.enter_edit_mode(content);
-            }
+        if let Some(item) = self.todo_list.items.get(item_index) {
+            self.edit_state.enter_edit_mode(item.content().to_string());
         }
     }
 
@@ -463,37 +2947,45 @@ impl Editable for App {
                 if self.navigation.selected_index >= self.todo_list.items.len() && !self.todo_list.items.is_empty() {
                     self.navigation.selected_index = self.todo_list.items.len() - 1;
                 }
+                
+                // Nothing was added after all, so there's nothing to undo
+                if self.edit_state.adding_new_todo {
+                    self.undo_manager.discard_last();
+                }
             }
         }
         
         self.edit_state.exit_edit_mode();
         
         // Save changes to file (in case we removed an empty todo)
-        self.todo_list.save_to_file()
+        self.save_to_file()
     }
 
     fn confirm_edit(&mut self) -> Result<()> {
+        // Whether to start another item of the same kind below this one
+        let mut continue_with_note = None;
         if self.navigation.selected_index < self.todo_list.items.len() {
             // Only save state if we're not confirming a newly added todo
             if !self.edit_state.adding_new_todo {
                 self.save_current_state();
             }
 
+            let untouched_template = self.edit_state.adding_new_todo && self.edit_state.is_untouched_template();
             let should_remove = if let Some(item) = self.todo_list.items.get_mut(self.navigation.selected_index) {
                 match item {
                     ListItem::Todo { content, .. } => {
-                        *content = self.edit_state.edit_buffer.clone();
-                        // Remove todo if it's empty after editing
-                        self.edit_state.edit_buffer.trim().is_empty()
+                        *content = normalize_multiline(&self.edit_state.edit_buffer);
+                        // Remove todo if it's empty after editing, or still just its template
+                        content.trim().is_empty() || untouched_template
                     }
                     ListItem::Note { content, .. } => {
-                        *content = self.edit_state.edit_buffer.clone();
+                        *content = normalize_multiline(&self.edit_state.edit_buffer);
                         // Remove note if it's empty after editing
-                        self.edit_state.edit_buffer.trim().is_empty()
+                        content.trim().is_empty()
                     }
-                    ListItem::Heading { content, .. } => {
+                    ListItem::Heading { content, .. } | ListItem::Raw { content } => {
                         *content = self.edit_state.edit_buffer.clone();
-                        // Don't remove headings even if empty
+                        // Don't remove headings or blank lines even if empty
                         false
                     }
                 }
@@ -508,6 +3000,20 @@ impl Editable for App {
                 if self.navigation.selected_index >= self.todo_list.items.len() && !self.todo_list.items.is_empty() {
                     self.navigation.selected_index = self.todo_list.items.len() - 1;
                 }
+                
+                if self.edit_state.adding_new_todo {
+                    self.undo_manager.discard_last();
+                }
+            } else if self.edit_state.adding_new_todo {
+                let index = self.navigation.selected_index;
+                if self.continue_lists {
+                    continue_with_note = match &self.todo_list.items[index] {
+                        ListItem::Todo { .. } => Some(false),
+                        ListItem::Note { .. } => Some(true),
+                        _ => None,
+                    };
+                }
+                self.finish_adding(index);
             }
         }
         
@@ -517,18 +3023,45 @@ impl Editable for App {
         self.search_state.clear_results();
         
         // Save changes to file
-        self.todo_list.save_to_file()
+        self.save_to_file()?;
+        
+        if let Some(note) = continue_with_note {
+            self.add_sibling(false, note)?;
+        }
+        Ok(())
     }
 }
 
-impl UndoableApp for App {
-    fn save_current_state(&mut self) {
-        let state = AppState::new(
+impl App {
+    // What happens once a new item at `index` is confirmed: its creation
+    // date, the activity log, hooks and plugins
+    fn finish_adding(&mut self, index: usize) {
+        let item = &mut self.todo_list.items[index];
+        if self.stamp_created_date && matches!(item, ListItem::Todo { .. } | ListItem::Note { .. }) {
+            *item.content_mut() = metadata::stamp_created_date(item.content(), today());
+        }
+        let entries = self.activity_entries(Activity::Added, [index]);
+        self.append_to_log(&entries);
+        self.run_hook(HookEvent::Add, Some(index));
+        self.run_plugin_event(PluginEvent::Added, index);
+    }
+
+    fn current_state(&self) -> AppState {
+        AppState::new(
             self.todo_list.clone(),
             self.navigation.selected_index,
             self.navigation.selected_items.clone(),
-        );
-        self.undo_manager.save_state(state);
+        )
+    }
+}
+
+impl UndoableApp for App {
+    fn save_current_state(&mut self) {
+        // Copying a large list for a snapshot that would be thrown away adds up
+        if self.undo_manager.wants_snapshot() {
+            let state = self.current_state();
+            self.undo_manager.save_state(state);
+        }
     }
 
     fn restore_state(&mut self, state: AppState) -> Result<()> {
@@ -540,11 +3073,22 @@ impl UndoableApp for App {
     }
 
     fn perform_undo(&mut self) -> Result<()> {
-        if let Some(state) = self.undo_manager.undo() {
+        if let Some(state) = self.undo_manager.undo(self.current_state()) {
+            self.restore_state(state)?;
+            
+            // Save changes to file
+            self.save_to_file()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn perform_redo(&mut self) -> Result<()> {
+        if let Some(state) = self.undo_manager.redo(self.current_state()) {
             self.restore_state(state)?;
             
             // Save changes to file
-            self.todo_list.save_to_file()
+            self.save_to_file()
         } else {
             Ok(())
         }
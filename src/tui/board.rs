@@ -0,0 +1,254 @@
+//! The board view (`g b`): the list laid out as columns of cards, like a
+//! kanban board. Columns are the top-level headings, or the todo states;
+//! cards are the top-level todos in them. It's only another view of the same
+//! items, so moving a card between columns moves the todo to another section
+//! or changes its state.
+
+use todo_core::models::{ListItem, TodoState};
+
+/// What the columns are made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardLayout {
+    Sections,
+    States,
+}
+
+// Left to right, as work usually moves through them
+const STATE_COLUMNS: [TodoState; 5] = [
+    TodoState::Open,
+    TodoState::InProgress,
+    TodoState::Deferred,
+    TodoState::Done,
+    TodoState::Cancelled,
+];
+
+/// Where a card moved into a column ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnTarget {
+    /// Under the heading at this index
+    Heading(usize),
+    State(TodoState),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardColumn {
+    pub title: String,
+    /// None for the todos above the first heading, which can't be moved back there
+    pub target: Option<ColumnTarget>,
+    /// Indices of the todos shown as cards
+    pub cards: Vec<usize>,
+}
+
+pub struct BoardState {
+    pub board_mode: bool,
+    pub layout: BoardLayout,
+    pub columns: Vec<BoardColumn>,
+    pub selected_column: usize,
+    pub selected_card: usize,
+}
+
+impl BoardState {
+    pub fn new() -> Self {
+        Self {
+            board_mode: false,
+            layout: BoardLayout::Sections,
+            columns: Vec::new(),
+            selected_column: 0,
+            selected_card: 0,
+        }
+    }
+
+    /// Opens the board with the card for the item at `index` selected, if it has one
+    pub fn open(&mut self, items: &[ListItem], index: usize) {
+        self.board_mode = true;
+        self.refresh(items);
+        self.select_item(index);
+    }
+
+    pub fn close(&mut self) {
+        self.board_mode = false;
+    }
+
+    /// Switches between section and state columns, keeping the selected card
+    pub fn toggle_layout(&mut self, items: &[ListItem]) {
+        let selected = self.selected_card();
+        self.layout = match self.layout {
+            BoardLayout::Sections => BoardLayout::States,
+            BoardLayout::States => BoardLayout::Sections,
+        };
+        self.refresh(items);
+        if let Some(index) = selected {
+            self.select_item(index);
+        }
+    }
+
+    /// Rebuilds the columns after the list changed, keeping the selection in range
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        self.columns = build_columns(items, self.layout);
+        self.selected_column = self.selected_column.min(self.columns.len().saturating_sub(1));
+        self.clamp_card();
+    }
+
+    /// Selects the card for the item at `index`, if there is one
+    pub fn select_item(&mut self, index: usize) {
+        for (column, board_column) in self.columns.iter().enumerate() {
+            if let Some(card) = board_column.cards.iter().position(|&card| card == index) {
+                self.selected_column = column;
+                self.selected_card = card;
+                return;
+            }
+        }
+    }
+
+    pub fn move_column(&mut self, delta: isize) {
+        self.selected_column = self
+            .selected_column
+            .saturating_add_signed(delta)
+            .min(self.columns.len().saturating_sub(1));
+        self.clamp_card();
+    }
+
+    pub fn move_card_selection(&mut self, delta: isize) {
+        self.selected_card = self.selected_card.saturating_add_signed(delta);
+        self.clamp_card();
+    }
+
+    fn clamp_card(&mut self) {
+        let cards = self.columns.get(self.selected_column).map_or(0, |column| column.cards.len());
+        self.selected_card = self.selected_card.min(cards.saturating_sub(1));
+    }
+
+    /// The item index of the selected card
+    pub fn selected_card(&self) -> Option<usize> {
+        self.columns.get(self.selected_column)?.cards.get(self.selected_card).copied()
+    }
+
+    /// Where the selected card goes when moved `delta` columns over
+    pub fn neighbour_target(&self, delta: isize) -> Option<ColumnTarget> {
+        let column = self.selected_column.checked_add_signed(delta)?;
+        self.columns.get(column)?.target
+    }
+}
+
+impl Default for BoardState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The columns for `items`, with the top-level todos as cards
+pub fn build_columns(items: &[ListItem], layout: BoardLayout) -> Vec<BoardColumn> {
+    let is_card = |item: &ListItem| matches!(item, ListItem::Todo { indent_level: 0, .. });
+    match layout {
+        BoardLayout::States => STATE_COLUMNS
+            .iter()
+            .map(|&column_state| BoardColumn {
+                title: state_title(column_state).to_string(),
+                target: Some(ColumnTarget::State(column_state)),
+                cards: items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| is_card(item) && matches!(item, ListItem::Todo { state, .. } if *state == column_state))
+                    .map(|(index, _)| index)
+                    .collect(),
+            })
+            .collect(),
+        BoardLayout::Sections => {
+            // Deeper headings are part of the column of the heading above them
+            let top_level = items
+                .iter()
+                .filter_map(|item| match item {
+                    ListItem::Heading { level, .. } => Some(*level),
+                    _ => None,
+                })
+                .min();
+            let mut columns = vec![BoardColumn { title: "(no heading)".to_string(), target: None, cards: Vec::new() }];
+            for (index, item) in items.iter().enumerate() {
+                match item {
+                    ListItem::Heading { content, level } if Some(*level) == top_level => columns.push(BoardColumn {
+                        title: content.clone(),
+                        target: Some(ColumnTarget::Heading(index)),
+                        cards: Vec::new(),
+                    }),
+                    item if is_card(item) => columns.last_mut().unwrap().cards.push(index),
+                    _ => {}
+                }
+            }
+            // Only shown when there's something above the first heading
+            if columns[0].cards.is_empty() {
+                columns.remove(0);
+            }
+            columns
+        }
+    }
+}
+
+fn state_title(state: TodoState) -> &'static str {
+    match state {
+        TodoState::Open => "Open",
+        TodoState::InProgress => "In progress",
+        TodoState::Deferred => "Deferred",
+        TodoState::Done => "Done",
+        TodoState::Cancelled => "Cancelled",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Loose".to_string(), false, 0),
+            ListItem::new_heading("Todo".to_string(), 1),
+            ListItem::new_todo("Write".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 1),
+            ListItem::new_heading("Later".to_string(), 2),
+            ListItem::new_todo_with_state("Read".to_string(), TodoState::InProgress, 0),
+            ListItem::new_heading("Done".to_string(), 1),
+            ListItem::new_todo("Shipped".to_string(), true, 0),
+        ]
+    }
+
+    fn cards(columns: &[BoardColumn]) -> Vec<(&str, Vec<usize>)> {
+        columns.iter().map(|column| (column.title.as_str(), column.cards.clone())).collect()
+    }
+
+    #[test]
+    fn test_section_columns() {
+        let columns = build_columns(&items(), BoardLayout::Sections);
+        assert_eq!(cards(&columns), [("(no heading)", vec![0]), ("Todo", vec![2, 5]), ("Done", vec![7])]);
+        assert_eq!(columns[0].target, None);
+        assert_eq!(columns[2].target, Some(ColumnTarget::Heading(6)));
+
+        let columns = build_columns(&items()[1..], BoardLayout::Sections);
+        assert_eq!(columns[0].title, "Todo");
+    }
+
+    #[test]
+    fn test_state_columns() {
+        let columns = build_columns(&items(), BoardLayout::States);
+        assert_eq!(columns.len(), STATE_COLUMNS.len());
+        assert_eq!(cards(&columns)[..2], [("Open", vec![0, 2]), ("In progress", vec![5])]);
+        assert_eq!(columns[3].cards, vec![7]);
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut board = BoardState::new();
+        board.open(&items(), 5);
+        assert_eq!((board.selected_column, board.selected_card), (1, 1));
+        assert_eq!(board.neighbour_target(1), Some(ColumnTarget::Heading(6)));
+        assert_eq!(board.neighbour_target(-1), None);
+
+        board.move_column(1);
+        assert_eq!(board.selected_card(), Some(7));
+        board.move_column(5);
+        assert_eq!(board.selected_column, 2);
+
+        board.toggle_layout(&items());
+        assert_eq!((board.selected_column, board.selected_card()), (3, Some(7)));
+        board.move_card_selection(-1);
+        assert_eq!(board.selected_card(), Some(7));
+    }
+}
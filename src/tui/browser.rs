@@ -0,0 +1,23 @@
+use anyhow::{bail, Context, Result};
+use std::process::{Command, Stdio};
+
+#[cfg(target_os = "macos")]
+const OPEN_COMMAND: &str = "open";
+#[cfg(not(target_os = "macos"))]
+const OPEN_COMMAND: &str = "xdg-open";
+
+/// Opens `url` in the system's default browser
+pub fn open_url(url: &str) -> Result<()> {
+    // Anything the opener prints would end up on top of the TUI
+    let status = Command::new(OPEN_COMMAND)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run '{}'", OPEN_COMMAND))?;
+    if !status.success() {
+        bail!("'{}' couldn't open {}", OPEN_COMMAND, url);
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! The calendar (`g c`): a month grid with the number of open todos due on
+//! each day, for planning ahead. Enter lists a day's todos to jump to one.
+
+use chrono::{Datelike, Days, Months, NaiveDate};
+use std::collections::HashMap;
+use todo_core::{metadata, models::ListItem};
+
+pub struct CalendarState {
+    pub calendar_mode: bool,
+    pub selected_date: NaiveDate,
+    /// Indices of the selected day's todos, once Enter has opened it
+    pub day_todos: Option<Vec<usize>>,
+    pub day_selected_index: usize,
+}
+
+impl CalendarState {
+    pub fn new(today: NaiveDate) -> Self {
+        Self {
+            calendar_mode: false,
+            selected_date: today,
+            day_todos: None,
+            day_selected_index: 0,
+        }
+    }
+
+    pub fn open(&mut self, today: NaiveDate) {
+        self.calendar_mode = true;
+        self.selected_date = today;
+        self.day_todos = None;
+    }
+
+    pub fn close(&mut self) {
+        self.calendar_mode = false;
+        self.day_todos = None;
+    }
+
+    pub fn move_days(&mut self, days: i64) {
+        let moved = if days < 0 {
+            self.selected_date.checked_sub_days(Days::new(days.unsigned_abs()))
+        } else {
+            self.selected_date.checked_add_days(Days::new(days as u64))
+        };
+        self.selected_date = moved.unwrap_or(self.selected_date);
+    }
+
+    /// Moves to the same day in another month, or its last day if it's shorter
+    pub fn move_months(&mut self, months: i32) {
+        let moved = if months < 0 {
+            self.selected_date.checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            self.selected_date.checked_add_months(Months::new(months as u32))
+        };
+        self.selected_date = moved.unwrap_or(self.selected_date);
+    }
+
+    /// Lists the selected day's todos
+    pub fn open_day(&mut self, items: &[ListItem]) {
+        self.day_todos = Some(todos_due_on(items, self.selected_date));
+        self.day_selected_index = 0;
+    }
+
+    pub fn close_day(&mut self) {
+        self.day_todos = None;
+    }
+
+    /// Re-reads the open day's todos after the list changed
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        if let Some(todos) = &mut self.day_todos {
+            *todos = todos_due_on(items, self.selected_date);
+            self.day_selected_index = self.day_selected_index.min(todos.len().saturating_sub(1));
+        }
+    }
+
+    pub fn move_day_selection_up(&mut self) {
+        self.day_selected_index = self.day_selected_index.saturating_sub(1);
+    }
+
+    pub fn move_day_selection_down(&mut self) {
+        let count = self.day_todos.as_ref().map_or(0, Vec::len);
+        if self.day_selected_index + 1 < count {
+            self.day_selected_index += 1;
+        }
+    }
+
+    pub fn selected_todo(&self) -> Option<usize> {
+        self.day_todos.as_ref()?.get(self.day_selected_index).copied()
+    }
+}
+
+/// How many open todos are due on each date
+pub fn due_counts(items: &[ListItem]) -> HashMap<NaiveDate, usize> {
+    let mut counts = HashMap::new();
+    for (_, due) in open_due_dates(items) {
+        *counts.entry(due).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Indices of the open todos due on `date`, in list order
+pub fn todos_due_on(items: &[ListItem], date: NaiveDate) -> Vec<usize> {
+    open_due_dates(items).filter(|&(_, due)| due == date).map(|(index, _)| index).collect()
+}
+
+fn open_due_dates(items: &[ListItem]) -> impl Iterator<Item = (usize, NaiveDate)> + '_ {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| matches!(item, ListItem::Todo { .. }) && !item.is_completed())
+        .filter_map(|(index, item)| Some((index, metadata::due_date(item.content())?)))
+}
+
+/// The weeks (Monday first) of the month containing `date`, with days from
+/// the neighbouring months left out
+pub fn month_grid(date: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = date.with_day(1).unwrap_or(date);
+    let mut weeks = Vec::new();
+    let mut week = [None; 7];
+    let mut day = first;
+    while day.month() == first.month() {
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        week[weekday] = Some(day);
+        if weekday == 6 {
+            weeks.push(std::mem::take(&mut week));
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    if week.iter().any(Option::is_some) {
+        weeks.push(week);
+    }
+    weeks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Report 📅 2024-05-12".to_string(), false, 0),
+            ListItem::new_todo("Invoice 📅 2024-05-12".to_string(), false, 1),
+            ListItem::new_todo("Done already 📅 2024-05-12".to_string(), true, 0),
+            ListItem::new_todo("Later 📅 2024-06-01".to_string(), false, 0),
+            ListItem::new_note("Note 📅 2024-05-12".to_string(), 0),
+        ]
+    }
+
+    #[test]
+    fn test_due_counts() {
+        let counts = due_counts(&create_test_items());
+        assert_eq!(counts.get(&date(5, 12)), Some(&2));
+        assert_eq!(counts.get(&date(6, 1)), Some(&1));
+        assert_eq!(counts.len(), 2);
+        assert_eq!(todos_due_on(&create_test_items(), date(5, 12)), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_month_grid() {
+        // May 2024 starts on a Wednesday and ends on a Friday
+        let weeks = month_grid(date(5, 20));
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][..3], [None, None, Some(date(5, 1))]);
+        assert_eq!(weeks[4][4], Some(date(5, 31)));
+        assert_eq!(weeks[4][5], None);
+    }
+
+    #[test]
+    fn test_navigation_and_day() {
+        let mut calendar = CalendarState::new(date(1, 31));
+        calendar.open(date(1, 31));
+        calendar.move_months(1);
+        assert_eq!(calendar.selected_date, date(2, 29));
+        calendar.move_days(-7);
+        assert_eq!(calendar.selected_date, date(2, 22));
+
+        calendar.selected_date = date(5, 12);
+        calendar.open_day(&create_test_items());
+        calendar.move_day_selection_down();
+        calendar.move_day_selection_down();
+        assert_eq!(calendar.selected_todo(), Some(1));
+
+        calendar.refresh(&create_test_items()[..1]);
+        assert_eq!(calendar.selected_todo(), Some(0));
+    }
+}
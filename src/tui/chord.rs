@@ -0,0 +1,89 @@
+//! Keys of a multi-key command (`g g`, `] t`, or a sequence bound in
+//! `[keys]` such as `"space f"`) typed so far. They're dropped if the next
+//! one doesn't come within `chord_timeout_ms`.
+
+use crate::tui::keymap::KeyPress;
+use std::time::{Duration, Instant};
+
+pub struct ChordState {
+    keys: Vec<KeyPress>,
+    // When the last key was pressed
+    pressed_at: Option<Instant>,
+    /// None waits for the next key however long it takes
+    timeout: Option<Duration>,
+}
+
+impl ChordState {
+    /// A timeout of 0 never gives up on the next key
+    pub fn new(timeout_ms: u64) -> Self {
+        Self {
+            keys: Vec::new(),
+            pressed_at: None,
+            timeout: (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms)),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[KeyPress] {
+        &self.keys
+    }
+
+    pub fn push(&mut self, key: KeyPress, now: Instant) {
+        self.keys.push(key);
+        self.pressed_at = Some(now);
+    }
+
+    /// The keys so far, leaving none pending
+    pub fn take(&mut self) -> Vec<KeyPress> {
+        self.pressed_at = None;
+        std::mem::take(&mut self.keys)
+    }
+
+    /// Drops the keys once the next one has been waited for too long; true
+    /// if that happened
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let expired = match (self.pressed_at, self.timeout) {
+            (Some(pressed_at), Some(timeout)) => now.saturating_duration_since(pressed_at) >= timeout,
+            _ => false,
+        };
+        if expired {
+            self.take();
+        }
+        expired
+    }
+
+    /// The keys as written in the help window, e.g. `g` or `Space f`
+    pub fn label(&self) -> String {
+        self.keys.iter().map(|key| key.label()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn key(c: char) -> KeyPress {
+        KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_timeout() {
+        let now = Instant::now();
+        let mut chord = ChordState::new(1000);
+        chord.push(key(' '), now);
+        chord.push(key('f'), now + Duration::from_millis(600));
+        assert_eq!(chord.label(), "Space f");
+        assert!(!chord.expire(now + Duration::from_millis(1500)));
+        assert!(chord.expire(now + Duration::from_millis(1600)));
+        assert!(!chord.is_pending());
+
+        let mut chord = ChordState::new(0);
+        chord.push(key('g'), now);
+        assert!(!chord.expire(now + Duration::from_secs(60)));
+        assert_eq!(chord.take(), [key('g')]);
+    }
+}
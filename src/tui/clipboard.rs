@@ -0,0 +1,106 @@
+use todo_core::{models::ListItem, writer::serialize_item};
+use anyhow::{Context, Result};
+
+/// Internal register holding yanked items until they're pasted
+#[derive(Default)]
+pub struct Register {
+    items: Vec<ListItem>,
+}
+
+impl Register {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Stores copies of the items at `indices` (in list order)
+    pub fn yank(&mut self, items: &[ListItem], indices: &[usize]) {
+        let mut indices = indices.to_vec();
+        indices.sort();
+        indices.dedup();
+        self.items = indices.iter().filter_map(|&i| items.get(i).cloned()).collect();
+    }
+
+    /// Returns the register contents re-indented so the shallowest item sits
+    /// at `base_indent`, keeping the nesting between the items.
+    pub fn items_for_paste(&self, base_indent: usize) -> Vec<ListItem> {
+        reindent(&self.items, base_indent)
+    }
+}
+
+/// Copies of `items` re-indented so the shallowest sits at `base_indent`,
+/// keeping the nesting between them
+pub fn reindent(items: &[ListItem], base_indent: usize) -> Vec<ListItem> {
+    let min_indent = items.iter().filter_map(|item| item.indent_level()).min().unwrap_or(0);
+    items
+        .iter()
+        .cloned()
+        .map(|mut item| {
+            if let Some(indent_level) = item.indent_level_mut() {
+                *indent_level = *indent_level - min_indent + base_indent;
+            }
+            item
+        })
+        .collect()
+}
+
+pub fn items_to_markdown(items: &[ListItem]) -> String {
+    items.iter().map(serialize_item).collect::<Vec<_>>().join("\n")
+}
+
+pub fn copy_to_system_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new().context("System clipboard is not available")?;
+    clipboard
+        .set_text(text.to_string())
+        .context("Failed to copy to the system clipboard")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Parent".to_string(), false, 1),
+            ListItem::new_todo("Child".to_string(), true, 2),
+            ListItem::new_note("Note".to_string(), 1),
+        ]
+    }
+
+    #[test]
+    fn test_yank_keeps_list_order() {
+        let items = create_test_items();
+        let mut register = Register::new();
+        register.yank(&items, &[2, 0]);
+
+        let pasted = register.items_for_paste(1);
+        assert_eq!(pasted, vec![items[0].clone(), items[2].clone()]);
+    }
+
+    #[test]
+    fn test_paste_reindents_relative_to_target() {
+        let items = create_test_items();
+        let mut register = Register::new();
+        register.yank(&items, &[0, 1]);
+
+        let pasted = register.items_for_paste(0);
+        assert_eq!(pasted[0].indent_level(), Some(0));
+        assert_eq!(pasted[1].indent_level(), Some(1));
+    }
+
+    #[test]
+    fn test_items_to_markdown() {
+        let items = create_test_items();
+        assert_eq!(
+            items_to_markdown(&items),
+            "  - [ ] Parent\n    - [x] Child\n  - Note"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::tui::handlers::complete_command;
+
+/// The `:` prompt at the bottom of the screen
+pub struct CommandLineState {
+    pub command_mode: bool,
+    pub input: String,
+}
+
+impl Default for CommandLineState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandLineState {
+    pub fn new() -> Self {
+        Self {
+            command_mode: false,
+            input: String::new(),
+        }
+    }
+
+    pub fn enter_command_mode(&mut self) {
+        self.command_mode = true;
+        self.input.clear();
+    }
+
+    pub fn cancel(&mut self) {
+        self.command_mode = false;
+        self.input.clear();
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    /// Deletes the last character; on an empty prompt this leaves command mode
+    pub fn backspace(&mut self) {
+        if self.input.pop().is_none() {
+            self.cancel();
+        }
+    }
+
+    /// Completes the command name being typed as far as it's unambiguous
+    pub fn complete(&mut self) {
+        if let Some(completed) = complete_command(&self.input) {
+            self.input = completed;
+        }
+    }
+
+    /// Leaves command mode, returning what was typed
+    pub fn take_input(&mut self) -> String {
+        self.command_mode = false;
+        std::mem::take(&mut self.input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing_and_completing_a_command() {
+        let mut command_line = CommandLineState::new();
+        command_line.enter_command_mode();
+        command_line.insert_char('s');
+        command_line.complete();
+        // Both :search and :sort start with s
+        assert_eq!(command_line.input, "s");
+        command_line.insert_char('o');
+        command_line.complete();
+        assert_eq!(command_line.input, "sort ");
+
+        assert_eq!(command_line.take_input(), "sort ");
+        assert!(!command_line.command_mode);
+    }
+
+    #[test]
+    fn test_backspace_on_empty_prompt_leaves_command_mode() {
+        let mut command_line = CommandLineState::new();
+        command_line.enter_command_mode();
+        command_line.insert_char('w');
+        command_line.backspace();
+        assert!(command_line.command_mode);
+
+        command_line.backspace();
+        assert!(!command_line.command_mode);
+    }
+}
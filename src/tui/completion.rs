@@ -0,0 +1,127 @@
+//! Tag completion while editing: a word starting with `#` or `@` offers the
+//! tags (or contexts) already used elsewhere in the file, most used first.
+//! ↑↓ pick one and Tab puts it in.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use todo_core::metadata;
+use todo_core::models::ListItem;
+
+// How many suggestions are offered at most
+const MAX_CANDIDATES: usize = 8;
+
+#[derive(Default)]
+pub struct CompletionState {
+    /// Byte offset of the `#` or `@` being completed
+    start: usize,
+    /// Whole words, marker included
+    candidates: Vec<String>,
+    selected: usize,
+}
+
+impl CompletionState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.candidates.is_empty()
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn close(&mut self) {
+        self.candidates.clear();
+        self.selected = 0;
+    }
+
+    /// Offers completions for the `#tag` or `@context` the cursor is at the
+    /// end of, from every item but the one being edited; closes if there's
+    /// none or nothing else starts with it
+    pub fn update(&mut self, buffer: &str, cursor: usize, items: &[ListItem], editing: usize) {
+        self.close();
+        if buffer[cursor..].starts_with(|c: char| !c.is_whitespace()) {
+            return;
+        }
+        let start = buffer[..cursor].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &buffer[start..cursor];
+        let (marker, names): (char, fn(&str) -> Vec<&str>) = match word.chars().next() {
+            Some('#') => ('#', metadata::tags),
+            Some('@') => ('@', metadata::contexts),
+            _ => return,
+        };
+        let typed = &word[1..];
+        if !typed.is_empty() && !metadata::is_tag_name(typed) {
+            return;
+        }
+
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, item) in items.iter().enumerate().filter(|&(index, _)| index != editing) {
+            for name in names(item.content()) {
+                *counts.entry(name).or_default() += 1;
+            }
+        }
+        let typed = typed.to_lowercase();
+        let mut names: Vec<(&str, usize)> = counts
+            .into_iter()
+            .filter(|(name, _)| name.to_lowercase().starts_with(&typed) && name.len() > typed.len())
+            .collect();
+        names.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        self.start = start;
+        self.candidates = names.into_iter().take(MAX_CANDIDATES).map(|(name, _)| format!("{}{}", marker, name)).collect();
+    }
+
+    pub fn move_selection(&mut self, forward: bool) {
+        let count = self.candidates.len();
+        if count > 0 {
+            self.selected = if forward { (self.selected + 1) % count } else { (self.selected + count - 1) % count };
+        }
+    }
+
+    /// The text to replace (up to `cursor`) and the chosen word, closing the menu
+    pub fn accept(&mut self, cursor: usize) -> Option<(Range<usize>, String)> {
+        let word = self.candidates.get(self.selected).cloned()?;
+        self.close();
+        Some((self.start..cursor, word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Editing #w".to_string(), false, 0),
+            ListItem::new_todo("Report #work #writing".to_string(), false, 0),
+            ListItem::new_todo("Slides #work @office".to_string(), false, 0),
+            ListItem::new_todo("Dishes #home".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_update_and_accept() {
+        let items = items();
+        let mut completion = CompletionState::new();
+        completion.update("Plan #w", 7, &items, 0);
+        assert_eq!(completion.candidates(), ["#work", "#writing"]);
+        completion.move_selection(true);
+        assert_eq!(completion.accept(7), Some((5..7, "#writing".to_string())));
+        assert!(!completion.is_open());
+
+        completion.update("Call @", 6, &items, 0);
+        assert_eq!(completion.candidates(), ["@office"]);
+        completion.update("Plan #work", 10, &items, 0);
+        assert!(!completion.is_open());
+        completion.update("Plan #w later", 6, &items, 0);
+        assert!(completion.candidates().is_empty());
+        completion.update("Plan #wx", 8, &items, 0);
+        assert!(!completion.is_open());
+    }
+}
@@ -0,0 +1,124 @@
+use todo_core::{models::ListItem, structure::ItemCreator};
+
+/// A deletion waiting for the user to confirm it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingDelete {
+    pub indices: Vec<usize>,
+    /// Nested items that are deleted along with their parents
+    pub nested: usize,
+    /// Children of the deleted items that aren't deleted themselves
+    pub kept_children: usize,
+}
+
+impl PendingDelete {
+    /// Builds a deletion of the given items, skipping headings (which can't be
+    /// deleted). Returns `None` if nothing would be removed.
+    pub fn new(items: &[ListItem], indices: &[usize]) -> Option<Self> {
+        let mut indices: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&i| matches!(items.get(i), Some(item) if !matches!(item, ListItem::Heading { .. })))
+            .collect();
+        indices.sort();
+        indices.dedup();
+        if indices.is_empty() {
+            return None;
+        }
+
+        let mut kept_children: Vec<usize> = indices
+            .iter()
+            .flat_map(|&i| {
+                let (start, end) = ItemCreator::get_block_range(items, i);
+                start + 1..=end
+            })
+            .filter(|i| !indices.contains(i))
+            .collect();
+        kept_children.sort();
+        kept_children.dedup();
+
+        Some(Self {
+            indices,
+            nested: 0,
+            kept_children: kept_children.len(),
+        })
+    }
+
+    /// Builds a deletion of the given items together with everything nested
+    /// under them.
+    pub fn with_children(items: &[ListItem], indices: &[usize]) -> Option<Self> {
+        let roots = Self::new(items, indices)?;
+        let mut pending = Self::new(items, &ItemCreator::get_block_indices(items, &roots.indices))?;
+        pending.nested = pending.indices.len() - roots.indices.len();
+        Some(pending)
+    }
+
+    pub fn message(&self) -> String {
+        let count = self.indices.len() - self.nested;
+        let mut message = if count == 1 {
+            "Delete 1 item".to_string()
+        } else {
+            format!("Delete {} items", count)
+        };
+        if self.nested > 0 {
+            message.push_str(&format!(" and {} nested item(s)", self.nested));
+        }
+        message.push('?');
+        if self.kept_children > 0 {
+            message.push_str(&format!(" ({} nested item(s) will be kept)", self.kept_children));
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child 1".to_string(), false, 1),
+            ListItem::new_todo("Child 2".to_string(), false, 1),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_pending_delete_skips_headings() {
+        let items = create_test_items();
+
+        assert_eq!(PendingDelete::new(&items, &[0]), None);
+
+        let pending = PendingDelete::new(&items, &[4, 0]).unwrap();
+        assert_eq!(pending.indices, vec![4]);
+        assert_eq!(pending.message(), "Delete 1 item?");
+    }
+
+    #[test]
+    fn test_pending_delete_with_children() {
+        let items = create_test_items();
+
+        let pending = PendingDelete::with_children(&items, &[1]).unwrap();
+        assert_eq!(pending.indices, vec![1, 2, 3]);
+        assert_eq!(pending.kept_children, 0);
+        assert_eq!(pending.message(), "Delete 1 item and 2 nested item(s)?");
+
+        // Selecting a child as well doesn't count it twice
+        let pending = PendingDelete::with_children(&items, &[1, 2]).unwrap();
+        assert_eq!(pending.indices, vec![1, 2, 3]);
+        assert_eq!(pending.nested, 1);
+    }
+
+    #[test]
+    fn test_pending_delete_counts_kept_children() {
+        let items = create_test_items();
+
+        let pending = PendingDelete::new(&items, &[1]).unwrap();
+        assert_eq!(pending.kept_children, 2);
+
+        let pending = PendingDelete::new(&items, &[1, 2]).unwrap();
+        assert_eq!(pending.kept_children, 1);
+        assert_eq!(pending.message(), "Delete 2 items? (1 nested item(s) will be kept)");
+    }
+}
@@ -1,10 +1,27 @@
 use anyhow::Result;
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Shown at the cursor position inside the edited text
+const CURSOR: char = '█';
 
 pub struct EditState {
     pub edit_mode: bool,
     pub edit_buffer: String,
     pub edit_cursor_position: usize,
     pub adding_new_todo: bool,
+    // What a new todo started as, from `[templates]`; confirmed untouched,
+    // the todo isn't kept
+    pub template: Option<String>,
+    // Columns available to the text, set while rendering; long lines wrap at it
+    pub wrap_width: usize,
+}
+
+impl Default for EditState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EditState {
@@ -14,6 +31,8 @@ impl EditState {
             edit_buffer: String::new(),
             edit_cursor_position: 0,
             adding_new_todo: false,
+            template: None,
+            wrap_width: usize::MAX,
         }
     }
 
@@ -28,6 +47,25 @@ impl EditState {
         self.edit_buffer.clear();
         self.edit_cursor_position = 0;
         self.adding_new_todo = false;
+        self.template = None;
+    }
+
+    /// Fills a new item's buffer with a template, the cursor at `cursor`
+    pub fn start_from_template(&mut self, text: String, cursor: usize) {
+        self.edit_cursor_position = cursor.min(text.len());
+        self.edit_buffer = text.clone();
+        self.template = Some(text);
+    }
+
+    /// Whether the buffer is still just the template it started as
+    pub fn is_untouched_template(&self) -> bool {
+        self.template.as_ref().is_some_and(|template| *template == self.edit_buffer)
+    }
+
+    /// Replaces `range` of the buffer with `text`, leaving the cursor after it
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        self.edit_cursor_position = range.start + text.len();
+        self.edit_buffer.replace_range(range, text);
     }
 
     pub fn insert_char(&mut self, c: char) {
@@ -35,70 +73,124 @@ impl EditState {
         self.edit_cursor_position += c.len_utf8();
     }
 
-    pub fn backspace(&mut self) {
-        if self.edit_cursor_position > 0 {
-            // Find the previous character boundary
-            let chars: Vec<char> = self.edit_buffer.chars().collect();
-            let mut byte_pos = 0;
-            let mut char_index = 0;
-            
-            // Find which character we're at
-            for (i, ch) in chars.iter().enumerate() {
-                if byte_pos >= self.edit_cursor_position {
-                    char_index = i;
-                    break;
+    #[cfg(test)]
+    fn split_at_cursor(&self) -> (&str, &str) {
+        self.edit_buffer.split_at(self.edit_cursor_position)
+    }
+
+    /// Byte ranges of the rows the buffer is displayed in: one per line,
+    /// soft-wrapped to `wrap_width`. Line breaks aren't part of any row.
+    fn visual_rows(&self) -> Vec<Range<usize>> {
+        // Leave a column for the cursor at the end of a full row
+        let width = self.wrap_width.saturating_sub(1).max(1);
+        let mut rows = Vec::new();
+        let mut line_start = 0;
+        for line in self.edit_buffer.split('\n') {
+            let mut row_start = line_start;
+            let mut row_width = 0;
+            for (offset, grapheme) in line.grapheme_indices(true) {
+                let grapheme_width = grapheme.width();
+                if row_width > 0 && row_width + grapheme_width > width {
+                    rows.push(row_start..line_start + offset);
+                    row_start = line_start + offset;
+                    row_width = 0;
                 }
-                byte_pos += ch.len_utf8();
-                char_index = i + 1;
-            }
-            
-            if char_index > 0 {
-                let char_to_remove = chars[char_index - 1];
-                self.edit_cursor_position -= char_to_remove.len_utf8();
-                self.edit_buffer.remove(self.edit_cursor_position);
+                row_width += grapheme_width;
             }
+            rows.push(row_start..line_start + line.len());
+            line_start += line.len() + 1;
         }
+        rows
     }
 
-    pub fn delete(&mut self) {
-        if self.edit_cursor_position < self.edit_buffer.len() {
-            self.edit_buffer.remove(self.edit_cursor_position);
-        }
+    fn cursor_row(&self, rows: &[Range<usize>]) -> usize {
+        // At a soft wrap the cursor shows at the start of the next row
+        rows.iter().rposition(|row| row.start <= self.edit_cursor_position).unwrap_or(0)
     }
 
-    pub fn move_cursor_left(&mut self) {
-        if self.edit_cursor_position > 0 {
-            // Find the previous character boundary
-            let chars: Vec<char> = self.edit_buffer.chars().collect();
-            let mut byte_pos = 0;
-            
-            for ch in chars.iter() {
-                if byte_pos >= self.edit_cursor_position {
-                    break;
-                }
-                if byte_pos + ch.len_utf8() >= self.edit_cursor_position {
-                    self.edit_cursor_position = byte_pos;
-                    return;
+    /// The text as displayed, one string per row, with the cursor drawn in
+    pub fn display_rows(&self) -> Vec<String> {
+        let rows = self.visual_rows();
+        let cursor_row = self.cursor_row(&rows);
+        rows.iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut text = self.edit_buffer[row.clone()].to_string();
+                if i == cursor_row {
+                    text.insert(self.edit_cursor_position - row.start, CURSOR);
                 }
-                byte_pos += ch.len_utf8();
+                text
+            })
+            .collect()
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        self.move_cursor_rows(-1);
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        self.move_cursor_rows(1);
+    }
+
+    // Move to the same column in another row, or as close as that row allows
+    fn move_cursor_rows(&mut self, delta: isize) {
+        let rows = self.visual_rows();
+        let current = self.cursor_row(&rows);
+        let Some(target) = current.checked_add_signed(delta).filter(|&row| row < rows.len()) else {
+            return;
+        };
+        let column = self.edit_buffer[rows[current].start..self.edit_cursor_position].width();
+        
+        let row = &rows[target];
+        let mut position = row.start;
+        let mut width = 0;
+        for (offset, grapheme) in self.edit_buffer[row.clone()].grapheme_indices(true) {
+            width += grapheme.width();
+            if width > column {
+                break;
             }
+            position = row.start + offset + grapheme.len();
         }
+        // The end of a wrapped row is the start of the next one
+        if position == row.end && rows.get(target + 1).is_some_and(|next| next.start == row.end) {
+            position = self.edit_buffer[..row.end].grapheme_indices(true).next_back().map_or(row.start, |(i, _)| i);
+        }
+        self.edit_cursor_position = position;
+    }
+
+    // Byte offset of the grapheme cluster boundary before the cursor, so that
+    // e.g. an emoji made of several code points is stepped over as one
+    fn previous_boundary(&self) -> usize {
+        self.edit_buffer[..self.edit_cursor_position]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.edit_buffer[self.edit_cursor_position..]
+            .graphemes(true)
+            .next()
+            .map_or(self.edit_buffer.len(), |g| self.edit_cursor_position + g.len())
+    }
+
+    pub fn backspace(&mut self) {
+        let start = self.previous_boundary();
+        self.edit_buffer.replace_range(start..self.edit_cursor_position, "");
+        self.edit_cursor_position = start;
+    }
+
+    pub fn delete(&mut self) {
+        let end = self.next_boundary();
+        self.edit_buffer.replace_range(self.edit_cursor_position..end, "");
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.edit_cursor_position = self.previous_boundary();
     }
 
     pub fn move_cursor_right(&mut self) {
-        if self.edit_cursor_position < self.edit_buffer.len() {
-            // Find the next character boundary
-            let chars: Vec<char> = self.edit_buffer.chars().collect();
-            let mut byte_pos = 0;
-            
-            for ch in chars.iter() {
-                if byte_pos >= self.edit_cursor_position {
-                    self.edit_cursor_position = byte_pos + ch.len_utf8();
-                    return;
-                }
-                byte_pos += ch.len_utf8();
-            }
-        }
+        self.edit_cursor_position = self.next_boundary();
     }
 
     pub fn move_cursor_home(&mut self) {
@@ -109,153 +201,77 @@ impl EditState {
         self.edit_cursor_position = self.edit_buffer.len();
     }
 
-    pub fn delete_word_backward(&mut self) {
-        if self.edit_cursor_position == 0 {
-            return;
-        }
-
-        let chars: Vec<char> = self.edit_buffer.chars().collect();
-        let mut byte_pos = 0;
-        let mut char_index = 0;
-        
-        // Find which character we're at
-        for (i, ch) in chars.iter().enumerate() {
-            if byte_pos >= self.edit_cursor_position {
-                char_index = i;
+    // Start of the word before the cursor: skip back over whitespace, then
+    // over the word itself
+    fn previous_word_start(&self) -> usize {
+        let mut graphemes = self.edit_buffer[..self.edit_cursor_position]
+            .grapheme_indices(true)
+            .rev()
+            .skip_while(|(_, g)| is_whitespace(g))
+            .peekable();
+        let mut start = graphemes.peek().map_or(0, |(i, _)| *i);
+        for (i, g) in graphemes {
+            if is_whitespace(g) {
                 break;
             }
-            byte_pos += ch.len_utf8();
-            char_index = i + 1;
+            start = i;
         }
+        start
+    }
 
-        // Find the start of the word to delete
-        let mut word_start = char_index;
-        let mut in_word = false;
-        
-        // Move backward from current position
-        for i in (0..char_index).rev() {
-            let ch = chars[i];
-            if ch.is_whitespace() {
-                if in_word {
-                    // Found whitespace after word chars, stop here
-                    word_start = i + 1;
-                    break;
-                }
-                // Still in whitespace, continue
-            } else {
-                // Found a word character
-                in_word = true;
-                word_start = i;
-            }
-        }
+    pub fn delete_word_backward(&mut self) {
+        let start = self.previous_word_start();
+        self.edit_buffer.replace_range(start..self.edit_cursor_position, "");
+        self.edit_cursor_position = start;
+    }
 
-        // Calculate byte positions for deletion
-        let mut delete_start_byte = 0;
-        for i in 0..word_start {
-            delete_start_byte += chars[i].len_utf8();
-        }
+    /// Deletes back to the start of the cursor's line (Ctrl+U)
+    pub fn kill_to_line_start(&mut self) {
+        let start = self.edit_buffer[..self.edit_cursor_position].rfind('\n').map_or(0, |i| i + 1);
+        self.edit_buffer.replace_range(start..self.edit_cursor_position, "");
+        self.edit_cursor_position = start;
+    }
 
-        // Delete the range
-        let delete_len = self.edit_cursor_position - delete_start_byte;
-        if delete_len > 0 {
-            for _ in 0..delete_len {
-                if delete_start_byte < self.edit_buffer.len() {
-                    self.edit_buffer.remove(delete_start_byte);
-                }
-            }
-            self.edit_cursor_position = delete_start_byte;
-        }
+    /// Deletes up to the end of the cursor's line (Ctrl+K)
+    pub fn kill_to_line_end(&mut self) {
+        let end = self.edit_buffer[self.edit_cursor_position..]
+            .find('\n')
+            .map_or(self.edit_buffer.len(), |i| self.edit_cursor_position + i);
+        self.edit_buffer.replace_range(self.edit_cursor_position..end, "");
     }
 
     pub fn move_to_previous_word(&mut self) {
-        if self.edit_cursor_position == 0 {
-            return;
-        }
-
-        let chars: Vec<char> = self.edit_buffer.chars().collect();
-        let mut byte_pos = 0;
-        let mut char_index = 0;
-        
-        // Find which character we're at
-        for (i, ch) in chars.iter().enumerate() {
-            if byte_pos >= self.edit_cursor_position {
-                char_index = i;
-                break;
-            }
-            byte_pos += ch.len_utf8();
-            char_index = i + 1;
-        }
-
-        // Find the start of the previous word
-        let mut target_pos = 0;
-        let mut found_word = false;
-        
-        // Move backward from current position
-        for i in (0..char_index).rev() {
-            let ch = chars[i];
-            if ch.is_whitespace() {
-                if found_word {
-                    // Found whitespace after word chars, stop at next position
-                    target_pos = i + 1;
-                    break;
-                }
-                // Still in whitespace, continue
-            } else {
-                // Found a word character
-                found_word = true;
-                target_pos = i;
-            }
-        }
-
-        // Calculate byte position for target
-        let mut target_byte_pos = 0;
-        for i in 0..target_pos {
-            target_byte_pos += chars[i].len_utf8();
-        }
-        
-        self.edit_cursor_position = target_byte_pos;
+        self.edit_cursor_position = self.previous_word_start();
     }
 
     pub fn move_to_next_word(&mut self) {
-        if self.edit_cursor_position >= self.edit_buffer.len() {
-            return;
-        }
-
-        let chars: Vec<char> = self.edit_buffer.chars().collect();
-        let mut byte_pos = 0;
-        let mut char_index = 0;
-        
-        // Find which character we're at
-        for (i, ch) in chars.iter().enumerate() {
-            if byte_pos >= self.edit_cursor_position {
-                char_index = i;
-                break;
-            }
-            byte_pos += ch.len_utf8();
-            char_index = i + 1;
-        }
-
-        // First skip any non-whitespace characters (current word)
-        let mut i = char_index;
-        while i < chars.len() && !chars[i].is_whitespace() {
-            i += 1;
-        }
-        
-        // Then skip any whitespace
-        while i < chars.len() && chars[i].is_whitespace() {
-            i += 1;
-        }
+        // Skip the rest of the current word, then the whitespace after it
+        let rest = &self.edit_buffer[self.edit_cursor_position..];
+        let offset = rest
+            .grapheme_indices(true)
+            .skip_while(|(_, g)| !is_whitespace(g))
+            .find(|(_, g)| !is_whitespace(g))
+            .map_or(rest.len(), |(i, _)| i);
+        self.edit_cursor_position += offset;
+    }
+}
 
-        // Calculate byte position for target
-        let mut target_byte_pos = 0;
-        for j in 0..i {
-            if j < chars.len() {
-                target_byte_pos += chars[j].len_utf8();
-            }
-        }
-        
-        self.edit_cursor_position = target_byte_pos;
+/// Tidies edited multi-line text for saving: blank lines can't be written as
+/// continuation lines, so they're dropped along with trailing whitespace
+pub fn normalize_multiline(text: &str) -> String {
+    // Single-line text is kept exactly as typed
+    if !text.contains('\n') {
+        return text.to_string();
     }
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
 }
 
 pub trait Editable {
@@ -389,6 +405,29 @@ mod tests {
         assert_eq!(edit_state.edit_cursor_position, 0);
     }
 
+    #[test]
+    fn test_kill_to_line_start_and_end() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("foo bar baz".to_string());
+        edit_state.edit_cursor_position = 4;
+        edit_state.kill_to_line_end();
+        assert_eq!(edit_state.edit_buffer, "foo ");
+        assert_eq!(edit_state.edit_cursor_position, 4);
+        edit_state.kill_to_line_start();
+        assert_eq!(edit_state.edit_buffer, "");
+        assert_eq!(edit_state.edit_cursor_position, 0);
+
+        // Only the cursor's line is affected
+        edit_state.enter_edit_mode("first\nsecond line\nthird".to_string());
+        edit_state.edit_cursor_position = 13;
+        edit_state.kill_to_line_start();
+        assert_eq!(edit_state.edit_buffer, "first\nline\nthird");
+        assert_eq!(edit_state.edit_cursor_position, 6);
+        edit_state.kill_to_line_end();
+        assert_eq!(edit_state.edit_buffer, "first\n\nthird");
+        assert_eq!(edit_state.edit_cursor_position, 6);
+    }
+
     #[test]
     fn test_move_to_previous_word() {
         let mut edit_state = EditState::new();
@@ -455,4 +494,112 @@ mod tests {
         edit_state.move_to_next_word();
         assert_eq!(edit_state.edit_cursor_position, 7); // Start of "test"
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multibyte_editing() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("café 日本".to_string());
+        
+        edit_state.move_cursor_left();
+        edit_state.move_cursor_left();
+        edit_state.move_cursor_left();
+        assert_eq!(edit_state.split_at_cursor(), ("café", " 日本"));
+        
+        edit_state.backspace();
+        assert_eq!(edit_state.edit_buffer, "caf 日本");
+        
+        edit_state.move_cursor_right();
+        edit_state.delete();
+        assert_eq!(edit_state.edit_buffer, "caf 本");
+        
+        edit_state.insert_char('語');
+        assert_eq!(edit_state.split_at_cursor(), ("caf 語", "本"));
+    }
+
+    #[test]
+    fn test_emoji_is_edited_as_one_character() {
+        let mut edit_state = EditState::new();
+        // Family emoji: several code points joined with zero-width joiners
+        edit_state.enter_edit_mode("hi 👨‍👩‍👧 👍🏽".to_string());
+        
+        edit_state.move_cursor_left();
+        assert_eq!(edit_state.split_at_cursor(), ("hi 👨‍👩‍👧 ", "👍🏽"));
+        
+        edit_state.move_cursor_left();
+        edit_state.backspace();
+        assert_eq!(edit_state.edit_buffer, "hi  👍🏽");
+        
+        edit_state.move_cursor_end();
+        edit_state.delete_word_backward();
+        assert_eq!(edit_state.edit_buffer, "hi  ");
+    }
+
+    #[test]
+    fn test_word_movement_over_cjk_text() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("買い物 リスト".to_string());
+        
+        edit_state.move_to_previous_word();
+        assert_eq!(edit_state.split_at_cursor(), ("買い物 ", "リスト"));
+        
+        edit_state.move_cursor_home();
+        edit_state.move_to_next_word();
+        assert_eq!(edit_state.split_at_cursor(), ("買い物 ", "リスト"));
+    }
+
+    #[test]
+    fn test_display_rows_wrap_and_show_cursor() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("abcdef\nxy".to_string());
+        edit_state.wrap_width = 5;
+        
+        assert_eq!(edit_state.display_rows(), vec!["abcd", "ef", "xy█"]);
+        
+        // Wide characters take two columns each
+        edit_state.enter_edit_mode("日本語テキスト".to_string());
+        edit_state.wrap_width = 7;
+        edit_state.move_cursor_home();
+        assert_eq!(edit_state.display_rows(), vec!["█日本語", "テキス", "ト"]);
+    }
+
+    #[test]
+    fn test_move_cursor_up_and_down() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("first line\nhi\nthird line".to_string());
+        
+        edit_state.move_cursor_up();
+        assert_eq!(edit_state.split_at_cursor().1, "\nthird line");
+        
+        edit_state.move_cursor_up();
+        assert_eq!(edit_state.split_at_cursor(), ("fi", "rst line\nhi\nthird line"));
+        
+        edit_state.move_cursor_home();
+        edit_state.move_cursor_right();
+        edit_state.move_cursor_down();
+        edit_state.move_cursor_down();
+        assert_eq!(edit_state.split_at_cursor().1, "hird line");
+        
+        // Nothing below the last row
+        edit_state.move_cursor_down();
+        assert_eq!(edit_state.split_at_cursor().1, "hird line");
+    }
+
+    #[test]
+    fn test_move_cursor_within_wrapped_line() {
+        let mut edit_state = EditState::new();
+        edit_state.enter_edit_mode("abcdefgh".to_string());
+        edit_state.wrap_width = 5;
+        
+        edit_state.move_cursor_up();
+        assert_eq!(edit_state.display_rows(), vec!["abc█d", "efgh"]);
+        
+        edit_state.move_cursor_down();
+        assert_eq!(edit_state.display_rows(), vec!["abcd", "efg█h"]);
+    }
+
+    #[test]
+    fn test_normalize_multiline() {
+        assert_eq!(normalize_multiline("first  \n\n  \nsecond\n"), "first\nsecond");
+        assert_eq!(normalize_multiline("single "), "single ");
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+use todo_core::models::ListItem;
+
+// Used when neither $VISUAL nor $EDITOR is set
+const DEFAULT_EDITOR: &str = "vi";
+
+/// A request to open the list's file in the user's editor, handled by the
+/// event loop since the terminal has to be handed over while it runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalEdit {
+    pub file_path: String,
+    pub line: usize,
+}
+
+/// The 1-based line in the saved file where the item at `index` starts
+pub fn line_number(items: &[ListItem], index: usize) -> usize {
+    // Multi-line items take up one extra line per continuation line
+    1 + items
+        .iter()
+        .take(index)
+        .map(|item| item.content().matches('\n').count() + 1)
+        .sum::<usize>()
+}
+
+// $VISUAL or $EDITOR, which may include arguments (e.g. `emacsclient -t`)
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+// Most terminal editors (vi, nano, emacs, micro, helix) accept `+LINE`
+fn editor_args(editor: &str, edit: &ExternalEdit) -> Vec<String> {
+    let mut args: Vec<String> = editor.split_whitespace().map(str::to_string).collect();
+    args.push(format!("+{}", edit.line));
+    args.push(edit.file_path.clone());
+    args
+}
+
+/// Runs the editor and waits for it to exit
+pub fn run(edit: &ExternalEdit) -> Result<()> {
+    let editor = editor_command();
+    let args = editor_args(&editor, edit);
+    let status = Command::new(&args[0])
+        .args(&args[1..])
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        bail!("Editor '{}' exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_number_counts_continuation_lines() {
+        let items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report\nwith details".to_string(), false, 0),
+            ListItem::new_todo("Invoice".to_string(), false, 0),
+        ];
+        assert_eq!(line_number(&items, 0), 1);
+        assert_eq!(line_number(&items, 1), 2);
+        assert_eq!(line_number(&items, 2), 4);
+    }
+
+    #[test]
+    fn test_editor_args() {
+        let edit = ExternalEdit { file_path: "/tmp/TODO.md".to_string(), line: 12 };
+        assert_eq!(editor_args("vim", &edit), vec!["vim", "+12", "/tmp/TODO.md"]);
+        assert_eq!(editor_args("emacsclient  -t", &edit), vec!["emacsclient", "-t", "+12", "/tmp/TODO.md"]);
+    }
+}
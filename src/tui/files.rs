@@ -0,0 +1,181 @@
+use crate::config::expand_path;
+use crate::tui::undo::UndoManager;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: String,
+}
+
+pub struct FilePickerState {
+    pub picker_mode: bool,
+    pub entries: Vec<FileEntry>,
+    pub selected_index: usize,
+    pub error: Option<String>,
+}
+
+impl FilePickerState {
+    pub fn new(entries: Vec<FileEntry>) -> Self {
+        Self {
+            picker_mode: false,
+            entries,
+            selected_index: 0,
+            error: None,
+        }
+    }
+
+    pub fn open(&mut self, current_path: &str) {
+        self.picker_mode = true;
+        self.error = None;
+        // Start on the file that's currently open
+        self.selected_index = self
+            .entries
+            .iter()
+            .position(|entry| entry.path == current_path)
+            .unwrap_or(0);
+    }
+
+    pub fn close(&mut self) {
+        self.picker_mode = false;
+        self.error = None;
+    }
+
+    pub fn move_selection_up(&mut self) {
+        if self.selected_index > 0 {
+            self.selected_index -= 1;
+        }
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selected_index < self.entries.len().saturating_sub(1) {
+            self.selected_index += 1;
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&FileEntry> {
+        self.entries.get(self.selected_index)
+    }
+}
+
+/// Per-file state that is kept while another file is open
+pub struct FileSession {
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+    pub undo_manager: UndoManager,
+}
+
+/// Builds the picker entries: the configured default file, the named files from
+/// the config, and the currently open file if it isn't one of those.
+pub fn build_file_entries(
+    current_path: &str,
+    default_path: &str,
+    named_files: &BTreeMap<String, String>,
+) -> Vec<FileEntry> {
+    let mut entries = Vec::new();
+
+    if !default_path.is_empty() {
+        entries.push(FileEntry {
+            name: "default".to_string(),
+            path: default_path.to_string(),
+        });
+    }
+
+    for (name, path) in named_files {
+        if !entries.iter().any(|entry| entry.path == *path) {
+            entries.push(FileEntry {
+                name: name.clone(),
+                path: path.clone(),
+            });
+        }
+    }
+
+    if !entries.iter().any(|entry| entry.path == current_path) {
+        let name = std::path::Path::new(current_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| current_path.to_string());
+        entries.insert(0, FileEntry {
+            name,
+            path: current_path.to_string(),
+        });
+    }
+
+    entries
+}
+
+/// Resolves the argument of `:open`: the name of a file from the picker, or a
+/// path where a leading `~` stands for the home directory
+pub fn resolve_file_argument(argument: &str, entries: &[FileEntry]) -> String {
+    if let Some(entry) = entries.iter().find(|entry| entry.name == argument) {
+        return entry.path.clone();
+    }
+    expand_path(argument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named_files() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("work".to_string(), "/notes/work.md".to_string()),
+            ("personal".to_string(), "/notes/personal.md".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_build_file_entries() {
+        let entries = build_file_entries("/notes/TODO.md", "/notes/TODO.md", &named_files());
+
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["default", "personal", "work"]);
+    }
+
+    #[test]
+    fn test_build_file_entries_includes_adhoc_file() {
+        let entries = build_file_entries("/tmp/scratch.md", "/notes/TODO.md", &named_files());
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].name, "scratch.md");
+        assert_eq!(entries[0].path, "/tmp/scratch.md");
+    }
+
+    #[test]
+    fn test_open_selects_current_file() {
+        let entries = build_file_entries("/notes/TODO.md", "/notes/TODO.md", &named_files());
+        let mut picker = FilePickerState::new(entries);
+
+        picker.open("/notes/work.md");
+        assert!(picker.picker_mode);
+        assert_eq!(picker.selected_entry().unwrap().name, "work");
+    }
+
+    #[test]
+    fn test_picker_navigation() {
+        let entries = build_file_entries("/notes/TODO.md", "/notes/TODO.md", &named_files());
+        let mut picker = FilePickerState::new(entries);
+        picker.open("/notes/TODO.md");
+
+        picker.move_selection_up();
+        assert_eq!(picker.selected_index, 0);
+
+        picker.move_selection_down();
+        picker.move_selection_down();
+        picker.move_selection_down();
+        assert_eq!(picker.selected_index, 2);
+
+        picker.close();
+        assert!(!picker.picker_mode);
+    }
+
+    #[test]
+    fn test_resolve_file_argument() {
+        let entries = build_file_entries("/notes/today.md", "/notes/TODO.md", &named_files());
+        assert_eq!(resolve_file_argument("default", &entries), "/notes/TODO.md");
+        assert_eq!(resolve_file_argument("/tmp/other.md", &entries), "/tmp/other.md");
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(resolve_file_argument("~/x.md", &entries), home.join("x.md").to_string_lossy());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use todo_core::models::ListItem;
+use crate::tui::navigation::ItemAnchor;
+use todo_core::structure::ItemCreator;
+
+pub struct FoldState {
+    folded: Vec<ItemAnchor>,
+}
+
+impl Default for FoldState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FoldState {
+    pub fn new() -> Self {
+        Self {
+            folded: Vec::new(),
+        }
+    }
+
+    pub fn is_folded(&self, index: usize) -> bool {
+        self.folded.iter().any(|anchor| anchor.index() == index)
+    }
+
+    /// Collapses the item at `index`. Only items with children can be folded.
+    pub fn fold(&mut self, items: &[ListItem], index: usize) -> bool {
+        if self.is_folded(index) || !Self::has_children(items, index) {
+            return false;
+        }
+        match ItemAnchor::new(items, index) {
+            Some(anchor) => {
+                self.folded.push(anchor);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unfold(&mut self, index: usize) -> bool {
+        let count = self.folded.len();
+        self.folded.retain(|anchor| anchor.index() != index);
+        self.folded.len() != count
+    }
+
+    pub fn toggle(&mut self, items: &[ListItem], index: usize) -> bool {
+        self.unfold(index) || self.fold(items, index)
+    }
+
+    pub fn unfold_all(&mut self) {
+        self.folded.clear();
+    }
+
+    /// Re-locates folded items after the list changed, dropping folds whose
+    /// item is gone or no longer has children.
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        self.folded.retain_mut(|anchor| match anchor.resolve(items) {
+            Some(index) => Self::has_children(items, index),
+            None => false,
+        });
+    }
+
+    /// Unfolds anything hiding the item at `index` so it can be shown.
+    pub fn reveal(&mut self, items: &[ListItem], index: usize) {
+        self.folded.retain(|anchor| {
+            let (start, end) = ItemCreator::get_fold_range(items, anchor.index());
+            !(start < index && index <= end)
+        });
+    }
+
+    /// Number of items hidden under the item at `index`, if it is folded
+    pub fn hidden_count(&self, items: &[ListItem], index: usize) -> Option<usize> {
+        if self.is_folded(index) {
+            let (start, end) = ItemCreator::get_fold_range(items, index);
+            Some(end - start)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_hidden(&self, items: &[ListItem], index: usize) -> bool {
+        self.folded.iter().any(|anchor| {
+            let (start, end) = ItemCreator::get_fold_range(items, anchor.index());
+            start < index && index <= end
+        })
+    }
+
+    pub fn visible_indices(&self, items: &[ListItem]) -> Vec<usize> {
+        let mut hidden = vec![false; items.len()];
+        for anchor in &self.folded {
+            let (start, end) = ItemCreator::get_fold_range(items, anchor.index());
+            for flag in hidden.iter_mut().take(end + 1).skip(start + 1) {
+                *flag = true;
+            }
+        }
+        (0..items.len()).filter(|&i| !hidden[i]).collect()
+    }
+
+    fn has_children(items: &[ListItem], index: usize) -> bool {
+        index < items.len() && ItemCreator::get_fold_range(items, index).1 > index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child 1".to_string(), false, 1),
+            ListItem::new_todo("Grandchild".to_string(), false, 2),
+            ListItem::new_todo("Child 2".to_string(), false, 1),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_fold_hides_children() {
+        let items = create_test_items();
+        let mut folds = FoldState::new();
+
+        assert!(folds.fold(&items, 0));
+        assert_eq!(folds.visible_indices(&items), vec![0, 4]);
+        assert_eq!(folds.hidden_count(&items, 0), Some(3));
+        assert!(folds.is_hidden(&items, 2));
+        assert!(!folds.is_hidden(&items, 4));
+    }
+
+    #[test]
+    fn test_fold_requires_children() {
+        let items = create_test_items();
+        let mut folds = FoldState::new();
+
+        assert!(!folds.fold(&items, 4));
+        assert!(!folds.fold(&items, 2));
+        assert_eq!(folds.visible_indices(&items).len(), 5);
+    }
+
+    #[test]
+    fn test_toggle_fold() {
+        let items = create_test_items();
+        let mut folds = FoldState::new();
+
+        assert!(folds.toggle(&items, 1));
+        assert_eq!(folds.visible_indices(&items), vec![0, 1, 3, 4]);
+        assert!(folds.toggle(&items, 1));
+        assert_eq!(folds.visible_indices(&items).len(), 5);
+    }
+
+    #[test]
+    fn test_reveal_unfolds_ancestors() {
+        let items = create_test_items();
+        let mut folds = FoldState::new();
+        folds.fold(&items, 1);
+        folds.fold(&items, 0);
+
+        folds.reveal(&items, 2);
+        assert!(!folds.is_folded(0));
+        assert!(!folds.is_folded(1));
+    }
+
+    #[test]
+    fn test_refresh_follows_moved_items() {
+        let mut items = create_test_items();
+        let mut folds = FoldState::new();
+        folds.fold(&items, 0);
+
+        items.insert(0, ListItem::new_todo("New first".to_string(), false, 0));
+        folds.refresh(&items);
+        assert!(folds.is_folded(1));
+        assert_eq!(folds.visible_indices(&items), vec![0, 1, 5]);
+
+        // Folds disappear once the item has no children left
+        items.drain(2..5);
+        folds.refresh(&items);
+        assert!(!folds.is_folded(1));
+    }
+}
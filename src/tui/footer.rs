@@ -0,0 +1,106 @@
+//! The footer in the list: a summary laid out by `footer_format` in the
+//! config (e.g. `"{done}/{total} done | {section}"`), followed by hints for
+//! what can be done next, which change with what's going on.
+
+use crate::config::footer::{parse, Part};
+use crate::tui::handlers::NormalModeAction;
+
+pub const DEFAULT_FORMAT: &str = "Items: {total} | Completed: {done} | Selected: {selection}";
+
+/// What the placeholders stand for
+pub struct FooterValues {
+    pub total: usize,
+    pub done: usize,
+    pub file: String,
+    /// The heading the cursor is under
+    pub section: Option<String>,
+    pub selection: usize,
+}
+
+impl FooterValues {
+    fn value(&self, placeholder: &str) -> String {
+        match placeholder {
+            "total" => self.total.to_string(),
+            "done" => self.done.to_string(),
+            "pending" => self.total.saturating_sub(self.done).to_string(),
+            "file" => self.file.clone(),
+            "section" => self.section.clone().unwrap_or_default(),
+            "selection" => self.selection.to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// `format` with its placeholders filled in; an invalid format is shown as written
+pub fn expand(format: &str, values: &FooterValues) -> String {
+    match parse(format) {
+        Ok(parts) => parts
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_string(),
+                Part::Placeholder(name) => values.value(name),
+            })
+            .collect(),
+        Err(_) => format.to_string(),
+    }
+}
+
+/// What the list is in the middle of, for picking hints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintContext {
+    List,
+    Selection,
+    SearchResults,
+    Zoomed,
+}
+
+/// The actions worth pointing out in `context`, with a word for each
+pub fn hints(context: HintContext) -> &'static [(NormalModeAction, &'static str)] {
+    match context {
+        HintContext::List => &[
+            (NormalModeAction::EnterSearchMode, "search"),
+            (NormalModeAction::ToggleItemSelection, "select"),
+            (NormalModeAction::ToggleHelpMode, "help"),
+            (NormalModeAction::Quit, "quit"),
+        ],
+        HintContext::Selection => &[
+            (NormalModeAction::ToggleSelectedItem, "toggle"),
+            (NormalModeAction::MoveSelectedItemsToCursor, "move here"),
+            (NormalModeAction::DeleteItem, "delete"),
+            (NormalModeAction::HandleEscape, "clear"),
+        ],
+        HintContext::SearchResults => &[
+            (NormalModeAction::HandleN, "next"),
+            (NormalModeAction::HandleShiftN, "previous"),
+            (NormalModeAction::HandleEscape, "clear"),
+        ],
+        HintContext::Zoomed => &[
+            (NormalModeAction::ToggleZoom, "show all"),
+            (NormalModeAction::ToggleHelpMode, "help"),
+            (NormalModeAction::Quit, "quit"),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> FooterValues {
+        FooterValues {
+            total: 5,
+            done: 2,
+            file: "TODO.md".to_string(),
+            section: Some("Work".to_string()),
+            selection: 1,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand(DEFAULT_FORMAT, &values()), "Items: 5 | Completed: 2 | Selected: 1");
+        assert_eq!(expand("{file} ▸ {section}: {pending} left {{x}}", &values()), "TODO.md ▸ Work: 3 left {x}");
+        let values = FooterValues { section: None, ..values() };
+        assert_eq!(expand("[{section}]", &values), "[]");
+    }
+}
@@ -1,5 +1,10 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use anyhow::Result;
+use crate::config::LineNumbers;
+use crate::tui::replace::Substitution;
+use chrono::NaiveDate;
+use todo_core::actions::SortKey;
+use todo_core::metadata;
 
 pub struct KeyHandler;
 
@@ -11,6 +16,21 @@ impl KeyHandler {
             KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
                 NormalModeAction::Quit
             }
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                NormalModeAction::Redo
+            }
+            KeyCode::Char('d') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                NormalModeAction::MoveHalfPageDown
+            }
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                NormalModeAction::MoveHalfPageUp
+            }
+            KeyCode::PageDown => NormalModeAction::MovePageDown,
+            KeyCode::PageUp => NormalModeAction::MovePageUp,
+            KeyCode::Char('G') => NormalModeAction::MoveToLastItem,
+            KeyCode::Char('e') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                NormalModeAction::OpenInEditor
+            }
             KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => {
                 if key_event.modifiers.contains(KeyModifiers::SHIFT) {
                     NormalModeAction::MoveItemUp
@@ -29,17 +49,23 @@ impl KeyHandler {
                 if key_event.modifiers.contains(KeyModifiers::SHIFT) {
                     NormalModeAction::UnindentItem
                 } else {
-                    NormalModeAction::None
+                    NormalModeAction::CollapseItem
                 }
             }
             KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('L') => {
                 if key_event.modifiers.contains(KeyModifiers::SHIFT) {
                     NormalModeAction::IndentItem
                 } else {
-                    NormalModeAction::None
+                    NormalModeAction::ExpandItem
                 }
             }
+            KeyCode::Enter if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                NormalModeAction::ToggleSubtree
+            }
             KeyCode::Enter => NormalModeAction::ToggleSelectedItem,
+            // Not every terminal reports Shift+Enter, so X does the same
+            KeyCode::Char('X') => NormalModeAction::ToggleSubtree,
+            KeyCode::Char('s') => NormalModeAction::CycleState,
             KeyCode::Char('e') => NormalModeAction::EnterEditMode,
             KeyCode::Char('a') => NormalModeAction::AddNewTodo,
             KeyCode::Char('A') => NormalModeAction::AddNewTodoAtTop,
@@ -47,10 +73,69 @@ impl KeyHandler {
             KeyCode::Char('N') => NormalModeAction::HandleShiftN,
             KeyCode::Char(' ') => NormalModeAction::ToggleItemSelection,
             KeyCode::Char('m') => NormalModeAction::MoveSelectedItemsToCursor,
+            KeyCode::Char('M') => NormalModeAction::OpenMovePicker,
             KeyCode::Char('?') => NormalModeAction::ToggleHelpMode,
             KeyCode::Char('u') => NormalModeAction::Undo,
+            // Terminals without extended key reporting send Ctrl+/ as Ctrl+7
+            KeyCode::Char('/') | KeyCode::Char('7') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                NormalModeAction::EnterSectionSearchMode
+            }
             KeyCode::Char('/') => NormalModeAction::EnterSearchMode,
             KeyCode::Char('d') => NormalModeAction::DeleteItem,
+            KeyCode::Char('D') => NormalModeAction::DeleteSubtree,
+            KeyCode::Char('+') => NormalModeAction::DuplicateItem,
+            KeyCode::Char('b') => NormalModeAction::OpenFilePicker,
+            KeyCode::Char('<') => NormalModeAction::PromoteSection,
+            KeyCode::Char('>') => NormalModeAction::DemoteSection,
+            KeyCode::Char('z') => NormalModeAction::ToggleFold,
+            KeyCode::Char('Z') => NormalModeAction::ToggleZoom,
+            KeyCode::Char('v') => NormalModeAction::ToggleVisualMode,
+            KeyCode::Char('c') => NormalModeAction::ToggleHideCompleted,
+            KeyCode::Char('B') => NormalModeAction::ToggleHideBlocked,
+            KeyCode::Char('F') => NormalModeAction::TogglePendingOnly,
+            KeyCode::Char('P') => NormalModeAction::TogglePomodoro,
+            KeyCode::Char('T') => NormalModeAction::OpenTagPanel,
+            KeyCode::Char('(') => NormalModeAction::OpenPreviousDay,
+            KeyCode::Char(')') => NormalModeAction::OpenNextDay,
+            KeyCode::Char('y') => NormalModeAction::YankItems,
+            KeyCode::Char('Y') => NormalModeAction::CopyItemsToClipboard,
+            KeyCode::Char('p') => NormalModeAction::PasteItems,
+            KeyCode::Char('o') => NormalModeAction::AddSiblingBelow,
+            KeyCode::Char('O') => NormalModeAction::AddSiblingAbove,
+            KeyCode::Tab => NormalModeAction::FocusOutline,
+            KeyCode::Char(':') => NormalModeAction::EnterCommandMode,
+            KeyCode::Char('{') => NormalModeAction::MoveToPreviousHeading,
+            KeyCode::Char('}') => NormalModeAction::MoveToNextHeading,
+            KeyCode::Char('g') => NormalModeAction::StartPrefix('g'),
+            KeyCode::Char('\'') => NormalModeAction::StartPrefix('\''),
+            KeyCode::Char(']') => NormalModeAction::StartPrefix(']'),
+            KeyCode::Char('[') => NormalModeAction::StartPrefix('['),
+            KeyCode::Char('Q') => NormalModeAction::RecordMacro,
+            KeyCode::Char('@') => NormalModeAction::StartPrefix('@'),
+            _ => NormalModeAction::None,
+        }
+    }
+
+    /// Second key of a two-key command such as `g a`
+    pub fn handle_prefix_key(prefix: char, key_event: KeyEvent) -> NormalModeAction {
+        match (prefix, key_event.code) {
+            ('g', KeyCode::Char('a')) => NormalModeAction::OpenAgenda,
+            ('g', KeyCode::Char('b')) => NormalModeAction::OpenBoard,
+            ('g', KeyCode::Char('c')) => NormalModeAction::OpenCalendar,
+            ('g', KeyCode::Char('g')) => NormalModeAction::MoveToFirstItem,
+            ('g', KeyCode::Char('x')) => NormalModeAction::OpenLink,
+            ('g', KeyCode::Char('f')) => NormalModeAction::FollowReference,
+            ('g', KeyCode::Char('s')) => NormalModeAction::SortSection,
+            (']', KeyCode::Char('h')) => NormalModeAction::MoveToNextHeading,
+            ('[', KeyCode::Char('h')) => NormalModeAction::MoveToPreviousHeading,
+            (']', KeyCode::Char('t')) => NormalModeAction::MoveToNextOpenTodo,
+            ('[', KeyCode::Char('t')) => NormalModeAction::MoveToPreviousOpenTodo,
+            // `m` only waits for a letter when there's no selection to move
+            ('m', KeyCode::Char(letter)) => NormalModeAction::SetMark(letter),
+            ('\'', KeyCode::Char(letter)) => NormalModeAction::JumpToMark(letter),
+            // `Q` only waits for a register when it isn't ending a recording
+            ('Q', KeyCode::Char(register)) => NormalModeAction::StartRecording(register),
+            ('@', KeyCode::Char(register)) => NormalModeAction::ReplayMacro(register),
             _ => NormalModeAction::None,
         }
     }
@@ -60,15 +145,164 @@ impl KeyHandler {
             KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('?') => {
                 HelpModeAction::ExitHelpMode
             }
+            KeyCode::Up | KeyCode::Char('k') => HelpModeAction::ScrollUp,
+            KeyCode::Down | KeyCode::Char('j') => HelpModeAction::ScrollDown,
+            KeyCode::Char('d') if key_event.modifiers.contains(KeyModifiers::CONTROL) => HelpModeAction::PageDown,
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => HelpModeAction::PageUp,
+            KeyCode::PageDown | KeyCode::Char(' ') => HelpModeAction::PageDown,
+            KeyCode::PageUp => HelpModeAction::PageUp,
+            KeyCode::Home | KeyCode::Char('g') => HelpModeAction::ScrollToTop,
+            KeyCode::End | KeyCode::Char('G') => HelpModeAction::ScrollToBottom,
+            KeyCode::Char('/') => HelpModeAction::StartSearch,
+            KeyCode::Char('n') => HelpModeAction::NextMatch,
+            KeyCode::Char('N') => HelpModeAction::PreviousMatch,
             _ => HelpModeAction::None,
         }
     }
 
+    /// Keys of a one-line prompt: the help window's search, or a tag's new
+    /// name in the tag panel
+    pub fn handle_prompt_key(key_event: KeyEvent) -> PromptAction {
+        match key_event.code {
+            KeyCode::Esc => PromptAction::Cancel,
+            KeyCode::Enter => PromptAction::Confirm,
+            KeyCode::Backspace => PromptAction::Backspace,
+            KeyCode::Char(c) => PromptAction::InsertChar(c),
+            _ => PromptAction::None,
+        }
+    }
+
+    pub fn handle_confirm_key(key_event: KeyEvent) -> ConfirmAction {
+        match key_event.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => ConfirmAction::Confirm,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => ConfirmAction::Cancel,
+            _ => ConfirmAction::None,
+        }
+    }
+
+    pub fn handle_conflict_key(key_event: KeyEvent) -> ConflictAction {
+        match key_event.code {
+            KeyCode::Char('r') => ConflictAction::Reload,
+            KeyCode::Char('o') => ConflictAction::Overwrite,
+            KeyCode::Char('m') => ConflictAction::Merge,
+            _ => ConflictAction::None,
+        }
+    }
+
+    pub fn handle_file_picker_key(key_event: KeyEvent) -> FilePickerAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => FilePickerAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => FilePickerAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => FilePickerAction::MoveSelectionDown,
+            KeyCode::Enter => FilePickerAction::OpenSelectedFile,
+            _ => FilePickerAction::None,
+        }
+    }
+
+    pub fn handle_move_picker_key(key_event: KeyEvent) -> MovePickerAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => MovePickerAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => MovePickerAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => MovePickerAction::MoveSelectionDown,
+            KeyCode::Enter => MovePickerAction::MoveItems,
+            _ => MovePickerAction::None,
+        }
+    }
+
+    pub fn handle_trash_picker_key(key_event: KeyEvent) -> TrashPickerAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => TrashPickerAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => TrashPickerAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => TrashPickerAction::MoveSelectionDown,
+            KeyCode::Enter => TrashPickerAction::Restore,
+            _ => TrashPickerAction::None,
+        }
+    }
+
+    pub fn handle_tag_panel_key(key_event: KeyEvent) -> TagPanelAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => TagPanelAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => TagPanelAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => TagPanelAction::MoveSelectionDown,
+            KeyCode::Enter => TagPanelAction::Filter,
+            KeyCode::Char('r') => TagPanelAction::Rename,
+            KeyCode::Char('d') => TagPanelAction::Remove,
+            _ => TagPanelAction::None,
+        }
+    }
+
+    pub fn handle_agenda_key(key_event: KeyEvent) -> AgendaAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => AgendaAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => AgendaAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => AgendaAction::MoveSelectionDown,
+            KeyCode::Enter => AgendaAction::JumpToItem,
+            _ => AgendaAction::None,
+        }
+    }
+
+    pub fn handle_calendar_key(key_event: KeyEvent) -> CalendarAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => CalendarAction::Close,
+            KeyCode::Left | KeyCode::Char('h') => CalendarAction::PreviousDay,
+            KeyCode::Right | KeyCode::Char('l') => CalendarAction::NextDay,
+            KeyCode::Up | KeyCode::Char('k') => CalendarAction::PreviousWeek,
+            KeyCode::Down | KeyCode::Char('j') => CalendarAction::NextWeek,
+            KeyCode::PageUp | KeyCode::Char('[') => CalendarAction::PreviousMonth,
+            KeyCode::PageDown | KeyCode::Char(']') => CalendarAction::NextMonth,
+            KeyCode::Char('t') => CalendarAction::Today,
+            KeyCode::Enter => CalendarAction::OpenDay,
+            _ => CalendarAction::None,
+        }
+    }
+
+    pub fn handle_board_key(key_event: KeyEvent) -> BoardAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => BoardAction::Close,
+            KeyCode::Left => BoardAction::PreviousColumn,
+            KeyCode::Right => BoardAction::NextColumn,
+            KeyCode::Up | KeyCode::Char('k') => BoardAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => BoardAction::MoveSelectionDown,
+            KeyCode::Char('h') => BoardAction::MoveCardLeft,
+            KeyCode::Char('l') => BoardAction::MoveCardRight,
+            KeyCode::Tab => BoardAction::ToggleLayout,
+            KeyCode::Char('u') => BoardAction::Undo,
+            KeyCode::Enter => BoardAction::JumpToItem,
+            _ => BoardAction::None,
+        }
+    }
+
+    pub fn handle_outline_key(key_event: KeyEvent) -> OutlineAction {
+        match key_event.code {
+            KeyCode::Esc | KeyCode::Char('q') => OutlineAction::Close,
+            KeyCode::Up | KeyCode::Char('k') => OutlineAction::MoveSelectionUp,
+            KeyCode::Down | KeyCode::Char('j') => OutlineAction::MoveSelectionDown,
+            KeyCode::Tab | KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => OutlineAction::FocusList,
+            _ => OutlineAction::None,
+        }
+    }
+
+    pub fn handle_command_mode_key(key_event: KeyEvent) -> CommandModeAction {
+        match key_event.code {
+            KeyCode::Esc => CommandModeAction::Cancel,
+            KeyCode::Enter => CommandModeAction::Execute,
+            KeyCode::Tab => CommandModeAction::Complete,
+            KeyCode::Backspace => CommandModeAction::Backspace,
+            KeyCode::Char(c) => CommandModeAction::InsertChar(c),
+            _ => CommandModeAction::None,
+        }
+    }
+
     pub fn handle_search_mode_key(key_event: KeyEvent) -> SearchModeAction {
         match key_event.code {
             KeyCode::Esc => SearchModeAction::CancelSearch,
             KeyCode::Enter => SearchModeAction::ConfirmSearch,
             KeyCode::Backspace => SearchModeAction::Backspace,
+            KeyCode::Up => SearchModeAction::HistoryPrevious,
+            KeyCode::Down => SearchModeAction::HistoryNext,
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::ALT) => SearchModeAction::ToggleRegex,
+            KeyCode::Char('f') if key_event.modifiers.contains(KeyModifiers::ALT) => SearchModeAction::ToggleFuzzy,
+            KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::ALT) => SearchModeAction::CycleCaseSensitivity,
             KeyCode::Char(c) => SearchModeAction::InsertChar(c),
             _ => SearchModeAction::None,
         }
@@ -77,7 +311,18 @@ impl KeyHandler {
     pub fn handle_edit_mode_key(key_event: KeyEvent) -> EditModeAction {
         match key_event.code {
             KeyCode::Esc => EditModeAction::CancelEdit,
+            KeyCode::Enter if key_event.modifiers.contains(KeyModifiers::ALT) => {
+                EditModeAction::SplitItem
+            }
+            // Shift+Enter only reaches us in terminals that report it
+            KeyCode::Enter if key_event.modifiers.contains(KeyModifiers::SHIFT) => {
+                EditModeAction::InsertNewline
+            }
+            KeyCode::Char('j') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                EditModeAction::InsertNewline
+            }
             KeyCode::Enter => EditModeAction::ConfirmEdit,
+            KeyCode::Tab => EditModeAction::AcceptCompletion,
             KeyCode::Backspace => EditModeAction::Backspace,
             KeyCode::Delete => EditModeAction::Delete,
             KeyCode::Left if key_event.modifiers.contains(KeyModifiers::ALT) => {
@@ -88,11 +333,19 @@ impl KeyHandler {
             }
             KeyCode::Left => EditModeAction::MoveCursorLeft,
             KeyCode::Right => EditModeAction::MoveCursorRight,
+            KeyCode::Up => EditModeAction::MoveCursorUp,
+            KeyCode::Down => EditModeAction::MoveCursorDown,
             KeyCode::Home => EditModeAction::MoveCursorHome,
             KeyCode::End => EditModeAction::MoveCursorEnd,
             KeyCode::Char('w') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
                 EditModeAction::DeleteWordBackward
             }
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                EditModeAction::KillToLineStart
+            }
+            KeyCode::Char('k') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                EditModeAction::KillToLineEnd
+            }
             KeyCode::Char('a') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
                 EditModeAction::MoveCursorHome
             }
@@ -119,7 +372,7 @@ impl KeyHandler {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum NormalModeAction {
     None,
     Quit,
@@ -131,6 +384,8 @@ pub enum NormalModeAction {
     IndentItem,
     UnindentItem,
     ToggleSelectedItem,
+    ToggleSubtree,
+    CycleState,
     EnterEditMode,
     AddNewTodo,
     AddNewTodoAtTop,
@@ -140,14 +395,320 @@ pub enum NormalModeAction {
     MoveSelectedItemsToCursor,
     ToggleHelpMode,
     Undo,
+    Redo,
     EnterSearchMode,
+    EnterSectionSearchMode,
     DeleteItem,
+    DeleteSubtree,
+    DuplicateItem,
+    OpenFilePicker,
+    OpenMovePicker,
+    ToggleZoom,
+    PromoteSection,
+    DemoteSection,
+    ToggleFold,
+    ToggleVisualMode,
+    ToggleHideCompleted,
+    ToggleHideBlocked,
+    /// Shows only open todos and the headings they're under
+    TogglePendingOnly,
+    YankItems,
+    PasteItems,
+    CopyItemsToClipboard,
+    CollapseItem,
+    ExpandItem,
+    StartPrefix(char),
+    FollowReference,
+    TogglePomodoro,
+    OpenTagPanel,
+    OpenPreviousDay,
+    OpenNextDay,
+    SetMark(char),
+    JumpToMark(char),
+    RecordMacro,
+    StartRecording(char),
+    ReplayMacro(char),
+    OpenAgenda,
+    OpenBoard,
+    OpenCalendar,
+    OpenInEditor,
+    OpenLink,
+    SortSection,
+    AddSiblingBelow,
+    AddSiblingAbove,
+    FocusOutline,
+    EnterCommandMode,
+    MoveToPreviousHeading,
+    MoveToNextHeading,
+    MoveToPreviousOpenTodo,
+    MoveToNextOpenTodo,
+    MoveToFirstItem,
+    MoveToLastItem,
+    MoveHalfPageUp,
+    MoveHalfPageDown,
+    MovePageUp,
+    MovePageDown,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum HelpModeAction {
     None,
     ExitHelpMode,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollToTop,
+    ScrollToBottom,
+    StartSearch,
+    NextMatch,
+    PreviousMatch,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PromptAction {
+    None,
+    Cancel,
+    Confirm,
+    Backspace,
+    InsertChar(char),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CommandModeAction {
+    None,
+    Cancel,
+    Execute,
+    Complete,
+    Backspace,
+    InsertChar(char),
+}
+
+/// A command entered at the `:` prompt
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Write,
+    Quit,
+    Open(String),
+    Sort(SortKey),
+    /// `--older-than 7d` only archives todos done more than that many days ago
+    Archive { older_than_days: Option<u32> },
+    Help(Option<String>),
+    Substitute(Substitution),
+    /// Adds (or with `remove`, strips) a tag on the selected todos
+    Tag { tag: String, remove: bool },
+    /// Sets or clears the due date of the selected todos
+    Due(Option<NaiveDate>),
+    /// Lists recently deleted items to restore one
+    Undelete,
+    /// Moves open todos from earlier dated headings to today's
+    Rollover,
+    /// Adds a heading after the current section, with `templates.section`
+    /// under it
+    Section(String),
+    /// Opens the search prompt, with `--section` only matching the section
+    /// the cursor is in
+    Search { section: bool },
+    /// `:set number`, `:set relativenumber` or `:set nonumber`
+    SetLineNumbers(LineNumbers),
+}
+
+/// Names offered for completion. Adding a command means adding it here, to
+/// `parse_command` and to `App::execute_command`.
+pub const COMMAND_NAMES: &[&str] = &["archive", "due", "help", "open", "q", "rollover", "search", "section", "set", "sort", "tag", "undelete", "w"];
+
+/// Parses a command line (without the leading `:`) into a command, or an
+/// error message for the status line
+pub fn parse_command(line: &str) -> Result<Command, String> {
+    if let Some(substitution) = Substitution::parse(line) {
+        return substitution.map(Command::Substitute);
+    }
+
+    let line = line.trim();
+    let (name, argument) = match line.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (line, ""),
+    };
+    let argument = (!argument.is_empty()).then(|| argument.to_string());
+
+    match name {
+        "w" | "write" => Ok(Command::Write),
+        "q" | "quit" => Ok(Command::Quit),
+        "open" | "e" | "edit" => argument.map(Command::Open).ok_or_else(|| "Usage: :open <file>".to_string()),
+        "sort" => match argument.as_deref() {
+            None | Some("status") => Ok(Command::Sort(SortKey::Status)),
+            Some("name") => Ok(Command::Sort(SortKey::Alphabetical)),
+            Some("due") => Ok(Command::Sort(SortKey::DueDate)),
+            Some("priority") => Ok(Command::Sort(SortKey::Priority)),
+            Some(_) => Err("Usage: :sort [status|name|due|priority]".to_string()),
+        },
+        "archive" => match argument {
+            None => Ok(Command::Archive { older_than_days: None }),
+            Some(argument) => argument
+                .strip_prefix("--older-than")
+                .map(|days| days.trim())
+                .and_then(|days| days.strip_suffix('d').unwrap_or(days).parse().ok())
+                .map(|days| Command::Archive { older_than_days: Some(days) })
+                .ok_or_else(|| "Usage: :archive [--older-than <days>d]".to_string()),
+        },
+        "help" | "h" => Ok(Command::Help(argument)),
+        "tag" => {
+            let argument = argument.unwrap_or_default();
+            let (name, remove) = match argument.strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (argument.strip_prefix('+').unwrap_or(&argument), false),
+            };
+            let tag = metadata::normalize_tag(name).ok_or_else(|| "Usage: :tag [+|-]<tag>".to_string())?;
+            Ok(Command::Tag { tag: tag.to_string(), remove })
+        }
+        "due" => match argument {
+            Some(date) => metadata::parse_date(&date)
+                .map(|date| Command::Due(Some(date)))
+                .ok_or_else(|| format!("Not a date (YYYY-MM-DD): {}", date)),
+            None => Ok(Command::Due(None)),
+        },
+        "undelete" | "trash" => Ok(Command::Undelete),
+        "rollover" => Ok(Command::Rollover),
+        "section" => argument.map(Command::Section).ok_or_else(|| "Usage: :section <name>".to_string()),
+        "search" => match argument.as_deref() {
+            None => Ok(Command::Search { section: false }),
+            Some("--section") => Ok(Command::Search { section: true }),
+            Some(_) => Err("Usage: :search [--section]".to_string()),
+        },
+        // Vim's names for the options
+        "set" => match argument.as_deref() {
+            Some("number" | "nu") => Ok(Command::SetLineNumbers(LineNumbers::Absolute)),
+            Some("relativenumber" | "rnu") => Ok(Command::SetLineNumbers(LineNumbers::Relative)),
+            Some("nonumber" | "nonu" | "norelativenumber" | "nornu") => Ok(Command::SetLineNumbers(LineNumbers::Off)),
+            _ => Err("Usage: :set number|relativenumber|nonumber".to_string()),
+        },
+        _ => Err(format!("Unknown command: {}", name)),
+    }
+}
+
+/// Command names that could complete what's been typed so far
+pub fn command_suggestions(input: &str) -> Vec<&'static str> {
+    if input.contains(char::is_whitespace) {
+        return Vec::new();
+    }
+    COMMAND_NAMES.iter().copied().filter(|name| name.starts_with(input)).collect()
+}
+
+/// Completes a unique command name (adding a space for its argument), or as
+/// much of several names as they share
+pub fn complete_command(input: &str) -> Option<String> {
+    match command_suggestions(input)[..] {
+        [] => None,
+        [name] => Some(format!("{} ", name)),
+        [first, ref rest @ ..] => {
+            let shared = rest.iter().fold(first.len(), |len, name| {
+                first.bytes().zip(name.bytes()).take(len).take_while(|(a, b)| a == b).count()
+            });
+            (shared > input.len()).then(|| first[..shared].to_string())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AgendaAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    JumpToItem,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CalendarAction {
+    None,
+    Close,
+    PreviousDay,
+    NextDay,
+    PreviousWeek,
+    NextWeek,
+    PreviousMonth,
+    NextMonth,
+    Today,
+    OpenDay,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BoardAction {
+    None,
+    Close,
+    PreviousColumn,
+    NextColumn,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    MoveCardLeft,
+    MoveCardRight,
+    ToggleLayout,
+    Undo,
+    JumpToItem,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OutlineAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    FocusList,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfirmAction {
+    None,
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConflictAction {
+    None,
+    Reload,
+    Overwrite,
+    Merge,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FilePickerAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    OpenSelectedFile,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MovePickerAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    MoveItems,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TrashPickerAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    Restore,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TagPanelAction {
+    None,
+    Close,
+    MoveSelectionUp,
+    MoveSelectionDown,
+    /// Shows only the items with the tag
+    Filter,
+    Rename,
+    /// Takes the tag off every item
+    Remove,
 }
 
 #[derive(Debug, PartialEq)]
@@ -155,6 +716,11 @@ pub enum SearchModeAction {
     None,
     CancelSearch,
     ConfirmSearch,
+    ToggleRegex,
+    ToggleFuzzy,
+    CycleCaseSensitivity,
+    HistoryPrevious,
+    HistoryNext,
     Backspace,
     InsertChar(char),
 }
@@ -168,11 +734,19 @@ pub enum EditModeAction {
     Delete,
     MoveCursorLeft,
     MoveCursorRight,
+    MoveCursorUp,
+    MoveCursorDown,
     MoveCursorHome,
     MoveCursorEnd,
     DeleteWordBackward,
+    KillToLineStart,
+    KillToLineEnd,
     MoveToPreviousWord,
     MoveToNextWord,
+    InsertNewline,
+    SplitItem,
+    /// Puts in the tag picked from the completion menu
+    AcceptCompletion,
     InsertChar(char),
 }
 
@@ -199,6 +773,16 @@ mod tests {
         assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::EnterEditMode);
     }
 
+    #[test]
+    fn test_normal_mode_section_search_key() {
+        for c in ['/', '7'] {
+            let key_event = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+            assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::EnterSectionSearchMode);
+        }
+        let key_event = KeyEvent::from(KeyCode::Char('/'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::EnterSearchMode);
+    }
+
     #[test]
     fn test_normal_mode_navigation_keys() {
         let key_event = KeyEvent::from(KeyCode::Up);
@@ -211,6 +795,248 @@ mod tests {
         assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveSelectionUp);
     }
 
+    #[test]
+    fn test_normal_mode_toggle_subtree_keys() {
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleSubtree);
+
+        let key_event = KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleSubtree);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleSelectedItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('s'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::CycleState);
+    }
+
+    #[test]
+    fn test_normal_mode_redo_key() {
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::Redo);
+
+        let key_event = KeyEvent::from(KeyCode::Char('u'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::Undo);
+    }
+
+    #[test]
+    fn test_normal_mode_external_editor_key() {
+        let key_event = KeyEvent::new(KeyCode::Char('e'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::OpenInEditor);
+
+        let key_event = KeyEvent::from(KeyCode::Char('e'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::EnterEditMode);
+    }
+
+    #[test]
+    fn test_normal_mode_open_link_key() {
+        let key_event = KeyEvent::from(KeyCode::Char('g'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::StartPrefix('g'));
+
+        let key_event = KeyEvent::from(KeyCode::Char('x'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::OpenLink);
+
+        let key_event = KeyEvent::from(KeyCode::Char('s'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::SortSection);
+    }
+
+    #[test]
+    fn test_macro_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('Q'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::RecordMacro);
+        let key_event = KeyEvent::from(KeyCode::Char('a'));
+        assert_eq!(KeyHandler::handle_prefix_key('Q', key_event), NormalModeAction::StartRecording('a'));
+        assert_eq!(KeyHandler::handle_prefix_key('@', key_event), NormalModeAction::ReplayMacro('a'));
+    }
+
+    #[test]
+    fn test_open_todo_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char(']'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::StartPrefix(']'));
+
+        let key_event = KeyEvent::from(KeyCode::Char('t'));
+        assert_eq!(KeyHandler::handle_prefix_key(']', key_event), NormalModeAction::MoveToNextOpenTodo);
+        assert_eq!(KeyHandler::handle_prefix_key('[', key_event), NormalModeAction::MoveToPreviousOpenTodo);
+    }
+
+    #[test]
+    fn test_normal_mode_add_sibling_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('o'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::AddSiblingBelow);
+
+        let key_event = KeyEvent::from(KeyCode::Char('O'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::AddSiblingAbove);
+    }
+
+    #[test]
+    fn test_page_navigation_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('g'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::MoveToFirstItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('G'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveToLastItem);
+
+        let key_event = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveHalfPageDown);
+
+        let key_event = KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveHalfPageUp);
+
+        let key_event = KeyEvent::from(KeyCode::PageDown);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MovePageDown);
+
+        let key_event = KeyEvent::from(KeyCode::PageUp);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MovePageUp);
+
+        // Plain d and u keep their meaning
+        let key_event = KeyEvent::from(KeyCode::Char('d'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::DeleteItem);
+    }
+
+    #[test]
+    fn test_command_mode_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char(':'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::EnterCommandMode);
+
+        let key_event = KeyEvent::from(KeyCode::Tab);
+        assert_eq!(KeyHandler::handle_command_mode_key(key_event), CommandModeAction::Complete);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_command_mode_key(key_event), CommandModeAction::Execute);
+
+        let key_event = KeyEvent::from(KeyCode::Char('w'));
+        assert_eq!(KeyHandler::handle_command_mode_key(key_event), CommandModeAction::InsertChar('w'));
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("w"), Ok(Command::Write));
+        assert_eq!(parse_command(" sort "), Ok(Command::Sort(SortKey::Status)));
+        assert_eq!(parse_command("sort due"), Ok(Command::Sort(SortKey::DueDate)));
+        assert!(parse_command("sort size").is_err());
+        assert_eq!(parse_command("archive"), Ok(Command::Archive { older_than_days: None }));
+        assert_eq!(parse_command("archive --older-than 7d"), Ok(Command::Archive { older_than_days: Some(7) }));
+        assert_eq!(parse_command("archive --older-than 30"), Ok(Command::Archive { older_than_days: Some(30) }));
+        assert!(parse_command("archive --older-than soon").is_err());
+        assert_eq!(parse_command("open ~/notes/work.md"), Ok(Command::Open("~/notes/work.md".to_string())));
+        assert_eq!(parse_command("help search"), Ok(Command::Help(Some("search".to_string()))));
+        assert_eq!(parse_command("help"), Ok(Command::Help(None)));
+        assert!(parse_command("open").is_err());
+        assert!(parse_command("frobnicate").is_err());
+        assert!(matches!(parse_command("%s/a/b/"), Ok(Command::Substitute(_))));
+        assert_eq!(parse_command("undelete"), Ok(Command::Undelete));
+        assert_eq!(parse_command("rollover"), Ok(Command::Rollover));
+        assert_eq!(parse_command("section Next week"), Ok(Command::Section("Next week".to_string())));
+        assert!(parse_command("section").is_err());
+        assert_eq!(parse_command("search --section"), Ok(Command::Search { section: true }));
+        assert!(parse_command("search everywhere").is_err());
+        assert_eq!(parse_command("set rnu"), Ok(Command::SetLineNumbers(LineNumbers::Relative)));
+        assert_eq!(parse_command("set nonumber"), Ok(Command::SetLineNumbers(LineNumbers::Off)));
+        assert!(parse_command("set list").is_err());
+    }
+
+    #[test]
+    fn test_parse_metadata_commands() {
+        let tag = |tag: &str, remove| Ok(Command::Tag { tag: tag.to_string(), remove });
+        assert_eq!(parse_command("tag urgent"), tag("urgent", false));
+        assert_eq!(parse_command("tag +#urgent"), tag("urgent", false));
+        assert_eq!(parse_command("tag -urgent"), tag("urgent", true));
+        assert!(parse_command("tag").is_err());
+        assert!(parse_command("tag two words").is_err());
+
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(parse_command("due 2024-05-01"), Ok(Command::Due(Some(date))));
+        assert_eq!(parse_command("due"), Ok(Command::Due(None)));
+        assert!(parse_command("due friday").is_err());
+    }
+
+    #[test]
+    fn test_complete_command() {
+        assert_eq!(complete_command("ar"), Some("archive ".to_string()));
+        assert_eq!(complete_command("x"), None);
+        assert_eq!(complete_command("sort now"), None);
+        assert_eq!(command_suggestions(""), COMMAND_NAMES.to_vec());
+        // Several matches: nothing more to fill in than what's shared
+        assert_eq!(complete_command(""), None);
+    }
+
+    #[test]
+    fn test_heading_jump_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('{'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveToPreviousHeading);
+
+        let key_event = KeyEvent::from(KeyCode::Char('}'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::MoveToNextHeading);
+    }
+
+    #[test]
+    fn test_outline_keys() {
+        let key_event = KeyEvent::from(KeyCode::Tab);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::FocusOutline);
+
+        let key_event = KeyEvent::from(KeyCode::Char('j'));
+        assert_eq!(KeyHandler::handle_outline_key(key_event), OutlineAction::MoveSelectionDown);
+
+        let key_event = KeyEvent::from(KeyCode::Up);
+        assert_eq!(KeyHandler::handle_outline_key(key_event), OutlineAction::MoveSelectionUp);
+
+        let key_event = KeyEvent::from(KeyCode::Tab);
+        assert_eq!(KeyHandler::handle_outline_key(key_event), OutlineAction::FocusList);
+
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_outline_key(key_event), OutlineAction::Close);
+    }
+
+    #[test]
+    fn test_normal_mode_clipboard_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('y'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::YankItems);
+
+        let key_event = KeyEvent::from(KeyCode::Char('p'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::PasteItems);
+
+        let key_event = KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::SHIFT);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::CopyItemsToClipboard);
+
+        let key_event = KeyEvent::from(KeyCode::Char('+'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::DuplicateItem);
+    }
+
+    #[test]
+    fn test_normal_mode_fold_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('z'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleFold);
+
+        let key_event = KeyEvent::from(KeyCode::Char('Z'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleZoom);
+
+        let key_event = KeyEvent::from(KeyCode::Left);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::CollapseItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('l'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ExpandItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('c'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleHideCompleted);
+
+        let key_event = KeyEvent::from(KeyCode::Char('B'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::ToggleHideBlocked);
+
+        let key_event = KeyEvent::from(KeyCode::Char('F'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::TogglePendingOnly);
+
+        let key_event = KeyEvent::from(KeyCode::Char('T'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::OpenTagPanel);
+    }
+
+    #[test]
+    fn test_tag_panel_keys() {
+        assert_eq!(KeyHandler::handle_tag_panel_key(KeyEvent::from(KeyCode::Enter)), TagPanelAction::Filter);
+        assert_eq!(KeyHandler::handle_tag_panel_key(KeyEvent::from(KeyCode::Char('r'))), TagPanelAction::Rename);
+        assert_eq!(KeyHandler::handle_tag_panel_key(KeyEvent::from(KeyCode::Char('d'))), TagPanelAction::Remove);
+        assert_eq!(KeyHandler::handle_tag_panel_key(KeyEvent::from(KeyCode::Esc)), TagPanelAction::Close);
+    }
+
     #[test]
     fn test_normal_mode_shift_keys() {
         let mut key_event = KeyEvent::from(KeyCode::Up);
@@ -228,6 +1054,12 @@ mod tests {
         let mut key_event = KeyEvent::from(KeyCode::Right);
         key_event.modifiers = KeyModifiers::SHIFT;
         assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::IndentItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('<'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::PromoteSection);
+
+        let key_event = KeyEvent::from(KeyCode::Char('>'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::DemoteSection);
     }
 
     #[test]
@@ -250,6 +1082,137 @@ mod tests {
 
         let key_event = KeyEvent::from(KeyCode::Char('x'));
         assert_eq!(KeyHandler::handle_help_mode_key(key_event), HelpModeAction::None);
+
+        let key_event = KeyEvent::from(KeyCode::Char('/'));
+        assert_eq!(KeyHandler::handle_help_mode_key(key_event), HelpModeAction::StartSearch);
+
+        let key_event = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_help_mode_key(key_event), HelpModeAction::PageDown);
+
+        let key_event = KeyEvent::from(KeyCode::Char('q'));
+        assert_eq!(KeyHandler::handle_prompt_key(key_event), PromptAction::InsertChar('q'));
+    }
+
+    #[test]
+    fn test_confirm_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('y'));
+        assert_eq!(KeyHandler::handle_confirm_key(key_event), ConfirmAction::Confirm);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_confirm_key(key_event), ConfirmAction::Confirm);
+
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_confirm_key(key_event), ConfirmAction::Cancel);
+
+        // Keys that would be easy to hit by accident do nothing
+        let key_event = KeyEvent::from(KeyCode::Char('d'));
+        assert_eq!(KeyHandler::handle_confirm_key(key_event), ConfirmAction::None);
+    }
+
+    #[test]
+    fn test_conflict_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('m'));
+        assert_eq!(KeyHandler::handle_conflict_key(key_event), ConflictAction::Merge);
+
+        // There's no way out without deciding what happens to the changes
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_conflict_key(key_event), ConflictAction::None);
+    }
+
+    #[test]
+    fn test_move_picker_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('M'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::OpenMovePicker);
+
+        let key_event = KeyEvent::from(KeyCode::Char('j'));
+        assert_eq!(KeyHandler::handle_move_picker_key(key_event), MovePickerAction::MoveSelectionDown);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_move_picker_key(key_event), MovePickerAction::MoveItems);
+
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_move_picker_key(key_event), MovePickerAction::Close);
+    }
+
+    #[test]
+    fn test_trash_picker_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('k'));
+        assert_eq!(KeyHandler::handle_trash_picker_key(key_event), TrashPickerAction::MoveSelectionUp);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_trash_picker_key(key_event), TrashPickerAction::Restore);
+
+        let key_event = KeyEvent::from(KeyCode::Char('q'));
+        assert_eq!(KeyHandler::handle_trash_picker_key(key_event), TrashPickerAction::Close);
+    }
+
+    #[test]
+    fn test_file_picker_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('b'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::OpenFilePicker);
+
+        let key_event = KeyEvent::from(KeyCode::Char('j'));
+        assert_eq!(KeyHandler::handle_file_picker_key(key_event), FilePickerAction::MoveSelectionDown);
+
+        let key_event = KeyEvent::from(KeyCode::Char('k'));
+        assert_eq!(KeyHandler::handle_file_picker_key(key_event), FilePickerAction::MoveSelectionUp);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_file_picker_key(key_event), FilePickerAction::OpenSelectedFile);
+
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_file_picker_key(key_event), FilePickerAction::Close);
+    }
+
+    #[test]
+    fn test_agenda_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('g'));
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::StartPrefix('g'));
+
+        let key_event = KeyEvent::from(KeyCode::Char('a'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::OpenAgenda);
+
+        let key_event = KeyEvent::from(KeyCode::Char('q'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::None);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_agenda_key(key_event), AgendaAction::JumpToItem);
+
+        let key_event = KeyEvent::from(KeyCode::Char('j'));
+        assert_eq!(KeyHandler::handle_agenda_key(key_event), AgendaAction::MoveSelectionDown);
+
+        let key_event = KeyEvent::from(KeyCode::Esc);
+        assert_eq!(KeyHandler::handle_agenda_key(key_event), AgendaAction::Close);
+    }
+
+    #[test]
+    fn test_calendar_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('c'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::OpenCalendar);
+
+        let key_event = KeyEvent::from(KeyCode::Char('j'));
+        assert_eq!(KeyHandler::handle_calendar_key(key_event), CalendarAction::NextWeek);
+
+        let key_event = KeyEvent::from(KeyCode::Char('['));
+        assert_eq!(KeyHandler::handle_calendar_key(key_event), CalendarAction::PreviousMonth);
+
+        let key_event = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(KeyHandler::handle_calendar_key(key_event), CalendarAction::OpenDay);
+    }
+
+    #[test]
+    fn test_board_keys() {
+        let key_event = KeyEvent::from(KeyCode::Char('b'));
+        assert_eq!(KeyHandler::handle_prefix_key('g', key_event), NormalModeAction::OpenBoard);
+
+        let key_event = KeyEvent::from(KeyCode::Char('l'));
+        assert_eq!(KeyHandler::handle_board_key(key_event), BoardAction::MoveCardRight);
+
+        let key_event = KeyEvent::from(KeyCode::Right);
+        assert_eq!(KeyHandler::handle_board_key(key_event), BoardAction::NextColumn);
+
+        let key_event = KeyEvent::from(KeyCode::Tab);
+        assert_eq!(KeyHandler::handle_board_key(key_event), BoardAction::ToggleLayout);
     }
 
     #[test]
@@ -265,6 +1228,18 @@ mod tests {
 
         let key_event = KeyEvent::from(KeyCode::Char('a'));
         assert_eq!(KeyHandler::handle_search_mode_key(key_event), SearchModeAction::InsertChar('a'));
+
+        let key_event = KeyEvent::new(KeyCode::Char('r'), KeyModifiers::ALT);
+        assert_eq!(KeyHandler::handle_search_mode_key(key_event), SearchModeAction::ToggleRegex);
+
+        let key_event = KeyEvent::new(KeyCode::Char('f'), KeyModifiers::ALT);
+        assert_eq!(KeyHandler::handle_search_mode_key(key_event), SearchModeAction::ToggleFuzzy);
+
+        let key_event = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT);
+        assert_eq!(KeyHandler::handle_search_mode_key(key_event), SearchModeAction::CycleCaseSensitivity);
+
+        let key_event = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(KeyHandler::handle_search_mode_key(key_event), SearchModeAction::HistoryPrevious);
     }
 
     #[test]
@@ -295,6 +1270,27 @@ mod tests {
 
         let key_event = KeyEvent::from(KeyCode::Char('x'));
         assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::InsertChar('x'));
+
+        let key_event = KeyEvent::from(KeyCode::Tab);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::AcceptCompletion);
+    }
+
+    #[test]
+    fn test_edit_mode_multiline_keys() {
+        let key_event = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::InsertNewline);
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::InsertNewline);
+
+        let key_event = KeyEvent::new(KeyCode::Enter, KeyModifiers::ALT);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::SplitItem);
+
+        let key_event = KeyEvent::from(KeyCode::Up);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::MoveCursorUp);
+
+        let key_event = KeyEvent::from(KeyCode::Down);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::MoveCursorDown);
     }
 
     #[test]
@@ -303,6 +1299,12 @@ mod tests {
         assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::DeleteItem);
     }
 
+    #[test]
+    fn test_normal_mode_delete_subtree_key() {
+        let key_event = KeyEvent::new(KeyCode::Char('D'), KeyModifiers::SHIFT);
+        assert_eq!(KeyHandler::handle_normal_mode_key(key_event), NormalModeAction::DeleteSubtree);
+    }
+
     #[test]
     fn test_edit_mode_ctrl_keys() {
         // Test Ctrl-W (delete word backward)
@@ -310,6 +1312,14 @@ mod tests {
         key_event.modifiers = KeyModifiers::CONTROL;
         assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::DeleteWordBackward);
 
+        // Test Ctrl-U (kill to start of line)
+        let key_event = KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::KillToLineStart);
+
+        // Test Ctrl-K (kill to end of line)
+        let key_event = KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(KeyHandler::handle_edit_mode_key(key_event), EditModeAction::KillToLineEnd);
+
         // Test Ctrl-A (move to beginning)
         let mut key_event = KeyEvent::from(KeyCode::Char('a'));
         key_event.modifiers = KeyModifiers::CONTROL;
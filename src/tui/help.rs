@@ -0,0 +1,284 @@
+//! The help window (`?`). The key lines come from the keymap, so they show
+//! the keys as actually bound, overrides included; what they can't cover
+//! (keys inside the prompts and the commands) is written out below.
+
+use crate::tui::keymap::{ACTIONS, Keymap};
+
+// Width of the key column
+const KEYS_WIDTH: usize = 18;
+
+// Lines added after a section's generated ones
+const SECTION_NOTES: &[(&str, &[&str])] = &[
+    ("NAVIGATION", &[
+        "  5j / 3} / 10G     A count before a move repeats it; before G or g g, it picks the row",
+        "  m<letter>         Mark the item (when nothing is selected); '<letter> jumps back to it",
+        "  ⛔ id             A todo with `⛔ id` waits on the open todo marked `🆔 id`;",
+        "                    it's dimmed with a 🔒 until that one is done",
+    ]),
+    ("SEARCH", &[
+        "  While searching:  Up/Down recall earlier searches, Alt+R toggles regex search,",
+        "                    Alt+F fuzzy search (best matches first), Alt+C cycles smart-case,",
+        "                    match case and ignore case",
+    ]),
+    ("EDITING", &[
+        "  While editing:    Ctrl+J (or Shift+Enter) starts a new line in a todo or note,",
+        "                    Alt+Enter splits the item in two at the cursor, ↑↓ move between lines,",
+        "                    Alt+B/F move back/forward a word, Ctrl+W deletes the word before the cursor,",
+        "                    Ctrl+U / Ctrl+K delete to the start/end of the line,",
+        "                    Backspace at the start of an item joins it onto the item above,",
+        "                    typing #tag or @context offers those used elsewhere in the file",
+        "                    (↑↓ pick one, Tab puts it in, Esc closes the list)",
+    ]),
+    ("OTHER", &[
+        "  On the board:     ←→ pick a column, h/l move the card to the next column",
+        "  @<letter>         Replay the macro recorded there (3@a: three times, @@: the last one)",
+        "  Plugins (see `plugins` in the config) can add commands and unused keys;",
+        "  `[keys]` in the config rebinds keys, e.g. \"ctrl+n\" = \"move_down\",",
+        "  or binds a sequence, e.g. \"space f\" = \"search\"",
+    ]),
+];
+
+const COMMAND_LINES: &[&str] = &[
+    "COMMANDS (: to enter, Tab to complete):",
+    "  :w                Save now",
+    "  :q                Quit",
+    "  :open <file>      Open another file",
+    "  :search           Search; --section: only the section the cursor is in",
+    "  :sort [key]       Sort the current section's todos: open ones first (status),",
+    "                    or by name, due date (due) or priority (🔺 ⏫ 🔼 🔽 ⏬)",
+    "  :rollover         Move open todos under earlier dated headings (## 2024-05-01) to today's",
+    "  :section <name>   Add a heading after the current section, with templates.section under it",
+    "  :archive          Move done and cancelled todos to the Archive section;",
+    "                    --older-than 7d: only ones checked off (✅ date) over 7 days ago",
+    "  :tag [-]<tag>     Add (or with -, remove) a #tag on the selected todos",
+    "  :due [date]       Set the selected todos' due date (YYYY-MM-DD), or clear it",
+    "  :help <topic>     Show help starting at a section, e.g. :help search",
+    "  :set number       Number the rows (relativenumber: count from the cursor; nonumber: hide)",
+    "  :undelete         Restore something deleted this session, even past the undo history",
+    "  :s/old/new/       Replace text in the current item (g: every occurrence)",
+    "  :%s/old/new/g     Replace text in every item, previewed as you type",
+];
+
+/// Lines of the help window for `keymap`; section titles are unindented and
+/// end with `:`. Actions with no key bound are left out.
+pub fn help_lines(keymap: &Keymap) -> Vec<String> {
+    let mut lines = vec!["Todo List - Keyboard Commands".to_string(), String::new()];
+    let mut sections: Vec<&str> = ACTIONS.iter().map(|info| info.section).collect();
+    sections.dedup();
+    for section in sections {
+        lines.push(format!("{}:", section));
+        for info in ACTIONS.iter().filter(|info| info.section == section) {
+            let keys = keymap.keys_for(&info.action);
+            if !keys.is_empty() {
+                lines.push(format!("  {:<width$} {}", keys.join(" / "), info.description, width = KEYS_WIDTH - 1));
+            }
+        }
+        let notes = SECTION_NOTES.iter().filter(|(name, _)| *name == section).flat_map(|(_, notes)| notes.iter());
+        lines.extend(notes.map(|note| note.to_string()));
+        lines.push(String::new());
+    }
+    lines.extend(COMMAND_LINES.iter().map(|line| line.to_string()));
+    lines.push(String::new());
+    lines.push("j/k scroll, / searches the help (n/N: next/previous match), ? or Esc closes".to_string());
+    lines
+}
+
+/// The scroll position and search of the help window
+pub struct HelpState {
+    pub lines: Vec<String>,
+    /// Index of the top line shown
+    pub scroll: usize,
+    viewport_height: usize,
+    /// What's typed after `/`, while the search prompt is open
+    pub search_input: Option<String>,
+    /// The last search, highlighted and repeated with n/N
+    pub query: String,
+    // The line n/N last went to; scroll can't say which once it's clamped
+    match_line: Option<usize>,
+}
+
+impl HelpState {
+    pub fn new(lines: Vec<String>) -> Self {
+        Self {
+            lines,
+            scroll: 0,
+            viewport_height: 1,
+            search_input: None,
+            query: String::new(),
+            match_line: None,
+        }
+    }
+
+    /// Shows the help from `line`, without a search
+    pub fn open(&mut self, line: usize) {
+        self.search_input = None;
+        self.query.clear();
+        self.match_line = None;
+        self.scroll = line;
+        self.clamp_scroll();
+    }
+
+    /// The line where the section named by `topic` starts, matching the
+    /// start of the title case-insensitively (`:help edit` finds "EDITING:")
+    pub fn topic_line(&self, topic: &str) -> Option<usize> {
+        let topic = topic.trim().to_uppercase();
+        if topic.is_empty() {
+            return None;
+        }
+        self.lines
+            .iter()
+            .position(|line| line.ends_with(':') && !line.starts_with(' ') && line.starts_with(&topic))
+    }
+
+    /// Called with the number of lines that fit, whenever the window is drawn
+    pub fn set_viewport_height(&mut self, height: usize) {
+        self.viewport_height = height.max(1);
+        self.clamp_scroll();
+    }
+
+    pub fn scroll_by(&mut self, lines: isize) {
+        self.scroll = self.scroll.saturating_add_signed(lines);
+        self.clamp_scroll();
+    }
+
+    pub fn scroll_pages(&mut self, pages: isize) {
+        self.scroll_by(pages * self.viewport_height as isize);
+    }
+
+    pub fn scroll_to_end(&mut self) {
+        self.scroll = usize::MAX;
+        self.clamp_scroll();
+    }
+
+    // Scrolled no further than showing the last line at the bottom
+    fn clamp_scroll(&mut self) {
+        self.scroll = self.scroll.min(self.lines.len().saturating_sub(self.viewport_height));
+    }
+
+    pub fn start_search(&mut self) {
+        self.search_input = Some(String::new());
+    }
+
+    /// Updates the search as it's typed, scrolling to the first match
+    pub fn set_search_input(&mut self, input: String) {
+        self.query = input.clone();
+        self.search_input = Some(input);
+        self.match_line = self.find_match(0, true);
+        if let Some(line) = self.match_line {
+            self.scroll_to(line);
+        }
+    }
+
+    /// Closes the prompt, keeping the query for n/N
+    pub fn finish_search(&mut self) {
+        self.search_input = None;
+    }
+
+    /// Scrolls to the next (or previous) line matching the query, wrapping
+    /// around; false if nothing matches
+    pub fn next_match(&mut self, forward: bool) -> bool {
+        let current = self.match_line.unwrap_or(self.scroll);
+        let start = if forward { current + 1 } else { current + self.lines.len() - 1 };
+        match self.find_match(start, forward) {
+            Some(line) => {
+                self.match_line = Some(line);
+                self.scroll_to(line);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        !self.query.is_empty() && line.to_lowercase().contains(&self.query.to_lowercase())
+    }
+
+    fn find_match(&self, start: usize, forward: bool) -> Option<usize> {
+        let count = self.lines.len();
+        (0..count)
+            .map(|step| if forward { (start + step) % count } else { (start + count * 2 - step) % count })
+            .find(|&line| self.matches(&self.lines[line]))
+    }
+
+    // Puts `line` at the top, or as near as the end of the help allows
+    fn scroll_to(&mut self, line: usize) {
+        self.scroll = line;
+        self.clamp_scroll();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn help() -> HelpState {
+        HelpState::new(help_lines(&Keymap::load(&BTreeMap::new()).0))
+    }
+
+    #[test]
+    fn test_topic_line() {
+        let help = help();
+        let line = help.topic_line("edit").unwrap();
+        assert_eq!(help.lines[line], "EDITING:");
+        assert_eq!(help.lines[help.topic_line("Search").unwrap()], "SEARCH:");
+        assert_eq!(help.topic_line("nonsense"), None);
+        assert_eq!(help.topic_line(""), None);
+    }
+
+    #[test]
+    fn test_lines_follow_keymap() {
+        let lines = help().lines;
+        assert!(lines.contains(&"  k / ↑             Move up".to_string()));
+        assert!(lines.iter().any(|line| line.starts_with("  d ") && line.contains("Delete current item")));
+
+        let keys = [("ctrl+n".to_string(), "move_down".to_string()), ("d".to_string(), "none".to_string())];
+        let lines = help_lines(&Keymap::load(&keys.into_iter().collect()).0);
+        assert!(lines.contains(&"  j / ↓ / Ctrl+N    Move down".to_string()));
+        assert!(!lines.iter().any(|line| line.contains("Delete current item (")));
+    }
+
+    #[test]
+    fn test_scroll_and_search() {
+        let mut help = help();
+        help.set_viewport_height(10);
+        help.scroll_to_end();
+        assert_eq!(help.scroll, help.lines.len() - 10);
+        help.scroll_by(5);
+        assert_eq!(help.scroll, help.lines.len() - 10);
+
+        help.open(0);
+        help.start_search();
+        help.set_search_input("heading".to_string());
+        let first = help.scroll;
+        assert!(help.matches(&help.lines[first]));
+        help.finish_search();
+        assert!(help.next_match(true));
+        assert!(help.scroll > first);
+        assert!(help.next_match(false));
+        assert_eq!(help.scroll, first);
+
+        help.set_search_input("no such thing".to_string());
+        assert!(!help.next_match(true));
+    }
+
+    #[test]
+    fn test_next_match_on_last_page() {
+        let lines = (0..20).map(|line| if line >= 15 && line % 2 == 1 { format!("match {}", line) } else { String::new() });
+        let mut help = HelpState::new(lines.collect());
+        help.set_viewport_height(10);
+        help.set_search_input("match".to_string());
+        assert_eq!(help.match_line, Some(15));
+        assert_eq!(help.scroll, 10);
+
+        help.next_match(true);
+        assert_eq!(help.match_line, Some(17));
+        help.next_match(true);
+        assert_eq!(help.match_line, Some(19));
+        help.next_match(true);
+        assert_eq!(help.match_line, Some(15));
+        help.next_match(false);
+        assert_eq!(help.match_line, Some(19));
+        assert_eq!(help.scroll, 10);
+    }
+}
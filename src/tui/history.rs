@@ -0,0 +1,141 @@
+//! Past search queries, recalled with Up/Down in the search prompt. They're
+//! kept one per line in the user's state directory (e.g.
+//! `~/.local/state/todo/search_history`), so they outlive the session.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+
+// Older queries are forgotten past this many
+const MAX_ENTRIES: usize = 100;
+
+pub struct SearchHistory {
+    // Oldest first
+    entries: Vec<String>,
+    // The entry being shown while going through the history
+    position: Option<usize>,
+    // What was typed before going through the history, shown again past the newest entry
+    draft: String,
+    path: Option<PathBuf>,
+}
+
+impl SearchHistory {
+    /// Where the history is kept, if the platform has somewhere for it
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("todo").join("search_history"))
+    }
+
+    /// Reads the history from `path`; a missing or unreadable file starts an
+    /// empty one. Without a path, nothing is read or saved.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| contents.lines().filter(|line| !line.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        Self {
+            entries,
+            position: None,
+            draft: String::new(),
+            path,
+        }
+    }
+
+    /// Adds a query that was searched for, moving it to the newest if it was
+    /// already there
+    pub fn push(&mut self, query: &str) {
+        self.reset();
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.push(query.to_string());
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let contents: String = self.entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        fs::write(path, contents).with_context(|| format!("Failed to save search history to {}", path.display()))
+    }
+
+    /// Starts over from the newest entry, for a new search
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// The entry before the one shown, remembering `current` as the draft
+    /// when starting to go back
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        Some(&self.entries[position])
+    }
+
+    /// The entry after the one shown, or the draft after the newest
+    pub fn newer(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(&self.entries[position + 1])
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recall() {
+        let mut history = SearchHistory::load(None);
+        assert_eq!(history.older("typed"), None);
+        history.push("milk");
+        history.push("dog");
+        history.push("milk");
+        history.push("");
+
+        assert_eq!(history.older("typed"), Some("milk"));
+        assert_eq!(history.older("milk"), Some("dog"));
+        assert_eq!(history.older("dog"), Some("dog"));
+        assert_eq!(history.newer(), Some("milk"));
+        assert_eq!(history.newer(), Some("typed"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("todo_search_history_{}", std::process::id()));
+        let path = dir.join("search_history");
+        let mut history = SearchHistory::load(Some(path.clone()));
+        for i in 0..MAX_ENTRIES + 2 {
+            history.push(&i.to_string());
+        }
+        history.save().unwrap();
+
+        let mut loaded = SearchHistory::load(Some(path));
+        assert_eq!(loaded.entries.len(), MAX_ENTRIES);
+        assert_eq!(loaded.entries[0], "2");
+        assert_eq!(loaded.older(""), Some((MAX_ENTRIES + 1).to_string().as_str()));
+        fs::remove_dir_all(&dir).ok();
+    }
+}
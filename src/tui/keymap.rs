@@ -0,0 +1,446 @@
+//! Which key does what in the list. The built-in bindings are those of
+//! `KeyHandler::handle_normal_mode_key`; the `[keys]` table of the config can
+//! bind other keys to actions by name, or unbind one with `none`:
+//!
+//! ```toml
+//! [keys]
+//! "ctrl+n" = "move_down"
+//! "x" = "toggle"
+//! "d" = "none"
+//! "space f" = "search"
+//! ```
+//!
+//! Keys separated by spaces are a sequence, pressed one after the other; its
+//! first key then waits for the rest instead of doing what it does alone.
+//! The help window lists the keys found here, so it can't fall behind them.
+
+use crate::tui::handlers::{KeyHandler, NormalModeAction};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+
+/// An action keys can be bound to, with the name used in the config and the
+/// line shown for it in the help window
+pub struct ActionInfo {
+    pub name: &'static str,
+    pub action: NormalModeAction,
+    /// Title of the help section it's listed in
+    pub section: &'static str,
+    pub description: &'static str,
+}
+
+const fn info(name: &'static str, action: NormalModeAction, section: &'static str, description: &'static str) -> ActionInfo {
+    ActionInfo { name, action, section, description }
+}
+
+/// Every action, in the order the help window lists them
+pub const ACTIONS: &[ActionInfo] = &[
+    info("move_up", NormalModeAction::MoveSelectionUp, "NAVIGATION", "Move up"),
+    info("move_down", NormalModeAction::MoveSelectionDown, "NAVIGATION", "Move down"),
+    info("previous_heading", NormalModeAction::MoveToPreviousHeading, "NAVIGATION", "Jump to the previous heading"),
+    info("next_heading", NormalModeAction::MoveToNextHeading, "NAVIGATION", "Jump to the next heading"),
+    info("previous_open_todo", NormalModeAction::MoveToPreviousOpenTodo, "NAVIGATION", "Jump to the previous unchecked todo"),
+    info("next_open_todo", NormalModeAction::MoveToNextOpenTodo, "NAVIGATION", "Jump to the next unchecked todo, skipping notes, headings and done ones"),
+    info("first_item", NormalModeAction::MoveToFirstItem, "NAVIGATION", "Jump to the first item"),
+    info("last_item", NormalModeAction::MoveToLastItem, "NAVIGATION", "Jump to the last item"),
+    info("half_page_down", NormalModeAction::MoveHalfPageDown, "NAVIGATION", "Move half a page down"),
+    info("half_page_up", NormalModeAction::MoveHalfPageUp, "NAVIGATION", "Move half a page up"),
+    info("page_down", NormalModeAction::MovePageDown, "NAVIGATION", "Move a page down"),
+    info("page_up", NormalModeAction::MovePageUp, "NAVIGATION", "Move a page up"),
+    info("toggle", NormalModeAction::ToggleSelectedItem, "NAVIGATION", "Toggle todo completion (with a selection: all of them, or reopen them if all done)"),
+    info("toggle_subtree", NormalModeAction::ToggleSubtree, "NAVIGATION", "Toggle todo together with its nested todos"),
+    info("cycle_state", NormalModeAction::CycleState, "NAVIGATION", "Cycle state: open, in progress, done, cancelled, deferred"),
+    info("toggle_completed", NormalModeAction::ToggleHideCompleted, "NAVIGATION", "Show/hide completed todos"),
+    info("toggle_blocked", NormalModeAction::ToggleHideBlocked, "NAVIGATION", "Show/hide todos blocked by open ones (⛔ id)"),
+    info("toggle_pending", NormalModeAction::TogglePendingOnly, "NAVIGATION", "Show only open todos and the headings they're under (again to show all)"),
+    info("tags", NormalModeAction::OpenTagPanel, "NAVIGATION", "Tags with counts: Enter shows only that tag's items (Esc: all), r renames, d removes"),
+    info("outline", NormalModeAction::FocusOutline, "NAVIGATION", "Outline of headings: j/k jump between sections, Tab back to the list, Esc to close"),
+    info("search", NormalModeAction::EnterSearchMode, "SEARCH", "Search (smart-case); the cursor follows the first match, Esc goes back"),
+    info("section_search", NormalModeAction::EnterSectionSearchMode, "SEARCH", "Search only the section the cursor is in (also :search --section)"),
+    info("next_match", NormalModeAction::HandleN, "SEARCH", "Go to the next search match (or add a note below the cursor if no search)"),
+    info("previous_match", NormalModeAction::HandleShiftN, "SEARCH", "Go to the previous search match (or add a note at the top if no search)"),
+    info("edit", NormalModeAction::EnterEditMode, "EDITING", "Edit current item"),
+    info("open_in_editor", NormalModeAction::OpenInEditor, "EDITING", "Open the file in $EDITOR at the current item"),
+    info("add_todo", NormalModeAction::AddNewTodo, "EDITING", "Add new todo below cursor"),
+    info("add_sibling_below", NormalModeAction::AddSiblingBelow, "EDITING", "Add new todo right below the item, at the same level"),
+    info("add_sibling_above", NormalModeAction::AddSiblingAbove, "EDITING", "Add new todo right above the item, at the same level"),
+    info("add_todo_at_top", NormalModeAction::AddNewTodoAtTop, "EDITING", "Add new todo at top/under heading"),
+    info("delete", NormalModeAction::DeleteItem, "EDITING", "Delete current item (or the selected items)"),
+    info("delete_subtree", NormalModeAction::DeleteSubtree, "EDITING", "Delete current item with everything nested under it"),
+    info("move_item_up", NormalModeAction::MoveItemUp, "MOVEMENT", "Move item up"),
+    info("move_item_down", NormalModeAction::MoveItemDown, "MOVEMENT", "Move item down"),
+    info("unindent", NormalModeAction::UnindentItem, "MOVEMENT", "Unindent item or selected items (on a heading: raise its level)"),
+    info("indent", NormalModeAction::IndentItem, "MOVEMENT", "Indent item or selected items (on a heading: lower its level)"),
+    info("promote_section", NormalModeAction::PromoteSection, "MOVEMENT", "Promote a heading along with its sub-headings"),
+    info("demote_section", NormalModeAction::DemoteSection, "MOVEMENT", "Demote a heading along with its sub-headings"),
+    info("toggle_fold", NormalModeAction::ToggleFold, "FOLDING", "Collapse/expand item's children (or heading's section)"),
+    info("zoom", NormalModeAction::ToggleZoom, "FOLDING", "Zoom into the current section, hiding everything else (again to show all)"),
+    info("collapse", NormalModeAction::CollapseItem, "FOLDING", "Collapse item"),
+    info("expand", NormalModeAction::ExpandItem, "FOLDING", "Expand item"),
+    info("select", NormalModeAction::ToggleItemSelection, "BULK OPERATIONS", "Select/deselect item for bulk operations"),
+    info("visual_mode", NormalModeAction::ToggleVisualMode, "BULK OPERATIONS", "Visual mode: select a range by moving the cursor"),
+    info("move_selected_here", NormalModeAction::MoveSelectedItemsToCursor, "BULK OPERATIONS", "Move selected items below cursor"),
+    info("move_to_section", NormalModeAction::OpenMovePicker, "BULK OPERATIONS", "Move the current item (or selection) to the end of a section"),
+    info("yank", NormalModeAction::YankItems, "CLIPBOARD", "Yank item with its children (or selected items)"),
+    info("paste", NormalModeAction::PasteItems, "CLIPBOARD", "Paste yanked items below cursor"),
+    info("copy", NormalModeAction::CopyItemsToClipboard, "CLIPBOARD", "Copy item(s) as markdown to the system clipboard"),
+    info("duplicate", NormalModeAction::DuplicateItem, "CLIPBOARD", "Duplicate item with its children below it"),
+    info("switch_file", NormalModeAction::OpenFilePicker, "OTHER", "Switch to another configured file"),
+    info("open_link", NormalModeAction::OpenLink, "OTHER", "Open the first link in the item in the browser"),
+    info("follow_reference", NormalModeAction::FollowReference, "OTHER", "Jump to the item a [[text]] or (#id) in this one refers to"),
+    info("pomodoro", NormalModeAction::TogglePomodoro, "OTHER", "Start a pomodoro on the todo, logged as ⏱ time spent; again to stop it"),
+    info("previous_day", NormalModeAction::OpenPreviousDay, "OTHER", "Open the previous day's file, with a dated file_path (~/notes/%Y-%m-%d.md)"),
+    info("next_day", NormalModeAction::OpenNextDay, "OTHER", "Open the next day's file"),
+    info("sort_section", NormalModeAction::SortSection, "OTHER", "Sort the current section (opens :sort)"),
+    info("agenda", NormalModeAction::OpenAgenda, "OTHER", "Agenda of overdue todos and todos due this week (📅 YYYY-MM-DD)"),
+    info("calendar", NormalModeAction::OpenCalendar, "OTHER", "Calendar of open todos due each day; Enter lists a day's todos"),
+    info("board", NormalModeAction::OpenBoard, "OTHER", "Board: top-level todos as cards in columns by heading (Tab: by state)"),
+    info("record_macro", NormalModeAction::RecordMacro, "OTHER", "Record a macro: Q then a letter starts, Q again stops"),
+    info("undo", NormalModeAction::Undo, "OTHER", "Undo last operation"),
+    info("redo", NormalModeAction::Redo, "OTHER", "Redo last undone operation"),
+    info("escape", NormalModeAction::HandleEscape, "OTHER", "Clear selection (or the tag filter)"),
+    info("command_line", NormalModeAction::EnterCommandMode, "OTHER", "Enter a command (see COMMANDS)"),
+    info("help", NormalModeAction::ToggleHelpMode, "OTHER", "Show this help"),
+    info("quit", NormalModeAction::Quit, "OTHER", "Quit application"),
+];
+
+/// The action named `name` in the config; `none` unbinds a key
+pub fn action_by_name(name: &str) -> Option<NormalModeAction> {
+    if name == "none" {
+        return Some(NormalModeAction::None);
+    }
+    ACTIONS.iter().find(|info| info.name == name).map(|info| info.action.clone())
+}
+
+/// A key with its modifiers, compared the way they're bound: a character
+/// key's Shift is part of the character (`K`, not `Shift+k`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if matches!(code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    /// Parses a key as written in the config: `j`, `G`, `ctrl+d`, `shift+up`,
+    /// `alt+enter`, `space`, `+`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("'{}' is not a key", spec);
+        // `+` is a key itself as well as the separator
+        let (modifiers, key) = match spec.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest.strip_suffix('+').unwrap_or(rest), "+"),
+            _ => spec.rsplit_once('+').unwrap_or(("", spec)),
+        };
+        let mut parsed_modifiers = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            parsed_modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if parsed_modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+            (Some(c), None) if parsed_modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(Self::new(code, parsed_modifiers))
+    }
+
+    // The event a terminal sends for this key
+    fn event(self) -> KeyEvent {
+        let mut modifiers = self.modifiers;
+        if let KeyCode::Char(c) = self.code
+            && c.is_ascii_uppercase()
+        {
+            modifiers.insert(KeyModifiers::SHIFT);
+        }
+        KeyEvent::new(self.code, modifiers)
+    }
+
+    /// How the key is written in the help window
+    pub fn label(self) -> String {
+        let mut label = String::new();
+        for (modifier, name) in [(KeyModifiers::CONTROL, "Ctrl+"), (KeyModifiers::ALT, "Alt+"), (KeyModifiers::SHIFT, "Shift+")] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => label.push_str("Space"),
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::CONTROL) => label.push(c.to_ascii_uppercase()),
+            KeyCode::Char(c) => label.push(c),
+            KeyCode::Up => label.push('↑'),
+            KeyCode::Down => label.push('↓'),
+            KeyCode::Left => label.push('←'),
+            KeyCode::Right => label.push('→'),
+            KeyCode::PageUp => label.push_str("PgUp"),
+            KeyCode::PageDown => label.push_str("PgDn"),
+            code => label.push_str(&format!("{:?}", code)),
+        }
+        label
+    }
+}
+
+// Keys without modifiers are tried first, so they come first in the help
+const SPECIAL_KEYS: [KeyCode; 13] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Esc,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+];
+
+/// Keys of a binding as written in the config, e.g. `g t` or `space f`
+fn parse_keys(spec: &str) -> Result<Vec<KeyPress>, String> {
+    // A lone " " is the space bar rather than an empty sequence
+    if spec.trim().is_empty() {
+        return KeyPress::parse(spec).map(|key| vec![key]);
+    }
+    spec.split_whitespace().map(KeyPress::parse).collect()
+}
+
+/// What the keys pressed so far come to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Chord {
+    Action(NormalModeAction),
+    /// The start of a longer command, waiting for the next key
+    Pending,
+}
+
+pub struct Keymap {
+    overrides: HashMap<KeyPress, NormalModeAction>,
+    // Bindings of more than one key
+    sequences: HashMap<Vec<KeyPress>, NormalModeAction>,
+}
+
+impl Keymap {
+    /// The built-in bindings with the config's `[keys]` laid over them, and a
+    /// message for each entry that couldn't be used
+    pub fn load(keys: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut overrides = HashMap::new();
+        let mut sequences = HashMap::new();
+        let mut errors = Vec::new();
+        for (spec, name) in keys {
+            match (parse_keys(spec), action_by_name(name)) {
+                (Ok(keys), Some(action)) => match keys[..] {
+                    [key] => {
+                        overrides.insert(key, action);
+                    }
+                    _ => {
+                        sequences.insert(keys, action);
+                    }
+                },
+                (Err(e), _) => errors.push(format!("keys: {}", e)),
+                (_, None) => errors.push(format!("keys: no action named '{}' (for {})", name, spec)),
+            }
+        }
+        (Self { overrides, sequences }, errors)
+    }
+
+    /// What pressing `key_event` after the `pending` keys comes to. Built-in
+    /// two-key commands start with a key bound to `StartPrefix`.
+    pub fn resolve(&self, pending: &[KeyPress], key_event: KeyEvent) -> Chord {
+        let mut keys = pending.to_vec();
+        keys.push(KeyPress::new(key_event.code, key_event.modifiers));
+        if let Some(action) = self.sequences.get(&keys) {
+            return Chord::Action(action.clone());
+        }
+        if self.starts_sequence(&keys) {
+            return Chord::Pending;
+        }
+        match pending {
+            [] => match self.action(key_event) {
+                NormalModeAction::StartPrefix(_) => Chord::Pending,
+                action => Chord::Action(action),
+            },
+            [KeyPress { code: KeyCode::Char(prefix), modifiers }] if modifiers.is_empty() => {
+                Chord::Action(KeyHandler::handle_prefix_key(*prefix, key_event))
+            }
+            _ => Chord::Action(NormalModeAction::None),
+        }
+    }
+
+    // Whether `keys` are the start of a longer sequence from the config
+    fn starts_sequence(&self, keys: &[KeyPress]) -> bool {
+        self.sequences.keys().any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+
+    /// What `key_event` does in the list
+    pub fn action(&self, key_event: KeyEvent) -> NormalModeAction {
+        match self.overrides.get(&KeyPress::new(key_event.code, key_event.modifiers)) {
+            Some(action) => action.clone(),
+            None => KeyHandler::handle_normal_mode_key(key_event),
+        }
+    }
+
+    /// Labels of the keys bound to `action`, found by trying every key a
+    /// binding could use; a two-key command is written like `g a`
+    pub fn keys_for(&self, action: &NormalModeAction) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::new();
+        for key in self.candidates() {
+            if self.action(key.event()) == *action && !self.starts_sequence(&[key]) {
+                let label = key.label();
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+            if let KeyCode::Char(prefix) = key.code
+                && key.modifiers.is_empty()
+                && self.action(key.event()) == NormalModeAction::StartPrefix(prefix)
+            {
+                for second in printable_keys() {
+                    if KeyHandler::handle_prefix_key(prefix, second.event()) == *action {
+                        labels.push(format!("{} {}", key.label(), second.label()));
+                    }
+                }
+            }
+        }
+        let mut sequences: Vec<String> = self
+            .sequences
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(keys, _)| keys.iter().map(|key| key.label()).collect::<Vec<_>>().join(" "))
+            .collect();
+        sequences.sort();
+        labels.extend(sequences);
+        labels
+    }
+
+    fn candidates(&self) -> Vec<KeyPress> {
+        let plain: Vec<KeyPress> = printable_keys().chain(SPECIAL_KEYS.map(|code| KeyPress::new(code, KeyModifiers::NONE))).collect();
+        // A modifier only counts when it changes what the key does, since most
+        // bindings ignore it
+        let modified = plain.iter().flat_map(|&key| {
+            let modifiers = match key.code {
+                KeyCode::Char(c) if c.is_ascii_lowercase() || c == '/' => vec![KeyModifiers::CONTROL],
+                KeyCode::Char(_) => vec![],
+                _ => vec![KeyModifiers::SHIFT, KeyModifiers::CONTROL, KeyModifiers::ALT],
+            };
+            modifiers
+                .into_iter()
+                .map(move |modifier| KeyPress::new(key.code, modifier))
+                .filter(move |modified| self.action(modified.event()) != self.action(key.event()))
+        });
+        let mut candidates: Vec<KeyPress> = plain.iter().copied().chain(modified).collect();
+        candidates.extend(self.overrides.keys().filter(|key| !plain.contains(key)));
+        candidates
+    }
+}
+
+fn printable_keys() -> impl Iterator<Item = KeyPress> {
+    (' '..='~').map(|c| KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(keys: &[(&str, &str)]) -> Keymap {
+        let keys = keys.iter().map(|(key, name)| (key.to_string(), name.to_string())).collect();
+        let (keymap, errors) = Keymap::load(&keys);
+        assert_eq!(errors, Vec::<String>::new());
+        keymap
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(KeyPress::parse("j"), Ok(KeyPress::new(KeyCode::Char('j'), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("ctrl+D"), Ok(KeyPress::new(KeyCode::Char('d'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyPress::parse("shift+a"), Ok(KeyPress::new(KeyCode::Char('A'), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("Shift+Up"), Ok(KeyPress::new(KeyCode::Up, KeyModifiers::SHIFT)));
+        assert_eq!(KeyPress::parse("+"), Ok(KeyPress::new(KeyCode::Char('+'), KeyModifiers::NONE)));
+        assert_eq!(KeyPress::parse("alt++"), Ok(KeyPress::new(KeyCode::Char('+'), KeyModifiers::ALT)));
+        assert_eq!(KeyPress::parse("space").unwrap().label(), "Space");
+        assert!(KeyPress::parse("hyper+x").is_err());
+        assert!(KeyPress::parse("nope").is_err());
+    }
+
+    #[test]
+    fn test_default_keys() {
+        let keymap = keymap(&[]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::MoveSelectionUp), ["k", "↑"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::MoveItemUp), ["K", "Shift+↑"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::MoveHalfPageDown), ["Ctrl+D"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::Quit), ["q", "Ctrl+C"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::MoveToFirstItem), ["g g"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::EnterSectionSearchMode), ["Ctrl+/"]);
+    }
+
+    #[test]
+    fn test_every_bound_action_is_named() {
+        let keymap = keymap(&[]);
+        for key in keymap.candidates() {
+            let action = keymap.action(key.event());
+            assert!(
+                matches!(action, NormalModeAction::None | NormalModeAction::StartPrefix(_))
+                    || ACTIONS.iter().any(|info| info.action == action),
+                "{:?} has no entry in ACTIONS",
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        let keymap = keymap(&[("ctrl+n", "move_down"), ("x", "toggle"), ("d", "none")]);
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(ctrl_n), NormalModeAction::MoveSelectionDown);
+        assert_eq!(keymap.keys_for(&NormalModeAction::MoveSelectionDown), ["j", "↓", "Ctrl+N"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::ToggleSelectedItem), ["x", "Enter"]);
+        assert_eq!(keymap.keys_for(&NormalModeAction::DeleteItem), Vec::<String>::new());
+
+        let keys = [("j".to_string(), "fly".to_string())].into_iter().collect();
+        assert_eq!(Keymap::load(&keys).1, ["keys: no action named 'fly' (for j)"]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        let keymap = keymap(&[("space f", "search"), ("g t", "tags")]);
+        let key = |c| KeyPress::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let event = |c| KeyEvent::from(KeyCode::Char(c));
+        assert_eq!(keymap.resolve(&[], event(' ')), Chord::Pending);
+        assert_eq!(keymap.resolve(&[key(' ')], event('f')), Chord::Action(NormalModeAction::EnterSearchMode));
+        assert_eq!(keymap.resolve(&[key(' ')], event('x')), Chord::Action(NormalModeAction::None));
+        assert_eq!(keymap.resolve(&[key('g')], event('t')), Chord::Action(NormalModeAction::OpenTagPanel));
+        assert_eq!(keymap.resolve(&[key('g')], event('g')), Chord::Action(NormalModeAction::MoveToFirstItem));
+        assert_eq!(keymap.resolve(&[], event('j')), Chord::Action(NormalModeAction::MoveSelectionDown));
+
+        assert_eq!(keymap.keys_for(&NormalModeAction::EnterSearchMode), ["/", "Space f"]);
+        assert!(keymap.keys_for(&NormalModeAction::ToggleItemSelection).is_empty());
+        assert_eq!(keymap.keys_for(&NormalModeAction::OpenTagPanel), ["T", "g t"]);
+    }
+}
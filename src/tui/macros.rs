@@ -0,0 +1,148 @@
+//! Keyboard macros, as in vim: `Q<letter>` records the keys pressed into a
+//! register until `Q` is pressed again, and `@<letter>` presses them again
+//! (`3@a` three times, `@@` the register used last).
+
+use crossterm::event::KeyEvent;
+use std::collections::BTreeMap;
+
+// A macro that replays itself stops after this many levels
+const MAX_DEPTH: usize = 10;
+
+pub struct MacroState {
+    registers: BTreeMap<char, Vec<KeyEvent>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    // The register last replayed, for `@@`
+    last: Option<char>,
+    depth: usize,
+}
+
+impl MacroState {
+    pub fn new() -> Self {
+        Self {
+            registers: BTreeMap::new(),
+            recording: None,
+            last: None,
+            depth: 0,
+        }
+    }
+
+    /// Letters can be registers, nothing else
+    pub fn is_register(letter: char) -> bool {
+        letter.is_ascii_alphabetic()
+    }
+
+    /// The register being recorded into
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    pub fn start(&mut self, register: char) -> bool {
+        if !Self::is_register(register) {
+            return false;
+        }
+        self.recording = Some((register, Vec::new()));
+        true
+    }
+
+    /// Adds a key pressed while recording
+    pub fn record(&mut self, key_event: KeyEvent) {
+        if let Some((_, keys)) = &mut self.recording {
+            keys.push(key_event);
+        }
+    }
+
+    /// Ends the recording, leaving out the `stop_keys` keys pressed to end
+    /// it; the register and how many keys went into it
+    pub fn stop(&mut self, stop_keys: usize) -> Option<(char, usize)> {
+        let (register, mut keys) = self.recording.take()?;
+        keys.truncate(keys.len().saturating_sub(stop_keys));
+        let len = keys.len();
+        self.registers.insert(register, keys);
+        Some((register, len))
+    }
+
+    /// The keys to press for `@register`; `@` is the register used last
+    pub fn keys(&mut self, register: char) -> Result<Vec<KeyEvent>, String> {
+        let register = match register {
+            '@' => self.last.ok_or("No macro replayed yet")?,
+            register => register,
+        };
+        match self.registers.get(&register) {
+            Some(keys) if !keys.is_empty() => {
+                self.last = Some(register);
+                Ok(keys.clone())
+            }
+            _ => Err(format!("Nothing recorded in @{}", register)),
+        }
+    }
+
+    /// Called before replaying; false when macros have called each other
+    /// too deeply to go on
+    pub fn enter(&mut self) -> bool {
+        if self.depth >= MAX_DEPTH {
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+impl Default for MacroState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyCode;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::from(KeyCode::Char(c))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut macros = MacroState::new();
+        assert!(!macros.start('1'));
+        assert!(macros.start('a'));
+        assert_eq!(macros.recording(), Some('a'));
+        for c in ['L', 'j', 'Q'] {
+            macros.record(key(c));
+        }
+        assert_eq!(macros.stop(1), Some(('a', 2)));
+        assert_eq!(macros.recording(), None);
+
+        assert!(macros.keys('@').is_err());
+        assert_eq!(macros.keys('a'), Ok(vec![key('L'), key('j')]));
+        assert_eq!(macros.keys('@'), Ok(vec![key('L'), key('j')]));
+        assert_eq!(macros.keys('b'), Err("Nothing recorded in @b".to_string()));
+    }
+
+    #[test]
+    fn test_stop_with_sequence() {
+        let mut macros = MacroState::new();
+        macros.start('a');
+        for c in ['L', ' ', 'q'] {
+            macros.record(key(c));
+        }
+        assert_eq!(macros.stop(2), Some(('a', 1)));
+        assert_eq!(macros.keys('a'), Ok(vec![key('L')]));
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut macros = MacroState::new();
+        for _ in 0..MAX_DEPTH {
+            assert!(macros.enter());
+        }
+        assert!(!macros.enter());
+        macros.exit();
+        assert!(macros.enter());
+    }
+}
@@ -0,0 +1,114 @@
+//! Named marks, as in vim: `m<letter>` marks the item under the cursor and
+//! `'<letter>` jumps back to it. Marks follow their item as others are added,
+//! moved or deleted around it.
+
+use crate::tui::navigation::ItemAnchor;
+use std::collections::BTreeMap;
+use todo_core::models::ListItem;
+
+pub struct MarkState {
+    marks: BTreeMap<char, ItemAnchor>,
+    // Length of the list when the marks were last located
+    len: usize,
+}
+
+impl MarkState {
+    pub fn new() -> Self {
+        Self { marks: BTreeMap::new(), len: 0 }
+    }
+
+    /// Letters can be marks, nothing else
+    pub fn is_mark(letter: char) -> bool {
+        letter.is_ascii_alphabetic()
+    }
+
+    pub fn set(&mut self, letter: char, items: &[ListItem], index: usize) -> bool {
+        match ItemAnchor::new(items, index).filter(|_| Self::is_mark(letter)) {
+            Some(anchor) => {
+                self.marks.insert(letter, anchor);
+                self.len = items.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Where the item marked `letter` is now
+    pub fn get(&mut self, letter: char, items: &[ListItem]) -> Option<usize> {
+        self.marks.get_mut(&letter)?.resolve(items)
+    }
+
+    /// Re-locates every mark after the list changed. An item edited in place
+    /// keeps its mark; one that's gone loses it.
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        // Editing leaves the length alone, so a mark that can't be found
+        // then is on an item whose text changed
+        let edited = items.len() == self.len;
+        self.marks.retain(|_, anchor| {
+            if anchor.resolve(items).is_some() {
+                return true;
+            }
+            match ItemAnchor::new(items, anchor.index()).filter(|_| edited) {
+                Some(edited) => {
+                    *anchor = edited;
+                    true
+                }
+                None => false,
+            }
+        });
+        self.len = items.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// The marks on the item at `index`, for showing beside it
+    pub fn marks_on(&self, index: usize) -> Vec<char> {
+        self.marks.iter().filter(|(_, anchor)| anchor.index() == index).map(|(&letter, _)| letter).collect()
+    }
+}
+
+impl Default for MarkState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Dishes".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_marks_follow_items() {
+        let mut items = items();
+        let mut marks = MarkState::new();
+        assert!(marks.set('a', &items, 3));
+        assert!(!marks.set('1', &items, 3));
+        assert!(!marks.set('b', &items, 9));
+
+        items.insert(0, ListItem::new_todo("New".to_string(), false, 0));
+        marks.refresh(&items);
+        assert_eq!(marks.get('a', &items), Some(4));
+        assert_eq!(marks.marks_on(4), ['a']);
+
+        *items[4].content_mut() = "Dishes and pans".to_string();
+        marks.refresh(&items);
+        assert_eq!(marks.get('a', &items), Some(4));
+
+        items.remove(1);
+        items.truncate(3);
+        marks.refresh(&items);
+        assert_eq!(marks.get('a', &items), None);
+        assert_eq!(marks.get('z', &items), None);
+    }
+}
@@ -1,10 +1,40 @@
 pub mod actions;
+pub mod agenda;
+pub mod board;
+pub mod browser;
+pub mod calendar;
+pub mod chord;
 pub mod app;
+pub mod clipboard;
+pub mod command;
+pub mod completion;
+pub mod confirm;
 pub mod edit;
+pub mod editor;
+pub mod files;
+pub mod fold;
+pub mod footer;
 pub mod handlers;
+pub mod help;
+pub mod history;
+pub mod keymap;
+pub mod macros;
+pub mod marks;
+pub mod move_picker;
 pub mod navigation;
+pub mod outline;
 pub mod persistence;
+pub mod plugins;
+pub mod pomodoro;
+pub mod reminders;
+pub mod replace;
 pub mod search;
 pub mod state;
+pub mod status;
+pub mod tags;
+pub mod theme;
+pub mod trash;
 pub mod undo;
-pub mod ui;
\ No newline at end of file
+pub mod ui;
+pub mod watcher;
+pub mod zoom;
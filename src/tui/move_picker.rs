@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+/// The popup for choosing a heading to move items under (`M`). Its entries
+/// are the file's headings, as in the outline.
+pub struct MovePickerState {
+    pub picker_mode: bool,
+    pub selected_index: usize,
+    /// The items being moved, fixed when the picker opens
+    pub targets: HashSet<usize>,
+}
+
+impl MovePickerState {
+    pub fn new() -> Self {
+        Self {
+            picker_mode: false,
+            selected_index: 0,
+            targets: HashSet::new(),
+        }
+    }
+
+    pub fn open(&mut self, targets: HashSet<usize>, selected_index: usize) {
+        self.picker_mode = true;
+        self.targets = targets;
+        self.selected_index = selected_index;
+    }
+
+    pub fn close(&mut self) {
+        self.picker_mode = false;
+        self.targets.clear();
+    }
+
+    pub fn move_selection_up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn move_selection_down(&mut self, entry_count: usize) {
+        if self.selected_index + 1 < entry_count {
+            self.selected_index += 1;
+        }
+    }
+}
+
+impl Default for MovePickerState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut picker = MovePickerState::new();
+        picker.open(HashSet::from([3]), 1);
+        picker.move_selection_down(3);
+        picker.move_selection_down(3);
+        assert_eq!(picker.selected_index, 2);
+        picker.move_selection_up();
+        picker.move_selection_up();
+        picker.move_selection_up();
+        assert_eq!(picker.selected_index, 0);
+
+        picker.close();
+        assert!(!picker.picker_mode);
+        assert!(picker.targets.is_empty());
+    }
+}
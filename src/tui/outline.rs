@@ -0,0 +1,117 @@
+use todo_core::models::ListItem;
+use todo_core::structure::ItemCreator;
+
+/// A heading shown in the outline sidebar
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub index: usize,
+    pub level: usize,
+}
+
+/// The outline sidebar doesn't keep a selection of its own: it highlights the
+/// section the list cursor is in, and moving through it moves the list cursor.
+pub struct OutlineState {
+    pub visible: bool,
+    pub focused: bool,
+}
+
+impl OutlineState {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            focused: false,
+        }
+    }
+
+    /// Shows the sidebar if needed and gives it the keyboard
+    pub fn focus(&mut self) {
+        self.visible = true;
+        self.focused = true;
+    }
+
+    /// Hands the keyboard back to the list, leaving the sidebar open
+    pub fn focus_list(&mut self) {
+        self.focused = false;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.focused = false;
+    }
+}
+
+impl Default for OutlineState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn build_outline(items: &[ListItem]) -> Vec<OutlineEntry> {
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| match item {
+            ListItem::Heading { level, .. } => Some(OutlineEntry { index, level: *level }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Position in `entries` of the section the item at `index` belongs to
+pub fn current_entry(entries: &[OutlineEntry], index: usize) -> Option<usize> {
+    entries.iter().rposition(|entry| entry.index <= index)
+}
+
+/// The heading to pin at the top of the list when the section shown there
+/// has had its heading scrolled out of view
+pub fn sticky_heading(items: &[ListItem], visible_indices: &[usize], scroll_offset: usize) -> Option<usize> {
+    let &top = visible_indices.get(scroll_offset)?;
+    let heading = ItemCreator::find_current_heading_context(items, top).checked_sub(1)?;
+    (heading < top).then_some(heading)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Inbox item".to_string(), false, 0),
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Meetings".to_string(), 2),
+            ListItem::new_todo("Standup".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+        ]
+    }
+
+    #[test]
+    fn test_build_outline() {
+        let entries = build_outline(&create_test_items());
+        assert_eq!(entries, vec![
+            OutlineEntry { index: 1, level: 1 },
+            OutlineEntry { index: 3, level: 2 },
+            OutlineEntry { index: 5, level: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_sticky_heading() {
+        let items = create_test_items();
+        let visible: Vec<usize> = (0..items.len()).collect();
+        assert_eq!(sticky_heading(&items, &visible, 0), None);
+        assert_eq!(sticky_heading(&items, &visible, 1), None);
+        assert_eq!(sticky_heading(&items, &visible, 2), Some(1));
+        assert_eq!(sticky_heading(&items, &visible, 4), Some(3));
+        assert_eq!(sticky_heading(&items, &visible, 9), None);
+    }
+
+    #[test]
+    fn test_current_entry() {
+        let entries = build_outline(&create_test_items());
+        assert_eq!(current_entry(&entries, 0), None);
+        assert_eq!(current_entry(&entries, 1), Some(0));
+        assert_eq!(current_entry(&entries, 4), Some(1));
+        assert_eq!(current_entry(&entries, 5), Some(2));
+    }
+}
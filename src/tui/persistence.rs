@@ -1,12 +1,102 @@
-use crate::todo::{models::TodoList, writer};
+use crate::encryption::{self, Encryption};
+use todo_core::{models::TodoList, writer::WriteOptions};
 use anyhow::Result;
+use std::time::{Duration, Instant};
 
 pub trait Persistence {
-    fn save_to_file(&self) -> Result<()>;
+    fn save_to_file(&self, options: &WriteOptions, encryption: Option<&Encryption>) -> Result<()>;
 }
 
 impl Persistence for TodoList {
-    fn save_to_file(&self) -> Result<()> {
-        writer::write_todo_file(self)
+    fn save_to_file(&self, options: &WriteOptions, encryption: Option<&Encryption>) -> Result<()> {
+        encryption::write_todo_file(self, options, encryption)
     }
-}
\ No newline at end of file
+}
+
+// Changes are saved after at most this many intervals, even while edits keep coming
+const MAX_PENDING_INTERVALS: u32 = 10;
+
+/// Tracks unsaved changes so that bursts of edits are written out together
+/// once they pause, rather than on every key press.
+pub struct Autosave {
+    interval: Duration,
+    first_change: Option<Instant>,
+    last_change: Option<Instant>,
+}
+
+impl Autosave {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            first_change: None,
+            last_change: None,
+        }
+    }
+
+    pub fn mark_dirty(&mut self) {
+        let now = Instant::now();
+        self.first_change.get_or_insert(now);
+        self.last_change = Some(now);
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.first_change = None;
+        self.last_change = None;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.last_change.is_some()
+    }
+
+    /// Whether there are changes that have been left alone for the whole
+    /// interval, or that have been pending for too long
+    pub fn is_due(&self, now: Instant) -> bool {
+        match (self.first_change, self.last_change) {
+            (Some(first_change), Some(last_change)) => {
+                now.saturating_duration_since(last_change) >= self.interval
+                    || now.saturating_duration_since(first_change) >= self.interval * MAX_PENDING_INTERVALS
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autosave_waits_for_interval() {
+        let mut autosave = Autosave::new(Duration::from_millis(500));
+        assert!(!autosave.is_dirty());
+        assert!(!autosave.is_due(Instant::now()));
+
+        autosave.mark_dirty();
+        let now = Instant::now();
+        assert!(autosave.is_dirty());
+        assert!(!autosave.is_due(now));
+        assert!(autosave.is_due(now + Duration::from_millis(500)));
+
+        autosave.mark_saved();
+        assert!(!autosave.is_due(now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_autosave_saves_during_continuous_edits() {
+        let mut autosave = Autosave::new(Duration::from_millis(500));
+        autosave.mark_dirty();
+        let start = Instant::now();
+
+        // Still dirty from the first change, even though a later one is recent
+        autosave.last_change = Some(start + Duration::from_millis(4900));
+        assert!(!autosave.is_due(start + Duration::from_millis(4950)));
+        assert!(autosave.is_due(start + Duration::from_millis(5000)));
+    }
+
+    #[test]
+    fn test_zero_interval_is_due_immediately() {
+        let mut autosave = Autosave::new(Duration::ZERO);
+        autosave.mark_dirty();
+        assert!(autosave.is_due(Instant::now()));
+    }
+}
@@ -0,0 +1,452 @@
+//! Rhai plugins listed under `plugins` in the config. A plugin registers
+//! commands and keys when it's loaded, and may define `on_toggle(list, index)`
+//! and `on_add(list, index)` to hear about todos checked off or added in the
+//! TUI:
+//!
+//! ```rhai
+//! register_command("today", "due_today"); // :today
+//! bind_key("T", "due_today");
+//!
+//! fn due_today(list, argument) {
+//!     list.set_content(list.selected, list.content(list.selected) + " 📅 " + today());
+//! }
+//! ```
+//!
+//! Scripts only see a copy of the list through a small API (`len`, `content`,
+//! `set_content`, `toggle`, `add`, `delete`, ...); what they change is applied
+//! to the real list afterwards, as one undo step. `print` shows a message in
+//! the status line.
+
+use crate::tui::agenda::today;
+use anyhow::{anyhow, bail, Context, Result};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Scope, AST};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use todo_core::{ItemActions, ItemCreator, ListItem};
+
+// Keeps a runaway script from hanging the TUI
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginEvent {
+    Toggled,
+    Added,
+}
+
+impl PluginEvent {
+    fn function(self) -> &'static str {
+        match self {
+            PluginEvent::Toggled => "on_toggle",
+            PluginEvent::Added => "on_add",
+        }
+    }
+}
+
+/// What a plugin call left behind: the list if it changed, where the cursor
+/// should go and anything printed
+#[derive(Debug, PartialEq)]
+pub struct PluginOutcome {
+    pub items: Option<Vec<ListItem>>,
+    pub selected: usize,
+    pub messages: Vec<String>,
+}
+
+struct ListState {
+    items: Vec<ListItem>,
+    selected: usize,
+    changed: bool,
+}
+
+/// The list as scripts see it. Copies share the same state, so a function
+/// changing its `list` argument changes what's handed back.
+#[derive(Clone)]
+struct ScriptList(Rc<RefCell<ListState>>);
+
+impl ScriptList {
+    fn new(items: &[ListItem], selected: usize) -> Self {
+        Self(Rc::new(RefCell::new(ListState {
+            items: items.to_vec(),
+            selected,
+            changed: false,
+        })))
+    }
+
+    fn index(&self, index: i64) -> ScriptResult<usize> {
+        let len = self.0.borrow().items.len();
+        usize::try_from(index)
+            .ok()
+            .filter(|&index| index < len)
+            .ok_or_else(|| format!("No item {} (the list has {})", index, len).into())
+    }
+
+    fn len(&mut self) -> i64 {
+        self.0.borrow().items.len() as i64
+    }
+
+    fn selected(&mut self) -> i64 {
+        self.0.borrow().selected as i64
+    }
+
+    fn select(&mut self, index: i64) -> ScriptResult<()> {
+        let index = self.index(index)?;
+        self.0.borrow_mut().selected = index;
+        Ok(())
+    }
+
+    fn content(&mut self, index: i64) -> ScriptResult<String> {
+        let index = self.index(index)?;
+        Ok(self.0.borrow().items[index].content().to_string())
+    }
+
+    fn is_todo(&mut self, index: i64) -> ScriptResult<bool> {
+        let index = self.index(index)?;
+        Ok(matches!(self.0.borrow().items[index], ListItem::Todo { .. }))
+    }
+
+    fn is_heading(&mut self, index: i64) -> ScriptResult<bool> {
+        let index = self.index(index)?;
+        Ok(matches!(self.0.borrow().items[index], ListItem::Heading { .. }))
+    }
+
+    fn is_done(&mut self, index: i64) -> ScriptResult<bool> {
+        let index = self.index(index)?;
+        Ok(self.0.borrow().items[index].is_completed())
+    }
+
+    /// The first item containing `text`, ignoring case, or -1
+    fn find(&mut self, text: &str) -> i64 {
+        let text = text.to_lowercase();
+        self.0
+            .borrow()
+            .items
+            .iter()
+            .position(|item| item.content().to_lowercase().contains(&text))
+            .map_or(-1, |index| index as i64)
+    }
+
+    fn set_content(&mut self, index: i64, content: &str) -> ScriptResult<()> {
+        let index = self.index(index)?;
+        let mut state = self.0.borrow_mut();
+        if matches!(state.items[index], ListItem::Heading { .. }) && content.contains('\n') {
+            return Err("Headings have to stay on one line".into());
+        }
+        *state.items[index].content_mut() = content.to_string();
+        state.changed = true;
+        Ok(())
+    }
+
+    fn toggle(&mut self, index: i64) -> ScriptResult<()> {
+        let index = self.index(index)?;
+        let mut state = self.0.borrow_mut();
+        if !ItemActions::toggle_todo_completion(&mut state.items, index) {
+            return Err(format!("Item {} isn't a todo", index).into());
+        }
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Adds a todo below the selected item, returning its index
+    fn add(&mut self, content: &str) -> i64 {
+        let mut state = self.0.borrow_mut();
+        let (index, indent) = ItemCreator::determine_sibling_insert_position(&state.items, state.selected, false);
+        state.items.insert(index, ListItem::new_todo(content.to_string(), false, indent));
+        state.changed = true;
+        index as i64
+    }
+
+    /// Adds a todo at the end of the section under `heading`
+    fn add_to(&mut self, heading: &str, content: &str) -> ScriptResult<i64> {
+        let mut state = self.0.borrow_mut();
+        let index = ItemCreator::end_of_section(&state.items, Some(heading))
+            .ok_or_else(|| format!("No heading '{}'", heading))?;
+        state.items.insert(index, ListItem::new_todo(content.to_string(), false, 0));
+        state.changed = true;
+        Ok(index as i64)
+    }
+
+    fn delete(&mut self, index: i64) -> ScriptResult<()> {
+        let index = self.index(index)?;
+        let mut state = self.0.borrow_mut();
+        if !ItemActions::delete_item(&mut state.items, index) {
+            return Err("Headings can't be deleted".into());
+        }
+        state.selected = state.selected.min(state.items.len().saturating_sub(1));
+        state.changed = true;
+        Ok(())
+    }
+}
+
+struct Plugin {
+    name: String,
+    ast: AST,
+}
+
+#[derive(Clone)]
+struct Handler {
+    plugin: usize,
+    function: String,
+}
+
+#[derive(Default)]
+struct Registry {
+    // The plugin being loaded, which registrations belong to
+    loading: usize,
+    commands: HashMap<String, Handler>,
+    keys: HashMap<char, Handler>,
+}
+
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+    registry: Rc<RefCell<Registry>>,
+    messages: Rc<RefCell<Vec<String>>>,
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        let registry = Rc::new(RefCell::new(Registry::default()));
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let printed = Rc::clone(&messages);
+        engine.on_print(move |text| printed.borrow_mut().push(text.to_string()));
+        let commands = Rc::clone(&registry);
+        engine.register_fn("register_command", move |name: &str, function: &str| {
+            let mut registry = commands.borrow_mut();
+            let handler = Handler { plugin: registry.loading, function: function.to_string() };
+            registry.commands.insert(name.to_string(), handler);
+        });
+        let keys = Rc::clone(&registry);
+        engine.register_fn("bind_key", move |key: &str, function: &str| -> ScriptResult<()> {
+            let mut chars = key.chars();
+            let (Some(key), None) = (chars.next(), chars.next()) else {
+                return Err(format!("bind_key takes a single character, not '{}'", key).into());
+            };
+            let mut registry = keys.borrow_mut();
+            let handler = Handler { plugin: registry.loading, function: function.to_string() };
+            registry.keys.insert(key, handler);
+            Ok(())
+        });
+        engine.register_fn("today", || today().to_string());
+
+        engine
+            .register_type_with_name::<ScriptList>("List")
+            .register_get("selected", ScriptList::selected)
+            .register_fn("len", ScriptList::len)
+            .register_fn("select", ScriptList::select)
+            .register_fn("content", ScriptList::content)
+            .register_fn("is_todo", ScriptList::is_todo)
+            .register_fn("is_heading", ScriptList::is_heading)
+            .register_fn("is_done", ScriptList::is_done)
+            .register_fn("find", ScriptList::find)
+            .register_fn("set_content", ScriptList::set_content)
+            .register_fn("toggle", ScriptList::toggle)
+            .register_fn("add", ScriptList::add)
+            .register_fn("add_to", ScriptList::add_to)
+            .register_fn("delete", ScriptList::delete);
+
+        Self {
+            engine,
+            plugins: Vec::new(),
+            registry,
+            messages,
+        }
+    }
+
+    /// Loads the plugins at `paths`, returning an error for each one that
+    /// couldn't be loaded; the others still are
+    pub fn load(paths: &[String]) -> (Self, Vec<String>) {
+        let mut host = Self::new();
+        let errors = paths
+            .iter()
+            .filter_map(|path| {
+                let name = Path::new(path).file_stem().map_or_else(|| path.clone(), |stem| stem.to_string_lossy().to_string());
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read plugin: {}", path))
+                    .and_then(|source| host.add_plugin(&name, &source))
+                    .err()
+                    .map(|e| format!("{:#}", e))
+            })
+            .collect();
+        (host, errors)
+    }
+
+    /// Compiles a plugin and runs its top level, where it registers commands
+    /// and keys
+    pub fn add_plugin(&mut self, name: &str, source: &str) -> Result<()> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|e| anyhow!("Plugin {}: {}", name, e))?;
+        let index = self.plugins.len();
+        self.registry.borrow_mut().loading = index;
+        if let Err(e) = self.engine.run_ast(&ast) {
+            // Whatever it registered before failing would point at nothing
+            let mut registry = self.registry.borrow_mut();
+            registry.commands.retain(|_, handler| handler.plugin != index);
+            registry.keys.retain(|_, handler| handler.plugin != index);
+            bail!("Plugin {}: {}", name, e);
+        }
+        self.plugins.push(Plugin { name: name.to_string(), ast });
+        Ok(())
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        self.registry.borrow().commands.contains_key(name)
+    }
+
+    pub fn has_key(&self, key: char) -> bool {
+        self.registry.borrow().keys.contains_key(&key)
+    }
+
+    /// Whether any plugin listens for `event`
+    pub fn handles(&self, event: PluginEvent) -> bool {
+        self.plugins.iter().any(|plugin| defines(&plugin.ast, event.function(), 2))
+    }
+
+    pub fn run_command(&self, name: &str, argument: &str, items: &[ListItem], selected: usize) -> Result<PluginOutcome> {
+        let handler = self.registry.borrow().commands.get(name).cloned().with_context(|| format!("Unknown command: {}", name))?;
+        let list = ScriptList::new(items, selected);
+        self.call(&handler, (list.clone(), argument.to_string()), list)
+    }
+
+    pub fn run_key(&self, key: char, items: &[ListItem], selected: usize) -> Result<PluginOutcome> {
+        let handler = self.registry.borrow().keys.get(&key).cloned().with_context(|| format!("No plugin bound to {}", key))?;
+        let list = ScriptList::new(items, selected);
+        self.call(&handler, (list.clone(), key.to_string()), list)
+    }
+
+    /// Tells every plugin listening for `event` about the item at `index`,
+    /// each seeing the changes made by the ones before
+    pub fn run_event(&self, event: PluginEvent, index: usize, items: &[ListItem], selected: usize) -> Result<PluginOutcome> {
+        let list = ScriptList::new(items, selected);
+        for (plugin, _) in self.plugins.iter().enumerate().filter(|(_, plugin)| defines(&plugin.ast, event.function(), 2)) {
+            let handler = Handler { plugin, function: event.function().to_string() };
+            self.call_function(&handler, (list.clone(), index as i64))?;
+        }
+        Ok(self.outcome(list))
+    }
+
+    fn call(&self, handler: &Handler, args: impl FuncArgs, list: ScriptList) -> Result<PluginOutcome> {
+        let result = self.call_function(handler, args);
+        // What was printed before an error is still worth showing
+        let outcome = self.outcome(list);
+        result.map(|()| outcome)
+    }
+
+    fn call_function(&self, handler: &Handler, args: impl FuncArgs) -> Result<()> {
+        let plugin = &self.plugins[handler.plugin];
+        if !defines(&plugin.ast, &handler.function, 2) {
+            bail!("Plugin {} has no function {}(list, argument)", plugin.name, handler.function);
+        }
+        // Only the function runs; the plugin's top level ran when it was loaded,
+        // and whatever it returns is ignored
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &plugin.ast, &handler.function, args)
+            .map(drop)
+            .map_err(|e| anyhow!("Plugin {}: {}", plugin.name, e))
+    }
+
+    fn outcome(&self, list: ScriptList) -> PluginOutcome {
+        let state = list.0.borrow();
+        PluginOutcome {
+            items: state.changed.then(|| state.items.clone()),
+            selected: state.selected,
+            messages: self.messages.borrow_mut().drain(..).collect(),
+        }
+    }
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn defines(ast: &AST, function: &str, params: usize) -> bool {
+    ast.iter_functions().any(|f| f.name == function && f.params.len() == params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_todo("Slides".to_string(), false, 0),
+        ]
+    }
+
+    const PLUGIN: &str = r#"
+        register_command("star", "star");
+        bind_key("S", "star");
+
+        fn star(list, argument) {
+            list.set_content(list.selected, "⭐ " + list.content(list.selected));
+            print("Starred");
+        }
+
+        fn on_toggle(list, index) {
+            if list.is_done(index) {
+                list.add_to("Work", "Follow up on " + list.content(index));
+            }
+        }
+    "#;
+
+    #[test]
+    fn test_commands_and_keys() {
+        let mut plugins = PluginHost::new();
+        plugins.add_plugin("star", PLUGIN).unwrap();
+        assert!(plugins.has_command("star"));
+        assert!(plugins.has_key('S'));
+        assert!(!plugins.has_key('x'));
+
+        let outcome = plugins.run_command("star", "", &create_test_items(), 2).unwrap();
+        let items = outcome.items.unwrap();
+        assert_eq!(items[2].content(), "⭐ Slides");
+        assert_eq!(outcome.messages, vec!["Starred"]);
+
+        let outcome = plugins.run_key('S', &items, 1).unwrap();
+        assert_eq!(outcome.items.unwrap()[1].content(), "⭐ Report");
+    }
+
+    #[test]
+    fn test_events() {
+        let mut plugins = PluginHost::new();
+        plugins.add_plugin("star", PLUGIN).unwrap();
+        assert!(plugins.handles(PluginEvent::Toggled));
+        assert!(!plugins.handles(PluginEvent::Added));
+
+        let mut items = create_test_items();
+        ItemActions::toggle_todo_completion(&mut items, 1);
+        let outcome = plugins.run_event(PluginEvent::Toggled, 1, &items, 1).unwrap();
+        assert_eq!(outcome.items.unwrap()[3].content(), "Follow up on Report");
+
+        // An open todo leaves the list alone
+        let outcome = plugins.run_event(PluginEvent::Toggled, 2, &items, 1).unwrap();
+        assert_eq!(outcome.items, None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut plugins = PluginHost::new();
+        assert!(plugins.add_plugin("broken", "fn oops(").is_err());
+        assert!(plugins.add_plugin("bad key", r#"register_command("x", "f"); bind_key("ab", "f");"#).is_err());
+        assert!(!plugins.has_command("x"));
+
+        plugins
+            .add_plugin("faulty", r#"register_command("bad", "bad"); register_command("loop", "spin"); fn bad(list, argument) { list.toggle(0); } fn spin(list, argument) { loop {} }"#)
+            .unwrap();
+        let error = plugins.run_command("bad", "", &create_test_items(), 0).unwrap_err();
+        assert!(error.to_string().contains("Item 0 isn't a todo"));
+        assert!(plugins.run_command("loop", "", &create_test_items(), 0).is_err());
+    }
+}
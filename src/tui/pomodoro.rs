@@ -0,0 +1,164 @@
+//! The pomodoro timer (`P`): a focus session on the todo under the cursor,
+//! counted down in the header. When it's over the time is logged on the todo
+//! as `⏱` time spent.
+
+use crate::tui::navigation::ItemAnchor;
+use notify_rust::Notification;
+use std::time::{Duration, Instant};
+use todo_core::models::ListItem;
+
+struct Session {
+    /// The todo being worked on, until it's deleted
+    task: Option<ItemAnchor>,
+    content: String,
+    ends_at: Instant,
+}
+
+/// A session that ran its full length
+#[derive(Debug, PartialEq)]
+pub struct Finished {
+    /// Where the todo is now, if it's still in the list
+    pub index: Option<usize>,
+    pub content: String,
+    pub minutes: u32,
+}
+
+impl Finished {
+    /// Shows the notification on its own thread, as hooks run, so a slow
+    /// notification daemon doesn't hold up the UI; a failure goes unseen
+    pub fn send(&self) {
+        let content = self.content.clone();
+        std::thread::spawn(move || {
+            Notification::new()
+                .appname("todo")
+                .summary("Pomodoro done")
+                .body(&content)
+                .show()
+        });
+    }
+}
+
+pub struct PomodoroState {
+    minutes: u32,
+    session: Option<Session>,
+    // Length of the list when the task was last located
+    len: usize,
+}
+
+impl PomodoroState {
+    pub fn new(minutes: u32) -> Self {
+        Self {
+            minutes: minutes.max(1),
+            session: None,
+            len: 0,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Starts a session on the todo at `index`; false if it isn't a todo
+    pub fn start(&mut self, items: &[ListItem], index: usize, now: Instant) -> bool {
+        let Some(item @ ListItem::Todo { .. }) = items.get(index) else {
+            return false;
+        };
+        self.session = Some(Session {
+            task: ItemAnchor::new(items, index),
+            content: item.content().to_string(),
+            ends_at: now + Duration::from_secs(u64::from(self.minutes) * 60),
+        });
+        self.len = items.len();
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.session = None;
+    }
+
+    /// Time left in the session and what it's on, for the header
+    pub fn remaining(&self, now: Instant) -> Option<(Duration, &str)> {
+        let session = self.session.as_ref()?;
+        Some((session.ends_at.saturating_duration_since(now), &session.content))
+    }
+
+    /// Follows the todo after the list changed, the way marks do: an edit in
+    /// place keeps it, a deletion loses it
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        let edited = items.len() == self.len;
+        self.len = items.len();
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        let index = match session.task.as_mut().map(|task| (task.resolve(items), task.index())) {
+            Some((Some(index), _)) => Some(index),
+            Some((None, last)) if edited => {
+                session.task = ItemAnchor::new(items, last);
+                Some(last)
+            }
+            _ => {
+                session.task = None;
+                None
+            }
+        };
+        if let Some(item) = index.and_then(|index| items.get(index)) {
+            session.content = item.content().to_string();
+        }
+    }
+
+    /// Ends the session once its time is up
+    pub fn take_finished(&mut self, items: &[ListItem], now: Instant) -> Option<Finished> {
+        if self.session.as_ref()?.ends_at > now {
+            return None;
+        }
+        let mut session = self.session.take()?;
+        Some(Finished {
+            index: session.task.as_mut().and_then(|task| task.resolve(items)),
+            content: session.content,
+            minutes: self.minutes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Write report".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_session() {
+        let now = Instant::now();
+        let mut items = items();
+        let mut pomodoro = PomodoroState::new(25);
+        assert!(!pomodoro.start(&items, 0, now));
+        assert!(pomodoro.start(&items, 1, now));
+        assert_eq!(pomodoro.remaining(now + Duration::from_secs(60)), Some((Duration::from_secs(24 * 60), "Write report")));
+
+        items.insert(0, ListItem::new_todo("Coffee".to_string(), false, 0));
+        pomodoro.refresh(&items);
+        assert_eq!(pomodoro.take_finished(&items, now + Duration::from_secs(60)), None);
+        let finished = pomodoro.take_finished(&items, now + Duration::from_secs(25 * 60)).unwrap();
+        assert_eq!(finished.index, Some(2));
+        assert_eq!(finished.minutes, 25);
+        assert!(!pomodoro.is_running());
+    }
+
+    #[test]
+    fn test_deleted_task() {
+        let now = Instant::now();
+        let mut items = items();
+        let mut pomodoro = PomodoroState::new(25);
+        pomodoro.start(&items, 1, now);
+        items.remove(1);
+        pomodoro.refresh(&items);
+        let finished = pomodoro.take_finished(&items, now + Duration::from_secs(25 * 60)).unwrap();
+        assert_eq!(finished.index, None);
+        assert_eq!(finished.content, "Write report");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::tui::agenda::{build_agenda, AgendaGroup};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use notify_rust::Notification;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use todo_core::models::ListItem;
+
+// How often the open list is checked for todos that became due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A desktop notification for an open todo that's due today or overdue
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reminder {
+    pub overdue: bool,
+    pub content: String,
+}
+
+impl Reminder {
+    pub fn summary(&self) -> &'static str {
+        if self.overdue { "Overdue" } else { "Due today" }
+    }
+
+    pub fn send(&self) -> Result<()> {
+        Notification::new()
+            .appname("todo")
+            .summary(self.summary())
+            .body(&self.content)
+            .show()
+            .context("Failed to show notification")?;
+        Ok(())
+    }
+}
+
+pub fn due_reminders(items: &[ListItem], today: NaiveDate) -> Vec<Reminder> {
+    build_agenda(items, today)
+        .into_iter()
+        .filter(|entry| entry.group != AgendaGroup::ThisWeek)
+        .map(|entry| Reminder {
+            overdue: entry.group == AgendaGroup::Overdue,
+            content: items[entry.index].content().to_string(),
+        })
+        .collect()
+}
+
+/// Remembers which todos the TUI already sent reminders for, so each one is
+/// sent once a day while the list is open
+pub struct ReminderState {
+    sent: HashSet<String>,
+    sent_on: Option<NaiveDate>,
+    last_check: Option<Instant>,
+}
+
+impl ReminderState {
+    pub fn new() -> Self {
+        Self {
+            sent: HashSet::new(),
+            sent_on: None,
+            last_check: None,
+        }
+    }
+
+    /// The reminders that haven't been sent yet today. The list is only looked
+    /// at once every `CHECK_INTERVAL`.
+    pub fn take_new(&mut self, items: &[ListItem], today: NaiveDate, now: Instant) -> Vec<Reminder> {
+        if self.last_check.is_some_and(|last| now.duration_since(last) < CHECK_INTERVAL) {
+            return Vec::new();
+        }
+        self.last_check = Some(now);
+        if self.sent_on != Some(today) {
+            self.sent.clear();
+            self.sent_on = Some(today);
+        }
+        due_reminders(items, today)
+            .into_iter()
+            .filter(|reminder| self.sent.insert(reminder.content.clone()))
+            .collect()
+    }
+}
+
+impl Default for ReminderState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Pay rent 📅 2024-05-01".to_string(), false, 0),
+            ListItem::new_todo("Call mom 📅 2024-05-10".to_string(), false, 0),
+            ListItem::new_todo("Renew passport 📅 2024-05-12".to_string(), false, 0),
+            ListItem::new_todo("Filed taxes 📅 2024-04-15".to_string(), true, 0),
+        ]
+    }
+
+    #[test]
+    fn test_due_reminders() {
+        let reminders = due_reminders(&create_test_items(), date(10));
+        assert_eq!(reminders, vec![
+            Reminder { overdue: true, content: "Pay rent 📅 2024-05-01".to_string() },
+            Reminder { overdue: false, content: "Call mom 📅 2024-05-10".to_string() },
+        ]);
+        assert_eq!(reminders[0].summary(), "Overdue");
+    }
+
+    #[test]
+    fn test_reminders_are_sent_once_a_day() {
+        let mut items = create_test_items();
+        let mut reminders = ReminderState::new();
+        let start = Instant::now();
+        assert_eq!(reminders.take_new(&items, date(10), start).len(), 2);
+
+        // Not checked again until the interval has passed
+        items.push(ListItem::new_todo("Water plants 📅 2024-05-10".to_string(), false, 0));
+        assert!(reminders.take_new(&items, date(10), start + Duration::from_secs(1)).is_empty());
+        let later = start + CHECK_INTERVAL;
+        assert_eq!(reminders.take_new(&items, date(10), later).len(), 1);
+        assert!(reminders.take_new(&items, date(10), later + CHECK_INTERVAL).is_empty());
+
+        // The next day everything that's still due is sent again
+        assert_eq!(reminders.take_new(&items, date(11), later + CHECK_INTERVAL * 2).len(), 3);
+    }
+}
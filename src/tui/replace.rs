@@ -0,0 +1,134 @@
+/// A vim-style `:s/pattern/replacement/flags` command. The pattern is plain
+/// text; `\/` stands for a literal delimiter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    /// `%s` replaces in every item, `s` only in the current one
+    pub whole_file: bool,
+    /// The `g` flag: replace every occurrence rather than the first in each item
+    pub every_occurrence: bool,
+}
+
+impl Substitution {
+    /// Parses a command line, returning `None` if it isn't a substitution at
+    /// all and an error message if it's a malformed one
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let (whole_file, rest) = match line.trim_start().strip_prefix('%') {
+            Some(rest) => (true, rest),
+            None => (false, line.trim_start()),
+        };
+        let rest = rest.strip_prefix('s')?;
+        // `:sort` and friends are commands of their own
+        let delimiter = rest.chars().next()?;
+        if delimiter.is_alphanumeric() || delimiter.is_whitespace() || delimiter == '\\' {
+            return None;
+        }
+
+        let parts = split_unescaped(&rest[delimiter.len_utf8()..], delimiter);
+        Some(Self::from_parts(&parts, whole_file))
+    }
+
+    fn from_parts(parts: &[String], whole_file: bool) -> Result<Self, String> {
+        let (pattern, replacement, flags) = match parts {
+            [pattern] => (pattern, "", ""),
+            [pattern, replacement] => (pattern, replacement.as_str(), ""),
+            [pattern, replacement, flags] => (pattern, replacement.as_str(), flags.as_str()),
+            _ => return Err("Usage: :%s/pattern/replacement/[g]".to_string()),
+        };
+        if pattern.is_empty() {
+            return Err("Nothing to replace".to_string());
+        }
+
+        let mut every_occurrence = false;
+        for flag in flags.chars() {
+            match flag {
+                'g' => every_occurrence = true,
+                _ => return Err(format!("Unknown flag '{}'", flag)),
+            }
+        }
+
+        Ok(Self {
+            pattern: pattern.clone(),
+            replacement: replacement.to_string(),
+            whole_file,
+            every_occurrence,
+        })
+    }
+
+    /// The content with the replacement made, or `None` if the pattern doesn't occur
+    pub fn apply(&self, content: &str) -> Option<String> {
+        if !content.contains(&self.pattern) {
+            return None;
+        }
+        Some(if self.every_occurrence {
+            content.replace(&self.pattern, &self.replacement)
+        } else {
+            content.replacen(&self.pattern, &self.replacement, 1)
+        })
+    }
+}
+
+fn split_unescaped(text: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                Some(next) => {
+                    let part = parts.last_mut().unwrap();
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => parts.last_mut().unwrap().push('\\'),
+            },
+            c if c == delimiter => parts.push(String::new()),
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_substitution() {
+        let substitution = Substitution::parse("%s/foo/bar/g").unwrap().unwrap();
+        assert_eq!(substitution, Substitution {
+            pattern: "foo".to_string(),
+            replacement: "bar".to_string(),
+            whole_file: true,
+            every_occurrence: true,
+        });
+
+        let substitution = Substitution::parse("s#a/b#c").unwrap().unwrap();
+        assert_eq!(substitution.pattern, "a/b");
+        assert_eq!(substitution.replacement, "c");
+        assert!(!substitution.whole_file);
+
+        let substitution = Substitution::parse("%s/1\\/2/half/").unwrap().unwrap();
+        assert_eq!(substitution.pattern, "1/2");
+    }
+
+    #[test]
+    fn test_parse_rejects_other_commands_and_bad_input() {
+        assert_eq!(Substitution::parse("sort"), None);
+        assert_eq!(Substitution::parse("w"), None);
+        assert!(Substitution::parse("%s//x/").unwrap().is_err());
+        assert!(Substitution::parse("%s/a/b/x").unwrap().is_err());
+        assert!(Substitution::parse("%s/a/b/g/extra").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let first = Substitution::parse("%s/a/o/").unwrap().unwrap();
+        assert_eq!(first.apply("banana"), Some("bonana".to_string()));
+        assert_eq!(first.apply("xyz"), None);
+
+        let every = Substitution::parse("%s/a/o/g").unwrap().unwrap();
+        assert_eq!(every.apply("banana"), Some("bonono".to_string()));
+    }
+}
@@ -1,10 +1,81 @@
-use crate::todo::models::ListItem;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+use todo_core::models::ListItem;
+
+/// How the query is matched against item content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    Text,
+    Regex,
+    /// The query's characters in order, not necessarily together (`wlkdg`
+    /// finds "Walk the dog"), with the best matches first
+    Fuzzy,
+}
+
+impl QueryKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            QueryKind::Text => "text",
+            QueryKind::Regex => "regex",
+            QueryKind::Fuzzy => "fuzzy",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// Case-insensitive unless the query contains an uppercase letter
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl CaseSensitivity {
+    pub fn next(self) -> Self {
+        match self {
+            CaseSensitivity::Smart => CaseSensitivity::Sensitive,
+            CaseSensitivity::Sensitive => CaseSensitivity::Insensitive,
+            CaseSensitivity::Insensitive => CaseSensitivity::Smart,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CaseSensitivity::Smart => "smart-case",
+            CaseSensitivity::Sensitive => "match case",
+            CaseSensitivity::Insensitive => "ignore case",
+        }
+    }
+}
+
+/// Where the cursor was when a search started, to go back to if it's cancelled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchOrigin {
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+}
 
 pub struct SearchState {
     pub search_mode: bool,
     pub search_query: String,
     pub search_matches: Vec<usize>,
     pub current_match_index: Option<usize>,
+    /// Options are kept between searches, like the query history of most editors
+    pub kind: QueryKind,
+    pub case_sensitivity: CaseSensitivity,
+    /// Why the query couldn't be used, e.g. an unclosed group in a regex
+    pub error: Option<String>,
+    pub origin: SearchOrigin,
+    /// Only items in this range are matched, when searching one section
+    pub section: Option<Range<usize>>,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SearchState {
@@ -14,21 +85,125 @@ impl SearchState {
             search_query: String::new(),
             search_matches: Vec::new(),
             current_match_index: None,
+            kind: QueryKind::Text,
+            case_sensitivity: CaseSensitivity::Smart,
+            error: None,
+            origin: SearchOrigin::default(),
+            section: None,
         }
     }
 
-    pub fn enter_search_mode(&mut self) {
+    pub fn enter_search_mode(&mut self, origin: SearchOrigin, section: Option<Range<usize>>) {
+        self.origin = origin;
+        self.section = section;
         self.search_mode = true;
         self.search_query.clear();
         self.search_matches.clear();
         self.current_match_index = None;
+        self.error = None;
     }
 
-    pub fn cancel_search(&mut self) {
+    /// Ends the search without results, returning where it started
+    pub fn cancel_search(&mut self) -> SearchOrigin {
         self.search_mode = false;
         self.search_query.clear();
         self.search_matches.clear();
         self.current_match_index = None;
+        self.error = None;
+        self.origin
+    }
+
+    /// The item to show while the query is typed: the match Enter would go
+    /// to, or where the search started if nothing matches
+    pub fn preview(&self) -> usize {
+        self.search_matches.first().copied().unwrap_or(self.origin.selected_index)
+    }
+
+    /// Switches to matching the query as `kind`, or back to plain text if it already is
+    pub fn toggle_kind(&mut self, kind: QueryKind, items: &[ListItem]) {
+        self.kind = if self.kind == kind { QueryKind::Text } else { kind };
+        self.update_search_matches(items);
+    }
+
+    pub fn cycle_case_sensitivity(&mut self, items: &[ListItem]) {
+        self.case_sensitivity = self.case_sensitivity.next();
+        self.update_search_matches(items);
+    }
+
+    /// The query compiled with the current options. Plain queries are escaped,
+    /// so both kinds go through the same matching.
+    pub fn matcher(&self) -> Result<Regex, String> {
+        let pattern = if self.kind == QueryKind::Regex {
+            self.search_query.clone()
+        } else {
+            regex::escape(&self.search_query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.case_insensitive())
+            .build()
+            // The full message spans several lines pointing at the error
+            .map_err(|error| match error {
+                regex::Error::Syntax(message) => {
+                    let last_line = message.lines().last().unwrap_or_default().trim();
+                    last_line.trim_start_matches("error: ").to_string()
+                }
+                other => other.to_string(),
+            })
+    }
+
+    fn case_insensitive(&self) -> bool {
+        match self.case_sensitivity {
+            CaseSensitivity::Smart => !self.search_query.chars().any(char::is_uppercase),
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+        }
+    }
+
+    fn fuzzy_matcher(&self) -> SkimMatcherV2 {
+        let matcher = SkimMatcherV2::default();
+        if self.case_insensitive() {
+            matcher.ignore_case()
+        } else {
+            matcher.respect_case()
+        }
+    }
+
+    /// Whether matches should be shown in the list: while typing a query, and
+    /// after confirming one until the results are cleared
+    pub fn is_active(&self) -> bool {
+        if self.search_mode {
+            !self.search_query.is_empty()
+        } else {
+            self.current_match_index.is_some()
+        }
+    }
+
+    /// Byte ranges of `content` to highlight for the current query
+    pub fn match_ranges(&self, content: &str) -> Vec<Range<usize>> {
+        if self.search_query.is_empty() {
+            return Vec::new();
+        }
+        if self.kind != QueryKind::Fuzzy {
+            let Ok(matcher) = self.matcher() else {
+                return Vec::new();
+            };
+            // A regex like `x*` matches the empty string everywhere
+            return matcher
+                .find_iter(content)
+                .map(|found| found.range())
+                .filter(|range| !range.is_empty())
+                .collect();
+        }
+        let Some((_, indices)) = self.fuzzy_matcher().fuzzy_indices(content, &self.search_query) else {
+            return Vec::new();
+        };
+        // The matcher reports character positions
+        content
+            .char_indices()
+            .enumerate()
+            .filter(|(position, _)| indices.contains(position))
+            .map(|(_, (start, c))| start..start + c.len_utf8())
+            .collect()
     }
 
     pub fn confirm_search(&mut self) -> Option<usize> {
@@ -46,6 +221,12 @@ impl SearchState {
         self.update_search_matches(items);
     }
 
+    /// Replaces the query, e.g. with one recalled from the history
+    pub fn set_query(&mut self, query: &str, items: &[ListItem]) {
+        self.search_query = query.to_string();
+        self.update_search_matches(items);
+    }
+
     pub fn backspace(&mut self, items: &[ListItem]) {
         if !self.search_query.is_empty() {
             self.search_query.pop();
@@ -56,24 +237,43 @@ impl SearchState {
     pub fn update_search_matches(&mut self, items: &[ListItem]) {
         self.search_matches.clear();
         self.current_match_index = None;
+        self.error = None;
         
         if self.search_query.is_empty() {
             return;
         }
 
-        let query_lower = self.search_query.to_lowercase();
-        
-        for (index, item) in items.iter().enumerate() {
-            let content = match item {
-                ListItem::Todo { content, .. } => content,
-                ListItem::Note { content, .. } => content,
-                ListItem::Heading { content, .. } => content,
-            };
-            
-            if content.to_lowercase().contains(&query_lower) {
-                self.search_matches.push(index);
-            }
+        if self.kind == QueryKind::Fuzzy {
+            let matcher = self.fuzzy_matcher();
+            let mut scored: Vec<(i64, usize)> = self
+                .searched_items(items)
+                .filter_map(|(index, item)| Some((matcher.fuzzy_match(item.content(), &self.search_query)?, index)))
+                .collect();
+            // Best first; the sort is stable so equal scores stay in list order
+            scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+            self.search_matches = scored.into_iter().map(|(_, index)| index).collect();
+            return;
         }
+
+        let matcher = match self.matcher() {
+            Ok(matcher) => matcher,
+            Err(error) => {
+                self.error = Some(error);
+                return;
+            }
+        };
+        
+        self.search_matches = self
+            .searched_items(items)
+            .filter(|(_, item)| matcher.is_match(item.content()))
+            .map(|(index, _)| index)
+            .collect();
+    }
+
+    // The items the query is matched against, with their indices
+    fn searched_items<'a>(&self, items: &'a [ListItem]) -> impl Iterator<Item = (usize, &'a ListItem)> + use<'a> {
+        let section = self.section.clone().unwrap_or(0..items.len());
+        items.iter().enumerate().skip(section.start).take(section.len())
     }
 
     pub fn next_match(&mut self) -> Option<usize> {
@@ -122,7 +322,7 @@ impl SearchState {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::todo::models::ListItem;
+    use todo_core::models::ListItem;
 
     fn create_test_items() -> Vec<ListItem> {
         vec![
@@ -146,7 +346,7 @@ mod tests {
     #[test]
     fn test_enter_search_mode() {
         let mut search_state = SearchState::new();
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         
         assert!(search_state.search_mode);
         assert!(search_state.search_query.is_empty());
@@ -159,7 +359,7 @@ mod tests {
         let mut search_state = SearchState::new();
         let items = create_test_items();
         
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         search_state.insert_char('b', &items);
         search_state.cancel_search();
         
@@ -169,12 +369,39 @@ mod tests {
         assert!(search_state.current_match_index.is_none());
     }
 
+    #[test]
+    fn test_preview_falls_back_to_origin() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+        let origin = SearchOrigin { selected_index: 3, scroll_offset: 1 };
+
+        search_state.enter_search_mode(origin, None);
+        assert_eq!(search_state.preview(), 3);
+        search_state.insert_char('d', &items);
+        assert_eq!(search_state.preview(), 1);
+        search_state.insert_char('x', &items);
+        assert_eq!(search_state.preview(), 3);
+        assert_eq!(search_state.cancel_search(), origin);
+    }
+
+    #[test]
+    fn test_search_in_section() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+
+        search_state.enter_search_mode(SearchOrigin::default(), Some(1..3));
+        search_state.insert_char('t', &items);
+        assert_eq!(search_state.search_matches, vec![1, 2]);
+        search_state.toggle_kind(QueryKind::Fuzzy, &items);
+        assert!(search_state.search_matches.iter().all(|index| (1..3).contains(index)));
+    }
+
     #[test]
     fn test_search_matches() {
         let mut search_state = SearchState::new();
         let items = create_test_items();
         
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         search_state.insert_char('b', &items);
         search_state.insert_char('u', &items);
         search_state.insert_char('y', &items);
@@ -188,7 +415,7 @@ mod tests {
         let mut search_state = SearchState::new();
         let items = create_test_items();
         
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         search_state.insert_char('t', &items); // Should match "Walk the dog" (1), "Remember to buy milk" (2), "Work Tasks" (3), "Finish project" (4)
         
         assert_eq!(search_state.search_matches, vec![1, 2, 3, 4]);
@@ -220,7 +447,7 @@ mod tests {
         let mut search_state = SearchState::new();
         let items = create_test_items();
         
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         search_state.insert_char('b', &items);
         search_state.insert_char('u', &items);
         search_state.insert_char('y', &items);
@@ -233,12 +460,112 @@ mod tests {
         assert_eq!(search_state.search_matches.len(), 2);
     }
 
+    fn search(search_state: &mut SearchState, query: &str, items: &[ListItem]) {
+        search_state.enter_search_mode(SearchOrigin::default(), None);
+        for c in query.chars() {
+            search_state.insert_char(c, items);
+        }
+    }
+
+    #[test]
+    fn test_smart_case() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+
+        search(&mut search_state, "work", &items);
+        assert_eq!(search_state.search_matches, vec![3]);
+
+        // An uppercase letter makes the search case-sensitive
+        search(&mut search_state, "Buy", &items);
+        assert_eq!(search_state.search_matches, vec![0]);
+
+        search_state.cycle_case_sensitivity(&items);
+        assert_eq!(search_state.case_sensitivity, CaseSensitivity::Sensitive);
+        search(&mut search_state, "work", &items);
+        assert!(search_state.search_matches.is_empty());
+
+        search_state.cycle_case_sensitivity(&items);
+        search(&mut search_state, "Buy", &items);
+        assert_eq!(search_state.search_matches, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+
+        // Special characters are literal until regex mode is on
+        search(&mut search_state, "^(buy|walk)", &items);
+        assert!(search_state.search_matches.is_empty());
+
+        search_state.toggle_kind(QueryKind::Regex, &items);
+        assert_eq!(search_state.search_matches, vec![0, 1]);
+        assert!(search_state.error.is_none());
+
+        search(&mut search_state, "(unclosed", &items);
+        assert!(search_state.search_matches.is_empty());
+        assert_eq!(search_state.error.as_deref(), Some("unclosed group"));
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+        search_state.toggle_kind(QueryKind::Fuzzy, &items);
+
+        search(&mut search_state, "wlkdg", &items);
+        assert_eq!(search_state.search_matches, vec![1]);
+        let ranges = search_state.match_ranges("Walk the dog");
+        assert_eq!(ranges, vec![0..1, 2..3, 3..4, 9..10, 11..12]);
+
+        // Closer matches are ranked first
+        search(&mut search_state, "wrk", &items);
+        assert_eq!(search_state.search_matches.first(), Some(&3));
+
+        search_state.toggle_kind(QueryKind::Fuzzy, &items);
+        assert_eq!(search_state.kind, QueryKind::Text);
+        assert!(search_state.match_ranges("Work Tasks").is_empty());
+    }
+
+    #[test]
+    fn test_match_ranges() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+
+        search(&mut search_state, "o", &items);
+        assert_eq!(search_state.match_ranges("Walk the dog"), vec![10..11]);
+        assert_eq!(search_state.match_ranges("Go to town"), vec![1..2, 4..5, 7..8]);
+
+        search_state.toggle_kind(QueryKind::Regex, &items);
+        search(&mut search_state, "b[a-z]+", &items);
+        assert_eq!(search_state.match_ranges("Buy groceries"), vec![0..3]);
+        search(&mut search_state, "x*", &items);
+        assert!(search_state.match_ranges("Buy groceries").is_empty());
+    }
+
+    #[test]
+    fn test_is_active() {
+        let mut search_state = SearchState::new();
+        let items = create_test_items();
+        assert!(!search_state.is_active());
+
+        search_state.enter_search_mode(SearchOrigin::default(), None);
+        assert!(!search_state.is_active());
+        search_state.insert_char('d', &items);
+        assert!(search_state.is_active());
+
+        search_state.confirm_search();
+        assert!(search_state.is_active());
+        search_state.clear_results();
+        assert!(!search_state.is_active());
+    }
+
     #[test]
     fn test_confirm_search() {
         let mut search_state = SearchState::new();
         let items = create_test_items();
         
-        search_state.enter_search_mode();
+        search_state.enter_search_mode(SearchOrigin::default(), None);
         search_state.insert_char('d', &items); // Should match "Walk the dog"
         
         let result = search_state.confirm_search();
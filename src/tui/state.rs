@@ -1,7 +1,8 @@
-use crate::todo::models::TodoList;
+use todo_core::models::TodoList;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AppState {
     pub todo_list: TodoList,
     pub selected_index: usize,
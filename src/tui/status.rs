@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// How long a message stays in the status line before the next one is shown
+const INFO_DURATION: Duration = Duration::from_secs(3);
+const ERROR_DURATION: Duration = Duration::from_secs(8);
+// Older messages are dropped rather than shown long after the fact
+const MAX_QUEUED_MESSAGES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageLevel {
+    Info,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusMessage {
+    pub text: String,
+    pub level: MessageLevel,
+}
+
+impl StatusMessage {
+    fn duration(&self) -> Duration {
+        match self.level {
+            MessageLevel::Info => INFO_DURATION,
+            MessageLevel::Error => ERROR_DURATION,
+        }
+    }
+}
+
+/// Queue of feedback messages shown one at a time in the status line, each
+/// for a fixed time, so errors never have to be printed over the TUI.
+#[derive(Default)]
+pub struct StatusBar {
+    messages: VecDeque<StatusMessage>,
+    // When the front message was first shown
+    shown_since: Option<Instant>,
+}
+
+impl StatusBar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(text.into(), MessageLevel::Info);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(text.into(), MessageLevel::Error);
+    }
+
+    fn push(&mut self, text: String, level: MessageLevel) {
+        // Repeating the message on screen would only restart its timer
+        if self.messages.back().is_some_and(|message| message.text == text) {
+            return;
+        }
+        self.messages.push_back(StatusMessage { text, level });
+        if self.messages.len() > MAX_QUEUED_MESSAGES {
+            self.messages.pop_front();
+            self.shown_since = None;
+        }
+    }
+
+    /// The message to display right now
+    pub fn current(&self) -> Option<&StatusMessage> {
+        self.messages.front()
+    }
+
+    /// Drops messages whose time is up. Called on every tick of the event loop.
+    pub fn tick(&mut self, now: Instant) {
+        while let Some(message) = self.messages.front() {
+            match self.shown_since {
+                None => {
+                    self.shown_since = Some(now);
+                    return;
+                }
+                Some(since) if now.saturating_duration_since(since) >= message.duration() => {
+                    self.messages.pop_front();
+                    self.shown_since = None;
+                }
+                Some(_) => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_are_shown_in_order_until_they_expire() {
+        let mut status = StatusBar::new();
+        status.info("Saved");
+        status.error("Failed to save file");
+        let start = Instant::now();
+
+        status.tick(start);
+        assert_eq!(status.current().unwrap().text, "Saved");
+
+        status.tick(start + INFO_DURATION);
+        let current = status.current().unwrap();
+        assert_eq!(current.text, "Failed to save file");
+        assert_eq!(current.level, MessageLevel::Error);
+
+        // The error's timer starts once it's on screen
+        status.tick(start + INFO_DURATION + Duration::from_secs(1));
+        assert!(status.current().is_some());
+        status.tick(start + INFO_DURATION + ERROR_DURATION);
+        assert!(status.current().is_none());
+    }
+
+    #[test]
+    fn test_repeated_and_excess_messages_are_dropped() {
+        let mut status = StatusBar::new();
+        status.info("Saved");
+        status.info("Saved");
+        assert_eq!(status.messages.len(), 1);
+
+        for i in 0..MAX_QUEUED_MESSAGES + 2 {
+            status.info(format!("Message {}", i));
+        }
+        assert_eq!(status.messages.len(), MAX_QUEUED_MESSAGES);
+        assert_eq!(status.current().unwrap().text, "Message 2");
+    }
+}
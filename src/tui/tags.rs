@@ -0,0 +1,144 @@
+//! The tag panel (`T`): every tag in the file with how many items use it.
+//! Enter shows only the items with the picked tag (and the headings they're
+//! under), `r` renames the tag everywhere and `d` removes it from every item.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use todo_core::metadata;
+use todo_core::models::ListItem;
+use todo_core::structure::ItemCreator;
+
+pub struct TagCount {
+    pub tag: String,
+    /// Items with the tag
+    pub count: usize,
+}
+
+/// Every tag in `items`, most used first
+pub fn tag_counts(items: &[ListItem]) -> Vec<TagCount> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for item in items {
+        let tags: HashSet<&str> = metadata::tags(item.content()).into_iter().collect();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<TagCount> = counts.into_iter().map(|(tag, count)| TagCount { tag: tag.to_string(), count }).collect();
+    counts.sort_by_key(|entry| Reverse(entry.count));
+    counts
+}
+
+/// The items tagged `tag` and the headings of their sections
+pub fn filter_indices(items: &[ListItem], tag: &str) -> HashSet<usize> {
+    let tagged = items.iter().enumerate().filter(|(_, item)| metadata::tags(item.content()).contains(&tag));
+    ItemCreator::with_headings(items, tagged.map(|(index, _)| index))
+}
+
+pub struct TagPanelState {
+    pub open: bool,
+    pub entries: Vec<TagCount>,
+    pub selected_index: usize,
+    /// The new name being typed, while renaming the selected tag
+    pub rename_input: Option<String>,
+    /// The tag the list is narrowed to
+    pub filter: Option<String>,
+}
+
+impl TagPanelState {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            entries: Vec::new(),
+            selected_index: 0,
+            rename_input: None,
+            filter: None,
+        }
+    }
+
+    pub fn open(&mut self, items: &[ListItem]) {
+        self.open = true;
+        self.selected_index = 0;
+        self.rename_input = None;
+        self.refresh(items);
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.rename_input = None;
+    }
+
+    /// Recounts after the tags changed, keeping the selection in range
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        self.entries = tag_counts(items);
+        self.selected_index = self.selected_index.min(self.entries.len().saturating_sub(1));
+        if self.filter.as_ref().is_some_and(|filter| !self.entries.iter().any(|entry| entry.tag == *filter)) {
+            self.filter = None;
+        }
+    }
+
+    pub fn selected_tag(&self) -> Option<&str> {
+        self.entries.get(self.selected_index).map(|entry| entry.tag.as_str())
+    }
+
+    pub fn move_selection_up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selected_index + 1 < self.entries.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    /// Starts typing a new name for the selected tag, from its current one
+    pub fn start_rename(&mut self) {
+        self.rename_input = self.selected_tag().map(str::to_string);
+    }
+}
+
+impl Default for TagPanelState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report #work #urgent #work".to_string(), false, 0),
+            ListItem::new_todo("Slides #work".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Dishes #home".to_string(), false, 0),
+            ListItem::new_todo("Bills #urgent".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_tag_counts() {
+        let counts: Vec<(String, usize)> = tag_counts(&items()).into_iter().map(|entry| (entry.tag, entry.count)).collect();
+        assert_eq!(counts, [("urgent".to_string(), 2), ("work".to_string(), 2), ("home".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_filter_indices() {
+        let shown = filter_indices(&items(), "urgent");
+        assert_eq!(shown, HashSet::from([0, 1, 3, 5]));
+    }
+
+    #[test]
+    fn test_refresh_drops_stale_filter() {
+        let mut items = items();
+        let mut panel = TagPanelState::new();
+        panel.open(&items);
+        panel.selected_index = 2;
+        panel.filter = Some("home".to_string());
+        items.remove(4);
+        panel.refresh(&items);
+        assert_eq!(panel.selected_index, 1);
+        assert_eq!(panel.filter, None);
+    }
+}
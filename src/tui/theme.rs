@@ -0,0 +1,245 @@
+use crate::config::ThemeConfig;
+use ratatui::style::Color;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use todo_core::metadata;
+
+/// Colors used when rendering the TUI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub header: Color,
+    pub text: Color,
+    pub note: Color,
+    pub completed: Color,
+    pub completed_strikethrough: bool,
+    pub in_progress: Color,
+    /// Inline `code` spans in item content
+    pub code: Color,
+    /// Background behind the parts of items that match the search
+    pub search_match: Color,
+    pub raw: Color,
+    /// Heading colors by level; deeper levels reuse the last one
+    pub headings: Vec<Color>,
+    pub bulk_selected: Color,
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    pub edit_fg: Color,
+    pub edit_bg: Color,
+    pub footer: Color,
+    pub muted: Color,
+    pub error: Color,
+    /// Colors of items with these tags, keyed by the lowercased tag
+    pub tag_colors: BTreeMap<String, Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            header: Color::Cyan,
+            text: Color::White,
+            note: Color::Gray,
+            completed: Color::DarkGray,
+            completed_strikethrough: true,
+            in_progress: Color::LightYellow,
+            code: Color::LightGreen,
+            search_match: Color::Rgb(0x80, 0x60, 0x00),
+            raw: Color::DarkGray,
+            headings: vec![Color::Yellow, Color::Cyan, Color::Green, Color::Blue],
+            bulk_selected: Color::Cyan,
+            selection_fg: Color::Black,
+            selection_bg: Color::Yellow,
+            edit_fg: Color::White,
+            edit_bg: Color::Blue,
+            footer: Color::Yellow,
+            muted: Color::DarkGray,
+            error: Color::Red,
+            tag_colors: BTreeMap::new(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            header: Color::Blue,
+            text: Color::Black,
+            note: Color::DarkGray,
+            completed: Color::Gray,
+            completed_strikethrough: true,
+            in_progress: Color::Rgb(0xb0, 0x60, 0x00),
+            code: Color::Rgb(0x00, 0x80, 0x80),
+            search_match: Color::LightYellow,
+            raw: Color::Gray,
+            headings: vec![Color::Magenta, Color::Blue, Color::Green, Color::Red],
+            bulk_selected: Color::Blue,
+            selection_fg: Color::White,
+            selection_bg: Color::Blue,
+            edit_fg: Color::Black,
+            edit_bg: Color::LightCyan,
+            footer: Color::Magenta,
+            muted: Color::Gray,
+            error: Color::Red,
+            tag_colors: BTreeMap::new(),
+        }
+    }
+
+    pub fn solarized() -> Self {
+        Self {
+            header: Color::Rgb(0x26, 0x8b, 0xd2),
+            text: Color::Rgb(0x93, 0xa1, 0xa1),
+            note: Color::Rgb(0x83, 0x94, 0x96),
+            completed: Color::Rgb(0x58, 0x6e, 0x75),
+            completed_strikethrough: true,
+            in_progress: Color::Rgb(0xcb, 0x4b, 0x16),
+            code: Color::Rgb(0x2a, 0xa1, 0x98),
+            search_match: Color::Rgb(0x07, 0x36, 0x42),
+            raw: Color::Rgb(0x58, 0x6e, 0x75),
+            headings: vec![
+                Color::Rgb(0xb5, 0x89, 0x00),
+                Color::Rgb(0x2a, 0xa1, 0x98),
+                Color::Rgb(0x85, 0x99, 0x00),
+                Color::Rgb(0x26, 0x8b, 0xd2),
+            ],
+            bulk_selected: Color::Rgb(0xd3, 0x36, 0x82),
+            selection_fg: Color::Rgb(0x00, 0x2b, 0x36),
+            selection_bg: Color::Rgb(0xb5, 0x89, 0x00),
+            edit_fg: Color::Rgb(0xee, 0xe8, 0xd5),
+            edit_bg: Color::Rgb(0x07, 0x36, 0x42),
+            footer: Color::Rgb(0xb5, 0x89, 0x00),
+            muted: Color::Rgb(0x58, 0x6e, 0x75),
+            error: Color::Rgb(0xdc, 0x32, 0x2f),
+            tag_colors: BTreeMap::new(),
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "solarized" => Some(Self::solarized()),
+            _ => None,
+        }
+    }
+
+    /// Adds `[tag_colors]`, with colors written as in the theme
+    pub fn with_tag_colors(mut self, tag_colors: &BTreeMap<String, String>) -> Result<Self, String> {
+        for (tag, value) in tag_colors {
+            let tag = metadata::normalize_tag(tag).ok_or_else(|| format!("Invalid tag '{}' in tag_colors", tag))?;
+            self.tag_colors.insert(tag.to_lowercase(), parse_color(value)?);
+        }
+        Ok(self)
+    }
+
+    /// The color of the first tag in `content` that has one
+    pub fn tag_color(&self, content: &str) -> Option<Color> {
+        if self.tag_colors.is_empty() {
+            return None;
+        }
+        metadata::tags(content).into_iter().find_map(|tag| self.tag_colors.get(&tag.to_lowercase()).copied())
+    }
+
+    /// Builds a theme from the configured preset and any individual overrides.
+    /// Colors can be names (`"red"`, `"lightblue"`), hex (`"#268bd2"`) or
+    /// 256-color indices (`"33"`).
+    pub fn from_config(config: &ThemeConfig) -> Result<Self, String> {
+        let mut theme = Self::preset(&config.preset)
+            .ok_or_else(|| format!("Unknown theme preset '{}' (expected dark, light or solarized)", config.preset))?;
+
+        let overrides = [
+            (&config.header, &mut theme.header),
+            (&config.text, &mut theme.text),
+            (&config.note, &mut theme.note),
+            (&config.completed, &mut theme.completed),
+            (&config.in_progress, &mut theme.in_progress),
+            (&config.code, &mut theme.code),
+            (&config.selection_fg, &mut theme.selection_fg),
+            (&config.selection_bg, &mut theme.selection_bg),
+            (&config.edit_fg, &mut theme.edit_fg),
+            (&config.edit_bg, &mut theme.edit_bg),
+        ];
+        for (value, color) in overrides {
+            if let Some(value) = value {
+                *color = parse_color(value)?;
+            }
+        }
+
+        if let Some(headings) = &config.headings {
+            theme.headings = headings.iter().map(|value| parse_color(value)).collect::<Result<_, _>>()?;
+        }
+        if let Some(strikethrough) = config.completed_strikethrough {
+            theme.completed_strikethrough = strikethrough;
+        }
+
+        Ok(theme)
+    }
+
+    pub fn heading(&self, level: usize) -> Color {
+        let index = level.saturating_sub(1).min(self.headings.len().saturating_sub(1));
+        self.headings.get(index).copied().unwrap_or(self.text)
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    Color::from_str(value).map_err(|_| format!("Invalid theme color '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(Theme::preset("dark"), Some(Theme::dark()));
+        assert!(Theme::preset("solarized").is_some());
+        assert!(Theme::preset("neon").is_none());
+    }
+
+    #[test]
+    fn test_from_config_applies_overrides() {
+        let config = ThemeConfig {
+            preset: "light".to_string(),
+            edit_bg: Some("#073642".to_string()),
+            headings: Some(vec!["red".to_string(), "blue".to_string()]),
+            completed_strikethrough: Some(false),
+            ..ThemeConfig::default()
+        };
+
+        let theme = Theme::from_config(&config).unwrap();
+        assert_eq!(theme.text, Theme::light().text);
+        assert_eq!(theme.edit_bg, Color::Rgb(0x07, 0x36, 0x42));
+        assert_eq!(theme.heading(1), Color::Red);
+        assert_eq!(theme.heading(5), Color::Blue);
+        assert!(!theme.completed_strikethrough);
+    }
+
+    #[test]
+    fn test_tag_colors() {
+        let colors = [("urgent".to_string(), "red".to_string()), ("#Home".to_string(), "#00ff00".to_string())];
+        let theme = Theme::dark().with_tag_colors(&colors.into_iter().collect()).unwrap();
+        assert_eq!(theme.tag_color("Call mum #phone #home"), Some(Color::Rgb(0, 0xff, 0)));
+        assert_eq!(theme.tag_color("Fix it #URGENT #home"), Some(Color::Red));
+        assert_eq!(theme.tag_color("Nothing special"), None);
+
+        let colors = [("urgent".to_string(), "blurple".to_string())];
+        assert!(Theme::dark().with_tag_colors(&colors.into_iter().collect()).is_err());
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_values() {
+        let config = ThemeConfig {
+            text: Some("not-a-color".to_string()),
+            ..ThemeConfig::default()
+        };
+        assert!(Theme::from_config(&config).is_err());
+
+        let config = ThemeConfig {
+            preset: "neon".to_string(),
+            ..ThemeConfig::default()
+        };
+        assert!(Theme::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{Local, NaiveTime};
+use std::collections::VecDeque;
+use todo_core::models::ListItem;
+
+// Oldest deletions are forgotten past this many
+const MAX_TRASH_ENTRIES: usize = 50;
+
+/// Items removed by one delete, newest first in the trash
+pub struct TrashEntry {
+    pub items: Vec<ListItem>,
+    pub deleted_at: NaiveTime,
+}
+
+impl TrashEntry {
+    /// The first item's first line, with a count of the rest
+    pub fn summary(&self) -> String {
+        let first = self.items.first().map_or("", |item| item.content().lines().next().unwrap_or(""));
+        match self.items.len() {
+            0 | 1 => first.to_string(),
+            count => format!("{} (+{} more)", first, count - 1),
+        }
+    }
+}
+
+/// Everything deleted this session, so it can be brought back with
+/// `:undelete` after it has fallen out of the undo history
+pub struct TrashState {
+    pub entries: VecDeque<TrashEntry>,
+    pub picker_mode: bool,
+    pub selected_index: usize,
+}
+
+impl TrashState {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            picker_mode: false,
+            selected_index: 0,
+        }
+    }
+
+    pub fn push(&mut self, items: Vec<ListItem>) {
+        if items.is_empty() {
+            return;
+        }
+        self.entries.push_front(TrashEntry { items, deleted_at: Local::now().time() });
+        self.entries.truncate(MAX_TRASH_ENTRIES);
+    }
+
+    pub fn open(&mut self) {
+        self.picker_mode = true;
+        self.selected_index = 0;
+    }
+
+    pub fn close(&mut self) {
+        self.picker_mode = false;
+    }
+
+    /// Removes the selected entry from the trash and closes the picker
+    pub fn take_selected(&mut self) -> Option<TrashEntry> {
+        self.close();
+        self.entries.remove(self.selected_index)
+    }
+
+    pub fn move_selection_up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn move_selection_down(&mut self) {
+        if self.selected_index + 1 < self.entries.len() {
+            self.selected_index += 1;
+        }
+    }
+}
+
+impl Default for TrashState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(content: &str) -> ListItem {
+        ListItem::new_todo(content.to_string(), false, 0)
+    }
+
+    #[test]
+    fn test_newest_first_and_take() {
+        let mut trash = TrashState::new();
+        trash.push(vec![todo("First")]);
+        trash.push(vec![todo("Second\nmore"), todo("Child")]);
+        trash.push(Vec::new());
+        assert_eq!(trash.entries.len(), 2);
+        assert_eq!(trash.entries[0].summary(), "Second (+1 more)");
+
+        trash.open();
+        trash.move_selection_down();
+        trash.move_selection_down();
+        let entry = trash.take_selected().unwrap();
+        assert_eq!(entry.summary(), "First");
+        assert!(!trash.picker_mode);
+        assert_eq!(trash.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_old_entries_are_dropped() {
+        let mut trash = TrashState::new();
+        for i in 0..MAX_TRASH_ENTRIES + 5 {
+            trash.push(vec![todo(&i.to_string())]);
+        }
+        assert_eq!(trash.entries.len(), MAX_TRASH_ENTRIES);
+        assert_eq!(trash.entries.back().unwrap().summary(), "5");
+    }
+}
@@ -1,13 +1,31 @@
-use crate::todo::models::ListItem as TodoListItem;
+use todo_core::inline::{parse_inline, InlineStyle};
+use todo_core::links::{find_links, find_references};
+use todo_core::models::{ListItem as TodoListItem, TodoState};
+use crate::config::LineNumbers;
+use crate::tui::agenda::AgendaGroup;
 use crate::tui::app::App;
+use crate::tui::board::BoardLayout;
+use crate::tui::calendar::{due_counts, month_grid};
+use crate::tui::handlers::command_suggestions;
+use crate::tui::outline::{current_entry, sticky_heading};
+use crate::tui::status::{MessageLevel, StatusMessage};
+use crate::tui::tags::TagPanelState;
+use crate::tui::theme::Theme;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use std::collections::HashSet;
+use std::ops::Range;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::{Line, Span},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Clear},
 };
 
+// Columns taken by the outline sidebar, borders included
+const OUTLINE_WIDTH: u16 = 30;
+
 pub fn draw(frame: &mut Frame, app: &mut App) {
     if app.help_mode {
         draw_help_window(frame, app);
@@ -23,196 +41,678 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
             .split(frame.size());
 
         draw_header(frame, chunks[0], app);
-        draw_todo_list(frame, chunks[1], app);
+        if app.outline_visible() {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(OUTLINE_WIDTH), Constraint::Min(0)])
+                .split(chunks[1]);
+            draw_outline(frame, panes[0], app);
+            draw_todo_list(frame, panes[1], app);
+        } else {
+            draw_todo_list(frame, chunks[1], app);
+        }
         draw_footer(frame, chunks[2], app);
+
+        if app.file_picker_mode() {
+            draw_file_picker(frame, app);
+        }
+        if app.move_picker_mode() {
+            draw_move_picker(frame, app);
+        }
+        if app.trash_picker_mode() {
+            draw_trash_picker(frame, app);
+        }
+        if let Some(panel) = app.tag_panel() {
+            draw_tag_panel(frame, app, panel);
+        }
+        if app.agenda_mode() {
+            draw_agenda(frame, app);
+        }
+        if app.board_mode() {
+            draw_board(frame, app);
+        }
+        if app.calendar_mode() {
+            draw_calendar(frame, app);
+        }
+        if let Some(pending) = app.pending_delete() {
+            draw_delete_confirmation(frame, app, &pending.message());
+        }
+        if app.has_conflict() {
+            draw_conflict(frame, app);
+        }
     }
 }
 
 fn draw_header(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
-    let header_text = format!("TODO List - {}", app.todo_list.file_path);
+    let mut header_text = app.breadcrumb().join(" ▸ ");
+    if app.is_modified() {
+        header_text.push_str(" [modified]");
+    }
+    if let Some(zoomed) = app.zoom_breadcrumb().last() {
+        header_text.push_str(&format!(" (zoomed to {}, Z: show all)", zoomed));
+    }
+    if let Some(warning) = app.read_only_warning() {
+        header_text.push_str(&format!(" [read-only: {}]", warning));
+    }
+    if let Some((remaining, task)) = app.pomodoro() {
+        let seconds = remaining.as_secs();
+        let task = task.lines().next().unwrap_or_default();
+        header_text.push_str(&format!(" 🍅 {:02}:{:02} {}", seconds / 60, seconds % 60, task));
+    }
+    // The last save goes at the right end, if there's room next to the breadcrumb
+    let width = area.width.saturating_sub(2) as usize;
+    if let Some(saved) = app.last_saved() {
+        let saved = format!("saved {}", saved.format("%H:%M"));
+        let gap = width.saturating_sub(header_text.width() + saved.width());
+        if gap > 0 {
+            header_text.push_str(&" ".repeat(gap));
+            header_text.push_str(&saved);
+        }
+    }
     let header = Paragraph::new(header_text)
         .block(Block::default().borders(Borders::ALL).title("Todo"))
-        .style(Style::default().fg(Color::Cyan));
+        .style(Style::default().fg(app.theme().header));
 
     frame.render_widget(header, area);
 }
 
 fn draw_todo_list(frame: &mut Frame, area: ratatui::layout::Rect, app: &mut App) {
-    let items: Vec<ListItem> = app
-        .todo_list
-        .items
+    // Items hidden by folds aren't rendered at all
+    let visible_indices = app.visible_indices();
+    let selected_row = visible_indices.iter().position(|&i| i == app.selected_index());
+    
+    // Scroll based on the real viewport, which changes whenever the terminal is resized
+    let viewport_height = area.height.saturating_sub(2) as usize;
+    app.fit_scroll(viewport_height, selected_row.unwrap_or(0), visible_indices.len());
+    
+    // Pinning the current section's heading takes a row away from the list
+    let mut sticky = sticky_heading(&app.todo_list.items, &visible_indices, app.scroll_offset());
+    if sticky.is_some() {
+        app.fit_scroll(viewport_height.saturating_sub(1), selected_row.unwrap_or(0), visible_indices.len());
+        sticky = sticky_heading(&app.todo_list.items, &visible_indices, app.scroll_offset());
+    }
+    
+    // Row numbers take a gutter as wide as the largest one, plus a space
+    let line_numbers = app.line_numbers();
+    let gutter_width = match line_numbers {
+        LineNumbers::Off => 0,
+        _ => visible_indices.len().max(1).to_string().len() + 1,
+    };
+    let text_width = (area.width.saturating_sub(2) as usize).saturating_sub(gutter_width);
+    
+    // The editor wraps its text to whatever space is left after the item's prefix
+    if let Some(item) = app.todo_list.items.get(app.selected_index()).filter(|_| app.edit_mode()) {
+        let is_bulk_selected = app.selected_items().contains(&app.selected_index());
+        let prefix_width = item_prefix(item, is_bulk_selected).width();
+        app.set_edit_wrap_width(text_width.saturating_sub(prefix_width));
+    }
+    
+    // While a `:s` command is being typed, items are shown as they'd be after it
+    let substitution = app.substitution_preview();
+    let substitution_range = substitution.as_ref().map(|s| app.substitution_range(s)).unwrap_or_default();
+    
+    // Items that don't match an active search are dimmed
+    let search_matches: HashSet<usize> = app.search_matches().iter().copied().collect();
+    let dim_unmatched = app.search_active();
+    
+    // Todos waiting on others are dimmed and locked
+    let blocked = app.blocked_todos();
+    
+    let theme = app.theme();
+    let gutter_style = Style::default().fg(theme.muted);
+    let items: Vec<ListItem> = visible_indices
         .iter()
         .enumerate()
-        .map(|(i, list_item)| {
+        .map(|(row, &i)| {
+            let list_item = &app.todo_list.items[i];
+            
             // Check if this item is being edited or selected for bulk operation
             let is_editing = app.edit_mode() && i == app.selected_index();
             let is_bulk_selected = app.selected_items().contains(&i);
+            let mut prefix = item_prefix(list_item, is_bulk_selected);
+            if blocked.contains(&i) && !is_editing {
+                prefix.push_str("🔒 ");
+            }
+            let replaced = substitution
+                .as_ref()
+                .filter(|_| substitution_range.contains(&i))
+                .and_then(|s| s.apply(list_item.content()));
             
-            match list_item {
-                TodoListItem::Todo {
-                    content,
-                    completed,
-                    indent_level,
-                    ..
-                } => {
-                    let checkbox = if *completed { "☑" } else { "☐" };
-                    let indent = "  ".repeat(*indent_level);
-                    let selection_indicator = if is_bulk_selected { "●" } else { " " };
-                    
-                    let display_content = if is_editing {
-                        // Show edit buffer with cursor
-                        let (before_cursor, after_cursor) = app.edit_buffer().split_at(app.edit_cursor_position());
-                        format!("{}{}{} {}█{}", selection_indicator, indent, checkbox, before_cursor, after_cursor)
-                    } else {
-                        format!("{}{}{} {}", selection_indicator, indent, checkbox, content)
-                    };
-
-                    let style = if is_editing {
+            let rows = if is_editing {
+                // Show edit buffer with cursor
+                app.edit_display_rows()
+            } else {
+                let content = replaced.as_deref().unwrap_or(list_item.content());
+                let mut rows: Vec<String> = content.split('\n').map(str::to_string).collect();
+                if let (Some(count), Some(last)) = (app.folded_child_count(i), rows.last_mut()) {
+                    last.push_str(&format!(" ▸ ({})", count));
+                }
+                let marks = app.marks_on(i);
+                if !marks.is_empty() && let Some(last) = rows.last_mut() {
+                    last.push_str(&format!(" '{}", marks.into_iter().collect::<String>()));
+                }
+                rows
+            };
+            
+            // Open todos and notes take the color of their tag, if one has a color
+            let tag_color = theme.tag_color(list_item.content());
+            let style = match list_item {
+                TodoListItem::Todo { state, .. } => {
+                    if is_editing {
                         Style::default()
-                            .bg(Color::Blue)
-                            .fg(Color::White)
+                            .bg(theme.edit_bg)
+                            .fg(theme.edit_fg)
                             .add_modifier(Modifier::BOLD)
                     } else if is_bulk_selected {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.bulk_selected)
                             .add_modifier(Modifier::BOLD)
-                    } else if *completed {
-                        Style::default()
-                            .fg(Color::DarkGray)
-                            .add_modifier(Modifier::CROSSED_OUT)
                     } else {
-                        Style::default().fg(Color::White)
-                    };
-
-                    let line = Line::from(Span::styled(display_content, style));
-                    ListItem::new(line)
+                        match state {
+                            TodoState::Open => Style::default().fg(tag_color.unwrap_or(theme.text)),
+                            TodoState::Done if theme.completed_strikethrough => Style::default()
+                                .fg(theme.completed)
+                                .add_modifier(Modifier::CROSSED_OUT),
+                            TodoState::Done => Style::default().fg(theme.completed),
+                            // Always struck through so it can't be mistaken for done
+                            TodoState::Cancelled => Style::default()
+                                .fg(theme.completed)
+                                .add_modifier(Modifier::CROSSED_OUT),
+                            TodoState::InProgress => Style::default().fg(tag_color.unwrap_or(theme.in_progress)),
+                            TodoState::Deferred => Style::default()
+                                .fg(theme.muted)
+                                .add_modifier(Modifier::ITALIC),
+                        }
+                    }
                 }
-                TodoListItem::Note {
-                    content,
-                    indent_level,
-                    ..
-                } => {
-                    let bullet = "•";
-                    let indent = "  ".repeat(*indent_level);
-                    let selection_indicator = if is_bulk_selected { "●" } else { " " };
-                    
-                    let display_content = if is_editing {
-                        // Show edit buffer with cursor
-                        let (before_cursor, after_cursor) = app.edit_buffer().split_at(app.edit_cursor_position());
-                        format!("{}{}{} {}█{}", selection_indicator, indent, bullet, before_cursor, after_cursor)
-                    } else {
-                        format!("{}{}{} {}", selection_indicator, indent, bullet, content)
-                    };
-
-                    let style = if is_editing {
+                TodoListItem::Note { .. } => {
+                    if is_editing {
                         Style::default()
-                            .bg(Color::Blue)
-                            .fg(Color::White)
+                            .bg(theme.edit_bg)
+                            .fg(theme.edit_fg)
                             .add_modifier(Modifier::BOLD)
                     } else if is_bulk_selected {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.bulk_selected)
                             .add_modifier(Modifier::BOLD)
                     } else {
                         Style::default()
-                            .fg(Color::Gray)
+                            .fg(tag_color.unwrap_or(theme.note))
                             .add_modifier(Modifier::ITALIC)
-                    };
-
-                    let line = Line::from(Span::styled(display_content, style));
-                    ListItem::new(line)
+                    }
                 }
-                TodoListItem::Heading { content, level, .. } => {
-                    let prefix = "#".repeat(*level);
-                    let selection_indicator = if is_bulk_selected { "●" } else { " " };
-                    
-                    let display_content = if is_editing {
-                        // Show edit buffer with cursor for headings
-                        let (before_cursor, after_cursor) = app.edit_buffer().split_at(app.edit_cursor_position());
-                        format!("{}{} {}█{}", selection_indicator, prefix, before_cursor, after_cursor)
-                    } else {
-                        format!("{}{} {}", selection_indicator, prefix, content)
-                    };
-
+                TodoListItem::Heading { level, .. } => {
                     let (color, modifier) = if is_editing {
-                        (Color::White, Modifier::BOLD)
+                        (theme.edit_fg, Modifier::BOLD)
                     } else if is_bulk_selected {
-                        (Color::Cyan, Modifier::BOLD)
+                        (theme.bulk_selected, Modifier::BOLD)
+                    } else if *level == 1 {
+                        (theme.heading(*level), Modifier::BOLD | Modifier::UNDERLINED)
                     } else {
-                        match level {
-                            1 => (Color::Yellow, Modifier::BOLD | Modifier::UNDERLINED),
-                            2 => (Color::Cyan, Modifier::BOLD),
-                            3 => (Color::Green, Modifier::BOLD),
-                            _ => (Color::Blue, Modifier::BOLD),
-                        }
+                        (theme.heading(*level), Modifier::BOLD)
                     };
 
-                    let style = if is_editing {
+                    if is_editing {
                         Style::default()
-                            .bg(Color::Blue)
+                            .bg(theme.edit_bg)
                             .fg(color)
                             .add_modifier(modifier)
                     } else {
                         Style::default().fg(color).add_modifier(modifier)
-                    };
+                    }
+                }
+                TodoListItem::Raw { .. } => {
+                    if is_editing {
+                        Style::default()
+                            .bg(theme.edit_bg)
+                            .fg(theme.edit_fg)
+                    } else if is_bulk_selected {
+                        Style::default().fg(theme.bulk_selected)
+                    } else {
+                        Style::default().fg(theme.raw)
+                    }
+                }
+            };
+            let style = if blocked.contains(&i) && !is_editing && !is_bulk_selected {
+                Style::default().fg(theme.muted)
+            } else {
+                style
+            };
+            let style = if replaced.is_some() { style.add_modifier(Modifier::REVERSED) } else { style };
+            let style = if dim_unmatched && !is_editing && !search_matches.contains(&i) {
+                style.fg(theme.muted)
+            } else {
+                style
+            };
 
-                    let line = Line::from(Span::styled(display_content, style));
-                    ListItem::new(line)
+            let highlights = if is_editing || replaced.is_some() {
+                Vec::new()
+            } else {
+                app.search_highlights(list_item.content())
+            };
+
+            // Raw lines are shown exactly as written, since they may be anything
+            let render_markdown = !matches!(list_item, TodoListItem::Raw { .. });
+            let mut row_start = 0;
+            let wrap_width = text_width.saturating_sub(prefix.width());
+            let rows = rows
+                .into_iter()
+                .flat_map(|row| {
+                    // The editor wraps its own rows
+                    if is_editing {
+                        return vec![vec![Span::styled(row, style)]];
+                    }
+                    let spans = if !highlights.is_empty() {
+                        highlighted_spans(&row, row_start, &highlights, style, theme)
+                    } else {
+                        content_spans(&row, style, theme, render_markdown)
+                    };
+                    row_start += row.len() + 1;
+                    wrap_spans(spans, wrap_width)
+                })
+                .collect();
+            let mut text = item_text(&prefix, rows, style);
+            if let Some(number) = line_number(line_numbers, row, selected_row) {
+                // Continuation rows get a blank gutter
+                for (line, text_line) in text.lines.iter_mut().enumerate() {
+                    let label = if line == 0 { number.to_string() } else { String::new() };
+                    text_line.spans.insert(0, Span::styled(format!("{:>width$} ", label, width = gutter_width - 1), gutter_style));
                 }
             }
+            ListItem::new(text)
         })
         .collect();
 
+    let block = Block::default().borders(Borders::ALL).title("Items");
+    let mut list_area = block.inner(area);
+    frame.render_widget(block, area);
+    if let Some(heading @ TodoListItem::Heading { content, level }) = sticky.map(|index| &app.todo_list.items[index]) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(list_area);
+        let pinned = Paragraph::new(Line::from(Span::styled(
+            format!("{}{}{}", " ".repeat(gutter_width), item_prefix(heading, false), content),
+            Style::default().fg(theme.heading(*level)).add_modifier(Modifier::BOLD),
+        )));
+        frame.render_widget(pinned, rows[0]);
+        list_area = rows[1];
+    }
+
+    // Lines from the top of the list to the top and bottom of the edited
+    // item, for placing the completion menu beside it
+    let edited_lines = selected_row.filter(|_| app.edit_mode()).map(|row| {
+        let top = items.iter().take(row).skip(app.scroll_offset()).map(ListItem::height).sum::<usize>();
+        (top, top + items[row].height())
+    });
+    let text_left = gutter_width + app.todo_list.items.get(app.selected_index()).map_or(0, |item| item_prefix(item, false).width());
+
     let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Items"))
         .highlight_style(
             Style::default()
-                .bg(Color::Yellow)
-                .fg(Color::Black)
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
                 .add_modifier(Modifier::BOLD),
         );
 
-    let mut list_state = ListState::default();
-    list_state.select(Some(app.selected_index()));
+    let mut list_state = ListState::default()
+        .with_offset(app.scroll_offset())
+        .with_selected(selected_row);
+
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    if let (Some((candidates, selected)), Some(lines)) = (app.tag_completion(), edited_lines) {
+        draw_tag_completion(frame, app, list_area, text_left, lines, candidates, selected);
+    }
+}
 
+// Under the edited item, which spans `(top, bottom)` lines of `area`, or
+// above it if there's no room below
+fn draw_tag_completion(
+    frame: &mut Frame,
+    app: &App,
+    area: ratatui::layout::Rect,
+    column: usize,
+    (top, bottom): (usize, usize),
+    candidates: &[String],
+    selected: usize,
+) {
+    let theme = app.theme();
+    let width = (candidates.iter().map(|word| word.width()).max().unwrap_or(0) + 4).min(area.width as usize) as u16;
+    let height = (candidates.len() + 2) as u16;
+    let x = area.x + (column as u16).min(area.width.saturating_sub(width));
+    let y = if bottom as u16 + height <= area.height {
+        area.y + bottom as u16
+    } else {
+        (area.y + top as u16).saturating_sub(height).max(area.y)
+    };
+    let items: Vec<ListItem> = candidates
+        .iter()
+        .map(|word| ListItem::new(Line::from(Span::styled(format!(" {}", word), Style::default().fg(theme.text)))))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).style(Style::default().fg(theme.footer)))
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut list_state = ListState::default().with_selected(Some(selected));
+    let popup = ratatui::layout::Rect { x, y, width, height: height.min(area.height) };
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut list_state);
+}
+
+// The number shown beside `row`: counted from 1, or for relative numbers,
+// the distance from the cursor's row, which shows its own number
+fn line_number(line_numbers: LineNumbers, row: usize, cursor_row: Option<usize>) -> Option<usize> {
+    match line_numbers {
+        LineNumbers::Off => None,
+        LineNumbers::Relative if cursor_row.is_some_and(|cursor_row| cursor_row != row) => {
+            cursor_row.map(|cursor_row| cursor_row.abs_diff(row))
+        }
+        _ => Some(row + 1),
+    }
+}
+
+fn checkbox(state: TodoState) -> &'static str {
+    match state {
+        TodoState::Open => "☐",
+        TodoState::Done => "☑",
+        TodoState::InProgress => "◐",
+        TodoState::Cancelled => "☒",
+        TodoState::Deferred => "↷",
+    }
+}
+
+// Everything shown before an item's text: selection mark, indent and marker
+fn item_prefix(item: &TodoListItem, is_bulk_selected: bool) -> String {
+    let selection_indicator = if is_bulk_selected { "●" } else { " " };
+    match item {
+        TodoListItem::Todo { state, indent_level, .. } => {
+            format!("{}{}{} ", selection_indicator, "  ".repeat(*indent_level), checkbox(*state))
+        }
+        TodoListItem::Note { indent_level, .. } => {
+            format!("{}{}• ", selection_indicator, "  ".repeat(*indent_level))
+        }
+        TodoListItem::Heading { level, .. } => format!("{}{} ", selection_indicator, "#".repeat(*level)),
+        TodoListItem::Raw { .. } => selection_indicator.to_string(),
+    }
+}
+
+// Splits a row into rows at most `width` columns wide, breaking between
+// words where it can. Spaces at a break hang past the edge rather than
+// starting the next row.
+fn wrap_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Vec<Span<'static>>> {
+    let width = width.max(1);
+    let mut rows: Vec<Vec<Span<'static>>> = vec![Vec::new()];
+    let mut row_width = 0;
+    let push = |rows: &mut Vec<Vec<Span<'static>>>, text: &str, style: Style| {
+        let row = rows.last_mut().unwrap();
+        match row.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(text),
+            _ => row.push(Span::styled(text.to_string(), style)),
+        }
+    };
+    for span in spans {
+        for word in span.content.split_inclusive(' ') {
+            let word_width = word.trim_end().width();
+            if row_width > 0 && row_width + word_width > width {
+                rows.push(Vec::new());
+                row_width = 0;
+            }
+            if word_width <= width - row_width {
+                push(&mut rows, word, span.style);
+                row_width += word.width();
+                continue;
+            }
+            // Too long for a row of its own, so it's broken up anywhere
+            for grapheme in word.graphemes(true) {
+                let grapheme_width = grapheme.width();
+                if row_width > 0 && row_width + grapheme_width > width && !grapheme.trim().is_empty() {
+                    rows.push(Vec::new());
+                    row_width = 0;
+                }
+                push(&mut rows, grapheme, span.style);
+                row_width += grapheme_width;
+            }
+        }
+    }
+    rows
+}
+
+// Lays out an item's rows, lining continuation rows up under the first one
+fn item_text(prefix: &str, rows: Vec<Vec<Span<'static>>>, style: Style) -> Text<'static> {
+    let padding = " ".repeat(prefix.width());
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let lead = if i == 0 { prefix } else { padding.as_str() };
+            let mut spans = vec![Span::styled(lead.to_string(), style)];
+            spans.extend(row);
+            Line::from(spans)
+        })
+        .collect();
+    Text::from(lines)
+}
+
+// A row of item content with its links underlined and, unless it's shown
+// verbatim, inline markdown rendered in place of its markers
+fn content_spans(row: &str, style: Style, theme: &Theme, render_markdown: bool) -> Vec<Span<'static>> {
+    let text_spans = |text: &str| -> Vec<Span<'static>> {
+        if !render_markdown {
+            return vec![Span::styled(text.to_string(), style)];
+        }
+        parse_inline(text)
+            .into_iter()
+            .map(|span| Span::styled(span.text, inline_style(style, span.style, theme)))
+            .collect()
+    };
+
+    // Links and references to other items are left alone, since URLs often
+    // contain `*` or `~`
+    let mut ranges: Vec<Range<usize>> = find_links(row).into_iter().map(|link| link.range).collect();
+    let references: Vec<Range<usize>> = find_references(row)
+        .into_iter()
+        .map(|reference| reference.range)
+        .filter(|reference| !ranges.iter().any(|link| link.start < reference.end && reference.start < link.end))
+        .collect();
+    ranges.extend(references);
+    ranges.sort_by_key(|range| range.start);
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for range in ranges {
+        // Overlaps one already shown
+        if range.start < position {
+            continue;
+        }
+        spans.extend(text_spans(&row[position..range.start]));
+        spans.push(Span::styled(row[range.clone()].to_string(), style.add_modifier(Modifier::UNDERLINED)));
+        position = range.end;
+    }
+    spans.extend(text_spans(&row[position..]));
+    spans
+}
+
+// Search highlights are byte ranges into the whole content, so a highlighted
+// row is shown as written, without markdown styling, for them to line up.
+// Ranges are in order and don't overlap.
+fn highlighted_spans(
+    row: &str,
+    row_start: usize,
+    highlights: &[Range<usize>],
+    style: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for range in highlights {
+        let start = range.start.max(row_start) - row_start;
+        let end = range.end.saturating_sub(row_start).min(row.len());
+        if start < position || start >= end {
+            continue;
+        }
+        if start > position {
+            spans.push(Span::styled(row[position..start].to_string(), style));
+        }
+        spans.push(Span::styled(row[start..end].to_string(), style.bg(theme.search_match)));
+        position = end;
+    }
+    if position < row.len() {
+        spans.push(Span::styled(row[position..].to_string(), style));
+    }
+    spans
+}
+
+fn inline_style(base: Style, inline: InlineStyle, theme: &Theme) -> Style {
+    let mut style = base;
+    if inline.bold {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if inline.italic {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if inline.strikethrough {
+        style = style.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if inline.code {
+        style = style.fg(theme.code);
+    }
+    style
+}
+
+fn draw_outline(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
+    let theme = app.theme();
+    let entries = app.outline_entries();
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let content = app.todo_list.items[entry.index].content();
+            let indent = "  ".repeat(entry.level.saturating_sub(1));
+            ListItem::new(Line::from(Span::styled(
+                format!("{}{}", indent, content),
+                Style::default().fg(theme.heading(entry.level)),
+            )))
+        })
+        .collect();
+
+    // The border shows which pane has the keyboard
+    let border_color = if app.outline_focused() { theme.footer } else { theme.muted };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Outline")
+                .border_style(Style::default().fg(border_color)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(current_entry(&entries, app.selected_index()));
     frame.render_stateful_widget(list, area, &mut list_state);
 }
 
 fn draw_footer(frame: &mut Frame, area: ratatui::layout::Rect, app: &App) {
-    let footer_text = if app.search_mode() {
-        let match_info = if app.search_matches().is_empty() {
+    let footer_text = if let Some(substitution) = app.substitution_preview() {
+        let range = app.substitution_range(&substitution);
+        let changed = app.todo_list.items[range]
+            .iter()
+            .filter(|item| substitution.apply(item.content()).is_some())
+            .count();
+        format!(":{}█ | {} items will change | Enter: replace | Esc: cancel", app.command_input(), changed)
+    } else if app.command_mode() {
+        let suggestions = command_suggestions(app.command_input());
+        if suggestions.is_empty() {
+            format!(":{}█", app.command_input())
+        } else {
+            format!(":{}█ | {} | Tab: complete | Esc: cancel", app.command_input(), suggestions.join(" "))
+        }
+    } else if app.search_mode() {
+        let match_info = if let Some(error) = app.search_error() {
+            format!("Invalid regex: {}", error)
+        } else if app.search_matches().is_empty() {
             "No matches".to_string()
         } else {
             format!("{} matches", app.search_matches().len())
         };
-        format!("SEARCH: {} | {} | Enter: confirm | Esc: cancel", app.search_query(), match_info)
+        format!(
+            "{}: {} | {} | [{}, {}] Alt+R: regex, Alt+F: fuzzy, Alt+C: case | Enter: confirm | Esc: cancel",
+            if app.search_in_section() { "SEARCH SECTION" } else { "SEARCH" },
+            app.search_query(),
+            match_info,
+            app.search_kind().label(),
+            app.search_case_sensitivity().label()
+        )
     } else if app.edit_mode() {
         "EDIT MODE | Enter: confirm | Esc: cancel | ←→: cursor | Backspace/Delete: edit".to_string()
-    } else {
-        let search_info = if !app.search_matches().is_empty() && app.current_match_index().is_some() {
-            let current = app.current_match_index().unwrap() + 1;
-            let total = app.search_matches().len();
-            format!(" | Search: {}/{} (n/N: next/prev, Esc: clear)", current, total)
-        } else {
-            String::new()
-        };
-        
+    } else if let Some(message) = app.status_message() {
+        draw_status_line(frame, area, app, message);
+        return;
+    } else if app.outline_focused() {
+        "OUTLINE | j/k: jump to section | Tab/Enter: back to list | Esc: close".to_string()
+    } else if app.visual_mode() {
         format!(
-            "Items: {} | Completed: {} | Selected: {}{} | /: search | ↑↓/j/k: navigate | Space: select | ?: help | q: quit",
-            app.total_items(),
-            app.completed_items(),
-            app.selected_items().len(),
-            search_info
+            "VISUAL | Selected: {} | j/k: extend | d: delete | H/L: unindent/indent | v: keep selection | Esc: cancel",
+            app.selected_items().len()
         )
+    } else {
+        let mut parts = vec![app.footer_summary()];
+        if let Some(register) = app.recording_macro() {
+            parts.push(format!("Recording @{}", register));
+        }
+        if let Some(count) = app.pending_count() {
+            parts.push(format!("Count: {}", count));
+        }
+        if let Some(keys) = app.pending_keys() {
+            parts.push(format!("Keys: {}", keys));
+        }
+        if !app.search_matches().is_empty()
+            && let Some(current) = app.current_match_index()
+        {
+            parts.push(format!("Search: {}/{}", current + 1, app.search_matches().len()));
+        }
+        if app.hide_completed() {
+            parts.push(format!("{} hidden", app.hidden_completed_count()));
+        }
+        if app.hide_blocked() {
+            parts.push(format!("{} blocked", app.blocked_todos().len()));
+        }
+        if app.pending_only() {
+            parts.push("Pending only".to_string());
+        }
+        if let Some(tag) = app.tag_filter() {
+            parts.push(format!("Only #{} (Esc: show all)", tag));
+        }
+        parts.extend(app.footer_hints());
+        parts.join(" | ")
     };
 
     let footer = Paragraph::new(footer_text)
         .block(Block::default().borders(Borders::ALL))
-        .style(Style::default().fg(Color::Yellow));
+        .style(Style::default().fg(app.theme().footer));
 
     frame.render_widget(footer, area);
 }
 
+// Timed feedback such as "Saved" or an error, shown in place of the footer
+fn draw_status_line(frame: &mut Frame, area: ratatui::layout::Rect, app: &App, message: &StatusMessage) {
+    let color = match message.level {
+        MessageLevel::Info => app.theme().footer,
+        MessageLevel::Error => app.theme().error,
+    };
+    let status_line = Paragraph::new(message.text.as_str())
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(color));
+
+    frame.render_widget(status_line, area);
+}
+
 fn draw_help_window(frame: &mut Frame, app: &mut App) {
     // First draw the normal interface
     let chunks = Layout::default()
@@ -229,59 +729,459 @@ fn draw_help_window(frame: &mut Frame, app: &mut App) {
     draw_todo_list(frame, chunks[1], app);
     draw_footer(frame, chunks[2], app);
 
-    // Then overlay the help window
-    let help_text = vec![
-        "Todo List - Keyboard Commands",
-        "",
-        "NAVIGATION:",
-        "  ↑↓ / j/k          Navigate up/down",
-        "  Enter             Toggle todo completion",
-        "",
-        "SEARCH:",
-        "  /                 Enter search mode",
-        "  n                 Go to next search match (or add note if no search)",
-        "  N                 Go to previous search match (or add note if no search)",
-        "",
-        "EDITING:",
-        "  e                 Edit current item",
-        "  a                 Add new todo below cursor",
-        "  Shift+A           Add new todo at top/under heading",
-        "  n                 Add new note below cursor (if no active search)",
-        "  Shift+N           Add new note at top/under heading (if no active search)",
-        "",
-        "MOVEMENT:",
-        "  Shift+↑↓ / J/K    Move item up/down",
-        "  Shift+←→ / H/L    Unindent/indent item",
-        "",
-        "BULK OPERATIONS:",
-        "  Space             Select/deselect item for bulk operations",
-        "  m                 Move selected items below cursor",
-        "",
-        "OTHER:",
-        "  u                 Undo last operation",
-        "  Esc               Clear selection",
-        "  ?                 Show this help (press ? or Esc to close)",
-        "  q / Ctrl+C        Quit application",
-        "",
-        "Press ? or Esc to close this help window",
-    ];
-
-    let help_paragraph = Paragraph::new(help_text.join("\n"))
+    // Then overlay the help window, with a row below the lines for the
+    // search prompt or where in the help it is
+    let theme = app.theme().clone();
+    let area = centered_rect(80, 70, frame.size());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Help - Keyboard Commands ")
+        .style(Style::default().fg(theme.footer));
+    let inner = block.inner(area);
+    let height = inner.height.saturating_sub(1) as usize;
+    let help = app.help_view(height);
+
+    let lines: Vec<Line> = help
+        .lines
+        .iter()
+        .skip(help.scroll)
+        .take(height)
+        .map(|line| {
+            let style = if help.matches(line) {
+                Style::default().fg(theme.selection_fg).bg(theme.selection_bg)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            Line::styled(line.as_str(), style)
+        })
+        .collect();
+    let prompt = match &help.search_input {
+        Some(input) => format!("/{}", input),
+        None => format!(
+            "{}-{} of {}",
+            (help.scroll + 1).min(help.lines.len()),
+            (help.scroll + height).min(help.lines.len()),
+            help.lines.len()
+        ),
+    };
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+    frame.render_widget(Paragraph::new(lines), rows[0]);
+    frame.render_widget(Paragraph::new(prompt).style(Style::default().fg(theme.footer)), rows[1]);
+}
+
+fn draw_file_picker(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let mut items: Vec<ListItem> = app
+        .file_entries()
+        .iter()
+        .map(|entry| {
+            let is_current = entry.path == app.todo_list.file_path;
+            let marker = if is_current { "●" } else { " " };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} {}", marker, entry.name), Style::default().fg(theme.text).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  {}", entry.path), Style::default().fg(theme.muted)),
+            ]))
+        })
+        .collect();
+
+    if let Some(error) = app.file_picker_error() {
+        items.push(ListItem::new(Line::from(Span::styled(error.to_string(), Style::default().fg(theme.error)))));
+    }
+
+    let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Help - Keyboard Commands ")
-                .style(Style::default().fg(Color::Yellow))
+                .title(" Switch File (Enter: open, Esc: cancel) ")
+                .style(Style::default().fg(theme.footer))
         )
-        .style(Style::default().fg(Color::White))
-        .wrap(ratatui::widgets::Wrap { trim: true });
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.file_picker_selected_index()));
+
+    let area = centered_rect(60, 40, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_move_picker(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let items: Vec<ListItem> = app
+        .move_picker_entries()
+        .iter()
+        .map(|entry| {
+            let indent = "  ".repeat(entry.level.saturating_sub(1));
+            ListItem::new(Line::from(Span::styled(
+                format!("{}{}", indent, app.todo_list.items[entry.index].content()),
+                Style::default().fg(theme.heading(entry.level)),
+            )))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Move to Section (Enter: move, Esc: cancel) ")
+                .style(Style::default().fg(theme.footer))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(Some(app.move_picker_selected_index()));
+
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_tag_panel(frame: &mut Frame, app: &App, panel: &TagPanelState) {
+    let theme = app.theme();
+    let mut items: Vec<ListItem> = panel
+        .entries
+        .iter()
+        .map(|entry| {
+            let marker = if app.tag_filter() == Some(entry.tag.as_str()) { "●" } else { " " };
+            let color = theme.tag_color(&format!("#{}", entry.tag)).unwrap_or(theme.text);
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} #{}", marker, entry.tag), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  {}", entry.count), Style::default().fg(theme.muted)),
+            ]))
+        })
+        .collect();
+    if let Some(input) = &panel.rename_input {
+        items.push(ListItem::new(Line::from(Span::styled(
+            format!("Rename to: #{}█ (Enter: rename, Esc: cancel)", input),
+            Style::default().fg(theme.footer),
+        ))));
+    }
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Tags (Enter: show only these, r: rename, d: remove, Esc: close) ")
+                .style(Style::default().fg(theme.footer))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(Some(panel.selected_index));
+
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_trash_picker(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let items: Vec<ListItem> = app
+        .trash_entries()
+        .map(|entry| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", entry.deleted_at.format("%H:%M")), Style::default().fg(theme.completed)),
+                Span::styled(entry.summary(), Style::default().fg(theme.text)),
+            ]))
+        })
+        .collect();
 
-    // Create a centered area for the help window
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Deleted Items (Enter: restore below cursor, Esc: close) ")
+                .style(Style::default().fg(theme.footer))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(Some(app.trash_picker_selected_index()));
+
+    let area = centered_rect(60, 60, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_agenda(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let mut rows: Vec<ListItem> = Vec::new();
+    let mut selected_row = None;
+    let mut current_group = None;
+
+    for (i, entry) in app.agenda_entries().iter().enumerate() {
+        // Each group gets a title row above its first entry
+        if current_group != Some(entry.group) {
+            current_group = Some(entry.group);
+            let color = if entry.group == AgendaGroup::Overdue { theme.error } else { theme.header };
+            rows.push(ListItem::new(Line::from(Span::styled(
+                entry.group.title(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))));
+        }
+        if i == app.agenda_selected_index() {
+            selected_row = Some(rows.len());
+        }
+        // Only the first line of a multi-line todo fits in the agenda
+        let content = app
+            .todo_list
+            .items
+            .get(entry.index)
+            .and_then(|item| item.content().lines().next())
+            .unwrap_or_default();
+        rows.push(ListItem::new(Line::from(vec![
+            Span::styled(format!("  {}  ", entry.due.format("%a %b %d")), Style::default().fg(theme.muted)),
+            Span::styled(content.to_string(), Style::default().fg(theme.text)),
+        ])));
+    }
+
+    if rows.is_empty() {
+        rows.push(ListItem::new(Line::from(Span::styled(
+            "Nothing due this week",
+            Style::default().fg(theme.muted),
+        ))));
+    }
+
+    let list = List::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Agenda (Enter: jump to item, Esc: close) ")
+                .style(Style::default().fg(theme.footer))
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(selected_row);
+
+    let area = centered_rect(70, 60, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+// Characters per day in the calendar grid
+const CALENDAR_CELL_WIDTH: usize = 9;
+
+fn draw_calendar(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let selected = app.calendar_selected_date();
+    let today = crate::tui::agenda::today();
+    let counts = due_counts(&app.todo_list.items);
+
+    let header: String = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+        .iter()
+        .map(|name| format!("{:^width$}", name, width = CALENDAR_CELL_WIDTH))
+        .collect();
+    let mut lines = vec![Line::from(Span::styled(header, Style::default().fg(theme.muted))), Line::from("")];
+    for week in month_grid(selected) {
+        let mut spans = Vec::new();
+        for day in week {
+            let Some(day) = day else {
+                spans.push(Span::raw(" ".repeat(CALENDAR_CELL_WIDTH)));
+                continue;
+            };
+            let count = counts.get(&day).copied().unwrap_or(0);
+            let mut day_style = Style::default().fg(theme.text);
+            let mut count_style = Style::default().fg(if day < today { theme.error } else { theme.header });
+            if day == today {
+                day_style = day_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            }
+            if day == selected {
+                day_style = day_style.bg(theme.selection_bg).fg(theme.selection_fg);
+                count_style = count_style.bg(theme.selection_bg).fg(theme.selection_fg);
+            }
+            let count_text = if count > 0 { format!("({})", count) } else { String::new() };
+            spans.push(Span::styled(format!(" {:>2} ", day.format("%-d")), day_style));
+            spans.push(Span::styled(format!("{:<width$}", count_text, width = CALENDAR_CELL_WIDTH - 4), count_style));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(""));
+    }
+
+    let calendar = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(
+                " {} (arrows: day, [ ]: month, t: today, Enter: todos due that day, Esc: close) ",
+                selected.format("%B %Y")
+            ))
+            .style(Style::default().fg(theme.footer)),
+    );
     let area = centered_rect(80, 70, frame.size());
-    
-    // Clear the area and render the help window
     frame.render_widget(Clear, area);
-    frame.render_widget(help_paragraph, area);
+    frame.render_widget(calendar, area);
+
+    if let Some(todos) = app.calendar_day_todos() {
+        draw_calendar_day(frame, app, todos);
+    }
+}
+
+// The todos due on the selected day, over the calendar
+fn draw_calendar_day(frame: &mut Frame, app: &App, todos: &[usize]) {
+    let theme = app.theme();
+    let mut rows: Vec<ListItem> = todos
+        .iter()
+        .filter_map(|&index| app.todo_list.items.get(index))
+        .map(|item| {
+            let content = item.content().lines().next().unwrap_or_default();
+            ListItem::new(Line::from(Span::styled(content.to_string(), Style::default().fg(theme.text))))
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(ListItem::new(Line::from(Span::styled("Nothing due", Style::default().fg(theme.muted)))));
+    }
+
+    let list = List::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Due {} (Enter: jump to item, Esc: back) ",
+                    app.calendar_selected_date().format("%a %b %d")
+                ))
+                .style(Style::default().fg(theme.footer)),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(theme.selection_bg)
+                .fg(theme.selection_fg)
+                .add_modifier(Modifier::BOLD),
+        );
+    let mut list_state = ListState::default().with_selected((!todos.is_empty()).then_some(app.calendar_day_selected_index()));
+
+    let area = centered_rect(60, 40, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_board(frame: &mut Frame, app: &App) {
+    let theme = app.theme();
+    let area = centered_rect(90, 80, frame.size());
+    frame.render_widget(Clear, area);
+
+    let (layout_name, other_layout) = match app.board_layout() {
+        BoardLayout::Sections => ("sections", "states"),
+        BoardLayout::States => ("states", "sections"),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(
+            " Board by {} (←→: column, h/l: move card, Tab: by {}, Enter: jump to item, Esc: close) ",
+            layout_name, other_layout
+        ))
+        .style(Style::default().fg(theme.footer));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let columns = app.board_columns();
+    if columns.is_empty() {
+        let empty = Paragraph::new("No headings to make columns of").style(Style::default().fg(theme.muted));
+        frame.render_widget(empty, inner);
+        return;
+    }
+    let (selected_column, selected_card) = app.board_selection();
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, columns.len() as u32); columns.len()])
+        .split(inner);
+
+    for (i, (column, column_area)) in columns.iter().zip(areas.iter()).enumerate() {
+        let is_selected = i == selected_column;
+        let cards: Vec<ListItem> = column
+            .cards
+            .iter()
+            .filter_map(|&index| app.todo_list.items.get(index))
+            .map(|item| {
+                // Only the first line of a multi-line todo fits on a card
+                let content = item.content().lines().next().unwrap_or_default();
+                let color = if item.is_completed() { theme.completed } else { theme.text };
+                ListItem::new(Line::from(Span::styled(content.to_string(), Style::default().fg(color))))
+            })
+            .collect();
+        let border = if is_selected { theme.header } else { theme.muted };
+        let list = List::new(cards)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ({}) ", column.title, column.cards.len()))
+                    .border_style(Style::default().fg(border)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(theme.selection_bg)
+                    .fg(theme.selection_fg)
+                    .add_modifier(Modifier::BOLD),
+            );
+        let mut list_state = ListState::default().with_selected((is_selected && !column.cards.is_empty()).then_some(selected_card));
+        frame.render_stateful_widget(list, *column_area, &mut list_state);
+    }
+}
+
+fn draw_delete_confirmation(frame: &mut Frame, app: &App, message: &str) {
+    let text = format!("{}\n\ny / Enter: delete    n / Esc: cancel", message);
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirm Delete ")
+                .style(Style::default().fg(app.theme().error))
+        )
+        .style(Style::default().fg(app.theme().text))
+        .wrap(ratatui::widgets::Wrap { trim: true });
+
+    let area = centered_rect(50, 20, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_conflict(frame: &mut Frame, app: &App) {
+    let text = format!(
+        "{} was changed by another program while you had unsaved changes.\n\n\
+         r: reload it (u brings your changes back)\n\
+         o: overwrite it with your version\n\
+         m: merge both; where the same items changed, both versions are kept",
+        app.todo_list.file_path
+    );
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" File Changed on Disk ")
+                .style(Style::default().fg(app.theme().error))
+        )
+        .style(Style::default().fg(app.theme().text))
+        .wrap(ratatui::widgets::Wrap { trim: true });
+
+    let area = centered_rect(60, 30, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
@@ -303,3 +1203,44 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ra
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(rows: &[Vec<Span>]) -> Vec<String> {
+        rows.iter().map(|row| row.iter().map(|span| span.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn test_wrap_spans_between_words() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let spans = vec![Span::raw("Call the "), Span::styled("plumber", bold), Span::raw(" about the sink")];
+        let rows = wrap_spans(spans, 12);
+        assert_eq!(texts(&rows), ["Call the ", "plumber ", "about the ", "sink"]);
+        assert_eq!(rows[1][0].style, bold);
+        assert_eq!(texts(&wrap_spans(vec![Span::raw("Short")], 12)), ["Short"]);
+    }
+
+    #[test]
+    fn test_wrap_spans_breaks_long_words() {
+        let rows = wrap_spans(vec![Span::raw("see https://example.com/x")], 8);
+        assert_eq!(texts(&rows), ["see ", "https://", "example.", "com/x"]);
+    }
+
+    #[test]
+    fn test_content_spans_with_nested_reference() {
+        let spans = content_spans("See [[Call (#bob) later]]", Style::default(), &Theme::default(), true);
+        assert_eq!(texts(&[spans]), ["See [[Call (#bob) later]]"]);
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        assert_eq!(line_number(LineNumbers::Off, 3, Some(1)), None);
+        assert_eq!(line_number(LineNumbers::Absolute, 3, Some(1)), Some(4));
+        assert_eq!(line_number(LineNumbers::Relative, 3, Some(1)), Some(2));
+        assert_eq!(line_number(LineNumbers::Relative, 0, Some(1)), Some(1));
+        assert_eq!(line_number(LineNumbers::Relative, 1, Some(1)), Some(2));
+        assert_eq!(line_number(LineNumbers::Relative, 3, None), Some(4));
+    }
+}
@@ -1,34 +1,420 @@
+use todo_core::models::{ListItem, TodoList};
 use crate::tui::state::AppState;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 
 pub struct UndoManager {
-    pub undo_stack: Vec<AppState>,
+    undo_stack: History,
+    redo_stack: History,
+    // Undo steps kept before the oldest is forgotten
+    limit: usize,
+    // While a group is open only the first snapshot is kept, so a whole user
+    // gesture becomes a single undo step
+    group_depth: usize,
+    pending: Option<AppState>,
 }
 
 impl UndoManager {
-    pub fn new() -> Self {
+    pub fn new(limit: usize) -> Self {
         Self {
-            undo_stack: Vec::new(),
+            undo_stack: History::default(),
+            redo_stack: History::default(),
+            limit,
+            group_depth: 0,
+            pending: None,
         }
     }
 
+    /// Hands over the history, e.g. to keep it while another file is open,
+    /// leaving this manager empty
+    pub fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::new(self.limit))
+    }
+
+    /// Whether `save_state` would keep a snapshot taken now; inside a group
+    /// only the first one is kept, so later ones needn't be taken at all
+    pub fn wants_snapshot(&self) -> bool {
+        self.group_depth == 0 || self.pending.is_none()
+    }
+
     pub fn save_state(&mut self, state: AppState) {
+        if self.group_depth > 0 {
+            if self.pending.is_none() {
+                self.pending = Some(state);
+            }
+            return;
+        }
+        self.push_state(state);
+    }
+
+    fn push_state(&mut self, state: AppState) {
         self.undo_stack.push(state);
-        
-        // Limit undo stack to 20 items
-        if self.undo_stack.len() > 20 {
-            self.undo_stack.remove(0);
+        // A new change invalidates anything that was undone before it
+        self.redo_stack.clear();
+        self.undo_stack.keep_newest(self.limit);
+    }
+
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Closes a group, recording its snapshot as one undo step unless the list
+    /// ended up unchanged.
+    pub fn end_group(&mut self, current: &TodoList) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth > 0 {
+            return;
+        }
+        if let Some(state) = self.pending.take()
+            && state.todo_list.items != current.items
+        {
+            self.push_state(state);
+        }
+    }
+
+    /// Drops the most recent undo step, e.g. when adding a new item is cancelled.
+    /// Inside a group (a macro replay) that step is still pending and the
+    /// stack's top belongs to an earlier change, so it's left for `end_group`,
+    /// which drops it if the list is back to how it was.
+    pub fn discard_last(&mut self) {
+        if self.group_depth > 0 && self.pending.is_some() {
+            return;
+        }
+        self.undo_stack.pop();
+    }
+
+    pub fn undo(&mut self, current: AppState) -> Option<AppState> {
+        let state = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        Some(state)
+    }
+
+    pub fn redo(&mut self, current: AppState) -> Option<AppState> {
+        let state = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        Some(state)
+    }
+
+    /// Writes the undo history next to the TODO file, together with `current`
+    /// so the next session can tell whether the file changed in between
+    pub fn save(&mut self, todo_path: &str, current: AppState) -> Result<()> {
+        let path = history_path(todo_path);
+        self.undo_stack.push(current);
+        let json = serde_json::to_string(&self.undo_stack);
+        self.undo_stack.pop();
+        fs::write(&path, json?).with_context(|| format!("Failed to write undo history: {}", path.display()))
+    }
+
+    /// Picks up the history saved by the last session, unless the file has
+    /// changed since or there is none
+    pub fn load(&mut self, todo_path: &str, current: &TodoList) -> Result<()> {
+        let path = history_path(todo_path);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read undo history: {}", path.display())),
+        };
+        let mut history: History = serde_json::from_str(&json).with_context(|| format!("Invalid undo history: {}", path.display()))?;
+        if history.pop().is_none_or(|saved| saved.todo_list.items != current.items) {
+            return Ok(());
+        }
+        // The path isn't part of the saved states
+        if let Some(newest) = history.newest.as_mut() {
+            newest.todo_list.file_path = current.file_path.clone();
+        }
+        history.keep_newest(self.limit);
+        self.undo_stack = history;
+        self.redo_stack.clear();
+        Ok(())
+    }
+}
+
+/// Where the undo history of the TODO file at `todo_path` is kept
+pub fn history_path(todo_path: &str) -> PathBuf {
+    let path = Path::new(todo_path);
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}.undo.json", file_name))
+}
+
+// A stack of states where only the newest is stored whole. Each older one is
+// kept as the change from the state after it, so a step costs memory for the
+// items it changed rather than for the whole list.
+#[derive(Default, Serialize, Deserialize)]
+struct History {
+    newest: Option<AppState>,
+    // Oldest first; the last one applies to `newest`
+    older: Vec<StateDiff>,
+}
+
+impl History {
+    fn len(&self) -> usize {
+        self.older.len() + usize::from(self.newest.is_some())
+    }
+
+    fn push(&mut self, state: AppState) {
+        if let Some(previous) = self.newest.take() {
+            self.older.push(StateDiff::between(&state, &previous));
         }
+        self.newest = Some(state);
     }
 
-    pub fn undo(&mut self) -> Option<AppState> {
-        self.undo_stack.pop()
+    fn pop(&mut self) -> Option<AppState> {
+        let state = self.newest.take()?;
+        self.newest = self.older.pop().map(|diff| diff.apply(&state));
+        Some(state)
+    }
+
+    fn keep_newest(&mut self, count: usize) {
+        while self.len() > count {
+            if self.older.is_empty() {
+                self.newest = None;
+            } else {
+                self.older.remove(0);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.newest = None;
+        self.older.clear();
+    }
+}
+
+// How to get from one state to another: the items between a shared start and
+// a shared end are replaced
+#[derive(Serialize, Deserialize)]
+struct StateDiff {
+    start: usize,
+    removed: usize,
+    items: Vec<ListItem>,
+    selected_index: usize,
+    selected_items: HashSet<usize>,
+}
+
+impl StateDiff {
+    fn between(from: &AppState, to: &AppState) -> Self {
+        let (from_items, to_items) = (&from.todo_list.items, &to.todo_list.items);
+        let start = from_items.iter().zip(to_items).take_while(|(a, b)| a == b).count();
+        let end = from_items[start..]
+            .iter()
+            .rev()
+            .zip(to_items[start..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Self {
+            start,
+            removed: from_items.len() - start - end,
+            items: to_items[start..to_items.len() - end].to_vec(),
+            selected_index: to.selected_index,
+            selected_items: to.selected_items.clone(),
+        }
     }
 
+    fn apply(&self, base: &AppState) -> AppState {
+        let base_items = &base.todo_list.items;
+        let mut items = Vec::with_capacity(base_items.len() - self.removed + self.items.len());
+        items.extend_from_slice(&base_items[..self.start]);
+        items.extend_from_slice(&self.items);
+        items.extend_from_slice(&base_items[self.start + self.removed..]);
+        let todo_list = TodoList {
+            items,
+            file_path: base.todo_list.file_path.clone(),
+            indent_style: base.todo_list.indent_style,
+            line_ending: base.todo_list.line_ending,
+            trailing_newline: base.todo_list.trailing_newline,
+        };
+        AppState::new(todo_list, self.selected_index, self.selected_items.clone())
+    }
 }
 
 pub trait UndoableApp {
     fn save_current_state(&mut self);
     fn restore_state(&mut self, state: AppState) -> Result<()>;
     fn perform_undo(&mut self) -> Result<()>;
-}
\ No newline at end of file
+    fn perform_redo(&mut self) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(contents: &[&str]) -> AppState {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        for content in contents {
+            todo_list.add_item(ListItem::new_todo(content.to_string(), false, 0));
+        }
+        AppState::new(todo_list, 0, HashSet::new())
+    }
+
+    fn contents(state: &AppState) -> Vec<&str> {
+        state.todo_list.items.iter().map(ListItem::content).collect()
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.save_state(state_with(&["A"]));
+
+        let restored = undo_manager.undo(state_with(&["A", "B"])).unwrap();
+        assert_eq!(restored.todo_list.items.len(), 1);
+
+        let redone = undo_manager.redo(restored).unwrap();
+        assert_eq!(redone.todo_list.items.len(), 2);
+        assert_eq!(undo_manager.undo_stack.len(), 1);
+        assert!(undo_manager.redo(redone).is_none());
+    }
+
+    #[test]
+    fn test_new_change_clears_redo() {
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.save_state(state_with(&["A"]));
+        undo_manager.undo(state_with(&["A", "B"]));
+        assert_eq!(undo_manager.redo_stack.len(), 1);
+
+        undo_manager.save_state(state_with(&["A"]));
+        assert_eq!(undo_manager.redo_stack.len(), 0);
+    }
+
+    #[test]
+    fn test_group_records_single_step() {
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.begin_group();
+        assert!(undo_manager.wants_snapshot());
+        undo_manager.save_state(state_with(&["A"]));
+        assert!(!undo_manager.wants_snapshot());
+        undo_manager.save_state(state_with(&["A", "B"]));
+        undo_manager.end_group(&state_with(&["A", "B", "C"]).todo_list);
+
+        assert_eq!(undo_manager.undo_stack.len(), 1);
+        let restored = undo_manager.undo(state_with(&["A", "B", "C"])).unwrap();
+        assert_eq!(restored.todo_list.items.len(), 1);
+    }
+
+    #[test]
+    fn test_group_without_changes_is_dropped() {
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.begin_group();
+        undo_manager.save_state(state_with(&["A"]));
+        undo_manager.end_group(&state_with(&["A"]).todo_list);
+
+        assert_eq!(undo_manager.undo_stack.len(), 0);
+    }
+
+    #[test]
+    fn test_discard_inside_group() {
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.save_state(state_with(&["A"]));
+
+        // Adding an item and cancelling it, both in one replay
+        undo_manager.begin_group();
+        undo_manager.save_state(state_with(&["A", "B"]));
+        undo_manager.discard_last();
+        undo_manager.end_group(&state_with(&["A", "B"]).todo_list);
+        assert_eq!(undo_manager.undo_stack.len(), 1);
+
+        // A change before the cancelled add is still undone in one step
+        undo_manager.begin_group();
+        undo_manager.save_state(state_with(&["A", "B"]));
+        undo_manager.save_state(state_with(&["A", "C"]));
+        undo_manager.discard_last();
+        undo_manager.end_group(&state_with(&["A", "C"]).todo_list);
+        assert_eq!(undo_manager.undo_stack.len(), 2);
+        let restored = undo_manager.undo(state_with(&["A", "C"])).unwrap();
+        assert_eq!(contents(&restored), ["A", "B"]);
+
+        // Outside a group the add's step is on the stack
+        undo_manager.discard_last();
+        assert_eq!(undo_manager.undo_stack.len(), 0);
+    }
+
+    #[test]
+    fn test_many_steps_round_trip() {
+        let states = [
+            state_with(&["A", "B", "C"]),
+            state_with(&["A", "B2", "C"]),
+            state_with(&["A", "B2"]),
+            state_with(&["X", "A", "B2"]),
+            state_with(&[]),
+            state_with(&["Y"]),
+        ];
+        let mut undo_manager = UndoManager::new(20);
+        for state in &states[..states.len() - 1] {
+            undo_manager.save_state(state.clone());
+        }
+
+        let mut current = states[states.len() - 1].clone();
+        for expected in states.iter().rev().skip(1) {
+            current = undo_manager.undo(current).unwrap();
+            assert_eq!(contents(&current), contents(expected));
+        }
+        assert!(undo_manager.undo(current.clone()).is_none());
+        for expected in states.iter().skip(1) {
+            current = undo_manager.redo(current).unwrap();
+            assert_eq!(contents(&current), contents(expected));
+        }
+    }
+
+    #[test]
+    fn test_limit_forgets_oldest_steps() {
+        let mut undo_manager = UndoManager::new(2);
+        for content in ["A", "B", "C"] {
+            undo_manager.save_state(state_with(&[content]));
+        }
+        assert_eq!(undo_manager.undo_stack.len(), 2);
+        let restored = undo_manager.undo(state_with(&["D"])).unwrap();
+        assert_eq!(contents(&restored), ["C"]);
+        let restored = undo_manager.undo(restored).unwrap();
+        assert_eq!(contents(&restored), ["B"]);
+        assert!(undo_manager.undo(restored).is_none());
+
+        let mut disabled = UndoManager::new(0);
+        disabled.save_state(state_with(&["A"]));
+        assert_eq!(disabled.undo_stack.len(), 0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("todo_undo_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let todo_path = dir.join("TODO.md").to_string_lossy().to_string();
+        let with_path = |contents: &[&str]| {
+            let mut state = state_with(contents);
+            state.todo_list.file_path = todo_path.clone();
+            state
+        };
+
+        let mut undo_manager = UndoManager::new(20);
+        undo_manager.save_state(with_path(&["A"]));
+        undo_manager.save_state(with_path(&["A", "B"]));
+        undo_manager.save(&todo_path, with_path(&["A", "B", "C"])).unwrap();
+        assert_eq!(undo_manager.undo_stack.len(), 2);
+
+        let mut next_session = UndoManager::new(20);
+        next_session.load(&todo_path, &with_path(&["A", "B", "C"]).todo_list).unwrap();
+        let restored = next_session.undo(with_path(&["A", "B", "C"])).unwrap();
+        assert_eq!(contents(&restored), ["A", "B"]);
+        assert_eq!(restored.todo_list.file_path, todo_path);
+        assert_eq!(contents(&next_session.undo(restored).unwrap()), ["A"]);
+
+        // A file changed by something else keeps its history from applying
+        let mut changed = UndoManager::new(20);
+        changed.load(&todo_path, &with_path(&["Other"]).todo_list).unwrap();
+        assert_eq!(changed.undo_stack.len(), 0);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_take_keeps_limit() {
+        let mut undo_manager = UndoManager::new(3);
+        undo_manager.save_state(state_with(&["A"]));
+        let taken = undo_manager.take();
+        assert_eq!(taken.undo_stack.len(), 1);
+        assert_eq!(undo_manager.undo_stack.len(), 0);
+        assert_eq!(undo_manager.limit, 3);
+    }
+}
@@ -0,0 +1,54 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+pub struct FileWatcher {
+    // Kept alive for as long as we want to receive events
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<notify::Result<Event>>,
+    file_path: PathBuf,
+}
+
+impl FileWatcher {
+    pub fn new(file_path: &str) -> notify::Result<Self> {
+        let file_path = Path::new(file_path)
+            .canonicalize()
+            .unwrap_or_else(|_| PathBuf::from(file_path));
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        // Watch the parent directory rather than the file itself, since many
+        // editors save by writing a new file and renaming it over the old one
+        let watch_dir = file_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        watcher.watch(watch_dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+            file_path,
+        })
+    }
+
+    /// Drains pending events and reports whether any of them touched the watched file.
+    pub fn has_changed(&self) -> bool {
+        let mut changed = false;
+        for event in self.receiver.try_iter().flatten() {
+            if Self::is_relevant(&event) && event.paths.iter().any(|path| self.matches(path)) {
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn is_relevant(event: &Event) -> bool {
+        event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove()
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        // Only the watched directory is observed, so comparing names is enough
+        path.file_name() == self.file_path.file_name()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::tui::navigation::ItemAnchor;
+use std::ops::RangeInclusive;
+use todo_core::models::ListItem;
+use todo_core::structure::ItemCreator;
+
+/// Narrows the view to one heading's section, like org-mode's narrowing.
+/// Everything outside the section is hidden until the view is widened again.
+pub struct ZoomState {
+    heading: Option<ItemAnchor>,
+}
+
+impl Default for ZoomState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZoomState {
+    pub fn new() -> Self {
+        Self { heading: None }
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.heading.is_some()
+    }
+
+    /// Zooms into the section containing `index`; fails above the first heading
+    pub fn zoom(&mut self, items: &[ListItem], index: usize) -> bool {
+        let heading = ItemCreator::find_current_heading_context(items, index);
+        if heading == 0 {
+            return false;
+        }
+        self.heading = ItemAnchor::new(items, heading - 1);
+        self.heading.is_some()
+    }
+
+    pub fn unzoom(&mut self) {
+        self.heading = None;
+    }
+
+    /// Re-locates the heading after the list changed. A heading that was
+    /// renamed in place is still followed; one that's gone ends the zoom.
+    pub fn refresh(&mut self, items: &[ListItem]) {
+        let Some(anchor) = &mut self.heading else {
+            return;
+        };
+        if anchor.resolve(items).is_none() {
+            let index = anchor.index();
+            self.heading = match items.get(index) {
+                Some(ListItem::Heading { .. }) => ItemAnchor::new(items, index),
+                _ => None,
+            };
+        }
+    }
+
+    /// The zoomed heading and everything in its section
+    pub fn range(&self, items: &[ListItem]) -> Option<RangeInclusive<usize>> {
+        let index = self.heading.as_ref()?.index();
+        let (start, end) = ItemCreator::get_fold_range(items, index);
+        Some(start..=end)
+    }
+
+    /// Whether the item at `index` can be changed from inside the zoom: it's
+    /// in the section, and isn't the zoomed heading itself
+    pub fn contains(&self, items: &[ListItem], index: usize) -> bool {
+        match self.range(items) {
+            Some(range) => index > *range.start() && index <= *range.end(),
+            None => true,
+        }
+    }
+
+    /// The zoomed heading and the headings it's nested under, outermost first
+    pub fn breadcrumb(&self, items: &[ListItem]) -> Vec<String> {
+        let Some(index) = self.heading.as_ref().map(ItemAnchor::index) else {
+            return Vec::new();
+        };
+        ItemCreator::heading_trail(items, index)
+            .into_iter()
+            .map(|heading| items[heading].content().to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Inbox item".to_string(), false, 0),
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Meetings".to_string(), 2),
+            ListItem::new_todo("Standup".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Laundry".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_zoom_range_and_breadcrumb() {
+        let items = create_test_items();
+        let mut zoom = ZoomState::new();
+        assert!(!zoom.zoom(&items, 0));
+        assert!(!zoom.is_zoomed());
+
+        assert!(zoom.zoom(&items, 2));
+        assert_eq!(zoom.range(&items), Some(1..=4));
+        assert_eq!(zoom.breadcrumb(&items), vec!["Work"]);
+        assert!(zoom.contains(&items, 4));
+        assert!(!zoom.contains(&items, 1));
+        assert!(!zoom.contains(&items, 5));
+
+        assert!(zoom.zoom(&items, 4));
+        assert_eq!(zoom.range(&items), Some(3..=4));
+        assert_eq!(zoom.breadcrumb(&items), vec!["Work", "Meetings"]);
+
+        zoom.unzoom();
+        assert_eq!(zoom.range(&items), None);
+        assert!(zoom.contains(&items, 0));
+    }
+
+    #[test]
+    fn test_refresh_follows_the_heading() {
+        let mut items = create_test_items();
+        let mut zoom = ZoomState::new();
+        zoom.zoom(&items, 6);
+
+        items.insert(0, ListItem::new_todo("New".to_string(), false, 0));
+        zoom.refresh(&items);
+        assert_eq!(zoom.range(&items), Some(6..=7));
+
+        *items[6].content_mut() = "House".to_string();
+        zoom.refresh(&items);
+        assert_eq!(zoom.breadcrumb(&items), vec!["House"]);
+
+        items.truncate(5);
+        zoom.refresh(&items);
+        assert!(!zoom.is_zoomed());
+    }
+}
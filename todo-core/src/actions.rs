@@ -0,0 +1,1518 @@
+//! In-place edits on a list of items.
+
+use crate::metadata;
+use crate::models::{ListItem, TodoState};
+use crate::structure::ItemCreator;
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Markdown has six levels of heading
+pub const MAX_HEADING_LEVEL: usize = 6;
+
+/// Heading of the section that completed todos are archived under
+pub const ARCHIVE_HEADING: &str = "Archive";
+
+/// What [`ItemActions::sort_section`] orders todos by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Open todos first, then done and cancelled ones
+    Status,
+    /// By content, ignoring case
+    Alphabetical,
+    /// Earliest due date first, undated todos last
+    DueDate,
+    /// Highest priority first
+    Priority,
+}
+
+impl SortKey {
+    fn compare(self, a: &ListItem, b: &ListItem) -> Ordering {
+        match self {
+            SortKey::Status => a.is_completed().cmp(&b.is_completed()),
+            SortKey::Alphabetical => a.content().to_lowercase().cmp(&b.content().to_lowercase()),
+            SortKey::DueDate => {
+                let due = |item: &ListItem| {
+                    let date = metadata::due_date(item.content());
+                    (date.is_none(), date)
+                };
+                due(a).cmp(&due(b))
+            }
+            SortKey::Priority => metadata::priority(a.content()).cmp(&metadata::priority(b.content())),
+        }
+    }
+}
+
+/// Edits that rearrange or change items in place
+pub struct ItemActions;
+
+impl ItemActions {
+    /// Checks an open, in-progress or deferred todo, and reopens a done or
+    /// cancelled one
+    pub fn toggle_todo_completion(items: &mut [ListItem], index: usize) -> bool {
+        if let Some(ListItem::Todo { state, .. }) = items.get_mut(index) {
+            *state = if state.is_closed() { TodoState::Open } else { TodoState::Done };
+            return true;
+        }
+        false
+    }
+
+    /// Toggles the selected todos together: if any of them is still open they
+    /// all get checked, otherwise they're all reopened
+    pub fn toggle_selected_completion(items: &mut [ListItem], selected_indices: &HashSet<usize>) -> bool {
+        let selected_todos = || {
+            selected_indices
+                .iter()
+                .filter(|&&index| matches!(items.get(index), Some(ListItem::Todo { .. })))
+        };
+        if selected_todos().next().is_none() {
+            return false;
+        }
+        let new_state = if selected_todos().all(|&index| items[index].is_completed()) {
+            TodoState::Open
+        } else {
+            TodoState::Done
+        };
+
+        for &index in selected_indices {
+            if let Some(ListItem::Todo { state, .. }) = items.get_mut(index) {
+                *state = new_state;
+            }
+        }
+        true
+    }
+
+    /// Dates the todos that were closed since `before` (a copy of the list from
+    /// before a toggle) with `today`, and clears the date of reopened ones
+    pub fn record_done_dates(items: &mut [ListItem], before: &[ListItem], today: NaiveDate) {
+        if items.len() != before.len() {
+            return;
+        }
+        for (item, previous) in items.iter_mut().zip(before) {
+            if !matches!(item, ListItem::Todo { .. }) || item.is_completed() == previous.is_completed() {
+                continue;
+            }
+            let date = item.is_completed().then_some(today);
+            *item.content_mut() = metadata::set_done_date(item.content(), date);
+        }
+    }
+
+    /// Moves a todo on to the next checkbox state (open, in progress, done,
+    /// cancelled, deferred)
+    pub fn cycle_todo_state(items: &mut [ListItem], index: usize) -> bool {
+        if let Some(ListItem::Todo { state, .. }) = items.get_mut(index) {
+            *state = state.next();
+            return true;
+        }
+        false
+    }
+
+    /// Puts a todo in `new_state`. Returns whether it's a todo that wasn't in
+    /// that state already.
+    pub fn set_todo_state(items: &mut [ListItem], index: usize, new_state: TodoState) -> bool {
+        match items.get_mut(index) {
+            Some(ListItem::Todo { state, .. }) if *state != new_state => {
+                *state = new_state;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Toggles the todo at `index` and sets every todo nested under it to the
+    /// same state
+    pub fn toggle_block_completion(items: &mut [ListItem], index: usize) -> bool {
+        let Some(ListItem::Todo { state, .. }) = items.get(index) else {
+            return false;
+        };
+        let new_state = if state.is_closed() { TodoState::Open } else { TodoState::Done };
+        
+        let (start, end) = ItemCreator::get_block_range(items, index);
+        for item in &mut items[start..=end] {
+            if let ListItem::Todo { state, .. } = item {
+                *state = new_state;
+            }
+        }
+        true
+    }
+
+    /// Brings the todos that the item at `index` is nested under in line with
+    /// their children: a parent is completed once all of its child todos are,
+    /// and un-completed when one of them isn't. Returns whether anything changed.
+    pub fn update_parent_completion(items: &mut [ListItem], index: usize) -> bool {
+        let mut changed = false;
+        let mut current = index;
+        while let Some(parent) = ItemCreator::find_parent(items, current) {
+            let (start, end) = ItemCreator::get_block_range(items, parent);
+            let all_done = items[start + 1..=end]
+                .iter()
+                .filter(|item| matches!(item, ListItem::Todo { .. }))
+                .all(|item| item.is_completed());
+            
+            let Some(ListItem::Todo { state, .. }) = items.get_mut(parent) else {
+                break;
+            };
+            // Anything further up already agrees with this parent
+            if state.is_closed() == all_done {
+                break;
+            }
+            *state = if all_done { TodoState::Done } else { TodoState::Open };
+            changed = true;
+            current = parent;
+        }
+        changed
+    }
+
+    pub fn move_single_item_up(items: &mut [ListItem], index: usize) -> Option<usize> {
+        if index > 0 && index < items.len() {
+            items.swap(index - 1, index);
+            Some(index - 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn move_single_item_down(items: &mut [ListItem], index: usize) -> Option<usize> {
+        if index < items.len().saturating_sub(1) {
+            items.swap(index, index + 1);
+            Some(index + 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn indent_block(items: &mut [ListItem], start_index: usize) -> bool {
+        if start_index >= items.len() {
+            return false;
+        }
+
+        // Get the block range to indent all items in the subtree
+        let (block_start, block_end) = ItemCreator::get_block_range(items, start_index);
+        
+        // First calculate max indent level for the parent item, looking past
+        // blank lines and other unstructured content
+        let previous_item = items[..block_start]
+            .iter()
+            .rev()
+            .find(|item| !matches!(item, ListItem::Raw { .. }));
+        let max_indent = match previous_item {
+            Some(ListItem::Todo { indent_level: prev_indent, .. }) => prev_indent + 1,
+            Some(ListItem::Note { indent_level: prev_indent, .. }) => prev_indent + 1,
+            Some(ListItem::Heading { .. }) => 1, // Can indent under headings
+            Some(ListItem::Raw { .. }) | None => 0, // First item can't be indented
+        };
+        
+        // Check if the parent item can be indented
+        if let Some(item) = items.get(block_start) {
+            let parent_indent = match item {
+                ListItem::Todo { indent_level, .. } => *indent_level,
+                ListItem::Note { indent_level, .. } => *indent_level,
+                ListItem::Heading { .. } => return false, // Can't indent headings
+                ListItem::Raw { .. } => return false,
+            };
+
+            if parent_indent < max_indent {
+                // Indent the entire block
+                for i in block_start..=block_end {
+                    if let Some(item) = items.get_mut(i) {
+                        match item {
+                            ListItem::Todo { indent_level, .. } => {
+                                *indent_level += 1;
+                            }
+                            ListItem::Note { indent_level, .. } => {
+                                *indent_level += 1;
+                            }
+                            ListItem::Heading { .. } | ListItem::Raw { .. } => {
+                                // Don't indent headings or unstructured lines
+                            }
+                        }
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Changes the level of the heading at `index` by `delta` (negative
+    /// promotes, `##` to `#`), and with `with_subheadings` the level of every
+    /// heading in its section too. Nothing changes if any level would leave
+    /// 1..=6. Returns whether anything changed.
+    pub fn shift_heading_level(items: &mut [ListItem], index: usize, delta: isize, with_subheadings: bool) -> bool {
+        if !matches!(items.get(index), Some(ListItem::Heading { .. })) || delta == 0 {
+            return false;
+        }
+        let end = if with_subheadings {
+            ItemCreator::get_fold_range(items, index).1
+        } else {
+            index
+        };
+
+        let new_level = |level: usize| {
+            level
+                .checked_add_signed(delta)
+                .filter(|level| (1..=MAX_HEADING_LEVEL).contains(level))
+        };
+        let fits = items[index..=end].iter().all(|item| match item {
+            ListItem::Heading { level, .. } => new_level(*level).is_some(),
+            _ => true,
+        });
+        if !fits {
+            return false;
+        }
+
+        for item in &mut items[index..=end] {
+            if let ListItem::Heading { level, .. } = item {
+                *level = new_level(*level).unwrap_or(*level);
+            }
+        }
+        true
+    }
+
+    pub fn unindent_block(items: &mut [ListItem], start_index: usize) -> bool {
+        if start_index >= items.len() {
+            return false;
+        }
+
+        // Get the block range to unindent all items in the subtree
+        let (block_start, block_end) = ItemCreator::get_block_range(items, start_index);
+        
+        // Check if the parent item can be unindented
+        if let Some(item) = items.get(block_start) {
+            let parent_indent = match item {
+                ListItem::Todo { indent_level, .. } => *indent_level,
+                ListItem::Note { indent_level, .. } => *indent_level,
+                ListItem::Heading { .. } => return false, // Can't unindent headings
+                ListItem::Raw { .. } => return false,
+            };
+
+            if parent_indent > 0 {
+                // Unindent the entire block
+                for i in block_start..=block_end {
+                    if let Some(item) = items.get_mut(i) {
+                        match item {
+                            ListItem::Todo { indent_level, .. } => {
+                                if *indent_level > 0 {
+                                    *indent_level -= 1;
+                                }
+                            }
+                            ListItem::Note { indent_level, .. } => {
+                                if *indent_level > 0 {
+                                    *indent_level -= 1;
+                                }
+                            }
+                            ListItem::Heading { .. } | ListItem::Raw { .. } => {
+                                // Don't unindent headings or unstructured lines
+                            }
+                        }
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Indents each selected todo or note by one level, as far as the item
+    /// above it allows. Items are processed top to bottom so a selected run of
+    /// siblings can be indented together.
+    pub fn indent_selected_items(items: &mut [ListItem], selected_indices: &HashSet<usize>) -> bool {
+        let mut indices: Vec<usize> = selected_indices.iter().cloned().collect();
+        indices.sort();
+
+        let mut changed = false;
+        for index in indices {
+            if index >= items.len() {
+                continue;
+            }
+            let previous_item = items[..index]
+                .iter()
+                .rev()
+                .find(|item| !matches!(item, ListItem::Raw { .. }));
+            let max_indent = match previous_item {
+                Some(ListItem::Todo { indent_level, .. }) | Some(ListItem::Note { indent_level, .. }) => indent_level + 1,
+                Some(ListItem::Heading { .. }) => 1,
+                Some(ListItem::Raw { .. }) | None => 0,
+            };
+            if let ListItem::Todo { indent_level, .. } | ListItem::Note { indent_level, .. } = &mut items[index]
+                && *indent_level < max_indent
+            {
+                *indent_level += 1;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn unindent_selected_items(items: &mut [ListItem], selected_indices: &HashSet<usize>) -> bool {
+        let mut changed = false;
+        for &index in selected_indices {
+            if let Some(ListItem::Todo { indent_level, .. } | ListItem::Note { indent_level, .. }) = items.get_mut(index)
+                && *indent_level > 0
+            {
+                *indent_level -= 1;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn move_selected_items_to_position(
+        items: &mut Vec<ListItem>,
+        selected_indices: &HashSet<usize>,
+        target_position: usize,
+    ) -> Option<usize> {
+        if selected_indices.is_empty() {
+            return None;
+        }
+
+        // Get indices in sorted order (highest to lowest for removal)
+        let mut indices: Vec<usize> = selected_indices.iter().cloned().collect();
+        indices.sort_by(|a, b| b.cmp(a)); // Sort descending
+
+        // Extract the selected items
+        let mut items_to_move = Vec::new();
+        for &index in &indices {
+            if index < items.len() {
+                items_to_move.push(items.remove(index));
+            }
+        }
+        
+        // Reverse to maintain original order when inserting
+        items_to_move.reverse();
+
+        // Calculate insertion point (adjust for removed items)
+        // Start with position after the current cursor (insert below)
+        let mut insertion_point = target_position + 1;
+        for &removed_index in &indices {
+            if removed_index < insertion_point {
+                insertion_point = insertion_point.saturating_sub(1);
+            }
+        }
+
+        // Insert items below the cursor position
+        for (i, item) in items_to_move.into_iter().enumerate() {
+            items.insert(insertion_point + i, item);
+        }
+
+        Some(insertion_point)
+    }
+
+    /// Splits the todo or note at `index` in two at byte `at` of its content.
+    /// The second half becomes a new item of the same kind and indentation
+    /// right below; a todo's half starts open. Returns the new item's index.
+    pub fn split_item(items: &mut Vec<ListItem>, index: usize, at: usize) -> Option<usize> {
+        let item = items.get_mut(index)?;
+        let indent = item.indent_level()?;
+        if !item.content().is_char_boundary(at) {
+            return None;
+        }
+
+        let content = item.content_mut();
+        let rest = content.split_off(at).trim_start().to_string();
+        content.truncate(content.trim_end().len());
+        let new_item = match item {
+            ListItem::Todo { .. } => ListItem::new_todo(rest, false, indent),
+            _ => ListItem::new_note(rest, indent),
+        };
+        items.insert(index + 1, new_item);
+        Some(index + 1)
+    }
+
+    /// Appends the content of the todo or note at `index` to the one right
+    /// above it and removes it. Returns the index of the merged item and the
+    /// byte offset where the joined text starts.
+    pub fn join_with_previous(items: &mut Vec<ListItem>, index: usize) -> Option<(usize, usize)> {
+        let previous = index.checked_sub(1)?;
+        items.get(index)?.indent_level()?;
+        items[previous].indent_level()?;
+
+        let removed = items.remove(index);
+        let content = items[previous].content_mut();
+        let joined_at = if content.is_empty() || removed.content().is_empty() {
+            content.len()
+        } else {
+            content.push(' ');
+            content.len()
+        };
+        content.push_str(removed.content());
+        Some((previous, joined_at))
+    }
+
+    /// Inserts a copy of the item and everything nested under it right after
+    /// the block, returning the index of the copy.
+    pub fn duplicate_block(items: &mut Vec<ListItem>, index: usize) -> Option<usize> {
+        if index >= items.len() {
+            return None;
+        }
+
+        let (start, end) = ItemCreator::get_block_range(items, index);
+        let copy: Vec<ListItem> = items[start..=end].to_vec();
+        items.splice(end + 1..end + 1, copy);
+        Some(end + 1)
+    }
+
+    /// Moves the items at `indices`, each with everything nested under it, to
+    /// the end of the heading's own items (before any sub-heading), keeping
+    /// their order. Indentation is shifted so the shallowest lands at the top
+    /// level. Headings can't be moved this way. Returns the new index of the
+    /// first moved item.
+    pub fn move_items_to_section(
+        items: &mut Vec<ListItem>,
+        indices: &HashSet<usize>,
+        heading_index: usize,
+    ) -> Option<usize> {
+        if !matches!(items.get(heading_index), Some(ListItem::Heading { .. })) {
+            return None;
+        }
+        let roots: Vec<usize> = indices
+            .iter()
+            .copied()
+            .filter(|&i| i < items.len() && !matches!(items[i], ListItem::Heading { .. }))
+            .collect();
+        let moved = ItemCreator::get_block_indices(items, &roots);
+        if moved.is_empty() {
+            return None;
+        }
+        let shift = moved.iter().filter_map(|&i| items[i].indent_level()).min().unwrap_or(0);
+
+        let section_end = items[heading_index + 1..]
+            .iter()
+            .position(|item| matches!(item, ListItem::Heading { .. }))
+            .map_or(items.len(), |offset| heading_index + 1 + offset);
+        // Blank lines before the next heading stay there
+        let mut insert_at = section_end;
+        while insert_at > heading_index + 1
+            && matches!(&items[insert_at - 1], ListItem::Raw { content } if content.trim().is_empty())
+        {
+            insert_at -= 1;
+        }
+        insert_at -= moved.iter().filter(|&&i| i < insert_at).count();
+
+        let mut block = Vec::with_capacity(moved.len());
+        for &i in moved.iter().rev() {
+            block.push(items.remove(i));
+        }
+        block.reverse();
+        for item in &mut block {
+            if let Some(level) = item.indent_level_mut() {
+                *level -= shift;
+            }
+        }
+        items.splice(insert_at..insert_at, block);
+        Some(insert_at)
+    }
+
+    /// Sorts the todos directly under the heading above `index` (up to the
+    /// next heading), moving each with everything nested under it. Notes and
+    /// other lines keep their places. Returns whether the order changed.
+    pub fn sort_section(items: &mut [ListItem], index: usize, key: SortKey) -> bool {
+        if index >= items.len() {
+            return false;
+        }
+        let start = ItemCreator::find_current_heading_context(items, index);
+        let end = items[start..]
+            .iter()
+            .position(|item| matches!(item, ListItem::Heading { .. }))
+            .map_or(items.len(), |offset| start + offset);
+
+        let mut blocks = Vec::new();
+        let mut i = start;
+        while i < end {
+            let (_, block_end) = ItemCreator::get_block_range(items, i);
+            blocks.push(i..block_end.min(end - 1) + 1);
+            i = block_end.min(end - 1) + 1;
+        }
+
+        let is_todo = |range: &Range<usize>| matches!(items[range.start], ListItem::Todo { .. });
+        let mut todo_blocks: Vec<Vec<ListItem>> = blocks
+            .iter()
+            .filter(|range| is_todo(range))
+            .map(|range| items[range.clone()].to_vec())
+            .collect();
+        // Stable, so todos with the same key keep their relative order
+        todo_blocks.sort_by(|a, b| key.compare(&a[0], &b[0]));
+
+        let mut sorted = todo_blocks.into_iter();
+        let reordered: Vec<ListItem> = blocks
+            .iter()
+            .flat_map(|range| {
+                if is_todo(range) {
+                    sorted.next().unwrap_or_default()
+                } else {
+                    items[range.clone()].to_vec()
+                }
+            })
+            .collect();
+
+        let changed = reordered[..] != items[start..end];
+        items[start..end].clone_from_slice(&reordered);
+        changed
+    }
+
+    /// Moves every done or cancelled top-level todo (with its nested items) to
+    /// the end of the archive section, creating the section if needed. Todos
+    /// already in the archive are left alone. With `done_before`, only todos
+    /// stamped as done before that date are moved. Returns how many were moved.
+    pub fn archive_completed(items: &mut Vec<ListItem>, done_before: Option<NaiveDate>) -> usize {
+        let archive = archive_section(items);
+        let mut kept = Vec::with_capacity(items.len());
+        let mut archived = Vec::new();
+        let mut count = 0;
+
+        let mut i = 0;
+        while i < items.len() {
+            let in_archive = archive.as_ref().is_some_and(|range| range.contains(&i));
+            let item = &items[i];
+            if !in_archive
+                && matches!(item, ListItem::Todo { .. })
+                && item.is_completed()
+                && done_before.is_none_or(|cutoff| metadata::done_date(item.content()).is_some_and(|date| date < cutoff))
+                && ItemCreator::find_parent(items, i).is_none()
+            {
+                let (_, end) = ItemCreator::get_block_range(items, i);
+                archived.extend_from_slice(&items[i..=end]);
+                count += 1;
+                i = end + 1;
+            } else {
+                kept.push(item.clone());
+                i += 1;
+            }
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        *items = kept;
+        let insert_at = match archive_section(items) {
+            Some(range) => range.end,
+            None => {
+                // Keep the new heading visually apart from the list above it
+                if items.last().is_some_and(|item| !item.content().trim().is_empty()) {
+                    items.push(ListItem::new_raw(String::new()));
+                }
+                items.push(ListItem::new_heading(ARCHIVE_HEADING.to_string(), 1));
+                items.len()
+            }
+        };
+        items.splice(insert_at..insert_at, archived);
+        count
+    }
+
+    /// Moves the open top-level todos (with their nested items) from dated
+    /// headings before `today` (`## 2024-05-01`) to the end of today's
+    /// section, adding today's heading if there isn't one: at the level of the
+    /// last day before, and on the side the days grow towards, with `section`
+    /// under it ahead of the todos. Returns how many todos were moved.
+    pub fn rollover(items: &mut Vec<ListItem>, today: NaiveDate, section: &[ListItem]) -> usize {
+        let past: Vec<Range<usize>> = dated_headings(items)
+            .into_iter()
+            .filter(|&(_, date, _)| date < today)
+            .map(|(heading, _, _)| heading + 1..ItemCreator::get_fold_range(items, heading).1 + 1)
+            .collect();
+        let mut kept = Vec::with_capacity(items.len());
+        let mut moved = Vec::new();
+        let mut count = 0;
+
+        let mut i = 0;
+        while i < items.len() {
+            let item = &items[i];
+            if past.iter().any(|range| range.contains(&i))
+                && matches!(item, ListItem::Todo { .. })
+                && !item.is_completed()
+                && ItemCreator::find_parent(items, i).is_none()
+            {
+                let (_, end) = ItemCreator::get_block_range(items, i);
+                moved.extend_from_slice(&items[i..=end]);
+                count += 1;
+                i = end + 1;
+            } else {
+                kept.push(item.clone());
+                i += 1;
+            }
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        *items = kept;
+        let dated = dated_headings(items);
+        if let Some(&(heading, _, _)) = dated.iter().find(|&&(_, date, _)| date == today) {
+            // After the section's last item, ahead of the blank lines that end it
+            let (_, mut end) = ItemCreator::get_fold_range(items, heading);
+            while end > heading && matches!(&items[end], ListItem::Raw { content } if content.trim().is_empty()) {
+                end -= 1;
+            }
+            items.splice(end + 1..end + 1, moved);
+            return count;
+        }
+
+        let latest = dated.iter().filter(|&&(_, date, _)| date < today).max_by_key(|&&(_, date, _)| date);
+        let Some(&(latest, _, level)) = latest else {
+            return count;
+        };
+        let newest_first = dated.first().zip(dated.last()).is_some_and(|(first, last)| first.1 > last.1);
+        let mut added = vec![ListItem::new_heading(today.format("%Y-%m-%d").to_string(), level)];
+        added.extend_from_slice(section);
+        added.extend(moved);
+        let insert_at = if newest_first { latest } else { ItemCreator::get_fold_range(items, latest).1 + 1 };
+        if insert_at < items.len() {
+            added.push(ListItem::new_raw(String::new()));
+        } else if items.last().is_some_and(|item| !item.content().trim().is_empty()) {
+            added.insert(0, ListItem::new_raw(String::new()));
+        }
+        items.splice(insert_at..insert_at, added);
+        count
+    }
+
+    /// Adds a heading called `name` with `section` under it, after the
+    /// section the item at `index` is in and at the same level (a top-level
+    /// heading ahead of the first one, if it's above every heading), set off
+    /// by blank lines. Returns where the heading went.
+    pub fn add_section(items: &mut Vec<ListItem>, index: usize, name: &str, section: Vec<ListItem>) -> usize {
+        let (insert_at, level) = match ItemCreator::heading_trail(items, index).last() {
+            Some(&heading) => match &items[heading] {
+                ListItem::Heading { level, .. } => (ItemCreator::get_fold_range(items, heading).1 + 1, *level),
+                _ => unreachable!("heading_trail only returns headings"),
+            },
+            // Above the first heading, at its level so it doesn't take the
+            // sections after it in
+            None => items
+                .iter()
+                .enumerate()
+                .find_map(|(index, item)| match item {
+                    ListItem::Heading { level, .. } => Some((index, *level)),
+                    _ => None,
+                })
+                .unwrap_or((items.len(), 1)),
+        };
+        let blank_before = insert_at > 0 && !items[insert_at - 1].content().trim().is_empty();
+        let mut added = Vec::new();
+        if blank_before {
+            added.push(ListItem::new_raw(String::new()));
+        }
+        added.push(ListItem::new_heading(name.to_string(), level));
+        added.extend(section);
+        if insert_at < items.len() {
+            added.push(ListItem::new_raw(String::new()));
+        }
+        items.splice(insert_at..insert_at, added);
+        insert_at + usize::from(blank_before)
+    }
+
+    pub fn delete_item(items: &mut Vec<ListItem>, index: usize) -> bool {
+        if index < items.len() {
+            // Check if the item is a Todo, Note or unstructured line (not a Heading)
+            match &items[index] {
+                ListItem::Todo { .. } | ListItem::Note { .. } | ListItem::Raw { .. } => {
+                    items.remove(index);
+                    true
+                }
+                ListItem::Heading { .. } => false, // Don't delete headings
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn delete_selected_items(items: &mut Vec<ListItem>, selected_indices: &HashSet<usize>) -> usize {
+        if selected_indices.is_empty() {
+            return 0;
+        }
+
+        // Get indices in sorted order (highest to lowest for removal)
+        let mut indices: Vec<usize> = selected_indices.iter().cloned().collect();
+        indices.sort_by(|a, b| b.cmp(a)); // Sort descending
+
+        let mut deleted_count = 0;
+        
+        // Remove items from highest index to lowest to avoid index shifting issues
+        for &index in &indices {
+            if index < items.len() {
+                // Check if the item is a Todo, Note or unstructured line (not a Heading)
+                match &items[index] {
+                    ListItem::Todo { .. } | ListItem::Note { .. } | ListItem::Raw { .. } => {
+                        items.remove(index);
+                        deleted_count += 1;
+                    }
+                    ListItem::Heading { .. } => {
+                        // Don't delete headings
+                    }
+                }
+            }
+        }
+        
+        deleted_count
+    }
+}
+
+// Headings that are just a date, with the date and their level, in order
+fn dated_headings(items: &[ListItem]) -> Vec<(usize, NaiveDate, usize)> {
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| match item {
+            ListItem::Heading { content, level } => Some((index, metadata::parse_date(content.trim())?, *level)),
+            _ => None,
+        })
+        .collect()
+}
+
+// The archive heading and everything in its section, up to the next heading
+// of the same or a higher level
+fn archive_section(items: &[ListItem]) -> Option<Range<usize>> {
+    let heading = items.iter().position(|item| {
+        matches!(item, ListItem::Heading { content, .. } if content.trim().eq_ignore_ascii_case(ARCHIVE_HEADING))
+    })?;
+    let (_, end) = ItemCreator::get_fold_range(items, heading);
+    Some(heading..end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_items() -> Vec<ListItem> {
+        vec![
+            ListItem::new_todo("Task A".to_string(), false, 0),
+            ListItem::new_todo("Task B".to_string(), false, 0),
+            ListItem::new_todo("Task C".to_string(), false, 1),
+            ListItem::new_todo("Task D".to_string(), false, 0),
+        ]
+    }
+
+    #[test]
+    fn test_toggle_todo_completion() {
+        let mut items = create_test_items();
+        
+        // Toggle first item
+        let result = ItemActions::toggle_todo_completion(&mut items, 0);
+        assert!(result);
+        
+        if let ListItem::Todo { state, .. } = &items[0] {
+            assert_eq!(*state, TodoState::Done);
+        } else {
+            panic!("Expected Todo item");
+        }
+        
+        // Toggle it back
+        let result = ItemActions::toggle_todo_completion(&mut items, 0);
+        assert!(result);
+        
+        if let ListItem::Todo { state, .. } = &items[0] {
+            assert_eq!(*state, TodoState::Open);
+        } else {
+            panic!("Expected Todo item");
+        }
+        
+        // Try invalid index
+        let result = ItemActions::toggle_todo_completion(&mut items, 10);
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_toggle_reopens_cancelled_and_checks_in_progress() {
+        let mut items = vec![
+            ListItem::new_todo_with_state("Dropped".to_string(), TodoState::Cancelled, 0),
+            ListItem::new_todo_with_state("Started".to_string(), TodoState::InProgress, 0),
+        ];
+        
+        ItemActions::toggle_todo_completion(&mut items, 0);
+        ItemActions::toggle_todo_completion(&mut items, 1);
+        assert_eq!(items[0], ListItem::new_todo("Dropped".to_string(), false, 0));
+        assert_eq!(items[1], ListItem::new_todo("Started".to_string(), true, 0));
+    }
+
+    #[test]
+    fn test_toggle_selected_completion() {
+        let mut items = create_test_items();
+        items[1] = ListItem::new_todo("Task B".to_string(), true, 1);
+
+        // A mix of open and done todos all gets checked
+        let selected = HashSet::from([0, 1, 2]);
+        assert!(ItemActions::toggle_selected_completion(&mut items, &selected));
+        assert!(selected.iter().all(|&i| items[i].is_completed()));
+        assert!(!items[3].is_completed());
+
+        // Once they're all done, they're all reopened
+        assert!(ItemActions::toggle_selected_completion(&mut items, &selected));
+        assert!(selected.iter().all(|&i| !items[i].is_completed()));
+
+        assert!(!ItemActions::toggle_selected_completion(&mut items, &HashSet::from([10])));
+    }
+
+    #[test]
+    fn test_cycle_todo_state() {
+        let mut items = vec![
+            ListItem::new_todo("Task".to_string(), false, 0),
+            ListItem::new_note("Note".to_string(), 0),
+        ];
+        
+        assert!(ItemActions::cycle_todo_state(&mut items, 0));
+        assert_eq!(items[0], ListItem::new_todo_with_state("Task".to_string(), TodoState::InProgress, 0));
+        assert!(!ItemActions::cycle_todo_state(&mut items, 1));
+    }
+
+    #[test]
+    fn test_set_todo_state() {
+        let mut items = vec![
+            ListItem::new_todo("Task".to_string(), false, 0),
+            ListItem::new_note("Note".to_string(), 0),
+        ];
+
+        assert!(ItemActions::set_todo_state(&mut items, 0, TodoState::Deferred));
+        assert_eq!(items[0], ListItem::new_todo_with_state("Task".to_string(), TodoState::Deferred, 0));
+        assert!(!ItemActions::set_todo_state(&mut items, 0, TodoState::Deferred));
+        assert!(!ItemActions::set_todo_state(&mut items, 1, TodoState::Done));
+    }
+
+    #[test]
+    fn test_toggle_block_completion() {
+        let mut items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Done child".to_string(), true, 1),
+            ListItem::new_todo("Open child".to_string(), false, 1),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ];
+        
+        assert!(ItemActions::toggle_block_completion(&mut items, 0));
+        assert!(items[..3].iter().all(|item| item.is_completed()));
+        assert!(!items[3].is_completed());
+        
+        assert!(ItemActions::toggle_block_completion(&mut items, 0));
+        assert!(items.iter().all(|item| !item.is_completed()));
+        
+        let mut items = vec![ListItem::new_note("Note".to_string(), 0)];
+        assert!(!ItemActions::toggle_block_completion(&mut items, 0));
+    }
+
+    #[test]
+    fn test_update_parent_completion() {
+        let mut items = vec![
+            ListItem::new_todo("Project".to_string(), false, 0),
+            ListItem::new_todo("Phase".to_string(), false, 1),
+            ListItem::new_todo("Step 1".to_string(), true, 2),
+            ListItem::new_todo("Step 2".to_string(), false, 2),
+            ListItem::new_note("Notes don't count".to_string(), 2),
+        ];
+        
+        // Checking the last open step completes every ancestor
+        ItemActions::toggle_todo_completion(&mut items, 3);
+        assert!(ItemActions::update_parent_completion(&mut items, 3));
+        assert!(items[1].is_completed());
+        assert!(items[0].is_completed());
+        
+        // Unchecking a step reopens them again
+        ItemActions::toggle_todo_completion(&mut items, 2);
+        assert!(ItemActions::update_parent_completion(&mut items, 2));
+        assert!(!items[1].is_completed());
+        assert!(!items[0].is_completed());
+        
+        // Nothing to do for top-level items
+        assert!(!ItemActions::update_parent_completion(&mut items, 0));
+    }
+
+    #[test]
+    fn test_move_single_item_up() {
+        let mut items = create_test_items();
+        
+        // Move second item up
+        let new_index = ItemActions::move_single_item_up(&mut items, 1);
+        assert_eq!(new_index, Some(0));
+        
+        // Check order
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task B");
+        }
+        if let ListItem::Todo { content, .. } = &items[1] {
+            assert_eq!(content, "Task A");
+        }
+        
+        // Try to move first item up (should fail)
+        let new_index = ItemActions::move_single_item_up(&mut items, 0);
+        assert_eq!(new_index, None);
+    }
+
+    #[test]
+    fn test_move_single_item_down() {
+        let mut items = create_test_items();
+        
+        // Move first item down
+        let new_index = ItemActions::move_single_item_down(&mut items, 0);
+        assert_eq!(new_index, Some(1));
+        
+        // Check order
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task B");
+        }
+        if let ListItem::Todo { content, .. } = &items[1] {
+            assert_eq!(content, "Task A");
+        }
+        
+        // Try to move last item down (should fail)
+        let items_len = items.len();
+        let new_index = ItemActions::move_single_item_down(&mut items, items_len - 1);
+        assert_eq!(new_index, None);
+    }
+
+    #[test]
+    fn test_indent_block() {
+        let mut items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 0),
+        ];
+        
+        // Indent second item under first
+        let result = ItemActions::indent_block(&mut items, 1);
+        assert!(result);
+        
+        if let ListItem::Todo { indent_level, .. } = &items[1] {
+            assert_eq!(*indent_level, 1);
+        }
+        
+        // Try to indent first item (should fail - no parent)
+        let result = ItemActions::indent_block(&mut items, 0);
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_unindent_block() {
+        let mut items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 1),
+        ];
+        
+        // Unindent child
+        let result = ItemActions::unindent_block(&mut items, 1);
+        assert!(result);
+        
+        if let ListItem::Todo { indent_level, .. } = &items[1] {
+            assert_eq!(*indent_level, 0);
+        }
+        
+        // Try to unindent further (should fail - already at 0)
+        let result = ItemActions::unindent_block(&mut items, 1);
+        assert!(!result);
+    }
+
+    #[test]
+    fn test_indent_and_unindent_selected_items() {
+        let mut items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Sibling 1".to_string(), false, 0),
+            ListItem::new_todo("Sibling 2".to_string(), false, 0),
+        ];
+        let selected = HashSet::from([0, 1, 2]);
+        
+        // The first item has nothing to indent under, the rest nest one level
+        assert!(ItemActions::indent_selected_items(&mut items, &selected));
+        let indents: Vec<usize> = items.iter().map(|item| match item {
+            ListItem::Todo { indent_level, .. } => *indent_level,
+            _ => 0,
+        }).collect();
+        assert_eq!(indents, vec![0, 1, 1]);
+        
+        assert!(ItemActions::unindent_selected_items(&mut items, &selected));
+        assert!(!ItemActions::unindent_selected_items(&mut items, &selected));
+    }
+
+    #[test]
+    fn test_move_selected_items_to_position() {
+        let mut items = create_test_items();
+        let mut selected = HashSet::new();
+        selected.insert(0); // Task A
+        selected.insert(2); // Task C
+        
+        // Move to position after Task B (index 1)
+        let result = ItemActions::move_selected_items_to_position(&mut items, &selected, 1);
+        assert!(result.is_some());
+        
+        // Check new order: Task B, Task A, Task C, Task D
+        // Original: Task A(0), Task B(1), Task C(2), Task D(3)
+        // Selected: Task A(0), Task C(2)
+        // After removal: Task B, Task D (remaining)
+        // After insertion at position 1+1=2 -> 1 (adjusted): Task B, Task A, Task C, Task D
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task B");
+        }
+        if let ListItem::Todo { content, .. } = &items[1] {
+            assert_eq!(content, "Task A");
+        }
+        if let ListItem::Todo { content, .. } = &items[2] {
+            assert_eq!(content, "Task C");
+        }
+        if let ListItem::Todo { content, .. } = &items[3] {
+            assert_eq!(content, "Task D");
+        }
+    }
+
+    #[test]
+    fn test_move_selected_items_empty_selection() {
+        let mut items = create_test_items();
+        let selected = HashSet::new();
+        
+        let result = ItemActions::move_selected_items_to_position(&mut items, &selected, 1);
+        assert!(result.is_none());
+        
+        // Items should remain unchanged
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn test_duplicate_block() {
+        let mut items = vec![
+            ListItem::new_todo("Checklist".to_string(), false, 0),
+            ListItem::new_todo("Step 1".to_string(), true, 1),
+            ListItem::new_todo("Other".to_string(), false, 0),
+        ];
+        
+        let result = ItemActions::duplicate_block(&mut items, 0);
+        assert_eq!(result, Some(2));
+        assert_eq!(items.len(), 5);
+        assert_eq!(items[2], items[0]);
+        assert_eq!(items[3], items[1]);
+        assert_eq!(items[4].content(), "Other");
+        
+        assert_eq!(ItemActions::duplicate_block(&mut items, 10), None);
+    }
+
+    #[test]
+    fn test_delete_todo_item() {
+        let mut items = create_test_items();
+        
+        // Delete first todo item
+        let result = ItemActions::delete_item(&mut items, 0);
+        assert!(result);
+        assert_eq!(items.len(), 3);
+        
+        // Check remaining items
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task B");
+        }
+    }
+
+    #[test]
+    fn test_delete_note_item() {
+        let mut items = vec![
+            ListItem::new_todo("Task A".to_string(), false, 0),
+            ListItem::new_note("Note B".to_string(), 0),
+            ListItem::new_todo("Task C".to_string(), false, 0),
+        ];
+        
+        // Delete note item
+        let result = ItemActions::delete_item(&mut items, 1);
+        assert!(result);
+        assert_eq!(items.len(), 2);
+        
+        // Check remaining items
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task A");
+        }
+        if let ListItem::Todo { content, .. } = &items[1] {
+            assert_eq!(content, "Task C");
+        }
+    }
+
+    #[test]
+    fn test_delete_heading_item_should_fail() {
+        let mut items = vec![
+            ListItem::new_heading("Heading".to_string(), 1),
+            ListItem::new_todo("Task A".to_string(), false, 0),
+        ];
+        
+        // Try to delete heading (should fail)
+        let result = ItemActions::delete_item(&mut items, 0);
+        assert!(!result);
+        assert_eq!(items.len(), 2); // No items removed
+        
+        // Check that heading is still there
+        if let ListItem::Heading { content, .. } = &items[0] {
+            assert_eq!(content, "Heading");
+        }
+    }
+
+    #[test]
+    fn test_delete_invalid_index() {
+        let mut items = create_test_items();
+        
+        // Try to delete with invalid index
+        let result = ItemActions::delete_item(&mut items, 10);
+        assert!(!result);
+        assert_eq!(items.len(), 4); // No items removed
+    }
+
+    #[test]
+    fn test_delete_selected_items() {
+        let mut items = vec![
+            ListItem::new_todo("Task A".to_string(), false, 0),    // index 0
+            ListItem::new_note("Note B".to_string(), 0),           // index 1
+            ListItem::new_todo("Task C".to_string(), false, 0),    // index 2
+            ListItem::new_heading("Heading D".to_string(), 1),     // index 3
+            ListItem::new_todo("Task E".to_string(), false, 0),    // index 4
+        ];
+        
+        let mut selected = HashSet::new();
+        selected.insert(0); // Task A
+        selected.insert(1); // Note B
+        selected.insert(2); // Task C
+        selected.insert(3); // Heading D (should not be deleted)
+        selected.insert(4); // Task E
+        
+        let deleted_count = ItemActions::delete_selected_items(&mut items, &selected);
+        
+        // Should delete 4 items (all except the heading)
+        assert_eq!(deleted_count, 4);
+        assert_eq!(items.len(), 1);
+        
+        // Check that only heading remains
+        if let ListItem::Heading { content, .. } = &items[0] {
+            assert_eq!(content, "Heading D");
+        } else {
+            panic!("Expected Heading item to remain");
+        }
+    }
+
+    #[test]
+    fn test_delete_selected_items_mixed() {
+        let mut items = vec![
+            ListItem::new_todo("Task A".to_string(), false, 0),    // index 0
+            ListItem::new_heading("Heading B".to_string(), 1),     // index 1
+            ListItem::new_note("Note C".to_string(), 0),           // index 2
+            ListItem::new_todo("Task D".to_string(), false, 0),    // index 3
+        ];
+        
+        let mut selected = HashSet::new();
+        selected.insert(0); // Task A - should be deleted
+        selected.insert(1); // Heading B - should NOT be deleted
+        selected.insert(2); // Note C - should be deleted
+        
+        let deleted_count = ItemActions::delete_selected_items(&mut items, &selected);
+        
+        // Should delete 2 items (Task A and Note C)
+        assert_eq!(deleted_count, 2);
+        assert_eq!(items.len(), 2);
+        
+        // Check remaining items (Heading B and Task D)
+        if let ListItem::Heading { content, .. } = &items[0] {
+            assert_eq!(content, "Heading B");
+        } else {
+            panic!("Expected Heading item");
+        }
+        
+        if let ListItem::Todo { content, .. } = &items[1] {
+            assert_eq!(content, "Task D");
+        } else {
+            panic!("Expected Todo item");
+        }
+    }
+
+    #[test]
+    fn test_delete_selected_items_empty_selection() {
+        let mut items = create_test_items();
+        let selected = HashSet::new();
+        
+        let deleted_count = ItemActions::delete_selected_items(&mut items, &selected);
+        
+        assert_eq!(deleted_count, 0);
+        assert_eq!(items.len(), 4); // No items removed
+    }
+
+    #[test]
+    fn test_delete_selected_items_invalid_indices() {
+        let mut items = create_test_items();
+        let mut selected = HashSet::new();
+        selected.insert(0); // Valid index
+        selected.insert(10); // Invalid index
+        selected.insert(15); // Invalid index
+        
+        let deleted_count = ItemActions::delete_selected_items(&mut items, &selected);
+        
+        // Should only delete the valid index (0)
+        assert_eq!(deleted_count, 1);
+        assert_eq!(items.len(), 3);
+        
+        // Check that first item was removed
+        if let ListItem::Todo { content, .. } = &items[0] {
+            assert_eq!(content, "Task B");
+        }
+    }
+
+    #[test]
+    fn test_delete_selected_items_only_headings() {
+        let mut items = vec![
+            ListItem::new_heading("Heading A".to_string(), 1),
+            ListItem::new_heading("Heading B".to_string(), 2),
+            ListItem::new_todo("Task C".to_string(), false, 0),
+        ];
+        
+        let mut selected = HashSet::new();
+        selected.insert(0); // Heading A
+        selected.insert(1); // Heading B
+        
+        let deleted_count = ItemActions::delete_selected_items(&mut items, &selected);
+        
+        // Should not delete any headings
+        assert_eq!(deleted_count, 0);
+        assert_eq!(items.len(), 3); // All items remain
+    }
+
+    fn contents(items: &[ListItem]) -> Vec<&str> {
+        items.iter().map(|item| item.content()).collect()
+    }
+
+    #[test]
+    fn test_sort_section_by_status() {
+        let mut items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Done A".to_string(), true, 0),
+            ListItem::new_todo("Subtask".to_string(), false, 1),
+            ListItem::new_todo("Open B".to_string(), false, 0),
+            ListItem::new_note("Note".to_string(), 0),
+            ListItem::new_todo("Open C".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Done D".to_string(), true, 0),
+            ListItem::new_todo("Open E".to_string(), false, 0),
+        ];
+
+        assert!(ItemActions::sort_section(&mut items, 2, SortKey::Status));
+        assert_eq!(contents(&items), vec![
+            "Work", "Open B", "Open C", "Note", "Done A", "Subtask", "Home", "Done D", "Open E",
+        ]);
+
+        // Already sorted
+        assert!(!ItemActions::sort_section(&mut items, 0, SortKey::Status));
+    }
+
+    #[test]
+    fn test_sort_section_by_other_keys() {
+        let mut items = vec![
+            ListItem::new_todo("walk dog".to_string(), false, 0),
+            ListItem::new_todo("Buy milk ⏬ 📅 2024-05-02".to_string(), false, 0),
+            ListItem::new_todo("Ship release 🔺 📅 2024-05-01".to_string(), false, 0),
+            ListItem::new_todo("Changelog".to_string(), false, 1),
+        ];
+
+        assert!(ItemActions::sort_section(&mut items, 0, SortKey::Alphabetical));
+        assert_eq!(contents(&items), vec![
+            "Buy milk ⏬ 📅 2024-05-02", "Ship release 🔺 📅 2024-05-01", "Changelog", "walk dog",
+        ]);
+
+        assert!(ItemActions::sort_section(&mut items, 0, SortKey::DueDate));
+        assert_eq!(contents(&items), vec![
+            "Ship release 🔺 📅 2024-05-01", "Changelog", "Buy milk ⏬ 📅 2024-05-02", "walk dog",
+        ]);
+
+        assert!(ItemActions::sort_section(&mut items, 0, SortKey::Priority));
+        assert_eq!(contents(&items), vec![
+            "Ship release 🔺 📅 2024-05-01", "Changelog", "walk dog", "Buy milk ⏬ 📅 2024-05-02",
+        ]);
+        assert!(!ItemActions::sort_section(&mut items, 0, SortKey::Priority));
+    }
+
+    #[test]
+    fn test_move_items_to_section() {
+        let mut items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_todo("Draft".to_string(), false, 1),
+            ListItem::new_todo("Outline".to_string(), false, 2),
+            ListItem::new_todo("Invoice".to_string(), false, 0),
+            ListItem::new_raw(String::new()),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Laundry".to_string(), false, 0),
+            ListItem::new_raw(String::new()),
+            ListItem::new_heading("Garden".to_string(), 2),
+        ];
+
+        // A nested item moves with its children, shifted to the top level
+        let moved = ItemActions::move_items_to_section(&mut items, &HashSet::from([2]), 6);
+        assert_eq!(moved, Some(6));
+        assert_eq!(contents(&items), vec![
+            "Work", "Report", "Invoice", "", "Home", "Laundry", "Draft", "Outline", "", "Garden",
+        ]);
+        assert_eq!(items[6].indent_level(), Some(0));
+        assert_eq!(items[7].indent_level(), Some(1));
+
+        // Several items keep their order, and headings in the selection stay put
+        let moved = ItemActions::move_items_to_section(&mut items, &HashSet::from([0, 2, 5]), 9);
+        assert_eq!(moved, Some(8));
+        assert_eq!(contents(&items), vec![
+            "Work", "Report", "", "Home", "Draft", "Outline", "", "Garden", "Invoice", "Laundry",
+        ]);
+
+        assert_eq!(ItemActions::move_items_to_section(&mut items, &HashSet::from([1]), 1), None);
+        assert_eq!(ItemActions::move_items_to_section(&mut items, &HashSet::from([0]), 3), None);
+    }
+
+    #[test]
+    fn test_shift_heading_level() {
+        let mut items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Meetings".to_string(), 2),
+            ListItem::new_heading("Home".to_string(), 1),
+        ];
+        let levels = |items: &[ListItem]| -> Vec<usize> {
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    ListItem::Heading { level, .. } => Some(*level),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert!(ItemActions::shift_heading_level(&mut items, 0, 1, false));
+        assert_eq!(levels(&items), vec![2, 2, 1]);
+        assert!(ItemActions::shift_heading_level(&mut items, 0, -1, false));
+
+        // Sub-headings move along, but only within the section
+        assert!(ItemActions::shift_heading_level(&mut items, 0, 1, true));
+        assert_eq!(levels(&items), vec![2, 3, 1]);
+
+        assert!(!ItemActions::shift_heading_level(&mut items, 3, -1, true));
+        assert!(!ItemActions::shift_heading_level(&mut items, 1, 1, false));
+        items[2] = ListItem::new_heading("Meetings".to_string(), 6);
+        assert!(!ItemActions::shift_heading_level(&mut items, 0, 1, true));
+        assert_eq!(levels(&items), vec![2, 6, 1]);
+    }
+
+    #[test]
+    fn test_split_and_join_items() {
+        let mut items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo_with_state("Write report and send it".to_string(), TodoState::InProgress, 1),
+        ];
+
+        assert_eq!(ItemActions::split_item(&mut items, 1, 13), Some(2));
+        assert_eq!(contents(&items), vec!["Work", "Write report", "and send it"]);
+        assert_eq!(items[2].indent_level(), Some(1));
+        assert!(matches!(items[2], ListItem::Todo { state: TodoState::Open, .. }));
+
+        assert_eq!(ItemActions::join_with_previous(&mut items, 2), Some((1, 13)));
+        assert_eq!(contents(&items), vec!["Work", "Write report and send it"]);
+
+        // Headings can't be split or joined into
+        assert_eq!(ItemActions::split_item(&mut items, 0, 2), None);
+        assert_eq!(ItemActions::join_with_previous(&mut items, 1), None);
+        assert_eq!(ItemActions::join_with_previous(&mut items, 0), None);
+    }
+
+    #[test]
+    fn test_archive_completed() {
+        let mut items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Done A".to_string(), true, 0),
+            ListItem::new_note("Detail".to_string(), 1),
+            ListItem::new_todo("Open B".to_string(), false, 0),
+            ListItem::new_todo("Done subtask".to_string(), true, 1),
+        ];
+
+        assert_eq!(ItemActions::archive_completed(&mut items, None), 1);
+        assert_eq!(contents(&items), vec!["Work", "Open B", "Done subtask", "", "Archive", "Done A", "Detail"]);
+
+        // Archived todos stay put, and new ones join them in the existing section
+        items.insert(1, ListItem::new_todo("Done C".to_string(), true, 0));
+        assert_eq!(ItemActions::archive_completed(&mut items, None), 1);
+        assert_eq!(contents(&items), vec!["Work", "Open B", "Done subtask", "", "Archive", "Done A", "Detail", "Done C"]);
+        assert_eq!(ItemActions::archive_completed(&mut items, None), 0);
+    }
+
+    #[test]
+    fn test_archive_completed_before_date() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let mut items = vec![
+            ListItem::new_todo("Old ✅ 2024-05-01".to_string(), true, 0),
+            ListItem::new_todo("Recent ✅ 2024-05-09".to_string(), true, 0),
+            ListItem::new_todo("Undated".to_string(), true, 0),
+        ];
+
+        assert_eq!(ItemActions::archive_completed(&mut items, Some(date(3))), 1);
+        assert_eq!(contents(&items), vec!["Recent ✅ 2024-05-09", "Undated", "", "Archive", "Old ✅ 2024-05-01"]);
+    }
+
+    #[test]
+    fn test_rollover() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let mut items = vec![
+            ListItem::new_heading("Journal".to_string(), 1),
+            ListItem::new_heading("2024-05-01".to_string(), 2),
+            ListItem::new_todo("Done".to_string(), true, 0),
+            ListItem::new_todo("Open A".to_string(), false, 0),
+            ListItem::new_todo("Step".to_string(), true, 1),
+            ListItem::new_raw(String::new()),
+            ListItem::new_heading("2024-05-02".to_string(), 2),
+            ListItem::new_todo("Open B".to_string(), false, 0),
+        ];
+
+        assert_eq!(ItemActions::rollover(&mut items, date(3), &[]), 2);
+        assert_eq!(contents(&items), vec![
+            "Journal", "2024-05-01", "Done", "", "2024-05-02", "", "2024-05-03", "Open A", "Step", "Open B",
+        ]);
+        assert!(matches!(items[6], ListItem::Heading { level: 2, .. }));
+        assert_eq!(ItemActions::rollover(&mut items, date(3), &[]), 0);
+
+        // Into today's existing section, ahead of its trailing blank line
+        items.splice(4..4, [ListItem::new_todo("Late".to_string(), false, 0)]);
+        items.push(ListItem::new_raw(String::new()));
+        assert_eq!(ItemActions::rollover(&mut items, date(3), &[]), 1);
+        assert_eq!(contents(&items)[6..], ["2024-05-03", "Open A", "Step", "Open B", "Late", ""]);
+    }
+
+    #[test]
+    fn test_rollover_newest_first() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let mut items = vec![
+            ListItem::new_heading("2024-05-02".to_string(), 1),
+            ListItem::new_todo("Open B".to_string(), false, 0),
+            ListItem::new_heading("2024-05-01".to_string(), 1),
+            ListItem::new_todo("Open A".to_string(), false, 0),
+        ];
+
+        assert_eq!(ItemActions::rollover(&mut items, date(3), &[]), 2);
+        assert_eq!(contents(&items), vec!["2024-05-03", "Open B", "Open A", "", "2024-05-02", "2024-05-01"]);
+
+        let section = [ListItem::new_todo("Plan the day".to_string(), false, 0)];
+        assert_eq!(ItemActions::rollover(&mut items, date(4), &section), 2);
+        assert_eq!(contents(&items)[..5], ["2024-05-04", "Plan the day", "Open B", "Open A", ""]);
+    }
+
+    #[test]
+    fn test_add_section() {
+        let mut items = vec![
+            ListItem::new_todo("Loose".to_string(), false, 0),
+            ListItem::new_heading("Work".to_string(), 2),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 2),
+        ];
+        let section = vec![ListItem::new_todo("Plan".to_string(), false, 0)];
+        assert_eq!(ItemActions::add_section(&mut items, 2, "Projects", section), 4);
+        assert_eq!(contents(&items), vec!["Loose", "Work", "Report", "", "Projects", "Plan", "", "Home"]);
+        assert!(matches!(items[4], ListItem::Heading { level: 2, .. }));
+
+        assert_eq!(ItemActions::add_section(&mut items, 0, "Inbox", Vec::new()), 2);
+        assert_eq!(contents(&items)[..4], ["Loose", "", "Inbox", ""]);
+        assert!(matches!(items[2], ListItem::Heading { level: 2, .. }));
+
+        let mut items = vec![ListItem::new_todo("Loose".to_string(), false, 0)];
+        assert_eq!(ItemActions::add_section(&mut items, 0, "Inbox", Vec::new()), 2);
+        assert!(matches!(items[2], ListItem::Heading { level: 1, .. }));
+    }
+
+    #[test]
+    fn test_record_done_dates() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let before = vec![
+            ListItem::new_todo("Write report".to_string(), false, 0),
+            ListItem::new_todo("Call bank ✅ 2024-04-20".to_string(), true, 0),
+            ListItem::new_todo("Untouched".to_string(), false, 0),
+        ];
+        let mut items = before.clone();
+        ItemActions::toggle_todo_completion(&mut items, 0);
+        ItemActions::toggle_todo_completion(&mut items, 1);
+
+        ItemActions::record_done_dates(&mut items, &before, today);
+        assert_eq!(contents(&items), vec!["Write report ✅ 2024-05-01", "Call bank", "Untouched"]);
+    }
+}
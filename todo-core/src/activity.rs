@@ -0,0 +1,186 @@
+//! An append-only log of the changes made to a TODO file, kept next to it
+//! (`TODO.md.log`). Each line holds a timestamp, what happened and the item's
+//! text, separated by tabs.
+
+use crate::models::ListItem;
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDateTime};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Added,
+    Completed,
+    Reopened,
+    Deleted,
+    Moved,
+}
+
+const ACTIVITIES: [Activity; 5] = [
+    Activity::Added,
+    Activity::Completed,
+    Activity::Reopened,
+    Activity::Deleted,
+    Activity::Moved,
+];
+
+impl Activity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Activity::Added => "added",
+            Activity::Completed => "completed",
+            Activity::Reopened => "reopened",
+            Activity::Deleted => "deleted",
+            Activity::Moved => "moved",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        ACTIVITIES.into_iter().find(|activity| activity.label() == label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: NaiveDateTime,
+    pub activity: Activity,
+    pub content: String,
+}
+
+impl LogEntry {
+    /// An entry for something that just happened to the item with `content`
+    pub fn now(activity: Activity, content: &str) -> Self {
+        Self {
+            timestamp: Local::now().naive_local(),
+            activity,
+            // Multi-line items are logged on one line
+            content: content.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("{}\t{}\t{}", self.timestamp.format(TIMESTAMP_FORMAT), self.activity.label(), self.content)
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(3, '\t');
+        let timestamp = NaiveDateTime::parse_from_str(fields.next()?, TIMESTAMP_FORMAT).ok()?;
+        let activity = Activity::from_label(fields.next()?)?;
+        Some(Self {
+            timestamp,
+            activity,
+            content: fields.next().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Entries for the todos that were checked off or reopened since `before`, a
+/// copy of the list from before a change that didn't add or remove items
+pub fn completion_entries(items: &[ListItem], before: &[ListItem]) -> Vec<LogEntry> {
+    if items.len() != before.len() {
+        return Vec::new();
+    }
+    items
+        .iter()
+        .zip(before)
+        .filter(|(item, previous)| matches!(item, ListItem::Todo { .. }) && item.is_completed() != previous.is_completed())
+        .map(|(item, _)| {
+            let activity = if item.is_completed() { Activity::Completed } else { Activity::Reopened };
+            LogEntry::now(activity, item.content())
+        })
+        .collect()
+}
+
+/// Where the log for the TODO file at `todo_path` is kept
+pub fn log_path(todo_path: &str) -> PathBuf {
+    let path = Path::new(todo_path);
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}.log", file_name))
+}
+
+pub fn append_entries(todo_path: &str, entries: &[LogEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let path = log_path(todo_path);
+    let lines: String = entries.iter().map(|entry| entry.to_line() + "\n").collect();
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| format!("Failed to write activity log: {}", path.display()))
+}
+
+/// The logged entries, oldest first. A file that was never logged to has none;
+/// lines that can't be read are skipped.
+pub fn read_log(todo_path: &str) -> Result<Vec<LogEntry>> {
+    let path = log_path(todo_path);
+    match fs::read_to_string(&path) {
+        Ok(content) => Ok(content.lines().filter_map(LogEntry::parse).collect()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read activity log: {}", path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trip() {
+        let entry = LogEntry::now(Activity::Completed, "Buy milk\nand eggs");
+        assert_eq!(entry.content, "Buy milk and eggs");
+        let line = entry.to_line();
+        assert!(line.ends_with("\tcompleted\tBuy milk and eggs"));
+
+        let parsed = LogEntry::parse(&line).unwrap();
+        assert_eq!(parsed.activity, Activity::Completed);
+        assert_eq!(parsed.content, entry.content);
+        assert_eq!(parsed.timestamp.and_utc().timestamp(), entry.timestamp.and_utc().timestamp());
+
+        assert_eq!(LogEntry::parse("not a log line"), None);
+    }
+
+    #[test]
+    fn test_completion_entries() {
+        let before = vec![
+            ListItem::new_todo("Write report".to_string(), false, 0),
+            ListItem::new_todo("Call bank".to_string(), true, 0),
+            ListItem::new_todo("Untouched".to_string(), false, 0),
+        ];
+        let mut items = before.clone();
+        crate::ItemActions::toggle_todo_completion(&mut items, 0);
+        crate::ItemActions::toggle_todo_completion(&mut items, 1);
+
+        let entries: Vec<(Activity, String)> = completion_entries(&items, &before)
+            .into_iter()
+            .map(|entry| (entry.activity, entry.content))
+            .collect();
+        assert_eq!(entries, vec![
+            (Activity::Completed, "Write report".to_string()),
+            (Activity::Reopened, "Call bank".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_append_and_read_log() {
+        let dir = std::env::temp_dir().join(format!("todo_activity_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let todo_path = dir.join("TODO.md").to_string_lossy().to_string();
+        assert_eq!(log_path(&todo_path), dir.join("TODO.md.log"));
+        assert!(read_log(&todo_path).unwrap().is_empty());
+
+        append_entries(&todo_path, &[LogEntry::now(Activity::Added, "Write report")]).unwrap();
+        append_entries(&todo_path, &[LogEntry::now(Activity::Deleted, "Write report")]).unwrap();
+        let activities: Vec<Activity> = read_log(&todo_path).unwrap().iter().map(|entry| entry.activity).collect();
+        assert_eq!(activities, vec![Activity::Added, Activity::Deleted]);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
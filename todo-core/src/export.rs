@@ -0,0 +1,366 @@
+//! Converting a list to and from formats other tools can read.
+
+use crate::actions::MAX_HEADING_LEVEL;
+use crate::inline::parse_inline;
+use crate::metadata::{self, Priority};
+use crate::models::{IndentStyle, ListItem, TodoList, TodoState};
+use crate::parser::continues_item;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// An item as exported to JSON: its stored fields plus the metadata read from
+/// its content, which is ignored again on import
+#[derive(Serialize)]
+struct JsonItem<'a> {
+    #[serde(flatten)]
+    item: &'a ListItem,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+}
+
+impl<'a> JsonItem<'a> {
+    fn new(item: &'a ListItem) -> Self {
+        let has_metadata = matches!(item, ListItem::Todo { .. } | ListItem::Note { .. });
+        let content = if has_metadata { item.content() } else { "" };
+        Self {
+            item,
+            completed: matches!(item, ListItem::Todo { .. }).then(|| item.is_completed()),
+            due: metadata::due_date(content).map(|date| date.to_string()),
+            tags: metadata::tags(content),
+            priority: Some(metadata::priority(content)).filter(|&priority| priority != Priority::Normal),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonList<'a> {
+    items: Vec<JsonItem<'a>>,
+}
+
+pub fn to_json(todo_list: &TodoList) -> Result<String> {
+    let list = JsonList {
+        items: todo_list.items.iter().map(JsonItem::new).collect(),
+    };
+    Ok(serde_json::to_string_pretty(&list)? + "\n")
+}
+
+/// One item as it appears in [`to_json`], on a single line
+pub fn item_to_json(item: &ListItem) -> Result<String> {
+    Ok(serde_json::to_string(&JsonItem::new(item))?)
+}
+
+/// Reads a list exported with [`to_json`], or written by hand in the same shape
+pub fn from_json(json: &str, file_path: &str) -> Result<TodoList> {
+    let mut todo_list: TodoList = serde_json::from_str(json).context("Not a JSON todo list")?;
+    for (index, item) in todo_list.items.iter().enumerate() {
+        if let Err(e) = check_item(item) {
+            bail!("Item {}: {}", index, e);
+        }
+    }
+    todo_list.file_path = file_path.to_string();
+    Ok(todo_list)
+}
+
+// Items that would be written as something that reads back differently
+fn check_item(item: &ListItem) -> Result<()> {
+    match item {
+        ListItem::Heading { level, .. } if !(1..=MAX_HEADING_LEVEL).contains(level) => {
+            bail!("heading level {} isn't between 1 and {}", level, MAX_HEADING_LEVEL)
+        }
+        ListItem::Heading { content, .. } | ListItem::Raw { content } if content.contains('\n') => {
+            bail!("only todos and notes can span lines")
+        }
+        ListItem::Todo { content, indent_level, .. } | ListItem::Note { content, indent_level, .. } => {
+            let indent = IndentStyle::default().indent(*indent_level);
+            if let Some(line) = content.lines().skip(1).find(|line| !continues_item(line, &indent, IndentStyle::default())) {
+                bail!("line '{}' would be read as an item of its own", line);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+const HTML_STYLE: &str = "body { font-family: system-ui, sans-serif; max-width: 44em; margin: 2em auto; padding: 0 1em; line-height: 1.5; color: #222; }
+ul { list-style: none; padding-left: 1.5em; }
+li.note { list-style: disc; }
+input { margin-right: 0.5em; }
+.done, .cancelled { color: #888; }
+.cancelled { text-decoration: line-through; }
+code { background: #f2f2f2; padding: 0 0.2em; border-radius: 3px; }";
+
+/// Renders the list as a standalone HTML page, with nested lists and read-only
+/// checkboxes, for sharing or printing
+pub fn to_html(todo_list: &TodoList) -> String {
+    let title = Path::new(&todo_list.file_path)
+        .file_stem()
+        .map_or_else(|| "TODO".to_string(), |stem| stem.to_string_lossy().to_string());
+    let mut body = String::new();
+    // Every open list has an unclosed <li> in it
+    let mut open_lists = 0;
+
+    for item in &todo_list.items {
+        match item {
+            ListItem::Todo { indent_level, .. } | ListItem::Note { indent_level, .. } => {
+                let level = (indent_level + 1).min(open_lists + 1);
+                if level > open_lists {
+                    body.push_str("<ul>\n");
+                    open_lists += 1;
+                } else {
+                    body.push_str("</li>\n");
+                    while open_lists > level {
+                        body.push_str("</ul>\n</li>\n");
+                        open_lists -= 1;
+                    }
+                }
+                body.push_str(&html_list_item(item));
+            }
+            ListItem::Heading { content, level } => {
+                close_lists(&mut body, &mut open_lists);
+                body.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline_html(content)));
+            }
+            ListItem::Raw { content } => {
+                close_lists(&mut body, &mut open_lists);
+                if !content.trim().is_empty() {
+                    body.push_str(&format!("<p>{}</p>\n", inline_html(content)));
+                }
+            }
+        }
+    }
+    close_lists(&mut body, &mut open_lists);
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&title),
+        HTML_STYLE,
+        body
+    )
+}
+
+fn html_list_item(item: &ListItem) -> String {
+    let content = inline_html(item.content()).replace('\n', "<br>");
+    match item {
+        ListItem::Todo { state, .. } => {
+            let (class, checked) = match state {
+                TodoState::Open => ("open", ""),
+                TodoState::Done => ("done", " checked"),
+                TodoState::InProgress => ("in-progress", ""),
+                TodoState::Cancelled => ("cancelled", ""),
+                TodoState::Deferred => ("deferred", ""),
+            };
+            format!("<li class=\"{}\"><input type=\"checkbox\" disabled{}>{}", class, checked, content)
+        }
+        _ => format!("<li class=\"note\">{}", content),
+    }
+}
+
+fn close_lists(body: &mut String, open_lists: &mut usize) {
+    if *open_lists == 0 {
+        return;
+    }
+    body.push_str("</li>\n");
+    while *open_lists > 0 {
+        body.push_str("</ul>\n");
+        *open_lists -= 1;
+        if *open_lists > 0 {
+            body.push_str("</li>\n");
+        }
+    }
+}
+
+fn inline_html(text: &str) -> String {
+    parse_inline(text)
+        .into_iter()
+        .map(|span| {
+            let mut html = escape_html(&span.text);
+            let style = span.style;
+            for (active, tag) in [(style.code, "code"), (style.italic, "em"), (style.bold, "strong"), (style.strikethrough, "del")] {
+                if active {
+                    html = format!("<{0}>{1}</{0}>", tag, html);
+                }
+            }
+            html
+        })
+        .collect()
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Writes the todos that have a due date as iCalendar VTODO entries, so
+/// deadlines show up in calendar apps. `now` (UTC) is the entries' timestamp.
+pub fn to_ics(todo_list: &TodoList, now: NaiveDateTime) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//todo//EN".to_string(),
+    ];
+    // How many todos with each text came before, to tell same-text todos apart
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for item in &todo_list.items {
+        let ListItem::Todo { content, state, .. } = item else {
+            continue;
+        };
+        let Some(due) = metadata::due_date(content) else {
+            continue;
+        };
+        let summary = metadata::set_due_date(content, None);
+        let status = match state {
+            TodoState::Open | TodoState::Deferred => "NEEDS-ACTION",
+            TodoState::InProgress => "IN-PROCESS",
+            TodoState::Done => "COMPLETED",
+            TodoState::Cancelled => "CANCELLED",
+        };
+        let occurrence = seen.entry(summary.clone()).or_default();
+        let uid = fnv1a(&format!("{}\n{}\n{}", todo_list.file_path, summary, occurrence));
+        *occurrence += 1;
+        lines.extend([
+            "BEGIN:VTODO".to_string(),
+            // Stable across exports as long as the todo keeps its text and
+            // its place among todos with the same text
+            format!("UID:{:016x}@todo", uid),
+            format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+            format!("SUMMARY:{}", escape_ics_text(&summary)),
+            format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")),
+            format!("STATUS:{}", status),
+            "END:VTODO".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_ics_line(line) + "\r\n").collect()
+}
+
+fn escape_ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes continue on lines starting with a space
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoState;
+    use crate::parser::parse_todo_content;
+
+    #[test]
+    fn test_json_includes_metadata() {
+        let list = parse_todo_content("TODO.md", "# Work\n- [x] Ship it #release 🔺 📅 2024-05-01\n  - Notes\n");
+        let json: serde_json::Value = serde_json::from_str(&to_json(&list).unwrap()).unwrap();
+        assert_eq!(json["items"][0], serde_json::json!({"type": "heading", "content": "Work", "level": 1}));
+        assert_eq!(json["items"][1], serde_json::json!({
+            "type": "todo",
+            "content": "Ship it #release 🔺 📅 2024-05-01",
+            "state": "done",
+            "indent_level": 0,
+            "completed": true,
+            "due": "2024-05-01",
+            "tags": ["release"],
+            "priority": "highest",
+        }));
+        assert_eq!(json["items"][2]["indent_level"], 1);
+    }
+
+    #[test]
+    fn test_html_nests_lists() {
+        let list = parse_todo_content("work.md", "# Work\n- [x] Ship **it**\n  - [ ] Changelog\n    - Notes\n- [ ] <Review>\n\nDone.\n");
+        let html = to_html(&list);
+        assert!(html.contains("<title>work</title>"));
+        let body = &html[html.find("<body>").unwrap()..];
+        assert_eq!(body, "<body>\n<h1>Work</h1>\n<ul>\n\
+            <li class=\"done\"><input type=\"checkbox\" disabled checked>Ship <strong>it</strong><ul>\n\
+            <li class=\"open\"><input type=\"checkbox\" disabled>Changelog<ul>\n\
+            <li class=\"note\">Notes</li>\n</ul>\n</li>\n</ul>\n</li>\n\
+            <li class=\"open\"><input type=\"checkbox\" disabled>&lt;Review&gt;</li>\n</ul>\n\
+            <p>Done.</p>\n</body>\n</html>\n");
+    }
+
+    #[test]
+    fn test_ics_has_todos_with_due_dates() {
+        let list = parse_todo_content("TODO.md", "- [ ] Pay rent, then relax 📅 2024-05-01\n- [x] Filed taxes due:2024-04-15\n- [ ] Someday\n");
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 4, 20).unwrap().and_hms_opt(8, 30, 0).unwrap();
+        let ics = to_ics(&list, now);
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+
+        assert_eq!(lines.iter().filter(|line| **line == "BEGIN:VTODO").count(), 2);
+        assert!(lines.contains(&"SUMMARY:Pay rent\\, then relax"));
+        assert!(lines.contains(&"DUE;VALUE=DATE:20240501"));
+        assert!(lines.contains(&"STATUS:COMPLETED"));
+        assert!(lines.contains(&"DTSTAMP:20240420T083000Z"));
+        assert!(!ics.contains("Someday"));
+        assert_eq!(to_ics(&list, now), ics);
+    }
+
+    #[test]
+    fn test_ics_uids_are_unique() {
+        let list = parse_todo_content("TODO.md", "- [ ] Water plants 📅 2024-05-01
+- [ ] Water plants 📅 2024-05-08
+");
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 4, 20).unwrap().and_hms_opt(8, 30, 0).unwrap();
+        let ics = to_ics(&list, now);
+        let uids: Vec<&str> = ics.split("\r\n").filter(|line| line.starts_with("UID:")).collect();
+        assert_eq!(uids.len(), 2);
+        assert_ne!(uids[0], uids[1]);
+    }
+
+    #[test]
+    fn test_fold_ics_line() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold_ics_line(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let list = parse_todo_content("TODO.md", "# Work\n- [/] Draft #writing\n\nSome text\n");
+        let imported = from_json(&to_json(&list).unwrap(), "other.md").unwrap();
+        assert_eq!(imported.items, list.items);
+        assert_eq!(imported.file_path, "other.md");
+
+        // Only the items are required
+        let imported = from_json(r#"{"items": [{"type": "todo", "content": "Call", "state": "open", "indent_level": 0}]}"#, "TODO.md").unwrap();
+        assert_eq!(imported.items, vec![ListItem::new_todo_with_state("Call".to_string(), TodoState::Open, 0)]);
+        assert!(from_json("[]", "TODO.md").is_err());
+    }
+
+    #[test]
+    fn test_json_items_must_read_back_the_same() {
+        let error = |json: &str| format!("{:#}", from_json(&format!(r#"{{"items": [{}]}}"#, json), "TODO.md").unwrap_err());
+        assert!(error(r#"{"type": "heading", "content": "Zero", "level": 0}"#).starts_with("Item 0: heading level 0"));
+        assert!(error(r#"{"type": "heading", "content": "Deep", "level": 12}"#).contains("heading level 12"));
+        assert!(error(r##"{"type": "heading", "content": "Two\nlines", "level": 1}"##).contains("span lines"));
+        assert!(error(r##"{"type": "raw", "content": "a\nb"}"##).contains("span lines"));
+        assert!(error(r##"{"type": "todo", "content": "Call\n# x", "state": "open", "indent_level": 0}"##).contains("'# x'"));
+        assert!(error(r##"{"type": "note", "content": "Call\n\nback", "indent_level": 1}"##).contains("own"));
+
+        let list = from_json(r##"{"items": [{"type": "todo", "content": "Call\nabout #x", "state": "open", "indent_level": 1}]}"##, "TODO.md").unwrap();
+        assert_eq!(parse_todo_content("TODO.md", &crate::serialize_todo_list(&list)).items, list.items);
+    }
+}
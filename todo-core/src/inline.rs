@@ -0,0 +1,147 @@
+//! Inline markdown emphasis in item content: `**bold**`, `*italic*`,
+//! `` `code` `` and `~~strikethrough~~`.
+//!
+//! This only decides how content is displayed; items always keep their raw
+//! text so nothing is lost when they're edited or saved.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InlineStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strikethrough: bool,
+}
+
+/// A run of text shown in one style, with its markers removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineSpan {
+    pub text: String,
+    pub style: InlineStyle,
+}
+
+#[derive(Clone, Copy)]
+enum Emphasis {
+    Bold,
+    Strikethrough,
+    Italic,
+}
+
+// Longest first, so `**` isn't read as two italic markers
+const DELIMITERS: [(&str, Emphasis); 3] = [
+    ("**", Emphasis::Bold),
+    ("~~", Emphasis::Strikethrough),
+    ("*", Emphasis::Italic),
+];
+
+fn flag(style: &mut InlineStyle, emphasis: Emphasis) -> &mut bool {
+    match emphasis {
+        Emphasis::Bold => &mut style.bold,
+        Emphasis::Strikethrough => &mut style.strikethrough,
+        Emphasis::Italic => &mut style.italic,
+    }
+}
+
+/// Splits `text` into styled spans. Markers without a matching partner, or
+/// with whitespace on the inside (`2 * 3 * 4`), are shown as they are.
+pub fn parse_inline(text: &str) -> Vec<InlineSpan> {
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let mut style = InlineStyle::default();
+    let mut position = 0;
+
+    while let Some(c) = text[position..].chars().next() {
+        let rest = &text[position..];
+
+        // Nothing inside a code span is formatted
+        if c == '`'
+            && let Some(len) = rest[1..].find('`').filter(|&len| len > 0)
+        {
+            flush(&mut spans, &mut current, style);
+            spans.push(InlineSpan {
+                text: rest[1..1 + len].to_string(),
+                style: InlineStyle { code: true, ..style },
+            });
+            position += len + 2;
+            continue;
+        }
+
+        if let Some(&(marker, emphasis)) = DELIMITERS.iter().find(|(marker, _)| rest.starts_with(marker)) {
+            let after = &rest[marker.len()..];
+            let closes = *flag(&mut style, emphasis) && !text[..position].ends_with(char::is_whitespace);
+            let opens = !*flag(&mut style, emphasis)
+                && after.starts_with(|c: char| !c.is_whitespace())
+                && has_closing_marker(after, marker);
+            if closes || opens {
+                flush(&mut spans, &mut current, style);
+                let active = flag(&mut style, emphasis);
+                *active = !*active;
+                position += marker.len();
+                continue;
+            }
+        }
+
+        current.push(c);
+        position += c.len_utf8();
+    }
+
+    flush(&mut spans, &mut current, style);
+    spans
+}
+
+// Whether `marker` appears later right after something other than whitespace
+fn has_closing_marker(text: &str, marker: &str) -> bool {
+    text.match_indices(marker)
+        .any(|(index, _)| index > 0 && !text[..index].ends_with(char::is_whitespace))
+}
+
+fn flush(spans: &mut Vec<InlineSpan>, current: &mut String, style: InlineStyle) {
+    if !current.is_empty() {
+        spans.push(InlineSpan {
+            text: std::mem::take(current),
+            style,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, style: InlineStyle) -> InlineSpan {
+        InlineSpan { text: text.to_string(), style }
+    }
+
+    const PLAIN: InlineStyle = InlineStyle { bold: false, italic: false, code: false, strikethrough: false };
+
+    #[test]
+    fn test_emphasis_markers_are_removed() {
+        assert_eq!(parse_inline("Ship **now** or *later*"), vec![
+            span("Ship ", PLAIN),
+            span("now", InlineStyle { bold: true, ..PLAIN }),
+            span(" or ", PLAIN),
+            span("later", InlineStyle { italic: true, ..PLAIN }),
+        ]);
+        assert_eq!(parse_inline("~~old~~ `new_name`"), vec![
+            span("old", InlineStyle { strikethrough: true, ..PLAIN }),
+            span(" ", PLAIN),
+            span("new_name", InlineStyle { code: true, ..PLAIN }),
+        ]);
+    }
+
+    #[test]
+    fn test_nested_emphasis() {
+        assert_eq!(parse_inline("**bold *both***"), vec![
+            span("bold ", InlineStyle { bold: true, ..PLAIN }),
+            span("both", InlineStyle { bold: true, italic: true, ..PLAIN }),
+        ]);
+    }
+
+    #[test]
+    fn test_unmatched_markers_stay_literal() {
+        assert_eq!(parse_inline("2 * 3 * 4"), vec![span("2 * 3 * 4", PLAIN)]);
+        assert_eq!(parse_inline("**unclosed"), vec![span("**unclosed", PLAIN)]);
+        assert_eq!(parse_inline("a ` b"), vec![span("a ` b", PLAIN)]);
+        assert_eq!(parse_inline("`**not bold**`"), vec![span("**not bold**", InlineStyle { code: true, ..PLAIN })]);
+        assert!(parse_inline("").is_empty());
+    }
+}
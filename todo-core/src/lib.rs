@@ -0,0 +1,36 @@
+//! The markdown TODO list model used by the `todo` TUI, usable on its own.
+//!
+//! A file is parsed into a [`TodoList`], a flat list of [`ListItem`]s (todos,
+//! notes, headings and raw lines that are kept verbatim) where nesting is
+//! expressed through each item's indent level. [`ItemActions`] edits the list
+//! in place and [`ItemCreator`] answers structural questions such as which
+//! items belong to a block. Lists are written back with [`write_todo_file`],
+//! which preserves anything it didn't understand.
+//!
+//! ```
+//! use todo_core::{parse_todo_content, serialize_todo_list, ItemActions};
+//!
+//! let mut list = parse_todo_content("TODO.md", "# Today\n- [ ] Write docs\n");
+//! ItemActions::toggle_todo_completion(&mut list.items, 1);
+//! assert_eq!(serialize_todo_list(&list), "# Today\n- [x] Write docs\n");
+//! ```
+
+pub mod actions;
+pub mod activity;
+pub mod export;
+pub mod inline;
+pub mod links;
+pub mod merge;
+pub mod metadata;
+pub mod models;
+pub mod parser;
+pub mod script;
+pub mod structure;
+pub mod sync;
+pub mod writer;
+
+pub use actions::ItemActions;
+pub use models::{IndentStyle, LineEnding, ListItem, TodoList, TodoState};
+pub use parser::{parse_todo_content, parse_todo_file};
+pub use structure::ItemCreator;
+pub use writer::{serialize_item, serialize_item_with_style, serialize_todo_list, write_todo_file, WriteOptions};
@@ -0,0 +1,235 @@
+//! Links in item content: bare `http(s)://` URLs and markdown `[text](url)`
+//! links, and references to other items in the file, `[[Some task]]` by its
+//! text or `(#id)` by its `🆔 id`.
+
+use crate::metadata;
+use crate::models::ListItem;
+use std::ops::Range;
+
+const SCHEMES: [&str; 2] = ["http://", "https://"];
+// Sentence punctuation right after a bare URL isn't part of it
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '\'', '"'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Byte range of the whole link in the content, including any markdown syntax
+    pub range: Range<usize>,
+    pub url: String,
+}
+
+/// All links in `content`, in the order they appear
+pub fn find_links(content: &str) -> Vec<Link> {
+    let mut links = markdown_links(content);
+    let bare: Vec<Link> = bare_links(content)
+        .into_iter()
+        .filter(|bare| !links.iter().any(|link| link.range.contains(&bare.range.start)))
+        .collect();
+    links.extend(bare);
+    links.sort_by_key(|link| link.range.start);
+    links
+}
+
+fn markdown_links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut position = 0;
+    while let Some(offset) = content[position..].find('[') {
+        let start = position + offset;
+        position = start + 1;
+
+        let Some(label_len) = content[start + 1..].find("](") else {
+            break;
+        };
+        let label = &content[start + 1..start + 1 + label_len];
+        let url_start = start + 1 + label_len + 2;
+        let Some(url_len) = content[url_start..].find(')') else {
+            break;
+        };
+        let url = &content[url_start..url_start + url_len];
+
+        // A `[` in the label means this bracket wasn't the start of the link
+        if label.contains(['[', ']']) || url.is_empty() || url.contains(char::is_whitespace) {
+            continue;
+        }
+        links.push(Link {
+            range: start..url_start + url_len + 1,
+            url: url.to_string(),
+        });
+        position = url_start + url_len + 1;
+    }
+    links
+}
+
+fn bare_links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for (start, _) in content.match_indices("http") {
+        let rest = &content[start..];
+        let Some(scheme) = SCHEMES.iter().find(|scheme| rest.starts_with(**scheme)) else {
+            continue;
+        };
+        // Only at the start of a word, so `xhttp://` isn't picked up
+        if content[..start].chars().next_back().is_some_and(char::is_alphanumeric) {
+            continue;
+        }
+
+        let len = rest.find(|c: char| c.is_whitespace() || matches!(c, '<' | '>')).unwrap_or(rest.len());
+        let mut url = rest[..len].trim_end_matches(TRAILING_PUNCTUATION);
+        // A closing parenthesis usually belongs to the surrounding text
+        if url.ends_with(')') && !url.contains('(') {
+            url = url.trim_end_matches(')');
+        }
+        if url.len() > scheme.len() {
+            links.push(Link {
+                range: start..start + url.len(),
+                url: url.to_string(),
+            });
+        }
+    }
+    links
+}
+
+/// What a reference to another item names
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceTarget {
+    /// `[[Some task]]`: the item whose text starts with this
+    Text(String),
+    /// `(#id)`: the item marked `🆔 id`
+    Id(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// Byte range of the whole reference in the content, brackets included
+    pub range: Range<usize>,
+    pub target: ReferenceTarget,
+}
+
+/// All references to other items in `content`, in the order they appear
+pub fn find_references(content: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    for (start, _) in content.match_indices("[[") {
+        let text_start = start + 2;
+        if references.last().is_some_and(|last: &Reference| last.range.end > start) {
+            continue;
+        }
+        let Some(len) = content[text_start..].find("]]") else {
+            break;
+        };
+        let text = content[text_start..text_start + len].trim();
+        if !text.is_empty() && !text.contains(['[', ']', '\n']) {
+            references.push(Reference {
+                range: start..text_start + len + 2,
+                target: ReferenceTarget::Text(text.to_string()),
+            });
+        }
+    }
+    for (start, _) in content.match_indices("(#") {
+        // Part of the text of a `[[...]]` reference
+        if references.iter().any(|reference| reference.range.contains(&start)) {
+            continue;
+        }
+        let id_start = start + 2;
+        let Some(len) = content[id_start..].find(')') else {
+            break;
+        };
+        let id = &content[id_start..id_start + len];
+        if !id.is_empty() && !id.contains(char::is_whitespace) {
+            references.push(Reference {
+                range: start..id_start + len + 1,
+                target: ReferenceTarget::Id(id.to_string()),
+            });
+        }
+    }
+    references.sort_by_key(|reference| reference.range.start);
+    references
+}
+
+/// The item `target` names, other than the one at `from` which refers to it.
+/// Text matches ignore case, and an item whose whole text matches wins over
+/// one that only starts with it.
+pub fn resolve_reference(items: &[ListItem], target: &ReferenceTarget, from: usize) -> Option<usize> {
+    let others = || items.iter().enumerate().filter(move |&(index, _)| index != from);
+    match target {
+        ReferenceTarget::Id(id) => others()
+            .find(|(_, item)| metadata::id(item.content()) == Some(id))
+            .map(|(index, _)| index),
+        ReferenceTarget::Text(text) => {
+            let text = text.to_lowercase();
+            let content = |item: &ListItem| item.content().trim().to_lowercase();
+            others()
+                .find(|(_, item)| content(item) == text)
+                .or_else(|| others().find(|(_, item)| content(item).starts_with(&text)))
+                .map(|(index, _)| index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(content: &str) -> Vec<String> {
+        find_links(content).into_iter().map(|link| link.url).collect()
+    }
+
+    #[test]
+    fn test_bare_links() {
+        assert_eq!(urls("Read https://example.com/a?b=1 later"), vec!["https://example.com/a?b=1"]);
+        assert_eq!(urls("See http://example.com."), vec!["http://example.com"]);
+        assert_eq!(urls("(docs: https://example.com/x)"), vec!["https://example.com/x"]);
+        assert_eq!(urls("<https://example.com>"), vec!["https://example.com"]);
+    }
+
+    #[test]
+    fn test_markdown_links() {
+        let content = "Check [the docs](https://example.com/docs) and https://example.org";
+        let links = find_links(content);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].url, "https://example.com/docs");
+        assert_eq!(&content[links[0].range.clone()], "[the docs](https://example.com/docs)");
+        assert_eq!(&content[links[1].range.clone()], "https://example.org");
+    }
+
+    #[test]
+    fn test_text_without_links() {
+        assert!(find_links("Plain text [not a link] (nor this)").is_empty());
+        assert!(find_links("http:// and https://").is_empty());
+        assert!(find_links("xhttps://example.com").is_empty());
+        assert_eq!(urls("[a] [b](https://example.com)"), vec!["https://example.com"]);
+    }
+
+    #[test]
+    fn test_find_references() {
+        let content = "After [[Write report]] and (#room), not [x](y) or (#) [[ ]]";
+        let references = find_references(content);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].target, ReferenceTarget::Text("Write report".to_string()));
+        assert_eq!(&content[references[0].range.clone()], "[[Write report]]");
+        assert_eq!(references[1].target, ReferenceTarget::Id("room".to_string()));
+        assert_eq!(&content[references[1].range.clone()], "(#room)");
+        assert!(find_references("[[unclosed").is_empty());
+    }
+
+    #[test]
+    fn test_id_inside_text_reference() {
+        let references = find_references("See [[Call (#bob) later]]");
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].range, 4..25);
+        assert_eq!(references[0].target, ReferenceTarget::Text("Call (#bob) later".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_reference() {
+        let items = vec![
+            ListItem::new_heading("Write".to_string(), 1),
+            ListItem::new_todo("Write report 📅 2024-05-01".to_string(), false, 0),
+            ListItem::new_todo("Book room 🆔 room".to_string(), false, 0),
+            ListItem::new_todo("Present [[write report]] in (#room)".to_string(), false, 0),
+        ];
+        let text = |text: &str| ReferenceTarget::Text(text.to_string());
+        assert_eq!(resolve_reference(&items, &text("write report"), 3), Some(1));
+        assert_eq!(resolve_reference(&items, &text("Write"), 3), Some(0));
+        assert_eq!(resolve_reference(&items, &ReferenceTarget::Id("room".to_string()), 3), Some(2));
+        assert_eq!(resolve_reference(&items, &ReferenceTarget::Id("room".to_string()), 2), None);
+        assert_eq!(resolve_reference(&items, &text("Nothing"), 3), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Three-way merge of item lists, for combining unsaved changes with changes
+//! another program made to the same file. Items are compared whole, like
+//! lines in a text merge.
+
+use crate::models::ListItem;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    pub items: Vec<ListItem>,
+    /// Stretches both sides changed differently; both versions are kept, ours
+    /// first
+    pub conflicts: usize,
+}
+
+/// Merges the changes from `base` to `ours` with those from `base` to `theirs`
+pub fn merge(base: &[ListItem], ours: &[ListItem], theirs: &[ListItem]) -> Merge {
+    let ours_by_base: HashMap<usize, usize> = common_items(base, ours).into_iter().collect();
+    // Base items both sides kept anchor the merge; what's between anchors is
+    // merged as a whole
+    let anchors = common_items(base, theirs)
+        .into_iter()
+        .filter_map(|(b, t)| ours_by_base.get(&b).map(|&o| (b, o, t)))
+        .chain([(base.len(), ours.len(), theirs.len())]);
+
+    let mut merged = Merge { items: Vec::new(), conflicts: 0 };
+    let (mut base_start, mut ours_start, mut theirs_start) = (0, 0, 0);
+    for (b, o, t) in anchors {
+        merged.merge_stretch(&base[base_start..b], &ours[ours_start..o], &theirs[theirs_start..t]);
+        if let Some(item) = base.get(b) {
+            merged.items.push(item.clone());
+        }
+        (base_start, ours_start, theirs_start) = (b + 1, o + 1, t + 1);
+    }
+    merged
+}
+
+impl Merge {
+    fn merge_stretch(&mut self, base: &[ListItem], ours: &[ListItem], theirs: &[ListItem]) {
+        if ours == theirs || theirs == base {
+            self.items.extend_from_slice(ours);
+        } else if ours == base {
+            self.items.extend_from_slice(theirs);
+        } else {
+            self.items.extend_from_slice(ours);
+            self.items.extend(theirs.iter().filter(|item| !ours.contains(item)).cloned());
+            self.conflicts += 1;
+        }
+    }
+}
+
+// Index pairs of a longest common subsequence of `a` and `b`, in order
+fn common_items(a: &[ListItem], b: &[ListItem]) -> Vec<(usize, usize)> {
+    // Most changes leave the start and end alone, which keeps the table small
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    // lengths[i][j]: the longest common subsequence of middle_a[i..] and middle_b[j..]
+    let width = middle_b.len() + 1;
+    let mut lengths = vec![0u32; (middle_a.len() + 1) * width];
+    for i in (0..middle_a.len()).rev() {
+        for j in (0..middle_b.len()).rev() {
+            lengths[i * width + j] = if middle_a[i] == middle_b[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < middle_a.len() && j < middle_b.len() {
+        if middle_a[i] == middle_b[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos(contents: &[&str]) -> Vec<ListItem> {
+        contents.iter().map(|content| ListItem::new_todo(content.to_string(), false, 0)).collect()
+    }
+
+    #[test]
+    fn test_merges_separate_changes() {
+        let base = todos(&["A", "B", "C", "D", "E"]);
+        let ours = todos(&["A", "B2", "C", "D", "E", "F"]);
+        let theirs = todos(&["Z", "A", "B", "C", "E"]);
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(merged.items, todos(&["Z", "A", "B2", "C", "E", "F"]));
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn test_same_change_on_both_sides() {
+        let base = todos(&["A", "B"]);
+        let both = todos(&["A", "B", "C"]);
+        assert_eq!(merge(&base, &both, &both), Merge { items: both.clone(), conflicts: 0 });
+    }
+
+    #[test]
+    fn test_conflicting_edits_keep_both() {
+        let base = todos(&["A", "B", "C"]);
+        let ours = todos(&["A", "Ours", "C"]);
+        let theirs = todos(&["A", "Theirs", "C"]);
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(merged.items, todos(&["A", "Ours", "Theirs", "C"]));
+        assert_eq!(merged.conflicts, 1);
+    }
+}
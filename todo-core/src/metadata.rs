@@ -0,0 +1,421 @@
+//! Inline task metadata stored in item content.
+//!
+//! Dates use the emoji markers of the Obsidian Tasks plugin (`📅 2024-05-01`),
+//! so files stay readable and compatible with other tools. A plain
+//! `due:2024-05-01` is accepted as well for people who'd rather not type emoji.
+//! Tags are `#words` anywhere in the content. Priorities use the plugin's
+//! markers too, from 🔺 (highest) to ⏬ (lowest), and so do dependencies: a
+//! todo names itself with `🆔 write-report` and waits on others with
+//! `⛔ write-report,book-room`. Time worked on a todo is kept as `⏱ 1h15m`.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+pub const DUE_MARKER: &str = "📅";
+pub const CREATED_MARKER: &str = "➕";
+pub const DONE_MARKER: &str = "✅";
+pub const ID_MARKER: &str = "🆔";
+pub const BLOCKED_BY_MARKER: &str = "⛔";
+pub const TIME_SPENT_MARKER: &str = "⏱";
+const DUE_PREFIX: &str = "due:";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TAG_MARKER: char = '#';
+const CONTEXT_MARKER: char = '@';
+
+/// How urgent an item is. Items without a priority marker count as `Normal`,
+/// which sits between medium and low as in Obsidian Tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Highest,
+    High,
+    Medium,
+    Normal,
+    Low,
+    Lowest,
+}
+
+const PRIORITY_MARKERS: [(&str, Priority); 5] = [
+    ("🔺", Priority::Highest),
+    ("⏫", Priority::High),
+    ("🔼", Priority::Medium),
+    ("🔽", Priority::Low),
+    ("⏬", Priority::Lowest),
+];
+
+pub fn priority(content: &str) -> Priority {
+    content
+        .split_whitespace()
+        .find_map(|word| PRIORITY_MARKERS.iter().find(|(marker, _)| *marker == word))
+        .map_or(Priority::Normal, |&(_, priority)| priority)
+}
+
+/// The date an item is due, if it has one
+pub fn due_date(content: &str) -> Option<NaiveDate> {
+    date_after_marker(content, DUE_MARKER).or_else(|| {
+        content
+            .split_whitespace()
+            .find_map(|word| word.strip_prefix(DUE_PREFIX).and_then(parse_date))
+    })
+}
+
+// Finds `<marker> YYYY-MM-DD` (or the date directly attached to the marker)
+fn date_after_marker(content: &str, marker: &str) -> Option<NaiveDate> {
+    let mut words = content.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == marker {
+            if let Some(date) = words.next().and_then(parse_date) {
+                return Some(date);
+            }
+        } else if let Some(date) = word.strip_prefix(marker).and_then(parse_date) {
+            return Some(date);
+        }
+    }
+    None
+}
+
+/// The date an item was created, if it was stamped with one
+pub fn created_date(content: &str) -> Option<NaiveDate> {
+    date_after_marker(content, CREATED_MARKER)
+}
+
+/// The content stamped with a creation date, unless it already has one
+pub fn stamp_created_date(content: &str, date: NaiveDate) -> String {
+    if created_date(content).is_some() {
+        return content.to_string();
+    }
+    append_word(content, &format!("{} {}", CREATED_MARKER, date.format(DATE_FORMAT)))
+}
+
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, DATE_FORMAT).ok()
+}
+
+/// Replaces the item's due date, or removes it when `date` is `None`. A new
+/// date is always written with the emoji marker.
+pub fn set_due_date(content: &str, date: Option<NaiveDate>) -> String {
+    let content = without_words(content, |word, next| {
+        match word.strip_prefix(DUE_PREFIX).and_then(parse_date) {
+            Some(_) => 1,
+            None => marked_date_words(word, next, DUE_MARKER),
+        }
+    });
+    with_marked_date(content, DUE_MARKER, date)
+}
+
+/// The date a todo was checked off, if it was stamped with one
+pub fn done_date(content: &str) -> Option<NaiveDate> {
+    date_after_marker(content, DONE_MARKER)
+}
+
+/// Replaces the date a todo was checked off, or removes it when `date` is `None`
+pub fn set_done_date(content: &str, date: Option<NaiveDate>) -> String {
+    let content = without_words(content, |word, next| marked_date_words(word, next, DONE_MARKER));
+    with_marked_date(content, DONE_MARKER, date)
+}
+
+// How many words a `<marker> YYYY-MM-DD` starting at `word` takes up, if any
+fn marked_date_words(word: &str, next: Option<&str>, marker: &str) -> usize {
+    if word == marker && next.and_then(parse_date).is_some() {
+        2
+    } else if word.strip_prefix(marker).and_then(parse_date).is_some() {
+        1
+    } else {
+        0
+    }
+}
+
+fn with_marked_date(content: String, marker: &str, date: Option<NaiveDate>) -> String {
+    match date {
+        Some(date) => append_word(&content, &format!("{} {}", marker, date.format(DATE_FORMAT))),
+        None => content,
+    }
+}
+
+/// The item's tags, without their `#`
+pub fn tags(content: &str) -> Vec<&str> {
+    marked_words(content, TAG_MARKER)
+}
+
+/// The item's `@contexts`, without their `@`
+pub fn contexts(content: &str) -> Vec<&str> {
+    marked_words(content, CONTEXT_MARKER)
+}
+
+fn marked_words(content: &str, marker: char) -> Vec<&str> {
+    content
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix(marker))
+        .filter(|name| is_tag_name(name))
+        .collect()
+}
+
+/// The tag `name` refers to, with or without its `#`, if it's a valid one
+pub fn normalize_tag(name: &str) -> Option<&str> {
+    let tag = name.strip_prefix(TAG_MARKER).unwrap_or(name);
+    is_tag_name(tag).then_some(tag)
+}
+
+/// The content with `#tag` added at the end, or `None` if it's already there
+pub fn add_tag(content: &str, tag: &str) -> Option<String> {
+    if tags(content).contains(&tag) {
+        return None;
+    }
+    Some(append_word(content, &format!("{}{}", TAG_MARKER, tag)))
+}
+
+/// The content with every `#tag` taken out, or `None` if it has none
+pub fn remove_tag(content: &str, tag: &str) -> Option<String> {
+    if !tags(content).contains(&tag) {
+        return None;
+    }
+    let marked = format!("{}{}", TAG_MARKER, tag);
+    Some(without_words(content, |word, _| usize::from(word == marked)))
+}
+
+/// The content with every `#from` changed to `#to` (or dropped, where `#to`
+/// is already there), or `None` if it has no `#from`
+pub fn rename_tag(content: &str, from: &str, to: &str) -> Option<String> {
+    if !tags(content).contains(&from) {
+        return None;
+    }
+    if tags(content).contains(&to) {
+        return remove_tag(content, from);
+    }
+    let (from, to) = (format!("{}{}", TAG_MARKER, from), format!("{}{}", TAG_MARKER, to));
+    Some(
+        words_with_spacing(content)
+            .map(|(word, spacing)| format!("{}{}", if word == from { &to } else { word }, spacing))
+            .collect(),
+    )
+}
+
+/// The id other todos can name to depend on this one
+pub fn id(content: &str) -> Option<&str> {
+    words_after_marker(content, ID_MARKER).next()
+}
+
+/// The ids of the todos this one waits on
+pub fn blocked_by(content: &str) -> Vec<&str> {
+    words_after_marker(content, BLOCKED_BY_MARKER)
+        .flat_map(|ids| ids.split(','))
+        .filter(|id| !id.is_empty())
+        .collect()
+}
+
+/// Minutes logged on the item with `⏱`, if any
+pub fn time_spent(content: &str) -> Option<u32> {
+    words_after_marker(content, TIME_SPENT_MARKER).find_map(parse_minutes)
+}
+
+/// The content with `minutes` more added to its `⏱` time
+pub fn add_time_spent(content: &str, minutes: u32) -> String {
+    let total = time_spent(content).unwrap_or(0).saturating_add(minutes);
+    let content = without_words(content, |word, next| {
+        if word == TIME_SPENT_MARKER && next.and_then(parse_minutes).is_some() {
+            2
+        } else {
+            usize::from(word.strip_prefix(TIME_SPENT_MARKER).and_then(parse_minutes).is_some())
+        }
+    });
+    append_word(&content, &format!("{} {}", TIME_SPENT_MARKER, format_minutes(total)))
+}
+
+/// Reads a time like `1h15m`, `2h` or `40m` as minutes
+pub fn parse_minutes(text: &str) -> Option<u32> {
+    let (hours, rest) = match text.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<u32>().ok()?, rest),
+        None => (0, text),
+    };
+    let minutes = match rest {
+        "" if text.ends_with('h') => 0,
+        _ => rest.strip_suffix('m')?.parse::<u32>().ok()?,
+    };
+    hours.checked_mul(60)?.checked_add(minutes)
+}
+
+/// Minutes written the way `parse_minutes` reads them
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+// The word after each `<marker>`, or attached to it
+fn words_after_marker<'a>(content: &'a str, marker: &'a str) -> impl Iterator<Item = &'a str> {
+    let mut words = content.split_whitespace();
+    std::iter::from_fn(move || {
+        while let Some(word) = words.next() {
+            if word == marker {
+                return words.next();
+            } else if let Some(rest) = word.strip_prefix(marker) {
+                return Some(rest);
+            }
+        }
+        None
+    })
+}
+
+/// Whether `tag` can follow a `#` or `@`: letters, digits, `-`, `_` and `/`
+pub fn is_tag_name(tag: &str) -> bool {
+    !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+}
+
+fn append_word(content: &str, word: &str) -> String {
+    let content = content.trim_end();
+    if content.is_empty() {
+        word.to_string()
+    } else {
+        format!("{} {}", content, word)
+    }
+}
+
+// Rebuilds the content line by line without the words `drop` picks out. It's
+// given each word and the one after it, and says how many words to drop.
+fn without_words(content: &str, drop: impl Fn(&str, Option<&str>) -> usize) -> String {
+    content
+        .lines()
+        .map(|line| {
+            let words: Vec<(&str, &str)> = words_with_spacing(line).collect();
+            let mut kept = String::new();
+            let mut index = 0;
+            while index < words.len() {
+                match drop(words[index].0, words.get(index + 1).map(|(word, _)| *word)) {
+                    0 => {
+                        kept.push_str(words[index].0);
+                        kept.push_str(words[index].1);
+                        index += 1;
+                    }
+                    count => index += count,
+                }
+            }
+            kept.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Each word with the whitespace character after it, so the words are those
+// `split_whitespace` finds (plus empty ones where whitespace repeats) and
+// joining them back gives the text unchanged
+fn words_with_spacing(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.split_inclusive(char::is_whitespace).map(|piece| {
+        let word = piece.trim_end_matches(char::is_whitespace);
+        (word, &piece[word.len()..])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_due_date_emoji_marker() {
+        assert_eq!(due_date("Pay rent 📅 2024-05-01"), Some(date(2024, 5, 1)));
+        assert_eq!(due_date("Pay rent 📅2024-05-01 #home"), Some(date(2024, 5, 1)));
+    }
+
+    #[test]
+    fn test_due_date_prefix() {
+        assert_eq!(due_date("Pay rent due:2024-05-01"), Some(date(2024, 5, 1)));
+    }
+
+    #[test]
+    fn test_set_due_date() {
+        assert_eq!(set_due_date("Pay rent", Some(date(2024, 5, 1))), "Pay rent 📅 2024-05-01");
+        assert_eq!(set_due_date("Pay rent 📅 2024-05-01 #home", Some(date(2024, 6, 1))), "Pay rent #home 📅 2024-06-01");
+        assert_eq!(set_due_date("Pay rent due:2024-05-01", None), "Pay rent");
+        assert_eq!(set_due_date("Pay rent 📅 someday", None), "Pay rent 📅 someday");
+    }
+
+    #[test]
+    fn test_set_done_date() {
+        let done = set_done_date("Pay rent 📅 2024-05-01", Some(date(2024, 4, 30)));
+        assert_eq!(done, "Pay rent 📅 2024-05-01 ✅ 2024-04-30");
+        assert_eq!(done_date(&done), Some(date(2024, 4, 30)));
+        assert_eq!(set_done_date(&done, None), "Pay rent 📅 2024-05-01");
+    }
+
+    #[test]
+    fn test_stamp_created_date() {
+        let stamped = stamp_created_date("Pay rent 📅 2024-06-01", date(2024, 5, 1));
+        assert_eq!(stamped, "Pay rent 📅 2024-06-01 ➕ 2024-05-01");
+        assert_eq!(created_date(&stamped), Some(date(2024, 5, 1)));
+        assert_eq!(due_date(&stamped), Some(date(2024, 6, 1)));
+        assert_eq!(stamp_created_date(&stamped, date(2024, 5, 2)), stamped);
+    }
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(priority("Fix prod 🔺 📅 2024-05-01"), Priority::Highest);
+        assert_eq!(priority("Tidy desk ⏬"), Priority::Lowest);
+        assert_eq!(priority("Water plants"), Priority::Normal);
+        assert!(Priority::Medium < Priority::Normal && Priority::Normal < Priority::Low);
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(tags("Call mum #home #phone"), vec!["home", "phone"]);
+        assert!(tags("Issue # 4, C#").is_empty());
+        assert_eq!(contexts("Call mum @phone me@example.com"), vec!["phone"]);
+        assert_eq!(rename_tag("Plan #work and #workshop", "work", "job").as_deref(), Some("Plan #job and #workshop"));
+        assert_eq!(rename_tag("Plan #work #job", "work", "job").as_deref(), Some("Plan #job"));
+        assert_eq!(rename_tag("Plan #job", "work", "job"), None);
+        assert_eq!(rename_tag("Plan\t#work\n#work", "work", "job").as_deref(), Some("Plan\t#job\n#job"));
+        assert_eq!(rename_tag("Plan\t#work #job", "work", "job").as_deref(), Some("Plan\t#job"));
+        assert_eq!(normalize_tag("#urgent"), Some("urgent"));
+        assert_eq!(normalize_tag("work/q3"), Some("work/q3"));
+        assert_eq!(normalize_tag("#"), None);
+        assert_eq!(normalize_tag("two words"), None);
+    }
+
+    #[test]
+    fn test_add_and_remove_tag() {
+        assert_eq!(add_tag("Call mum", "urgent"), Some("Call mum #urgent".to_string()));
+        assert_eq!(add_tag("", "urgent"), Some("#urgent".to_string()));
+        assert_eq!(add_tag("Call mum #urgent", "urgent"), None);
+
+        assert_eq!(remove_tag("Call #urgent mum #urgent", "urgent"), Some("Call mum".to_string()));
+        assert_eq!(remove_tag("Call mum #urgently", "urgent"), None);
+    }
+
+    #[test]
+    fn test_dependencies() {
+        assert_eq!(id("Write report 🆔 report ⛔ data,room"), Some("report"));
+        assert_eq!(blocked_by("Write report 🆔 report ⛔ data,room"), vec!["data", "room"]);
+        assert_eq!(blocked_by("Present ⛔report ⛔ slides"), vec!["report", "slides"]);
+        assert_eq!(id("Write report"), None);
+        assert!(blocked_by("Write report ⛔").is_empty());
+    }
+
+    #[test]
+    fn test_time_spent() {
+        assert_eq!(parse_minutes("1h15m"), Some(75));
+        assert_eq!(parse_minutes("2h"), Some(120));
+        assert_eq!(parse_minutes("40m"), Some(40));
+        assert_eq!(parse_minutes("h"), None);
+        assert_eq!(parse_minutes("soon"), None);
+        assert_eq!(format_minutes(75), "1h15m");
+
+        let logged = add_time_spent("Write report #work", 25);
+        assert_eq!(logged, "Write report #work ⏱ 25m");
+        assert_eq!(add_time_spent(&logged, 35), "Write report #work ⏱ 1h");
+        let most = format!("Write report ⏱ {}", format_minutes(u32::MAX));
+        assert_eq!(time_spent(&add_time_spent(&most, 25)), Some(u32::MAX));
+        assert_eq!(time_spent("Write ⏱45m report"), Some(45));
+        assert_eq!(time_spent("Write report"), None);
+    }
+
+    #[test]
+    fn test_missing_or_invalid_due_date() {
+        assert_eq!(due_date("Pay rent"), None);
+        assert_eq!(due_date("Pay rent 📅 someday"), None);
+        assert_eq!(due_date("Pay rent due:2024-13-01"), None);
+    }
+}
@@ -0,0 +1,353 @@
+//! Data structures for a parsed TODO file.
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// The state of a todo's checkbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoState {
+    /// `- [ ]`
+    #[default]
+    Open,
+    /// `- [x]`
+    Done,
+    /// `- [/]`
+    InProgress,
+    /// `- [-]`
+    Cancelled,
+    /// `- [>]`
+    Deferred,
+}
+
+impl TodoState {
+    /// The state written between the brackets, e.g. `/` for in progress
+    pub fn from_marker(marker: char) -> Option<Self> {
+        match marker {
+            ' ' => Some(Self::Open),
+            'x' | 'X' => Some(Self::Done),
+            '/' => Some(Self::InProgress),
+            '-' => Some(Self::Cancelled),
+            '>' => Some(Self::Deferred),
+            _ => None,
+        }
+    }
+
+    pub fn marker(self) -> char {
+        match self {
+            Self::Open => ' ',
+            Self::Done => 'x',
+            Self::InProgress => '/',
+            Self::Cancelled => '-',
+            Self::Deferred => '>',
+        }
+    }
+
+    /// Done and cancelled todos have nothing left to do
+    pub fn is_closed(self) -> bool {
+        matches!(self, Self::Done | Self::Cancelled)
+    }
+
+    /// The next state when cycling through all of them
+    pub fn next(self) -> Self {
+        match self {
+            Self::Open => Self::InProgress,
+            Self::InProgress => Self::Done,
+            Self::Done => Self::Cancelled,
+            Self::Cancelled => Self::Deferred,
+            Self::Deferred => Self::Open,
+        }
+    }
+}
+
+/// One line of a TODO file. In JSON it's an object tagged with its `type`,
+/// e.g. `{"type": "todo", "content": "...", "state": "open", "indent_level": 0}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ListItem {
+    Todo {
+        content: String,
+        state: TodoState,
+        indent_level: usize,
+    },
+    Note {
+        content: String,
+        indent_level: usize,
+    },
+    Heading {
+        content: String,
+        level: usize, // 1 for #, 2 for ##, etc.
+    },
+    /// Any line the parser doesn't understand (blank lines, paragraphs, code
+    /// blocks, frontmatter), kept verbatim so saving never loses content
+    Raw {
+        content: String,
+    },
+}
+
+impl ListItem {
+    pub fn new_todo(content: String, completed: bool, indent_level: usize) -> Self {
+        let state = if completed { TodoState::Done } else { TodoState::Open };
+        Self::new_todo_with_state(content, state, indent_level)
+    }
+
+    pub fn new_todo_with_state(content: String, state: TodoState, indent_level: usize) -> Self {
+        Self::Todo {
+            content,
+            state,
+            indent_level,
+        }
+    }
+
+    pub fn new_note(content: String, indent_level: usize) -> Self {
+        Self::Note {
+            content,
+            indent_level,
+        }
+    }
+
+    pub fn new_heading(content: String, level: usize) -> Self {
+        Self::Heading {
+            content,
+            level,
+        }
+    }
+
+    pub fn new_raw(content: String) -> Self {
+        Self::Raw { content }
+    }
+
+    pub fn content(&self) -> &str {
+        match self {
+            Self::Todo { content, .. } => content,
+            Self::Note { content, .. } => content,
+            Self::Heading { content, .. } => content,
+            Self::Raw { content } => content,
+        }
+    }
+
+    /// Mutable access to the item's text
+    pub fn content_mut(&mut self) -> &mut String {
+        match self {
+            Self::Todo { content, .. } => content,
+            Self::Note { content, .. } => content,
+            Self::Heading { content, .. } => content,
+            Self::Raw { content } => content,
+        }
+    }
+
+    /// Whether the item's content can span several lines, which are written
+    /// as indented continuation lines under it
+    pub fn allows_multiline(&self) -> bool {
+        matches!(self, Self::Todo { .. } | Self::Note { .. })
+    }
+
+    /// Whether this is a todo that is done or cancelled
+    pub fn is_completed(&self) -> bool {
+        match self {
+            Self::Todo { state, .. } => state.is_closed(),
+            Self::Note { .. } => false,
+            Self::Heading { .. } => false,
+            Self::Raw { .. } => false,
+        }
+    }
+
+    /// Indentation of list items; headings and raw lines have none
+    pub fn indent_level(&self) -> Option<usize> {
+        match self {
+            Self::Todo { indent_level, .. } | Self::Note { indent_level, .. } => Some(*indent_level),
+            Self::Heading { .. } | Self::Raw { .. } => None,
+        }
+    }
+
+    pub fn indent_level_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Self::Todo { indent_level, .. } | Self::Note { indent_level, .. } => Some(indent_level),
+            Self::Heading { .. } | Self::Raw { .. } => None,
+        }
+    }
+
+}
+
+/// How nesting is written in a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndentStyle {
+    /// This many spaces per level
+    Spaces(usize),
+    /// One tab per level
+    Tabs,
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        Self::Spaces(2)
+    }
+}
+
+impl IndentStyle {
+    /// The leading whitespace for an item at `level`
+    pub fn indent(self, level: usize) -> String {
+        match self {
+            Self::Spaces(width) => " ".repeat(width * level),
+            Self::Tabs => "\t".repeat(level),
+        }
+    }
+}
+
+/// Line separator used by a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// A parsed file. Its path isn't part of the serialized form, and the
+/// formatting fields may be left out, so a JSON list only needs `items`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodoList {
+    pub items: Vec<ListItem>,
+    #[serde(skip)]
+    pub file_path: String,
+    /// Indentation used by the file, reused when saving so diffs stay small
+    #[serde(default)]
+    pub indent_style: IndentStyle,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    /// Whether the file ends with a line ending
+    #[serde(default = "default_trailing_newline")]
+    pub trailing_newline: bool,
+}
+
+fn default_trailing_newline() -> bool {
+    true
+}
+
+impl TodoList {
+    pub fn new(file_path: String) -> Self {
+        Self {
+            items: Vec::new(),
+            file_path,
+            indent_style: IndentStyle::default(),
+            line_ending: LineEnding::default(),
+            trailing_newline: true,
+        }
+    }
+
+    pub fn add_item(&mut self, item: ListItem) {
+        self.items.push(item);
+    }
+
+    pub fn total_items(&self) -> usize {
+        self.items.iter().filter(|item| matches!(item, ListItem::Todo { .. })).count()
+    }
+
+    pub fn completed_items(&self) -> usize {
+        self.items.iter().filter(|item| item.is_completed()).count()
+    }
+
+    /// Finds the first heading whose text matches `name` (case-insensitive) and
+    /// returns the range of items in its section, including nested sub-headings.
+    /// The heading itself is not part of the range.
+    pub fn section_range(&self, name: &str) -> Option<Range<usize>> {
+        let name_lower = name.trim().to_lowercase();
+        let (heading_index, heading_level) = self.items.iter().enumerate().find_map(|(i, item)| match item {
+            ListItem::Heading { content, level } if content.to_lowercase() == name_lower => Some((i, *level)),
+            _ => None,
+        })?;
+
+        let end = self.items
+            .iter()
+            .enumerate()
+            .skip(heading_index + 1)
+            .find(|(_, item)| matches!(item, ListItem::Heading { level, .. } if *level <= heading_level))
+            .map(|(i, _)| i)
+            .unwrap_or(self.items.len());
+
+        Some(heading_index + 1..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_list() -> TodoList {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_heading("Work".to_string(), 1));
+        todo_list.add_item(ListItem::new_todo("Task A".to_string(), false, 0));
+        todo_list.add_item(ListItem::new_heading("Meetings".to_string(), 2));
+        todo_list.add_item(ListItem::new_todo("Task B".to_string(), true, 0));
+        todo_list.add_item(ListItem::new_heading("Personal".to_string(), 1));
+        todo_list.add_item(ListItem::new_todo("Task C".to_string(), false, 0));
+        todo_list
+    }
+
+    #[test]
+    fn test_todo_state_markers_round_trip() {
+        for state in [TodoState::Open, TodoState::Done, TodoState::InProgress, TodoState::Cancelled, TodoState::Deferred] {
+            assert_eq!(TodoState::from_marker(state.marker()), Some(state));
+        }
+        assert_eq!(TodoState::from_marker('X'), Some(TodoState::Done));
+        assert_eq!(TodoState::from_marker('?'), None);
+    }
+
+    #[test]
+    fn test_todo_state_cycle_visits_every_state() {
+        let mut state = TodoState::Open;
+        let mut seen = vec![state];
+        loop {
+            state = state.next();
+            if state == TodoState::Open {
+                break;
+            }
+            seen.push(state);
+        }
+        assert_eq!(seen.len(), 5);
+    }
+
+    #[test]
+    fn test_cancelled_todos_count_as_completed() {
+        let item = ListItem::new_todo_with_state("Dropped".to_string(), TodoState::Cancelled, 0);
+        assert!(item.is_completed());
+        let item = ListItem::new_todo_with_state("Started".to_string(), TodoState::InProgress, 0);
+        assert!(!item.is_completed());
+    }
+
+    #[test]
+    fn test_indent_style() {
+        assert_eq!(IndentStyle::default().indent(2), "    ");
+        assert_eq!(IndentStyle::Spaces(4).indent(1), "    ");
+        assert_eq!(IndentStyle::Tabs.indent(2), "\t\t");
+    }
+
+    #[test]
+    fn test_section_range_includes_subheadings() {
+        let todo_list = create_test_list();
+        assert_eq!(todo_list.section_range("Work"), Some(1..4));
+        assert_eq!(todo_list.section_range("meetings"), Some(3..4));
+    }
+
+    #[test]
+    fn test_section_range_last_section() {
+        let todo_list = create_test_list();
+        assert_eq!(todo_list.section_range("Personal"), Some(5..6));
+    }
+
+    #[test]
+    fn test_section_range_missing_heading() {
+        let todo_list = create_test_list();
+        assert_eq!(todo_list.section_range("Nonexistent"), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,521 @@
+//! Reading markdown into a [`TodoList`].
+
+use crate::models::{IndentStyle, LineEnding, ListItem, TodoList, TodoState};
+use anyhow::{Context, Result};
+use std::fs;
+
+pub fn parse_todo_file(file_path: &str) -> Result<TodoList> {
+    let content = fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read TODO file: {}", file_path))?;
+
+    Ok(parse_todo_content(file_path, &content))
+}
+
+pub fn parse_todo_content(file_path: &str, content: &str) -> TodoList {
+    let mut todo_list = TodoList::new(file_path.to_string());
+    todo_list.indent_style = detect_indent_style(content);
+    // Keep the file's conventions so saving doesn't rewrite every line
+    if content.contains("\r\n") {
+        todo_list.line_ending = LineEnding::CrLf;
+    }
+    todo_list.trailing_newline = content.is_empty() || content.ends_with('\n');
+    let mut in_yaml_frontmatter = false;
+    let mut code_fence: Option<&str> = None;
+    // Lines starting with this belong to the list item above them
+    let mut continuation_prefix: Option<String> = None;
+
+    for (line_number, line) in content.lines().enumerate() {
+        // Keep YAML frontmatter verbatim; it can only start on the first line
+        if line_number == 0 && line.trim() == "---" {
+            in_yaml_frontmatter = true;
+            todo_list.add_item(ListItem::new_raw(line.to_string()));
+            continue;
+        }
+        if in_yaml_frontmatter {
+            if line.trim() == "---" {
+                in_yaml_frontmatter = false;
+            }
+            todo_list.add_item(ListItem::new_raw(line.to_string()));
+            continue;
+        }
+
+        if let Some(prefix) = &continuation_prefix
+            && let Some(text) = line.strip_prefix(prefix.as_str())
+            && is_continuation_text(text)
+            && parse_line(line, todo_list.indent_style).is_none()
+            && let Some(item) = todo_list.items.last_mut()
+        {
+            let content = item.content_mut();
+            content.push('\n');
+            content.push_str(text);
+            continue;
+        }
+        continuation_prefix = None;
+
+        // Lines inside fenced code blocks are never list items
+        let trimmed = line.trim_start();
+        if let Some(fence) = code_fence {
+            if trimmed.starts_with(fence) {
+                code_fence = None;
+            }
+            todo_list.add_item(ListItem::new_raw(line.to_string()));
+            continue;
+        }
+        if let Some(fence) = ["```", "~~~"].into_iter().find(|fence| trimmed.starts_with(fence)) {
+            code_fence = Some(fence);
+            todo_list.add_item(ListItem::new_raw(line.to_string()));
+            continue;
+        }
+
+        let item = parse_line(line, todo_list.indent_style).unwrap_or_else(|| ListItem::new_raw(line.to_string()));
+        if item.allows_multiline() {
+            let leading_whitespace = &line[..line.len() - line.trim_start().len()];
+            continuation_prefix = Some(format!("{}{}", leading_whitespace, CONTINUATION_INDENT));
+        }
+        todo_list.add_item(item);
+    }
+
+    todo_list
+}
+
+/// Extra indentation of an item's continuation lines, relative to the item
+pub const CONTINUATION_INDENT: &str = "  ";
+
+// Indented text under an item continues it, unless it's blank or starts a
+// code block, which stays a separate raw line
+fn is_continuation_text(text: &str) -> bool {
+    let trimmed = text.trim();
+    !trimmed.is_empty() && !trimmed.starts_with("```") && !trimmed.starts_with("~~~")
+}
+
+/// Whether `text`, written on its own line under an item indented with
+/// `indent`, is read back as part of that item rather than as a line of its own
+pub fn continues_item(text: &str, indent: &str, indent_style: IndentStyle) -> bool {
+    is_continuation_text(text) && parse_line(&format!("{}{}{}", indent, CONTINUATION_INDENT, text), indent_style).is_none()
+}
+
+/// Works out whether a file indents its lists with tabs or with some number
+/// of spaces, from the first tab-indented item or the shallowest space indent
+pub fn detect_indent_style(content: &str) -> IndentStyle {
+    let mut min_spaces: Option<usize> = None;
+    for line in content.lines() {
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        if !trimmed.starts_with("- ") || trimmed.len() == line.len() {
+            continue;
+        }
+        if line.starts_with('\t') {
+            return IndentStyle::Tabs;
+        }
+        let spaces = line.len() - trimmed.len();
+        min_spaces = Some(min_spaces.map_or(spaces, |min| min.min(spaces)));
+    }
+    min_spaces.map_or(IndentStyle::default(), IndentStyle::Spaces)
+}
+
+fn parse_line(line: &str, indent_style: IndentStyle) -> Option<ListItem> {
+    let trimmed = line.trim();
+    
+    // Skip empty lines
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    // Check for headings first
+    if let Some((level, content)) = extract_heading_content(trimmed) {
+        return Some(ListItem::new_heading(content, level));
+    }
+
+    // Check for todo items
+    let trimmed_start = line.trim_start();
+    let indent_level = indent_level_for_style(line, indent_style);
+
+    // Check for checkbox patterns: - [ ], - [x] and the extended states like - [/].
+    // Unknown markers are left as raw lines so they survive a save unchanged.
+    if let Some(content) = extract_checkbox_content(trimmed_start) {
+        let state = checkbox_state(trimmed_start)?;
+        return Some(ListItem::new_todo_with_state(content, state, indent_level));
+    }
+
+    // Check for bullet points without checkboxes: - content
+    if let Some(content) = extract_bullet_content(trimmed_start) {
+        return Some(ListItem::new_note(content, indent_level));
+    }
+
+    None
+}
+
+/// Nesting level of a line given the file's indent style. Tabs are always one
+/// level; in tab-indented files, stray spaces count four to a level.
+pub fn indent_level_for_style(line: &str, indent_style: IndentStyle) -> usize {
+    let width = match indent_style {
+        IndentStyle::Spaces(width) => width.max(1),
+        IndentStyle::Tabs => 4,
+    };
+    let leading = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+    let tabs = leading.chars().filter(|&c| c == '\t').count();
+    let spaces = leading.len() - tabs;
+    tabs + spaces / width
+}
+
+/// Nesting level of a line in the default two-space style
+pub fn calculate_indent_level(line: &str) -> usize {
+    indent_level_for_style(line, IndentStyle::default())
+}
+
+fn extract_heading_content(line: &str) -> Option<(usize, String)> {
+    if line.starts_with('#') {
+        let level = line.chars().take_while(|&ch| ch == '#').count();
+        
+        // Markdown headings need a space after the #'s (otherwise it's e.g. a #tag)
+        let rest = &line[level..];
+        if level > 6 || !rest.starts_with([' ', '\t']) {
+            return None;
+        }
+        
+        let content = rest.trim_start();
+        if !content.is_empty() {
+            return Some((level, content.to_string()));
+        }
+    }
+    None
+}
+
+fn extract_checkbox_content(line: &str) -> Option<String> {
+    // Match patterns like "- [ ] content" or "- [x] content"
+    if line.starts_with("- [") && line.len() > 5 {
+        let checkbox_end = line.find(']')?;
+        // For "- [ ]" pattern, ] should be at position 4
+        // For "- [x]" pattern, ] should be at position 4 
+        if checkbox_end == 4 {
+            // Extract content after "]" (skip the space if present)
+            let start_pos = if line.len() > checkbox_end + 1 && line.chars().nth(checkbox_end + 1) == Some(' ') {
+                checkbox_end + 2
+            } else {
+                checkbox_end + 1
+            };
+            
+            if line.len() > start_pos {
+                let content = line[start_pos..].trim();
+                if !content.is_empty() {
+                    return Some(content.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn extract_bullet_content(line: &str) -> Option<String> {
+    // Match patterns like "- content" but NOT "- [ ]" or "- [x]"
+    if line.starts_with("- ") && line.len() > 2 {
+        // Make sure it's not a checkbox pattern
+        if line.len() > 4 && line.chars().nth(2) == Some('[') {
+            return None; // This is a checkbox, not a bullet note
+        }
+        
+        let content = line[2..].trim(); // Skip "- " and trim whitespace
+        if !content.is_empty() {
+            return Some(content.to_string());
+        }
+    }
+    None
+}
+
+fn checkbox_state(line: &str) -> Option<TodoState> {
+    TodoState::from_marker(line.chars().nth(3)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uncompleted_checkbox() {
+        let item = parse_line("- [ ] Buy groceries", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Todo { content, state, indent_level, .. } => {
+                assert_eq!(content, "Buy groceries");
+                assert_eq!(state, TodoState::Open);
+                assert_eq!(indent_level, 0);
+            }
+            _ => panic!("Expected Todo item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_completed_checkbox() {
+        let item = parse_line("- [x] Finish project", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Todo { content, state, indent_level, .. } => {
+                assert_eq!(content, "Finish project");
+                assert_eq!(state, TodoState::Done);
+                assert_eq!(indent_level, 0);
+            }
+            _ => panic!("Expected Todo item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_extended_checkbox_states() {
+        let cases = [
+            ("- [/] Writing", TodoState::InProgress),
+            ("- [-] Dropped", TodoState::Cancelled),
+            ("- [>] Next week", TodoState::Deferred),
+            ("- [X] Shouty", TodoState::Done),
+        ];
+        for (line, expected) in cases {
+            match parse_line(line, IndentStyle::default()) {
+                Some(ListItem::Todo { state, .. }) => assert_eq!(state, expected, "{}", line),
+                other => panic!("Expected Todo item for {:?}, got {:?}", line, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_checkbox_marker_is_kept_raw() {
+        let todo_list = parse_todo_content("test.md", "- [?] Maybe\n");
+        assert_eq!(todo_list.items, vec![ListItem::new_raw("- [?] Maybe".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_indented_checkbox() {
+        let item = parse_line("  - [ ] Subtask", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Todo { content, state, indent_level, .. } => {
+                assert_eq!(content, "Subtask");
+                assert_eq!(state, TodoState::Open);
+                assert_eq!(indent_level, 1);
+            }
+            _ => panic!("Expected Todo item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_heading() {
+        let item = parse_line("# Main Section", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Heading { content, level, .. } => {
+                assert_eq!(content, "Main Section");
+                assert_eq!(level, 1);
+            }
+            _ => panic!("Expected Heading item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_nested_heading() {
+        let item = parse_line("## Subsection", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Heading { content, level, .. } => {
+                assert_eq!(content, "Subsection");
+                assert_eq!(level, 2);
+            }
+            _ => panic!("Expected Heading item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_bullet_note() {
+        let item = parse_line("- This is a bullet note", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Note { content, indent_level, .. } => {
+                assert_eq!(content, "This is a bullet note");
+                assert_eq!(indent_level, 0);
+            }
+            _ => panic!("Expected Note item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_indented_bullet_note() {
+        let item = parse_line("  - This is an indented note", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Note { content, indent_level, .. } => {
+                assert_eq!(content, "This is an indented note");
+                assert_eq!(indent_level, 1);
+            }
+            _ => panic!("Expected Note item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_non_checkbox_line() {
+        let item = parse_line("This is just a note", IndentStyle::default());
+        assert!(item.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_checkbox() {
+        let item = parse_line("- [invalid] content", IndentStyle::default());
+        assert!(item.is_none());
+    }
+
+    #[test]
+    fn test_parse_tab_indented_checkbox() {
+        let item = parse_line("\t- [ ] Tab indented task", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Todo { content, state, indent_level, .. } => {
+                assert_eq!(content, "Tab indented task");
+                assert_eq!(state, TodoState::Open);
+                assert_eq!(indent_level, 1);
+            }
+            _ => panic!("Expected Todo item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_double_tab_indented_checkbox() {
+        let item = parse_line("\t\t- [ ] Double tab indented task", IndentStyle::default());
+        assert!(item.is_some());
+        let item = item.unwrap();
+        match item {
+            ListItem::Todo { content, state, indent_level, .. } => {
+                assert_eq!(content, "Double tab indented task");
+                assert_eq!(state, TodoState::Open);
+                assert_eq!(indent_level, 2);
+            }
+            _ => panic!("Expected Todo item"),
+        }
+    }
+
+    #[test]
+    fn test_parse_detects_line_endings() {
+        let todo_list = parse_todo_content("test.md", "# Work\r\n- [ ] Task\r\n");
+        assert_eq!(todo_list.line_ending, LineEnding::CrLf);
+        assert!(todo_list.trailing_newline);
+        assert_eq!(todo_list.items[1].content(), "Task");
+
+        let todo_list = parse_todo_content("test.md", "# Work\n- [ ] Task");
+        assert_eq!(todo_list.line_ending, LineEnding::Lf);
+        assert!(!todo_list.trailing_newline);
+    }
+
+    #[test]
+    fn test_parse_continuation_lines() {
+        let todo_list = parse_todo_content(
+            "test.md",
+            "- [ ] Plan trip\n  book flights\n  and hotel\n  - [ ] Pack\n    first check the weather\n\n  after a blank line\n",
+        );
+        
+        assert_eq!(todo_list.items[0].content(), "Plan trip\nbook flights\nand hotel");
+        assert_eq!(todo_list.items[1].content(), "Pack\nfirst check the weather");
+        // A blank line ends the item
+        assert!(matches!(todo_list.items[3], ListItem::Raw { .. }));
+        assert_eq!(todo_list.items.len(), 4);
+    }
+
+    #[test]
+    fn test_detect_indent_style() {
+        assert_eq!(detect_indent_style("- [ ] Top\n  - [ ] Child\n    - [ ] Grandchild\n"), IndentStyle::Spaces(2));
+        assert_eq!(detect_indent_style("- [ ] Top\n    - [ ] Child\n"), IndentStyle::Spaces(4));
+        assert_eq!(detect_indent_style("- [ ] Top\n\t- [ ] Child\n"), IndentStyle::Tabs);
+        // Indented paragraphs don't count, and flat lists get the default
+        assert_eq!(detect_indent_style("    some code\n- [ ] Top\n"), IndentStyle::default());
+    }
+
+    #[test]
+    fn test_parse_four_space_indented_file() {
+        let todo_list = parse_todo_content("test.md", "- [ ] Top\n    - [ ] Child\n        - Note\n");
+        assert_eq!(todo_list.indent_style, IndentStyle::Spaces(4));
+        let levels: Vec<_> = todo_list.items.iter().map(|item| item.indent_level()).collect();
+        assert_eq!(levels, vec![Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_calculate_indent_level() {
+        assert_eq!(calculate_indent_level("- [ ] No indent"), 0);
+        assert_eq!(calculate_indent_level("  - [ ] Two spaces"), 1);
+        assert_eq!(calculate_indent_level("    - [ ] Four spaces"), 2);
+        assert_eq!(calculate_indent_level("\t- [ ] One tab"), 1);
+        assert_eq!(calculate_indent_level("\t\t- [ ] Two tabs"), 2);
+    }
+
+    #[test]
+    fn test_roundtrip_with_notes() {
+        use crate::writer;
+        use std::fs;
+        
+        // Create test content with notes
+        let original_content = "# Test Project\n\n- [ ] First task\n- This is a note\n  - Nested note\n- [x] Completed task\n  - [ ] Subtask\n  - Another note under task\n";
+        
+        // Create temporary file
+        let temp_file = "/tmp/test_notes_roundtrip.md";
+        fs::write(temp_file, original_content).unwrap();
+        
+        // Parse the file
+        let todo_list = parse_todo_file(temp_file).unwrap();
+        
+        // Verify we parsed the correct number of items
+        assert_eq!(todo_list.items.len(), 8); // 1 heading + 1 blank line + 6 items
+        
+        // Verify the types are correct
+        assert!(matches!(todo_list.items[0], ListItem::Heading { .. }));
+        assert!(matches!(todo_list.items[1], ListItem::Raw { .. })); // blank line
+        assert!(matches!(todo_list.items[2], ListItem::Todo { .. }));
+        assert!(matches!(todo_list.items[3], ListItem::Note { .. }));
+        assert!(matches!(todo_list.items[4], ListItem::Note { .. })); // nested note
+        assert!(matches!(todo_list.items[5], ListItem::Todo { .. }));
+        assert!(matches!(todo_list.items[6], ListItem::Todo { .. })); // subtask
+        assert!(matches!(todo_list.items[7], ListItem::Note { .. })); // note under task
+        
+        // Serialize it back
+        let serialized = writer::serialize_todo_list(&todo_list);
+        
+        // The output should contain all the essential information
+        assert!(serialized.contains("# Test Project"));
+        assert!(serialized.contains("- [ ] First task"));
+        assert!(serialized.contains("- This is a note"));
+        assert!(serialized.contains("  - Nested note"));
+        assert!(serialized.contains("- [x] Completed task"));
+        assert!(serialized.contains("  - [ ] Subtask"));
+        assert!(serialized.contains("  - Another note under task"));
+        
+        // Clean up
+        fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_roundtrip_preserves_unrecognized_lines() {
+        use crate::writer;
+        
+        let original_content = "---\ntitle: Tasks\n---\n# Project\n\nSome intro paragraph.\n\n- [ ] First task\n```\n- [ ] not a task\n```\n---\n- [invalid] content\n#hashtag\n";
+        let todo_list = parse_todo_content("test.md", original_content);
+        
+        // Only the real heading and todo are structured items
+        let structured = todo_list.items.iter().filter(|item| !matches!(item, ListItem::Raw { .. })).count();
+        assert_eq!(structured, 2);
+        
+        assert_eq!(writer::serialize_todo_list(&todo_list), original_content);
+    }
+
+    #[test]
+    fn test_parse_code_block_contents_as_raw() {
+        let todo_list = parse_todo_content("test.md", "~~~\n# not a heading\n- [x] not a todo\n~~~\n- [ ] Real task\n");
+        
+        assert_eq!(todo_list.items.len(), 5);
+        assert!(todo_list.items[..4].iter().all(|item| matches!(item, ListItem::Raw { .. })));
+        assert!(matches!(todo_list.items[4], ListItem::Todo { .. }));
+    }
+
+    #[test]
+    fn test_parse_heading_requires_space() {
+        assert!(parse_line("#hashtag", IndentStyle::default()).is_none());
+        assert!(parse_line("####### Too deep", IndentStyle::default()).is_none());
+    }
+}
\ No newline at end of file
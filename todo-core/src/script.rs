@@ -0,0 +1,232 @@
+//! Operations on a list read from a script, for `todo exec`. A script is
+//! either JSON (one operation object or an array of them) or one operation
+//! per line:
+//!
+//! ```text
+//! add Buy milk                # after the items above the first heading
+//! add "Work" Write the report # at the end of the Work section
+//! toggle 3
+//! move 3 up                   # or down, or to Work
+//! indent 4
+//! unindent 4
+//! ```
+//!
+//! Items are referred to by their index in the list, as in
+//! `todo export --format json`. Blank lines and `#` comments are skipped.
+
+use crate::actions::ItemActions;
+use crate::models::{ListItem, TodoList};
+use crate::structure::ItemCreator;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    Add {
+        content: String,
+        #[serde(default)]
+        heading: Option<String>,
+    },
+    Toggle { item: usize },
+    Move { item: usize, to: MoveTarget },
+    Indent { item: usize },
+    Unindent { item: usize },
+}
+
+/// In JSON, `"up"`, `"down"` or `{"heading": "Work"}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveTarget {
+    Up,
+    Down,
+    Heading(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonScript {
+    Many(Vec<Operation>),
+    One(Operation),
+}
+
+pub fn parse_script(script: &str) -> Result<Vec<Operation>> {
+    let trimmed = script.trim_start();
+    if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        return match serde_json::from_str(trimmed).context("Invalid JSON script")? {
+            JsonScript::Many(operations) => Ok(operations),
+            JsonScript::One(operation) => Ok(vec![operation]),
+        };
+    }
+    script
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number, strip_comment(line).trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| parse_line(line).with_context(|| format!("Line {}", number + 1)))
+        .collect()
+}
+
+// `#` starts a comment unless it's part of a word, like a `#tag`
+fn strip_comment(line: &str) -> &str {
+    let comment = line
+        .char_indices()
+        .find(|&(i, c)| c == '#' && (i == 0 || line[..i].ends_with(char::is_whitespace)) && !line[i + 1..].starts_with(|c: char| c.is_alphanumeric()));
+    comment.map_or(line, |(i, _)| &line[..i])
+}
+
+fn parse_line(line: &str) -> Result<Operation> {
+    let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    match name {
+        "add" => {
+            let (heading, content) = match rest.strip_prefix('"').and_then(|quoted| quoted.split_once('"')) {
+                Some((heading, content)) => (Some(heading.to_string()), content.trim()),
+                None => (None, rest),
+            };
+            if content.is_empty() {
+                bail!("add needs the text of the todo");
+            }
+            Ok(Operation::Add { content: content.to_string(), heading })
+        }
+        "toggle" => Ok(Operation::Toggle { item: parse_index(rest)? }),
+        "indent" => Ok(Operation::Indent { item: parse_index(rest)? }),
+        "unindent" => Ok(Operation::Unindent { item: parse_index(rest)? }),
+        "move" => {
+            let (item, target) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let to = match target.trim() {
+                "up" => MoveTarget::Up,
+                "down" => MoveTarget::Down,
+                target => match target.strip_prefix("to ") {
+                    Some(heading) => MoveTarget::Heading(heading.trim().trim_matches('"').to_string()),
+                    None => bail!("move needs up, down or to <heading>"),
+                },
+            };
+            Ok(Operation::Move { item: parse_index(item)?, to })
+        }
+        _ => bail!("Unknown operation '{}'", name),
+    }
+}
+
+fn parse_index(text: &str) -> Result<usize> {
+    text.parse().map_err(|_| anyhow!("Expected an item index, got '{}'", text))
+}
+
+/// Applies one operation, returning the index the affected item ended up at
+pub fn apply(todo_list: &mut TodoList, operation: &Operation) -> Result<usize> {
+    let len = todo_list.items.len();
+    let check = |item: usize| {
+        if item < len {
+            Ok(item)
+        } else {
+            Err(anyhow!("No item {} (the list has {})", item, len))
+        }
+    };
+    // Looked up before `items` borrows the list
+    let heading_index = match operation {
+        Operation::Move { to: MoveTarget::Heading(heading), .. } => Some(
+            todo_list
+                .section_range(heading)
+                .with_context(|| format!("No heading '{}'", heading))?
+                .start
+                - 1,
+        ),
+        _ => None,
+    };
+    let items = &mut todo_list.items;
+    match operation {
+        Operation::Add { content, heading } => {
+            let index = ItemCreator::end_of_section(items, heading.as_deref())
+                .with_context(|| format!("No heading '{}'", heading.as_deref().unwrap_or_default()))?;
+            items.insert(index, ListItem::new_todo(content.clone(), false, 0));
+            Ok(index)
+        }
+        Operation::Toggle { item } => {
+            let item = check(*item)?;
+            if !ItemActions::toggle_todo_completion(items, item) {
+                bail!("Item {} isn't a todo", item);
+            }
+            Ok(item)
+        }
+        Operation::Move { item, to } => {
+            let item = check(*item)?;
+            let moved = match to {
+                MoveTarget::Up => ItemActions::move_single_item_up(items, item),
+                MoveTarget::Down => ItemActions::move_single_item_down(items, item),
+                MoveTarget::Heading(_) => {
+                    ItemActions::move_items_to_section(items, &HashSet::from([item]), heading_index.unwrap_or_default())
+                }
+            };
+            moved.with_context(|| format!("Item {} can't be moved there", item))
+        }
+        Operation::Indent { item } => {
+            let item = check(*item)?;
+            if !ItemActions::indent_block(items, item) {
+                bail!("Item {} can't be indented further", item);
+            }
+            Ok(item)
+        }
+        Operation::Unindent { item } => {
+            let item = check(*item)?;
+            if !ItemActions::unindent_block(items, item) {
+                bail!("Item {} can't be unindented further", item);
+            }
+            Ok(item)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_todo_content;
+    use crate::writer::serialize_todo_list;
+
+    #[test]
+    fn test_parse_dsl() {
+        let script = "# set up\nadd Buy milk #errands\nadd \"Work\" Write the report\n\ntoggle 3  # done\nmove 2 up\nmove 2 to \"Home\"\nindent 4\nunindent 4\n";
+        assert_eq!(parse_script(script).unwrap(), vec![
+            Operation::Add { content: "Buy milk #errands".to_string(), heading: None },
+            Operation::Add { content: "Write the report".to_string(), heading: Some("Work".to_string()) },
+            Operation::Toggle { item: 3 },
+            Operation::Move { item: 2, to: MoveTarget::Up },
+            Operation::Move { item: 2, to: MoveTarget::Heading("Home".to_string()) },
+            Operation::Indent { item: 4 },
+            Operation::Unindent { item: 4 },
+        ]);
+
+        let error = parse_script("toggle 1\ntoggle first\n").unwrap_err();
+        assert_eq!(format!("{:#}", error), "Line 2: Expected an item index, got 'first'");
+        assert!(parse_script("explode 1").is_err());
+        assert!(parse_script("move 1 sideways").is_err());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let script = r#"[{"op": "add", "content": "Call", "heading": "Work"}, {"op": "move", "item": 1, "to": {"heading": "Home"}}]"#;
+        assert_eq!(parse_script(script).unwrap(), vec![
+            Operation::Add { content: "Call".to_string(), heading: Some("Work".to_string()) },
+            Operation::Move { item: 1, to: MoveTarget::Heading("Home".to_string()) },
+        ]);
+        assert_eq!(parse_script(r#"{"op": "toggle", "item": 2}"#).unwrap(), vec![Operation::Toggle { item: 2 }]);
+        assert!(parse_script(r#"{"op": "toggle"}"#).is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut list = parse_todo_content("TODO.md", "- [ ] Loose\n\n# Work\n- [ ] Report\n\n# Home\n- [ ] Laundry\n");
+        let script = "add \"Work\" Slides\ntoggle 3\nindent 4\nmove 7 up\nmove 0 to Home\n";
+        let indices: Vec<usize> = parse_script(script)
+            .unwrap()
+            .iter()
+            .map(|operation| apply(&mut list, operation).unwrap())
+            .collect();
+        assert_eq!(indices, vec![4, 3, 4, 6, 7]);
+        assert_eq!(serialize_todo_list(&list), "\n# Work\n- [x] Report\n  - [ ] Slides\n\n- [ ] Laundry\n# Home\n- [ ] Loose\n");
+
+        assert!(apply(&mut list, &Operation::Toggle { item: 1 }).is_err());
+        assert!(apply(&mut list, &Operation::Toggle { item: 99 }).is_err());
+        assert!(apply(&mut list, &Operation::Add { content: "x".to_string(), heading: Some("Nope".to_string()) }).is_err());
+    }
+}
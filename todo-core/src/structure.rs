@@ -0,0 +1,502 @@
+//! How items nest under each other and under headings.
+
+use crate::metadata;
+use crate::models::ListItem;
+use crate::parser::calculate_indent_level;
+use std::collections::HashSet;
+
+/// Queries about how items relate to each other: blocks of nested items,
+/// heading sections, and where new items should go.
+pub struct ItemCreator;
+
+impl ItemCreator {
+    pub fn find_current_heading_context(items: &[ListItem], selected_index: usize) -> usize {
+        if items.is_empty() {
+            return 0;
+        }
+
+        // Look backwards from current position to find the most recent heading
+        for i in (0..=selected_index).rev() {
+            if let Some(ListItem::Heading { .. }) = items.get(i) {
+                // Found a heading, insert right after it
+                return i + 1;
+            }
+        }
+        
+        // No heading found above current position, insert at the very top
+        0
+    }
+
+    pub fn get_block_range(items: &[ListItem], start_index: usize) -> (usize, usize) {
+        if start_index >= items.len() {
+            return (start_index, start_index);
+        }
+
+        let start_item = &items[start_index];
+        let base_indent = match start_item {
+            ListItem::Todo { indent_level, .. } => *indent_level,
+            ListItem::Note { indent_level, .. } => *indent_level,
+            ListItem::Heading { .. } => 0,
+            ListItem::Raw { .. } => return (start_index, start_index),
+        };
+
+        let mut end_index = start_index;
+        
+        // Find all items that belong to this block
+        for (i, item) in items.iter().enumerate().skip(start_index + 1) {
+            match item {
+                ListItem::Todo { indent_level, .. } => {
+                    if *indent_level > base_indent {
+                        // This item is nested under the current item
+                        end_index = i;
+                    } else {
+                        // We've reached a sibling or parent, stop here
+                        break;
+                    }
+                }
+                ListItem::Note { indent_level, .. } => {
+                    if *indent_level > base_indent {
+                        // This item is nested under the current item
+                        end_index = i;
+                    } else {
+                        // We've reached a sibling or parent, stop here
+                        break;
+                    }
+                }
+                ListItem::Heading { .. } => {
+                    // Headings always break blocks
+                    break;
+                }
+                ListItem::Raw { content } => {
+                    // Indented text (e.g. a continuation paragraph) belongs to the
+                    // item above it, but blank lines and other content end the block
+                    if !content.trim().is_empty() && calculate_indent_level(content) > base_indent {
+                        end_index = i;
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+
+        (start_index, end_index)
+    }
+
+    /// The item that the item at `index` is nested under, if any
+    pub fn find_parent(items: &[ListItem], index: usize) -> Option<usize> {
+        let indent = items.get(index)?.indent_level()?;
+        for i in (0..index).rev() {
+            match &items[i] {
+                ListItem::Heading { .. } => return None,
+                item => match item.indent_level() {
+                    Some(level) if level < indent => {
+                        let (_, end) = Self::get_block_range(items, i);
+                        return (end >= index).then_some(i);
+                    }
+                    _ => {}
+                },
+            }
+        }
+        None
+    }
+
+    /// The headings whose sections contain the item at `index` (the item
+    /// itself, if it's a heading), outermost first
+    pub fn heading_trail(items: &[ListItem], index: usize) -> Vec<usize> {
+        let mut trail = Vec::new();
+        let mut deepest = usize::MAX;
+        for i in (0..=index.min(items.len().saturating_sub(1))).rev() {
+            if let Some(ListItem::Heading { level, .. }) = items.get(i)
+                && *level < deepest
+            {
+                trail.push(i);
+                deepest = *level;
+            }
+        }
+        trail.reverse();
+        trail
+    }
+
+    /// The items at `indices` along with the headings of their sections, for
+    /// showing just those items in context
+    pub fn with_headings(items: &[ListItem], indices: impl IntoIterator<Item = usize>) -> HashSet<usize> {
+        let mut shown = HashSet::new();
+        for index in indices {
+            shown.insert(index);
+            shown.extend(Self::heading_trail(items, index));
+        }
+        shown
+    }
+
+    /// Open todos that wait (`⛔ id`) on a todo with that `🆔 id` which isn't
+    /// closed yet. Ids no todo has don't block anything.
+    pub fn blocked_todos(items: &[ListItem]) -> HashSet<usize> {
+        let open_todo = |item: &ListItem| matches!(item, ListItem::Todo { .. }) && !item.is_completed();
+        let open_ids: HashSet<&str> = items
+            .iter()
+            .filter(|item| open_todo(item))
+            .filter_map(|item| metadata::id(item.content()))
+            .collect();
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| open_todo(item))
+            .filter(|(_, item)| metadata::blocked_by(item.content()).iter().any(|id| open_ids.contains(id)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Expands each index to its whole block (the item and everything nested
+    /// under it), returning the sorted, de-duplicated indices.
+    pub fn get_block_indices(items: &[ListItem], indices: &[usize]) -> Vec<usize> {
+        let mut block_indices: Vec<usize> = indices
+            .iter()
+            .filter(|&&i| i < items.len())
+            .flat_map(|&i| {
+                let (start, end) = Self::get_block_range(items, i);
+                start..=end
+            })
+            .collect();
+        block_indices.sort();
+        block_indices.dedup();
+        block_indices
+    }
+
+    /// Like `get_block_range`, but a heading's range covers its whole section
+    /// (up to the next heading of the same or a higher level).
+    pub fn get_fold_range(items: &[ListItem], start_index: usize) -> (usize, usize) {
+        if let Some(ListItem::Heading { level, .. }) = items.get(start_index) {
+            let end_index = items
+                .iter()
+                .enumerate()
+                .skip(start_index + 1)
+                .find(|(_, item)| matches!(item, ListItem::Heading { level: next_level, .. } if next_level <= level))
+                .map(|(i, _)| i - 1)
+                .unwrap_or(items.len() - 1);
+            return (start_index, end_index);
+        }
+        
+        Self::get_block_range(items, start_index)
+    }
+
+    /// Whether two items are the same entry, ignoring completion state and
+    /// indentation which change without the item becoming a different one.
+    pub fn is_same_item(a: &ListItem, b: &ListItem) -> bool {
+        std::mem::discriminant(a) == std::mem::discriminant(b) && a.content() == b.content()
+    }
+
+    pub fn create_new_todo(content: String, completed: bool, indent_level: usize) -> ListItem {
+        ListItem::new_todo(content, completed, indent_level)
+    }
+
+    pub fn create_new_note(content: String, indent_level: usize) -> ListItem {
+        ListItem::new_note(content, indent_level)
+    }
+
+    pub fn determine_insert_position_for_new_todo(
+        items: &[ListItem],
+        selected_index: usize,
+    ) -> (usize, usize) {
+        if items.is_empty() {
+            return (0, 0); // Position 0, indent level 0
+        }
+
+        if selected_index >= items.len() {
+            return (items.len(), 0);
+        }
+
+        let current_item = &items[selected_index];
+        
+        match current_item {
+            ListItem::Todo { indent_level: current_indent, .. } |
+            ListItem::Note { indent_level: current_indent, .. } => {
+                // Check if this item has children
+                let (_, block_end) = Self::get_block_range(items, selected_index);
+                
+                if block_end > selected_index {
+                    // This item has children, add new child after the last child
+                    let child_indent = current_indent + 1;
+                    (block_end + 1, child_indent)
+                } else {
+                    // This item has no children, add sibling with same indentation
+                    (selected_index + 1, *current_indent)
+                }
+            }
+            ListItem::Heading { .. } | ListItem::Raw { .. } => {
+                // New todos under headings start at level 0
+                (selected_index + 1, 0)
+            }
+        }
+    }
+
+    /// Finds the item equal to `target` closest to `near`, used to keep the
+    /// cursor on the same item when the list is replaced underneath it.
+    pub fn find_nearest_matching_item(items: &[ListItem], target: &ListItem, near: usize) -> Option<usize> {
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| *item == target)
+            .min_by_key(|(i, _)| i.abs_diff(near))
+            .map(|(i, _)| i)
+    }
+
+    /// Where a new sibling of the item at `selected_index` goes: right above
+    /// it, or right below it and anything nested under it. Returns the
+    /// position and the sibling's indent level.
+    pub fn determine_sibling_insert_position(items: &[ListItem], selected_index: usize, above: bool) -> (usize, usize) {
+        let Some(item) = items.get(selected_index) else {
+            return (items.len(), 0);
+        };
+        let indent = item.indent_level().unwrap_or(0);
+        if above {
+            (selected_index, indent)
+        } else {
+            (Self::get_block_range(items, selected_index).1 + 1, indent)
+        }
+    }
+
+    pub fn determine_insert_position_for_new_todo_at_top(
+        items: &[ListItem],
+        selected_index: usize,
+    ) -> usize {
+        Self::find_current_heading_context(items, selected_index)
+    }
+
+    /// Where an item appended to a section goes: after the items directly
+    /// under `heading` (matched ignoring case), or after those above the first
+    /// heading for `None`, staying above the blank lines before the next
+    /// heading. `None` if there's no such heading.
+    pub fn end_of_section(items: &[ListItem], heading: Option<&str>) -> Option<usize> {
+        let is_heading = |item: &ListItem| matches!(item, ListItem::Heading { .. });
+        let start = match heading {
+            Some(name) => items.iter().position(|item| is_heading(item) && item.content().eq_ignore_ascii_case(name))? + 1,
+            None => 0,
+        };
+        let mut end = items[start..].iter().position(is_heading).map_or(items.len(), |offset| start + offset);
+        while end > start && matches!(&items[end - 1], ListItem::Raw { content } if content.trim().is_empty()) {
+            end -= 1;
+        }
+        Some(end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_trail() {
+        let items = vec![
+            ListItem::new_todo("Loose".to_string(), false, 0),
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_heading("Project X".to_string(), 2),
+            ListItem::new_todo("Task".to_string(), false, 0),
+            ListItem::new_heading("Other".to_string(), 2),
+        ];
+        assert_eq!(ItemCreator::heading_trail(&items, 0), Vec::<usize>::new());
+        assert_eq!(ItemCreator::heading_trail(&items, 3), vec![1, 2]);
+        assert_eq!(ItemCreator::heading_trail(&items, 4), vec![1, 4]);
+        assert_eq!(ItemCreator::heading_trail(&[], 0), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_with_headings() {
+        let items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_heading("Project X".to_string(), 2),
+            ListItem::new_todo("Task".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Dishes".to_string(), false, 0),
+        ];
+        assert_eq!(ItemCreator::with_headings(&items, [2]), HashSet::from([0, 1, 2]));
+        assert_eq!(ItemCreator::with_headings(&items, [4, 2]), HashSet::from([0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_blocked_todos() {
+        let items = vec![
+            ListItem::new_todo("Gather data 🆔 data".to_string(), false, 0),
+            ListItem::new_todo("Book room 🆔 room".to_string(), true, 0),
+            ListItem::new_todo("Write report ⛔ data,room".to_string(), false, 0),
+            ListItem::new_todo("Present ⛔ room".to_string(), false, 0),
+            ListItem::new_todo("Celebrate ⛔ data".to_string(), true, 0),
+            ListItem::new_todo("Mystery ⛔ nowhere".to_string(), false, 0),
+        ];
+        assert_eq!(ItemCreator::blocked_todos(&items), HashSet::from([2]));
+    }
+
+    #[test]
+    fn test_find_current_heading_context() {
+        let items = vec![
+            ListItem::new_todo("Task 1".to_string(), false, 0),
+            ListItem::new_heading("Section A".to_string(), 1),
+            ListItem::new_todo("Task 2".to_string(), false, 0),
+            ListItem::new_heading("Section B".to_string(), 1),
+            ListItem::new_todo("Task 3".to_string(), false, 0),
+        ];
+        
+        // When selected on Task 3 (index 4), should find Section B (index 3) and return 4
+        let context = ItemCreator::find_current_heading_context(&items, 4);
+        assert_eq!(context, 4); // Insert after Section B
+        
+        // When selected on Task 2 (index 2), should find Section A (index 1) and return 2
+        let context = ItemCreator::find_current_heading_context(&items, 2);
+        assert_eq!(context, 2); // Insert after Section A
+        
+        // When selected on Task 1 (index 0), no heading above, should return 0
+        let context = ItemCreator::find_current_heading_context(&items, 0);
+        assert_eq!(context, 0); // Insert at top
+    }
+
+    #[test]
+    fn test_end_of_section() {
+        let items = vec![
+            ListItem::new_todo("Loose".to_string(), false, 0),
+            ListItem::new_raw(String::new()),
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Report".to_string(), false, 0),
+            ListItem::new_heading("Meetings".to_string(), 2),
+        ];
+        assert_eq!(ItemCreator::end_of_section(&items, None), Some(1));
+        assert_eq!(ItemCreator::end_of_section(&items, Some("work")), Some(4));
+        assert_eq!(ItemCreator::end_of_section(&items, Some("Meetings")), Some(5));
+        assert_eq!(ItemCreator::end_of_section(&items, Some("Home")), None);
+    }
+
+    #[test]
+    fn test_get_block_range() {
+        let items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child 1".to_string(), false, 1),
+            ListItem::new_todo("Child 2".to_string(), false, 1),
+            ListItem::new_todo("Next parent".to_string(), false, 0),
+        ];
+        
+        let (start, end) = ItemCreator::get_block_range(&items, 0);
+        assert_eq!(start, 0);
+        assert_eq!(end, 2); // Should include both children
+        
+        let (start, end) = ItemCreator::get_block_range(&items, 3);
+        assert_eq!(start, 3);
+        assert_eq!(end, 3); // No children
+    }
+
+    #[test]
+    fn test_get_block_range_with_raw_lines() {
+        let items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_raw("  continuation text".to_string()),
+            ListItem::new_todo("Child".to_string(), false, 1),
+            ListItem::new_raw(String::new()),
+            ListItem::new_todo("Next parent".to_string(), false, 0),
+        ];
+        
+        // Indented text is part of the block, a blank line ends it
+        let (start, end) = ItemCreator::get_block_range(&items, 0);
+        assert_eq!(start, 0);
+        assert_eq!(end, 2);
+    }
+
+    #[test]
+    fn test_find_parent() {
+        let items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_note("Child note".to_string(), 1),
+            ListItem::new_todo("Grandchild".to_string(), false, 2),
+            ListItem::new_raw(String::new()),
+            ListItem::new_todo("Detached".to_string(), false, 1),
+            ListItem::new_heading("Section".to_string(), 1),
+            ListItem::new_todo("Top level".to_string(), false, 0),
+        ];
+        
+        assert_eq!(ItemCreator::find_parent(&items, 2), Some(1));
+        assert_eq!(ItemCreator::find_parent(&items, 1), Some(0));
+        assert_eq!(ItemCreator::find_parent(&items, 0), None);
+        // A blank line separates the item from the list above it
+        assert_eq!(ItemCreator::find_parent(&items, 4), None);
+        assert_eq!(ItemCreator::find_parent(&items, 6), None);
+    }
+
+    #[test]
+    fn test_get_block_indices() {
+        let items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 1),
+            ListItem::new_todo("Grandchild".to_string(), false, 2),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ];
+        
+        // Overlapping blocks are only counted once
+        assert_eq!(ItemCreator::get_block_indices(&items, &[1, 0]), vec![0, 1, 2]);
+        assert_eq!(ItemCreator::get_block_indices(&items, &[3, 9]), vec![3]);
+    }
+
+    #[test]
+    fn test_determine_insert_position_for_new_todo() {
+        let items = vec![
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 1),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ];
+        
+        // Inserting after parent with children should create new child
+        let (pos, indent) = ItemCreator::determine_insert_position_for_new_todo(&items, 0);
+        assert_eq!(pos, 2); // After the child
+        assert_eq!(indent, 1); // Child indentation
+        
+        // Inserting after item with no children should create sibling
+        let (pos, indent) = ItemCreator::determine_insert_position_for_new_todo(&items, 2);
+        assert_eq!(pos, 3); // After the sibling
+        assert_eq!(indent, 0); // Same level as sibling
+    }
+
+    #[test]
+    fn test_determine_sibling_insert_position() {
+        let items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Parent".to_string(), false, 0),
+            ListItem::new_todo("Child".to_string(), false, 1),
+            ListItem::new_todo("Sibling".to_string(), false, 0),
+        ];
+
+        // Below a parent means after its children, at the parent's level
+        assert_eq!(ItemCreator::determine_sibling_insert_position(&items, 1, false), (3, 0));
+        assert_eq!(ItemCreator::determine_sibling_insert_position(&items, 2, false), (3, 1));
+        assert_eq!(ItemCreator::determine_sibling_insert_position(&items, 2, true), (2, 1));
+        assert_eq!(ItemCreator::determine_sibling_insert_position(&items, 0, false), (1, 0));
+        assert_eq!(ItemCreator::determine_sibling_insert_position(&[], 0, true), (0, 0));
+    }
+
+    #[test]
+    fn test_get_fold_range_for_heading() {
+        let items = vec![
+            ListItem::new_heading("Work".to_string(), 1),
+            ListItem::new_todo("Task".to_string(), false, 0),
+            ListItem::new_heading("Meetings".to_string(), 2),
+            ListItem::new_todo("Standup".to_string(), false, 0),
+            ListItem::new_heading("Home".to_string(), 1),
+            ListItem::new_todo("Dishes".to_string(), false, 0),
+        ];
+        
+        assert_eq!(ItemCreator::get_fold_range(&items, 0), (0, 3));
+        assert_eq!(ItemCreator::get_fold_range(&items, 2), (2, 3));
+        assert_eq!(ItemCreator::get_fold_range(&items, 4), (4, 5));
+    }
+
+    #[test]
+    fn test_find_nearest_matching_item() {
+        let items = vec![
+            ListItem::new_todo("Repeat".to_string(), false, 0),
+            ListItem::new_todo("Other".to_string(), false, 0),
+            ListItem::new_todo("Repeat".to_string(), false, 0),
+        ];
+        let target = ListItem::new_todo("Repeat".to_string(), false, 0);
+        
+        assert_eq!(ItemCreator::find_nearest_matching_item(&items, &target, 0), Some(0));
+        assert_eq!(ItemCreator::find_nearest_matching_item(&items, &target, 2), Some(2));
+        
+        // Completion state is part of the identity
+        let completed = ListItem::new_todo("Repeat".to_string(), true, 0);
+        assert_eq!(ItemCreator::find_nearest_matching_item(&items, &completed, 0), None);
+    }
+}
@@ -0,0 +1,339 @@
+//! Two-way sync between a TODO file and a task service such as Todoist.
+//! Headings map to projects and todos to tasks. The pairs linked by the last
+//! sync are kept next to the file (`TODO.md.todoist.json`), so a change on
+//! either side can be told from one on the other. When both sides changed the
+//! same task, the newer change wins.
+//!
+//! Todos are linked by their text, so editing a todo's text in the file reads
+//! as deleting it and adding a new one.
+
+use crate::models::{ListItem, TodoList, TodoState};
+use crate::structure::ItemCreator;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A todo and the task it was synced to, as both were after the last sync
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    pub task_id: String,
+    pub content: String,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncState {
+    #[serde(default)]
+    pub links: Vec<Link>,
+}
+
+/// An open task as fetched from the service. Tasks in the inbox have no
+/// project; completed tasks aren't fetched at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTask {
+    pub id: String,
+    pub content: String,
+    pub project: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// A change to make on the service to bring it in line with the file
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteChange {
+    /// Once created, the task is linked to the todo with `content`
+    Create { project: Option<String>, content: String },
+    Update { task_id: String, content: String },
+    Complete { task_id: String },
+    Reopen { task_id: String },
+    Delete { task_id: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncPlan {
+    pub changes: Vec<RemoteChange>,
+    /// The links to keep, not counting the tasks still to be created
+    pub links: Vec<Link>,
+    /// How many todos were added or changed in the file
+    pub local_changes: usize,
+}
+
+/// Where the sync state for the TODO file at `todo_path` is kept, per service
+pub fn state_path(todo_path: &str, service: &str) -> PathBuf {
+    let path = Path::new(todo_path);
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!("{}.{}.json", file_name, service))
+}
+
+/// The state left by the last sync; a file that was never synced has none
+pub fn load_state(todo_path: &str, service: &str) -> Result<SyncState> {
+    load_state_with(todo_path, service, |content| Ok(String::from_utf8(content)?))
+}
+
+/// Like [`load_state`], but what's read is passed through `decode` first,
+/// e.g. to decrypt it
+pub fn load_state_with(todo_path: &str, service: &str, decode: impl FnOnce(Vec<u8>) -> Result<String>) -> Result<SyncState> {
+    let path = state_path(todo_path, service);
+    let content = match fs::read(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(SyncState::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read sync state: {}", path.display())),
+    };
+    decode(content)
+        .and_then(|json| Ok(serde_json::from_str(&json)?))
+        .with_context(|| format!("Invalid sync state: {}", path.display()))
+}
+
+pub fn save_state(todo_path: &str, service: &str, state: &SyncState) -> Result<()> {
+    save_state_with(todo_path, service, state, |json| Ok(json.into_bytes()))
+}
+
+/// Like [`save_state`], but the JSON is passed through `encode` before it's
+/// written, e.g. to encrypt it
+pub fn save_state_with(todo_path: &str, service: &str, state: &SyncState, encode: impl FnOnce(String) -> Result<Vec<u8>>) -> Result<()> {
+    let path = state_path(todo_path, service);
+    encode(serde_json::to_string_pretty(state)? + "\n")
+        .and_then(|content| Ok(fs::write(&path, content)?))
+        .with_context(|| format!("Failed to write sync state: {}", path.display()))
+}
+
+/// Applies the service's changes to `todo_list` and works out the changes to
+/// make on the service. `local_modified` is when the file was last changed,
+/// which decides conflicts against the tasks' `updated_at`.
+pub fn plan_sync(todo_list: &mut TodoList, state: &SyncState, remote: &[RemoteTask], local_modified: DateTime<Utc>) -> SyncPlan {
+    let items = &mut todo_list.items;
+    let tasks: HashMap<&str, &RemoteTask> = remote.iter().map(|task| (task.id.as_str(), task)).collect();
+    let remote_is_newer = |task: &RemoteTask| task.updated_at.is_some_and(|updated| updated > local_modified);
+    let mut plan = SyncPlan::default();
+    let mut claimed = vec![false; items.len()];
+    let mut linked_tasks = HashSet::new();
+    let mut additions = Vec::new();
+
+    for link in &state.links {
+        let local = (0..items.len()).find(|&i| {
+            !claimed[i] && matches!(items[i], ListItem::Todo { .. }) && items[i].content() == link.content
+        });
+        let task = tasks.get(link.task_id.as_str()).copied();
+        if let Some(i) = local {
+            claimed[i] = true;
+        }
+        if task.is_some() {
+            linked_tasks.insert(link.task_id.as_str());
+        }
+
+        match (local, task) {
+            (None, None) => {}
+            (None, Some(task)) => {
+                // Removed from the file, but edited on the service since
+                let remote_changed = task.content != link.content || link.completed;
+                if remote_changed && remote_is_newer(task) {
+                    additions.push(task);
+                } else {
+                    plan.changes.push(RemoteChange::Delete { task_id: task.id.clone() });
+                }
+            }
+            (Some(i), None) => {
+                // Completed or deleted on the service
+                if !link.completed && !items[i].is_completed() {
+                    set_state(&mut items[i], TodoState::Done);
+                    plan.local_changes += 1;
+                } else if link.completed && !items[i].is_completed() {
+                    plan.changes.push(RemoteChange::Reopen { task_id: link.task_id.clone() });
+                    plan.links.push(Link { completed: false, ..link.clone() });
+                }
+            }
+            (Some(i), Some(task)) => {
+                let local_changed = items[i].is_completed() != link.completed;
+                let remote_changed = task.content != link.content || link.completed;
+                if remote_changed && (!local_changed || remote_is_newer(task)) {
+                    *items[i].content_mut() = task.content.clone();
+                    set_state(&mut items[i], TodoState::Open);
+                    plan.local_changes += 1;
+                } else if local_changed {
+                    let task_id = task.id.clone();
+                    if task.content != link.content {
+                        plan.changes.push(RemoteChange::Update { task_id: task_id.clone(), content: link.content.clone() });
+                    }
+                    plan.changes.push(if items[i].is_completed() {
+                        RemoteChange::Complete { task_id }
+                    } else {
+                        RemoteChange::Reopen { task_id }
+                    });
+                }
+                plan.links.push(Link {
+                    task_id: task.id.clone(),
+                    content: items[i].content().to_string(),
+                    completed: items[i].is_completed(),
+                });
+            }
+        }
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        if !claimed[i] && matches!(item, ListItem::Todo { .. }) && !item.is_completed() {
+            plan.changes.push(RemoteChange::Create {
+                project: heading_for(items, i),
+                content: item.content().to_string(),
+            });
+        }
+    }
+
+    additions.extend(remote.iter().filter(|task| !linked_tasks.contains(task.id.as_str())));
+    for task in additions {
+        let index = insertion_point(items, task.project.as_deref());
+        items.insert(index, ListItem::new_todo(task.content.clone(), false, 0));
+        plan.links.push(Link {
+            task_id: task.id.clone(),
+            content: task.content.clone(),
+            completed: false,
+        });
+        plan.local_changes += 1;
+    }
+    plan
+}
+
+fn set_state(item: &mut ListItem, new_state: TodoState) {
+    if let ListItem::Todo { state, .. } = item {
+        *state = new_state;
+    }
+}
+
+/// The heading the item at `index` is under, which names its project
+fn heading_for(items: &[ListItem], index: usize) -> Option<String> {
+    let heading = ItemCreator::find_current_heading_context(items, index).checked_sub(1)?;
+    Some(items[heading].content().to_string())
+}
+
+/// Where a new todo for a task in `project` goes: the end of that heading's
+/// own items, or above the first heading for the inbox. A project without a
+/// heading gets a new one at the end of the file.
+fn insertion_point(items: &mut Vec<ListItem>, project: Option<&str>) -> usize {
+    if let Some(index) = ItemCreator::end_of_section(items, project) {
+        return index;
+    }
+    if items.last().is_some_and(|item| !item.content().trim().is_empty()) {
+        items.push(ListItem::new_raw(String::new()));
+    }
+    items.push(ListItem::new_heading(project.unwrap_or_default().to_string(), 1));
+    items.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_todo_content;
+    use crate::writer::serialize_todo_list;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn task(id: &str, content: &str, project: Option<&str>, hour: u32) -> RemoteTask {
+        RemoteTask {
+            id: id.to_string(),
+            content: content.to_string(),
+            project: project.map(str::to_string),
+            updated_at: Some(at(hour)),
+        }
+    }
+
+    fn link(task_id: &str, content: &str, completed: bool) -> Link {
+        Link {
+            task_id: task_id.to_string(),
+            content: content.to_string(),
+            completed,
+        }
+    }
+
+    #[test]
+    fn test_first_sync_creates_both_ways() {
+        let mut list = parse_todo_content("TODO.md", "- [ ] Loose end\n\n# Work\n- [ ] Report\n- [x] Old\n\n# Home\n");
+        let remote = vec![
+            task("1", "Call plumber", Some("Home"), 9),
+            task("2", "Buy stamps", None, 9),
+            task("3", "Plan trip", Some("Travel"), 9),
+        ];
+        let plan = plan_sync(&mut list, &SyncState::default(), &remote, at(10));
+
+        assert_eq!(plan.changes, vec![
+            RemoteChange::Create { project: None, content: "Loose end".to_string() },
+            RemoteChange::Create { project: Some("Work".to_string()), content: "Report".to_string() },
+        ]);
+        assert_eq!(plan.links.len(), 3);
+        assert_eq!(plan.local_changes, 3);
+        assert_eq!(
+            serialize_todo_list(&list),
+            "- [ ] Loose end\n- [ ] Buy stamps\n\n# Work\n- [ ] Report\n- [x] Old\n\n# Home\n- [ ] Call plumber\n\n# Travel\n- [ ] Plan trip\n"
+        );
+    }
+
+    #[test]
+    fn test_one_sided_changes() {
+        let mut list = parse_todo_content("TODO.md", "- [x] Checked here\n- [ ] Renamed there\n- [ ] Done there\n");
+        let state = SyncState {
+            links: vec![
+                link("1", "Checked here", false),
+                link("2", "Renamed there", false),
+                link("3", "Done there", false),
+                link("4", "Deleted here", false),
+            ],
+        };
+        let remote = vec![
+            task("1", "Checked here", None, 9),
+            task("2", "Renamed on the service", None, 9),
+            task("4", "Deleted here", None, 9),
+        ];
+        let plan = plan_sync(&mut list, &state, &remote, at(10));
+
+        assert_eq!(plan.changes, vec![
+            RemoteChange::Complete { task_id: "1".to_string() },
+            RemoteChange::Delete { task_id: "4".to_string() },
+        ]);
+        assert_eq!(serialize_todo_list(&list), "- [x] Checked here\n- [ ] Renamed on the service\n- [x] Done there\n");
+        assert_eq!(plan.links, vec![link("1", "Checked here", true), link("2", "Renamed on the service", false)]);
+    }
+
+    #[test]
+    fn test_conflicts_go_to_the_newer_side() {
+        let state = SyncState {
+            links: vec![link("1", "Draft", false)],
+        };
+
+        // Checked off in the file after the task was renamed
+        let mut list = parse_todo_content("TODO.md", "- [x] Draft\n");
+        let plan = plan_sync(&mut list, &state, &[task("1", "Draft v2", None, 9)], at(10));
+        assert_eq!(plan.changes, vec![
+            RemoteChange::Update { task_id: "1".to_string(), content: "Draft".to_string() },
+            RemoteChange::Complete { task_id: "1".to_string() },
+        ]);
+        assert_eq!(serialize_todo_list(&list), "- [x] Draft\n");
+
+        // Renamed after it was checked off in the file
+        let mut list = parse_todo_content("TODO.md", "- [x] Draft\n");
+        let plan = plan_sync(&mut list, &state, &[task("1", "Draft v2", None, 11)], at(10));
+        assert!(plan.changes.is_empty());
+        assert_eq!(serialize_todo_list(&list), "- [ ] Draft v2\n");
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = std::env::temp_dir().join(format!("todo_sync_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let todo_path = dir.join("TODO.md").to_string_lossy().to_string();
+        assert_eq!(state_path(&todo_path, "todoist"), dir.join("TODO.md.todoist.json"));
+        assert_eq!(load_state(&todo_path, "todoist").unwrap(), SyncState::default());
+
+        let state = SyncState {
+            links: vec![link("1", "Draft", false)],
+        };
+        save_state(&todo_path, "todoist", &state).unwrap();
+        assert_eq!(load_state(&todo_path, "todoist").unwrap(), state);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
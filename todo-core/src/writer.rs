@@ -0,0 +1,318 @@
+//! Turning a [`TodoList`] back into markdown and saving it.
+
+use crate::models::{IndentStyle, LineEnding, ListItem, TodoList};
+use crate::parser::CONTINUATION_INDENT;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    /// Copy the previous file contents to `<file>.bak` before replacing it
+    pub backup: bool,
+}
+
+pub fn write_todo_file(todo_list: &TodoList, options: &WriteOptions) -> Result<()> {
+    write_todo_file_with(todo_list, options, |content| Ok(content.into_bytes()))
+}
+
+/// Like [`write_todo_file`], but the markdown is passed through `encode`
+/// before it's written, e.g. to encrypt it
+pub fn write_todo_file_with(
+    todo_list: &TodoList,
+    options: &WriteOptions,
+    encode: impl FnOnce(String) -> Result<Vec<u8>>,
+) -> Result<()> {
+    encode(serialize_todo_list(todo_list))
+        .and_then(|content| write_atomically(Path::new(&todo_list.file_path), &content, options))
+        .with_context(|| format!("Failed to write TODO file: {}", todo_list.file_path))
+}
+
+// Write to a temporary file next to the target and rename it into place, so a
+// crash mid-write never leaves a truncated TODO file behind
+fn write_atomically(path: &Path, content: &[u8], options: &WriteOptions) -> Result<()> {
+    // Replace the file a symlink points to rather than the symlink itself
+    let target = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let temp_path = sibling_path(&target, &format!(".tmp-{}", std::process::id()), true);
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+
+        if let Ok(metadata) = fs::metadata(&target) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+            if options.backup {
+                fs::copy(&target, sibling_path(&target, ".bak", false))?;
+            }
+        }
+
+        fs::rename(&temp_path, &target)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+    result
+}
+
+fn sibling_path(path: &Path, suffix: &str, hidden: bool) -> PathBuf {
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let prefix = if hidden { "." } else { "" };
+    path.with_file_name(format!("{}{}{}", prefix, file_name, suffix))
+}
+
+// Every line after the first is indented to sit under the item's text
+fn with_continuation_lines(content: &str, indent: &str) -> String {
+    content.replace('\n', &format!("\n{}{}", indent, CONTINUATION_INDENT))
+}
+
+pub fn serialize_todo_list(todo_list: &TodoList) -> String {
+    let lines: Vec<String> = todo_list
+        .items
+        .iter()
+        .map(|item| serialize_item_with_style(item, todo_list.indent_style))
+        .collect();
+    
+    let mut content = lines.join("\n");
+    if todo_list.trailing_newline {
+        content.push('\n');
+    }
+    // Multi-line items contain line breaks of their own, so convert at the end
+    match todo_list.line_ending {
+        LineEnding::Lf => content,
+        LineEnding::CrLf => content.replace('\n', "\r\n"),
+    }
+}
+
+/// Writes a single item using the default two-space indentation
+pub fn serialize_item(item: &ListItem) -> String {
+    serialize_item_with_style(item, IndentStyle::default())
+}
+
+pub fn serialize_item_with_style(item: &ListItem, indent_style: IndentStyle) -> String {
+    match item {
+        ListItem::Todo { content, state, indent_level, .. } => {
+            let indent = indent_style.indent(*indent_level);
+            format!("{}- [{}] {}", indent, state.marker(), with_continuation_lines(content, &indent))
+        }
+        ListItem::Note { content, indent_level, .. } => {
+            let indent = indent_style.indent(*indent_level);
+            format!("{}- {}", indent, with_continuation_lines(content, &indent))
+        }
+        ListItem::Heading { content, level, .. } => {
+            let prefix = "#".repeat(*level);
+            format!("{} {}", prefix, content)
+        }
+        ListItem::Raw { content } => content.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TodoState;
+    use crate::parser;
+
+    #[test]
+    fn test_serialize_empty_list() {
+        let todo_list = TodoList::new("test.md".to_string());
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "\n");
+    }
+
+    #[test]
+    fn test_serialize_single_todo() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_todo("Buy groceries".to_string(), false, 0));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "- [ ] Buy groceries\n");
+    }
+
+    #[test]
+    fn test_serialize_completed_todo() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_todo("Finish project".to_string(), true, 0));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "- [x] Finish project\n");
+    }
+
+    #[test]
+    fn test_serialize_extended_states() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_todo_with_state("Writing".to_string(), TodoState::InProgress, 0));
+        todo_list.add_item(ListItem::new_todo_with_state("Dropped".to_string(), TodoState::Cancelled, 0));
+        todo_list.add_item(ListItem::new_todo_with_state("Later".to_string(), TodoState::Deferred, 1));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "- [/] Writing\n- [-] Dropped\n  - [>] Later\n");
+    }
+
+    #[test]
+    fn test_serialize_indented_todo() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_todo("Subtask".to_string(), false, 2));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "    - [ ] Subtask\n");
+    }
+
+    #[test]
+    fn test_serialize_heading() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_heading("Main Section".to_string(), 1));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "# Main Section\n");
+    }
+
+    #[test]
+    fn test_serialize_nested_heading() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_heading("Subsection".to_string(), 2));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "## Subsection\n");
+    }
+
+    #[test]
+    fn test_serialize_note() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_note("This is a note".to_string(), 0));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "- This is a note\n");
+    }
+
+    #[test]
+    fn test_serialize_indented_note() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_note("Indented note".to_string(), 1));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "  - Indented note\n");
+    }
+
+    #[test]
+    fn test_serialize_mixed_content() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_heading("Project".to_string(), 1));
+        todo_list.add_item(ListItem::new_todo("Task 1".to_string(), false, 0));
+        todo_list.add_item(ListItem::new_note("Project notes".to_string(), 0));
+        todo_list.add_item(ListItem::new_todo("Task 2".to_string(), true, 0));
+        todo_list.add_item(ListItem::new_todo("Subtask".to_string(), false, 1));
+        todo_list.add_item(ListItem::new_note("Nested note".to_string(), 1));
+        
+        let result = serialize_todo_list(&todo_list);
+        let expected = "# Project\n- [ ] Task 1\n- Project notes\n- [x] Task 2\n  - [ ] Subtask\n  - Nested note\n";
+        assert_eq!(result, expected);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("todo_writer_{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_replaces_file_without_leftovers() {
+        let dir = temp_dir("atomic");
+        let file_path = dir.join("TODO.md");
+        fs::write(&file_path, "- [ ] Old task\n").unwrap();
+        
+        let mut todo_list = TodoList::new(file_path.to_string_lossy().to_string());
+        todo_list.add_item(ListItem::new_todo("New task".to_string(), false, 0));
+        write_todo_file(&todo_list, &WriteOptions::default()).unwrap();
+        
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "- [ ] New task\n");
+        // Only the target file should remain in the directory
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_write_with_backup() {
+        let dir = temp_dir("backup");
+        let file_path = dir.join("TODO.md");
+        fs::write(&file_path, "- [ ] Old task\n").unwrap();
+        
+        let mut todo_list = TodoList::new(file_path.to_string_lossy().to_string());
+        todo_list.add_item(ListItem::new_todo("New task".to_string(), false, 0));
+        write_todo_file(&todo_list, &WriteOptions { backup: true }).unwrap();
+        
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "- [ ] New task\n");
+        assert_eq!(fs::read_to_string(dir.join("TODO.md.bak")).unwrap(), "- [ ] Old task\n");
+        
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_roundtrip_parse_and_serialize() {
+        use std::fs;
+        
+        // Create test content
+        let original_content = "# Test Project\n\n- [ ] First task\n- [x] Completed task\n  - [ ] Subtask\n## Section 2\n- [ ] Another task\n";
+        
+        // Create temporary file
+        let temp_file = "/tmp/test_roundtrip.md";
+        fs::write(temp_file, original_content).unwrap();
+        
+        // Parse the file
+        let todo_list = parser::parse_todo_file(temp_file).unwrap();
+        
+        // Serialize it back
+        let serialized = serialize_todo_list(&todo_list);
+        
+        // The output should contain all the essential information
+        // (might differ slightly in whitespace but should have same structure)
+        assert!(serialized.contains("# Test Project"));
+        assert!(serialized.contains("- [ ] First task"));
+        assert!(serialized.contains("- [x] Completed task"));
+        assert!(serialized.contains("  - [ ] Subtask"));
+        assert!(serialized.contains("## Section 2"));
+        assert!(serialized.contains("- [ ] Another task"));
+        
+        // Clean up
+        fs::remove_file(temp_file).ok();
+    }
+
+    #[test]
+    fn test_serialize_multiline_item() {
+        let mut todo_list = TodoList::new("test.md".to_string());
+        todo_list.add_item(ListItem::new_todo("Plan trip\nbook flights".to_string(), false, 1));
+        
+        let result = serialize_todo_list(&todo_list);
+        assert_eq!(result, "  - [ ] Plan trip\n    book flights\n");
+        assert_eq!(parser::parse_todo_content("test.md", &result).items, todo_list.items);
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_line_endings() {
+        for content in [
+            "# Work\r\n- [ ] Task\r\n  - Note\r\n",
+            "# Work\n- [ ] Task",
+            "# Work\r\n- [x] Task",
+            "- [ ] Task\r\n  continued\r\n",
+        ] {
+            let todo_list = parser::parse_todo_content("test.md", content);
+            assert_eq!(serialize_todo_list(&todo_list), content);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_keeps_indent_style() {
+        for content in [
+            "- [ ] Top\n\t- [ ] Child\n\t\t- Note\n",
+            "- [ ] Top\n    - [ ] Child\n        - Note\n",
+        ] {
+            let todo_list = parser::parse_todo_content("test.md", content);
+            assert_eq!(serialize_todo_list(&todo_list), content);
+        }
+    }
+}
\ No newline at end of file